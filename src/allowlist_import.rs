@@ -0,0 +1,124 @@
+//! --allowed-from: import an allow-list kept for another tool. Reads a file with one license
+//! per line, or JSON: an array of ids, or of license objects as in the SPDX license list
+//! (`licenseId`) and CycloneDX (`id`, `license.id`), optionally under a `licenses` key.
+
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use crate::license_groups;
+use crate::spdx_deprecations;
+
+/// Read the licenses listed in an allow-list file
+pub fn read_allowlist(path: &Path) -> Result<Vec<String>, String> {
+    let content = fs
+        ::read_to_string(path)
+        .map_err(|e| format!("Failed to read allowed licenses from {}: {}", path.display(), e))?;
+    parse_allowlist(&content).map_err(|e| format!("Invalid allow-list {}: {}", path.display(), e))
+}
+
+/// The licenses of an allow-list, spelled as the SPDX license list does when the id is known
+pub fn parse_allowlist(content: &str) -> Result<Vec<String>, String> {
+    let trimmed = content.trim_start();
+    let entries = if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        let json: Value = serde_json::from_str(trimmed).map_err(|e| e.to_string())?;
+        json_entries(&json)?
+    } else {
+        content
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    let mut licenses: Vec<String> = Vec::new();
+    for entry in entries {
+        let license = license_groups::spdx_flags(&entry).map_or(entry, |(id, _, _)| id.to_string());
+        if !licenses.contains(&license) {
+            licenses.push(license);
+        }
+    }
+    Ok(licenses)
+}
+
+fn json_entries(json: &Value) -> Result<Vec<String>, String> {
+    let items = match json {
+        Value::Array(items) => items,
+        Value::Object(document) => {
+            match document.get("licenses") {
+                Some(Value::Array(items)) => items,
+                _ => {
+                    return Err("expected an array of license ids or a \"licenses\" array".to_string());
+                }
+            }
+        }
+        _ => {
+            return Err("expected an array of license ids".to_string());
+        }
+    };
+
+    items
+        .iter()
+        .map(|item| {
+            let id = match item {
+                Value::String(id) => Some(id.as_str()),
+                Value::Object(_) => {
+                    item["licenseId"].as_str().or(item["id"].as_str()).or(item["license"]["id"].as_str())
+                }
+                _ => None,
+            };
+            id.map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .ok_or_else(|| format!("no license id in entry {}", item))
+        })
+        .collect()
+}
+
+/// Imported entries that aren't ids of the SPDX license list. Wildcard patterns,
+/// `@group` entries and `LicenseRef-` ids are never reported.
+pub fn unrecognized_ids(licenses: &[String]) -> Vec<&str> {
+    licenses
+        .iter()
+        .map(String::as_str)
+        .filter(|license| !license.contains('*') && !license.starts_with('@') && !license.starts_with("LicenseRef-"))
+        .filter(|license| {
+            license_groups::spdx_flags(license).is_none() && spdx_deprecations::replacement_for(license).is_none()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_json_array_of_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("approved-licenses.json");
+        fs::write(&path, r#"["MIT", "apache-2.0", "BSD-3-Clause", "MIT", "Apache-*", "busl-1.1", "Not-A-License"]"#)
+            .unwrap();
+
+        let licenses = read_allowlist(&path).unwrap();
+        assert_eq!(licenses, ["MIT", "Apache-2.0", "BSD-3-Clause", "Apache-*", "BUSL-1.1", "Not-A-License"]);
+        // BUSL-1.1 is neither OSI approved nor FSF libre, but it is an SPDX id
+        assert_eq!(unrecognized_ids(&licenses), ["Not-A-License"]);
+
+        let missing = read_allowlist(&dir.path().join("missing.json")).unwrap_err();
+        assert!(missing.starts_with("Failed to read allowed licenses from"), "{}", missing);
+    }
+
+    #[test]
+    fn test_import_other_formats() {
+        let lines = parse_allowlist("# Approved by legal\nMIT\n\nISC  # since 2023\nLicenseRef-Acme\n").unwrap();
+        assert_eq!(lines, ["MIT", "ISC", "LicenseRef-Acme"]);
+        assert!(unrecognized_ids(&lines).is_empty());
+
+        let spdx_list = r#"{ "licenses": [{ "licenseId": "0BSD" }, { "license": { "id": "GPL-3.0" } }] }"#;
+        assert_eq!(parse_allowlist(spdx_list).unwrap(), ["0BSD", "GPL-3.0"]);
+
+        assert!(parse_allowlist(r#"{ "allowed": ["MIT"] }"#).is_err());
+        let err = parse_allowlist(r#"["MIT", 42]"#).unwrap_err();
+        assert_eq!(err, "no license id in entry 42");
+    }
+}
@@ -1,11 +1,75 @@
 use flate2::read::GzDecoder;
+use once_cell::sync::OnceCell;
+use reqwest::blocking::Client;
 use std::fs::{ self, File };
 use std::io::{ self };
 use std::path::{ Path, PathBuf };
+use std::sync::{ Condvar, Mutex };
 use tar::Archive;
 use tempfile::TempDir;
 use zip::ZipArchive;
 
+use crate::integrity::IntegrityHash;
+use crate::package::{ is_unknown_license, LicenseCandidate, LicenseSource, Package, UNKNOWN_LICENSE };
+
+// Leading bytes of gzip and zip content, for URLs like GitHub's /tarball/main that carry no suffix
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZIP_MAGIC: [u8; 2] = [0x50, 0x4b];
+
+pub const DEFAULT_MAX_DOWNLOADS: usize = 2;
+
+/// Counting semaphore bounding how many archive downloads run at once, independent of the
+/// number of worker threads
+pub struct DownloadSlots {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+/// A taken download slot, given back when dropped
+pub struct DownloadPermit<'a> {
+    slots: &'a DownloadSlots,
+}
+
+impl DownloadSlots {
+    pub fn new(slots: usize) -> Self {
+        DownloadSlots {
+            available: Mutex::new(slots.max(1)),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Wait for a free slot
+    pub fn acquire(&self) -> DownloadPermit<'_> {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.released.wait(available).unwrap();
+        }
+        *available -= 1;
+        DownloadPermit { slots: self }
+    }
+}
+
+impl Drop for DownloadPermit<'_> {
+    fn drop(&mut self) {
+        *self.slots.available.lock().unwrap() += 1;
+        self.slots.released.notify_one();
+    }
+}
+
+static DOWNLOAD_SLOTS: OnceCell<DownloadSlots> = OnceCell::new();
+
+/// Set how many archive downloads may run at once; must happen before the first download
+pub fn configure_max_downloads(max_downloads: usize) -> Result<(), String> {
+    DOWNLOAD_SLOTS.set(DownloadSlots::new(max_downloads)).map_err(|_|
+        "archive download concurrency is already configured".to_string()
+    )
+}
+
+/// Wait until another archive download may start; hold the permit for the download
+pub fn download_permit() -> DownloadPermit<'static> {
+    DOWNLOAD_SLOTS.get_or_init(|| DownloadSlots::new(DEFAULT_MAX_DOWNLOADS)).acquire()
+}
+
 pub struct ArchiveHandler {
     temp_dir: TempDir,
 }
@@ -23,73 +87,54 @@ impl ArchiveHandler {
         self.temp_dir.path()
     }
 
-    /// Download with retry logic
+    /// Download with retry logic, honoring Retry-After on rate limits
     fn download_with_retry(
         &self,
+        client: &Client,
         url: &str,
-        max_retries: usize
-    ) -> Result<Vec<u8>, reqwest::Error> {
-        let client = reqwest::blocking::Client
-            ::builder()
-            .timeout(std::time::Duration::from_secs(15))
-            .build()?;
-
-        let mut retries = 0;
-        let mut last_error = None;
-
-        while retries < max_retries {
-            match client.get(url).send() {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        return response.bytes().map(|b| b.to_vec());
-                    }
-
-                    // If we got a 429 Too Many Requests, wait longer before retrying
-                    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
-                        std::thread::sleep(
-                            std::time::Duration::from_secs(5 * ((retries + 1) as u64))
-                        );
-                    }
-                }
-                Err(e) => {
-                    last_error = Some(e);
-                }
-            }
-
-            retries += 1;
-            std::thread::sleep(std::time::Duration::from_secs(1 * (retries as u64)));
+        max_attempts: usize
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let response = crate::http_retry::send_with_retry(
+            // Applied per request so caller-supplied clients honor --download-timeout too
+            || client.get(url).timeout(crate::http_retry::download_timeout()),
+            max_attempts
+        )?;
+
+        if !response.status().is_success() {
+            return Err(format!("Download failed with status code {}", response.status().as_u16()).into());
         }
 
-        // The issue being fixed: If all retries fail but none returned an actual error,
-        // we still need to return some kind of error.
-        // For example, if all responses were 404 or 500 status codes.
-        match last_error {
-            Some(e) => Err(e),
-            None => {
-                // Create a simple request that will fail and use that error
-                // This ensures we always return a reqwest::Error
-                let err = client
-                    .get("invalid://example.com")
-                    .send()
-                    .expect_err("Expected error request to fail");
-
-                Err(err)
-            }
-        }
+        Ok(response.bytes()?.to_vec())
     }
 
-    /// Download and extract an archive based on its URL
-    pub fn download_and_extract(&self, url: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    /// Download and extract an archive based on its URL, checking it against the lockfile's
+    /// integrity hash when one is known
+    pub fn download_and_extract(
+        &self,
+        client: &Client,
+        url: &str,
+        integrity: Option<&IntegrityHash>
+    ) -> Result<PathBuf, Box<dyn std::error::Error>> {
         println!("Downloading archive from: {}", url);
 
         // Download with retry logic
-        let content = self.download_with_retry(url, 3)?;
+        let content = self.download_with_retry(client, url, 3)?;
+
+        // Never read licenses out of an archive that isn't the one the lockfile pinned
+        if let Some(integrity) = integrity {
+            integrity.verify(&content)?;
+        }
+
+        self.extract(url, &content)
+    }
 
-        // Determine archive type from URL and extract accordingly
-        if url.ends_with(".zip") {
-            self.extract_zip(&content)
-        } else if url.ends_with(".tar.gz") || url.ends_with(".tgz") {
-            self.extract_tar_gz(&content)
+    /// Extract by URL suffix, or by the content's magic bytes when the suffix doesn't say
+    fn extract(&self, url: &str, content: &[u8]) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let suffixed = url.ends_with(".zip") || url.ends_with(".tar.gz") || url.ends_with(".tgz");
+        if url.ends_with(".zip") || (!suffixed && content.starts_with(&ZIP_MAGIC)) {
+            self.extract_zip(content)
+        } else if suffixed || content.starts_with(&GZIP_MAGIC) {
+            self.extract_tar_gz(content)
         } else {
             Err("Unsupported archive format".into())
         }
@@ -214,41 +259,114 @@ impl ArchiveHandler {
     }
 }
 
+/// License information found in a downloaded archive
+pub struct ArchiveLicenseInfo {
+    pub license: String,
+    pub license_content: Option<String>, // Text of the license file, if any
+    pub license_source: Option<LicenseSource>, // None when the license is unknown
+    pub confidence: f32,
+    pub candidates: Vec<LicenseCandidate>, // The package.json and license file licenses, each when found
+}
+
 /// Extract license info from an archive URL
 /// Note: This should be used as a fallback after trying to get info from npm registry
 pub fn extract_info_from_archive(
-    url: &str
-) -> Result<(String, Option<String>), Box<dyn std::error::Error>> {
+    client: &Client,
+    url: &str,
+    integrity: Option<&IntegrityHash>
+) -> Result<ArchiveLicenseInfo, Box<dyn std::error::Error>> {
     // Create a new archive handler
     let handler = ArchiveHandler::new()?;
 
     // Download and extract the archive
-    let extract_dir = handler.download_and_extract(url)?;
+    let extract_dir = handler.download_and_extract(client, url, integrity)?;
+
+    let (info, _) = read_extracted(&handler, &extract_dir)?;
+    Ok(info)
+}
+
+/// Resolve an archive on disk, e.g. a vendored .tgz without a lockfile, to a single package
+/// described by its package.json and license file
+pub fn package_from_archive_file(path: &Path) -> Result<Package, Box<dyn std::error::Error>> {
+    let handler = ArchiveHandler::new()?;
+    let content = fs::read(path)?;
+    let extract_dir = handler.extract(&path.to_string_lossy(), &content)?;
+    let (info, manifest) = read_extracted(&handler, &extract_dir)?;
+
+    // Without a package.json, name the package after the file
+    let manifest = manifest.unwrap_or_default();
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let name = manifest["name"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            let stem = file_name.trim_end_matches(".tar.gz").trim_end_matches(".tgz");
+            stem.trim_end_matches(".zip").to_string()
+        });
+    let version = manifest["version"].as_str().unwrap_or("UNKNOWN").to_string();
+
+    let mut package = Package::new(name, version, path.display().to_string(), None);
+    if !manifest.is_null() {
+        package.registry = "npm".to_string();
+    }
+    package.display_name = format!("{}@{}", package.name, package.version);
+    package.url = manifest["homepage"].as_str().unwrap_or_default().to_string();
+    package.license = info.license;
+    package.license_source = info.license_source;
+    package.confidence = info.confidence;
+    package.processed = true;
+    package.source_lockfiles = vec![path.display().to_string()];
+    Ok(package)
+}
+
+/// Whether a project path points directly at an archive rather than a lockfile
+pub fn is_archive_file(path: &Path) -> bool {
+    path.is_file() && is_archive_url(&path.to_string_lossy())
+}
 
+/// License information from an extracted archive, along with its package.json when it has one
+fn read_extracted(
+    handler: &ArchiveHandler,
+    extract_dir: &Path
+) -> Result<(ArchiveLicenseInfo, Option<serde_json::Value>), Box<dyn std::error::Error>> {
     // Try to find package.json
-    let mut license = "UNKNOWN".to_string();
-    if let Some(package_json_path) = handler.find_package_json(&extract_dir) {
+    let mut manifest = None;
+    let mut license = UNKNOWN_LICENSE.to_string();
+    let mut license_source = None;
+    let mut confidence = 0.0;
+    let mut candidates = Vec::new();
+    if let Some(package_json_path) = handler.find_package_json(extract_dir) {
         // Read and parse package.json
         let content = handler.read_file_content(&package_json_path)?;
         if let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) {
             // Extract license information
             if let Some(lic) = json["license"].as_str() {
                 license = crate::license_detection::normalize_license_id(lic);
+                license_source = Some(LicenseSource::PackageManifest);
+                confidence = crate::license_detection::declared_confidence(&license);
+                candidates.extend(crate::license_sources::declared(LicenseSource::PackageManifest, lic));
             }
+            manifest = Some(json);
         }
     }
 
     // Try to find license file content
-    let license_content = if let Some(license_path) = handler.find_license_file(&extract_dir) {
+    let license_content = if let Some(license_path) = handler.find_license_file(extract_dir) {
         if let Ok(content) = handler.read_file_content(&license_path) {
-            // If license is still unknown, try to detect it from the license file content
-            if license == "UNKNOWN" {
-                if
-                    let Some(detected_license) = crate::license_detection::detect_license_from_text(
-                        &content
-                    )
-                {
+            if
+                let Some((detected_license, detected_confidence)) =
+                    crate::license_detection::detect_license_from_text(&content)
+            {
+                candidates.push(LicenseCandidate {
+                    source: LicenseSource::LicenseFile,
+                    license: detected_license.clone(),
+                    confidence: detected_confidence,
+                });
+                // The license file only decides when package.json has no license
+                if is_unknown_license(&license) {
                     license = detected_license;
+                    license_source = Some(LicenseSource::LicenseFile);
+                    confidence = detected_confidence;
                 }
             }
             Some(content)
@@ -259,10 +377,177 @@ pub fn extract_info_from_archive(
         None
     };
 
-    Ok((license, license_content))
+    Ok((ArchiveLicenseInfo { license, license_content, license_source, confidence, candidates }, manifest))
 }
 
 /// Check if a URL points to an archive that needs special handling
 pub fn is_archive_url(url: &str) -> bool {
-    url.ends_with(".zip") || url.ends_with(".tar.gz") || url.ends_with(".tgz")
+    url.ends_with(".zip") ||
+        url.ends_with(".tar.gz") ||
+        url.ends_with(".tgz") ||
+        // GitHub tarball and zipball links, e.g. .../tarball/main or codeload.github.com/.../tar.gz/<sha>
+        url.contains("/tarball/") ||
+        url.contains("/zipball/") ||
+        (url.contains("codeload.github.com/") && (url.contains("/tar.gz/") || url.contains("/zip/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{ HeaderMap, HeaderValue };
+    use std::io::{ Read, Write };
+    use std::net::TcpListener;
+
+    const PACKAGE_JSON: &str = r#"{ "name": "widget", "license": "MIT" }"#;
+
+    fn gzip_tarball(path: &str, content: &str) -> Vec<u8> {
+        let mut builder = tar::Builder::new(
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default())
+        );
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn zip_archive(path: &str, content: &str) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        writer.start_file(path, zip::write::FileOptions::default()).unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_suffixless_archives_are_sniffed() {
+        let tarball_url = "https://github.com/owner/widget/tarball/main";
+        let zipball_url = "https://github.com/owner/widget/zipball/main";
+        assert!(is_archive_url(tarball_url));
+        assert!(is_archive_url(zipball_url));
+        assert!(is_archive_url("https://codeload.github.com/owner/widget/tar.gz/3f2a9c1"));
+        assert!(!is_archive_url("https://github.com/owner/widget"));
+
+        let handler = ArchiveHandler::new().unwrap();
+        let extract_dir = handler
+            .extract(tarball_url, &gzip_tarball("owner-widget-3f2a9c1/package.json", PACKAGE_JSON))
+            .unwrap();
+        let package_json = handler.find_package_json(&extract_dir).unwrap();
+        assert_eq!(handler.read_file_content(&package_json).unwrap(), PACKAGE_JSON);
+
+        let handler = ArchiveHandler::new().unwrap();
+        let extract_dir = handler
+            .extract(zipball_url, &zip_archive("owner-widget-3f2a9c1/package.json", PACKAGE_JSON))
+            .unwrap();
+        let package_json = handler.find_package_json(&extract_dir).unwrap();
+        assert_eq!(handler.read_file_content(&package_json).unwrap(), PACKAGE_JSON);
+
+        let err = handler.extract(tarball_url, b"<html>Not Found</html>").unwrap_err();
+        assert_eq!(err.to_string(), "Unsupported archive format");
+    }
+
+    #[test]
+    fn test_download_slots_bound_concurrency() {
+        use std::sync::atomic::{ AtomicUsize, Ordering };
+        use std::sync::Arc;
+
+        let slots = Arc::new(DownloadSlots::new(2));
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let workers: Vec<_> = (0..8)
+            .map(|_| {
+                let (slots, running, peak) = (Arc::clone(&slots), Arc::clone(&running), Arc::clone(&peak));
+                std::thread::spawn(move || {
+                    let _permit = slots.acquire();
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(*slots.available.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_package_from_archive_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let tgz_path = dir.path().join("widget-2.1.0.tgz");
+        let manifest = r#"{ "name": "widget", "version": "2.1.0", "license": "Apache-2.0" }"#;
+        fs::write(&tgz_path, gzip_tarball("package/package.json", manifest)).unwrap();
+        assert!(is_archive_file(&tgz_path));
+        assert!(!is_archive_file(&dir.path().join("missing.tgz")));
+
+        let package = package_from_archive_file(&tgz_path).unwrap();
+        assert_eq!((package.name.as_str(), package.version.as_str()), ("widget", "2.1.0"));
+        assert_eq!(package.license, "Apache-2.0");
+        assert_eq!(package.license_source, Some(LicenseSource::PackageManifest));
+        assert_eq!(package.source_lockfiles, vec![tgz_path.display().to_string()]);
+
+        // No package.json: the license file decides and the file names the package
+        let zip_path = dir.path().join("vendored.zip");
+        let mit = "The MIT License (MIT)\n\nPermission is hereby granted, free of charge, to any person obtaining a copy";
+        fs::write(&zip_path, zip_archive("LICENSE", mit)).unwrap();
+        let package = package_from_archive_file(&zip_path).unwrap();
+        assert_eq!(package.name, "vendored");
+        assert_eq!(package.license, "MIT");
+        assert_eq!(package.license_source, Some(LicenseSource::LicenseFile));
+    }
+
+    #[test]
+    fn test_manifest_and_license_file_are_both_candidates() {
+        let handler = ArchiveHandler::new().unwrap();
+        let extract_dir = handler.temp_dir_path().join("package");
+        fs::create_dir_all(&extract_dir).unwrap();
+        fs::write(extract_dir.join("package.json"), r#"{ "name": "widget", "license": "ISC" }"#).unwrap();
+        let mit = "The MIT License (MIT)\n\nPermission is hereby granted, free of charge, to any person obtaining a copy";
+        fs::write(extract_dir.join("LICENSE"), mit).unwrap();
+
+        let (info, _) = read_extracted(&handler, handler.temp_dir_path()).unwrap();
+        // package.json decides, but the license file's disagreement is kept
+        assert_eq!(info.license, "ISC");
+        let candidates: Vec<(LicenseSource, &str)> = info.candidates
+            .iter()
+            .map(|candidate| (candidate.source, candidate.license.as_str()))
+            .collect();
+        assert_eq!(candidates, vec![(LicenseSource::PackageManifest, "ISC"), (LicenseSource::LicenseFile, "MIT")]);
+    }
+
+    #[test]
+    fn test_download_uses_injected_client() {
+        let tarball = gzip_tarball("package/package.json", PACKAGE_JSON);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/widget/-/widget-1.0.0.tgz", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let read = stream.read(&mut request).unwrap();
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                tarball.len()
+            );
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(&tarball).unwrap();
+            String::from_utf8_lossy(&request[..read]).to_lowercase()
+        });
+
+        // Mark requests from this client so the server can tell which client sent them
+        let mut headers = HeaderMap::new();
+        headers.insert("x-scanner-client", HeaderValue::from_static("injected"));
+        let client = Client::builder().default_headers(headers).no_proxy().build().unwrap();
+
+        let info = extract_info_from_archive(&client, &url, None).unwrap();
+        let request = server.join().unwrap();
+
+        assert!(request.contains("x-scanner-client: injected"), "{}", request);
+        assert_eq!(info.license, "MIT");
+        assert_eq!(info.license_source, Some(LicenseSource::PackageManifest));
+    }
 }
@@ -0,0 +1,95 @@
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::license_checker::LicenseChecker;
+use crate::output::{ self, OutputFormat, OutputRequest, Report };
+use crate::package::Package;
+
+/// The violations recorded in an approved-state JSON report, as name@version
+pub fn load_violations(path: &Path) -> Result<BTreeSet<String>, String> {
+    let content = fs
+        ::read_to_string(path)
+        .map_err(|e| format!("Failed to read baseline {}: {}", path.display(), e))?;
+    let report: Value = serde_json
+        ::from_str(&content)
+        .map_err(|e| format!("Baseline {} is not a JSON report: {}", path.display(), e))?;
+
+    Ok(
+        report["packages"]
+            .as_array()
+            .map(|packages| {
+                packages
+                    .iter()
+                    .filter(|p| p["allowed"] == false)
+                    .filter_map(|p| Some(format!("{}@{}", p["name"].as_str()?, p["version"].as_str()?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    )
+}
+
+/// How packages are identified when comparing scans
+pub fn package_id(package: &Package) -> String {
+    format!("{}@{}", package.name, package.version)
+}
+
+/// Violations in the current results that the baseline doesn't already accept
+pub fn new_violations<'a>(
+    packages: &'a [Package],
+    license_checker: &LicenseChecker,
+    baseline: &BTreeSet<String>
+) -> Vec<&'a Package> {
+    packages
+        .iter()
+        .filter(|p| !license_checker.is_package_allowed(p))
+        .filter(|p| !baseline.contains(&package_id(p)))
+        .collect()
+}
+
+/// Write the report as the new baseline, in the JSON format load_violations reads
+pub fn write(path: &Path, report: &Report) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    output::write_report(&OutputRequest::new(OutputFormat::Json), report, &mut file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, license: &str) -> Package {
+        let mut package = Package::new(name.to_string(), "1.0.0".to_string(), String::new(), None);
+        package.license = license.to_string();
+        package
+    }
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("licenses-baseline.json");
+        let license_checker = LicenseChecker::new(vec!["MIT".to_string()]);
+        let packages = vec![package("left-pad", "MIT"), package("gpl-lib", "GPL-3.0")];
+        let report = Report::new(&packages, &license_checker);
+        write(&path, &report).unwrap();
+
+        let written: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["summary"]["total"], 2);
+        assert_eq!(written["summary"]["violations"], 1);
+
+        let baseline = load_violations(&path).unwrap();
+        assert_eq!(baseline, BTreeSet::from(["gpl-lib@1.0.0".to_string()]));
+
+        // Only violations missing from the baseline are new
+        let mut current = packages.clone();
+        current.push(package("agpl-lib", "AGPL-3.0"));
+        let new: Vec<&str> = new_violations(&current, &license_checker, &baseline)
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(new, vec!["agpl-lib"]);
+
+        assert!(load_violations(&dir.path().join("missing.json")).is_err());
+    }
+}
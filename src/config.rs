@@ -0,0 +1,380 @@
+use serde::{ Deserialize, Serialize, Serializer };
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use crate::license_checker::{ LicenseException, SeverityRule };
+use crate::license_overrides::LicenseOverride;
+
+/// Config files looked for in the working directory when --config is not given, in order
+pub const DEFAULT_CONFIG_FILES: &[&str] = &[".licenserc.toml", ".licenserc.yaml", ".licenserc.yml"];
+
+/// Scan settings read from a `.licenserc` file. Every key is optional and mirrors the
+/// command-line flag of the same name; flags given on the command line take precedence.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>, // Base config merged under this one, relative to this file
+    pub allowed: Vec<String>,
+    pub exceptions: Vec<LicenseException>,
+    pub license_overrides: Vec<LicenseOverride>, // Written by --interactive
+    pub license_severity: Vec<SeverityRule>, // Checked in order before the allowed list
+    pub fail_on_license: Vec<String>, // SPDX ids that fail the scan whatever else is allowed
+    pub exclude_packages: Vec<String>,
+    pub project_license: Option<String>,
+    pub registry: Option<String>,
+    pub registry_auth_header: Option<String>,
+    pub registry_mirror_map: Vec<String>, // PATTERN=URL entries, in order
+    #[serde(serialize_with = "serialize_confidence")]
+    pub min_confidence: Option<f32>,
+    pub strict_spdx: bool,
+    pub deep_scan: bool,
+    pub recursive: bool,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub registries: BTreeMap<String, RegistryConfig>, // Keyed by registry: npm, github, gitlab, pypi, nuget
+}
+
+// Widening an f32 to f64 prints 0.9 as 0.8999999761581421; go through its shortest decimal form
+fn serialize_confidence<S: Serializer>(confidence: &Option<f32>, serializer: S) -> Result<S::Ok, S::Error> {
+    confidence
+        .map(|value| value.to_string().parse::<f64>().unwrap_or(value as f64))
+        .serialize(serializer)
+}
+
+/// Settings that apply to packages from one registry
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RegistryConfig {
+    pub allowed: Vec<String>, // Replaces the global allowed list for this registry
+}
+
+// `[registry.<name>]` is another spelling of `[registries.<name>]`, while a string `registry`
+// is the npm registry URL; the tables are moved under `registries` before deserializing
+fn move_toml_registry_tables(table: &mut toml::Table) -> Result<(), String> {
+    let Some(toml::Value::Table(per_registry)) = table.get("registry") else {
+        return Ok(());
+    };
+    let per_registry = per_registry.clone();
+    table.remove("registry");
+    match table.entry("registries").or_insert_with(|| toml::Value::Table(toml::Table::new())) {
+        toml::Value::Table(registries) => {
+            registries.extend(per_registry);
+            Ok(())
+        }
+        _ => Err("registries must be a table".to_string()),
+    }
+}
+
+fn move_yaml_registry_tables(mapping: &mut serde_yaml::Mapping) -> Result<(), String> {
+    let Some(serde_yaml::Value::Mapping(per_registry)) = mapping.get("registry") else {
+        return Ok(());
+    };
+    let per_registry = per_registry.clone();
+    mapping.remove("registry");
+    let registries = mapping
+        .entry("registries".into())
+        .or_insert_with(|| serde_yaml::Value::Mapping(serde_yaml::Mapping::new()));
+    match registries.as_mapping_mut() {
+        Some(registries) => {
+            registries.extend(per_registry);
+            Ok(())
+        }
+        None => Err("registries must be a mapping".to_string()),
+    }
+}
+
+/// Syntax of a config file, detected from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(ConfigFormat::Toml),
+            Some("yaml" | "yml") => Ok(ConfigFormat::Yaml),
+            _ => Err(format!("Config file {} must end in .toml, .yaml or .yml", path.display())),
+        }
+    }
+}
+
+impl Config {
+    /// Parse config file content in the given syntax
+    pub fn parse(content: &str, format: ConfigFormat) -> Result<Self, String> {
+        let config: Config = match format {
+            ConfigFormat::Toml => {
+                let mut table: toml::Table = toml::from_str(content).map_err(|e| e.to_string())?;
+                move_toml_registry_tables(&mut table)?;
+                toml::Value::Table(table).try_into().map_err(|e: toml::de::Error| e.to_string())?
+            }
+            ConfigFormat::Yaml => {
+                // An empty YAML document means "no settings", not an error
+                if content.trim().is_empty() {
+                    return Ok(Config::default());
+                }
+                let mut value: serde_yaml::Value = serde_yaml::from_str(content).map_err(|e| e.to_string())?;
+                if let Some(mapping) = value.as_mapping_mut() {
+                    move_yaml_registry_tables(mapping)?;
+                }
+                serde_yaml::from_value(value).map_err(|e| e.to_string())?
+            }
+        };
+
+        if config.min_confidence.is_some_and(|confidence| !(0.0..=1.0).contains(&confidence)) {
+            return Err("min-confidence must be between 0.0 and 1.0".to_string());
+        }
+        Ok(config)
+    }
+
+    /// Read a config file, choosing the syntax from its extension, merged over the configs it extends
+    pub fn load(path: &Path) -> Result<Self, String> {
+        Config::load_extending(path, &mut Vec::new())
+    }
+
+    // `chain` holds the files being loaded, from the one given down to this one
+    fn load_extending(path: &Path, chain: &mut Vec<PathBuf>) -> Result<Self, String> {
+        let format = ConfigFormat::from_path(path)?;
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        if chain.contains(&canonical) {
+            let cycle: Vec<String> = chain
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|file| file.display().to_string())
+                .collect();
+            return Err(format!("Config files extend each other in a cycle: {}", cycle.join(" -> ")));
+        }
+
+        let content = fs
+            ::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        let mut config = Config::parse(&content, format).map_err(|e|
+            format!("Invalid config file {}: {}", path.display(), e)
+        )?;
+
+        if let Some(base) = config.extends.take() {
+            let base_path = path.parent().unwrap_or(Path::new(".")).join(base);
+            chain.push(canonical);
+            let base = Config::load_extending(&base_path, chain)?;
+            chain.pop();
+            config = base.extended_by(config);
+        }
+        Ok(config)
+    }
+
+    /// Apply a config that extends this one: lists are combined and the child's settings win.
+    /// The child's entries come first in lists where the first match wins.
+    fn extended_by(self, child: Config) -> Config {
+        let mut registries = self.registries;
+        for (registry, registry_config) in child.registries {
+            let allowed = registries.remove(&registry).unwrap_or_default().allowed;
+            registries.insert(registry, RegistryConfig { allowed: union(allowed, registry_config.allowed) });
+        }
+
+        Config {
+            extends: None,
+            allowed: union(self.allowed, child.allowed),
+            exceptions: union(self.exceptions, child.exceptions),
+            license_overrides: union(child.license_overrides, self.license_overrides),
+            license_severity: union(child.license_severity, self.license_severity),
+            fail_on_license: union(self.fail_on_license, child.fail_on_license),
+            exclude_packages: union(self.exclude_packages, child.exclude_packages),
+            project_license: child.project_license.or(self.project_license),
+            registry: child.registry.or(self.registry),
+            registry_auth_header: child.registry_auth_header.or(self.registry_auth_header),
+            registry_mirror_map: union(child.registry_mirror_map, self.registry_mirror_map),
+            min_confidence: child.min_confidence.or(self.min_confidence),
+            // A flag can't be switched back off, since an absent key reads as false
+            strict_spdx: self.strict_spdx || child.strict_spdx,
+            deep_scan: self.deep_scan || child.deep_scan,
+            recursive: self.recursive || child.recursive,
+            registries,
+        }
+    }
+
+    /// Find a default config file in a directory
+    pub fn discover(dir: &Path) -> Option<PathBuf> {
+        DEFAULT_CONFIG_FILES.iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    }
+}
+
+// The entries of `first`, then those of `second` not already listed
+fn union<T: PartialEq>(mut first: Vec<T>, second: Vec<T>) -> Vec<T> {
+    for item in second {
+        if !first.contains(&item) {
+            first.push(item);
+        }
+    }
+    first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML_CONFIG: &str =
+        r#"
+allowed = ["MIT", "Apache-*"]
+exclude-packages = ["@acme/*"]
+project-license = "MIT"
+min-confidence = 0.75
+strict-spdx = true
+fail-on-license = ["AGPL-3.0"]
+
+[[exceptions]]
+name = "readline-sync"
+version = "1.4.10"
+license = "GPL-3.0"
+
+[[license-severity]]
+pattern = "LGPL-*"
+severity = "warn"
+
+[registries.github]
+allowed = ["MIT"]
+"#;
+
+    const YAML_CONFIG: &str =
+        r#"
+allowed:
+  - MIT
+  - Apache-*
+exclude-packages: ["@acme/*"]
+project-license: MIT
+min-confidence: 0.75
+strict-spdx: true
+fail-on-license: [AGPL-3.0]
+exceptions:
+  - name: readline-sync
+    version: 1.4.10
+    license: GPL-3.0
+license-severity:
+  - pattern: LGPL-*
+    severity: warn
+registries:
+  github:
+    allowed: [MIT]
+"#;
+
+    #[test]
+    fn test_toml_and_yaml_parse_identically() {
+        let from_toml = Config::parse(TOML_CONFIG, ConfigFormat::Toml).unwrap();
+        let from_yaml = Config::parse(YAML_CONFIG, ConfigFormat::Yaml).unwrap();
+        assert_eq!(from_toml, from_yaml);
+
+        assert_eq!(from_toml.allowed, vec!["MIT", "Apache-*"]);
+        assert_eq!(from_toml.exceptions[0].version.as_deref(), Some("1.4.10"));
+        assert_eq!(from_toml.registries["github"].allowed, vec!["MIT"]);
+        assert_eq!(from_toml.license_severity[0].severity, crate::license_checker::Severity::Warn);
+        assert_eq!(from_toml.fail_on_license, vec!["AGPL-3.0"]);
+        assert!(!from_toml.deep_scan);
+
+        // Each serializes back to the same config in the other syntax
+        let toml_text = toml::to_string(&from_yaml).unwrap();
+        let yaml_text = serde_yaml::to_string(&from_toml).unwrap();
+        assert_eq!(Config::parse(&toml_text, ConfigFormat::Toml).unwrap(), from_toml);
+        assert_eq!(Config::parse(&yaml_text, ConfigFormat::Yaml).unwrap(), from_toml);
+    }
+
+    #[test]
+    fn test_registry_tables_are_another_spelling_of_registries() {
+        let from_toml = Config::parse("[registry.github]\nallowed = [\"MIT\"]", ConfigFormat::Toml).unwrap();
+        let from_yaml = Config::parse("registry:\n  github:\n    allowed: [MIT]", ConfigFormat::Yaml).unwrap();
+        assert_eq!(from_toml, from_yaml);
+        assert_eq!(from_toml.registries["github"].allowed, vec!["MIT"]);
+
+        // A string `registry` is still the npm registry URL
+        let with_url = Config::parse(
+            "registry: https://npm.example.com\nregistries:\n  github:\n    allowed: [MIT]",
+            ConfigFormat::Yaml
+        ).unwrap();
+        assert_eq!(with_url.registry.as_deref(), Some("https://npm.example.com"));
+        assert_eq!(with_url.registries["github"].allowed, vec!["MIT"]);
+    }
+
+    #[test]
+    fn test_unknown_keys_and_extensions_are_rejected() {
+        assert!(Config::parse("alowed = [\"MIT\"]", ConfigFormat::Toml).is_err());
+        assert!(Config::parse("min-confidence: 2", ConfigFormat::Yaml).is_err());
+        assert!(ConfigFormat::from_path(Path::new(".licenserc.json")).is_err());
+        assert_eq!(ConfigFormat::from_path(Path::new("ci/.licenserc.yml")), Ok(ConfigFormat::Yaml));
+        assert_eq!(Config::parse("", ConfigFormat::Yaml), Ok(Config::default()));
+    }
+
+    #[test]
+    fn test_discover_prefers_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Config::discover(dir.path()), None);
+
+        fs::write(dir.path().join(".licenserc.yml"), "deep-scan: true").unwrap();
+        assert_eq!(Config::discover(dir.path()), Some(dir.path().join(".licenserc.yml")));
+
+        fs::write(dir.path().join(".licenserc.toml"), "deep-scan = true").unwrap();
+        assert_eq!(Config::discover(dir.path()), Some(dir.path().join(".licenserc.toml")));
+    }
+
+    #[test]
+    fn test_extends_merges_base_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        let team = dir.path().join("team");
+        fs::create_dir_all(team.join("service")).unwrap();
+        fs::write(
+            dir.path().join("base-licenserc.toml"),
+            r#"
+allowed = ["MIT", "ISC"]
+fail-on-license = ["AGPL-3.0"]
+project-license = "MIT"
+min-confidence = 0.5
+
+[registries.github]
+allowed = ["MIT"]
+"#
+        ).unwrap();
+        fs::write(
+            team.join(".licenserc.yaml"),
+            "extends: ../base-licenserc.toml\nallowed: [Apache-*, MIT]\nmin-confidence: 0.8\ndeep-scan: true\n"
+        ).unwrap();
+        fs::write(
+            team.join("service/.licenserc.toml"),
+            r#"
+extends = "../.licenserc.yaml"
+allowed = ["BSD-3-Clause"]
+project-license = "Apache-2.0"
+
+[registries.github]
+allowed = ["ISC"]
+"#
+        ).unwrap();
+
+        let config = Config::load(&team.join("service/.licenserc.toml")).unwrap();
+        assert_eq!(config.allowed, vec!["MIT", "ISC", "Apache-*", "BSD-3-Clause"]);
+        assert_eq!(config.fail_on_license, vec!["AGPL-3.0"]);
+        assert_eq!(config.project_license.as_deref(), Some("Apache-2.0"));
+        assert_eq!(config.min_confidence, Some(0.8));
+        assert!(config.deep_scan);
+        assert_eq!(config.registries["github"].allowed, vec!["MIT", "ISC"]);
+        assert_eq!(config.extends, None);
+    }
+
+    #[test]
+    fn test_extends_cycles_and_missing_bases_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.toml"), "extends = \"b.toml\"").unwrap();
+        fs::write(dir.path().join("b.toml"), "extends = \"a.toml\"").unwrap();
+        let err = Config::load(&dir.path().join("a.toml")).unwrap_err();
+        assert!(err.starts_with("Config files extend each other in a cycle"), "{}", err);
+
+        fs::write(dir.path().join("self.toml"), "extends = \"./self.toml\"").unwrap();
+        assert!(Config::load(&dir.path().join("self.toml")).is_err());
+
+        fs::write(dir.path().join("orphan.toml"), "extends = \"missing.toml\"").unwrap();
+        let err = Config::load(&dir.path().join("orphan.toml")).unwrap_err();
+        assert!(err.starts_with("Failed to read config file"), "{}", err);
+    }
+}
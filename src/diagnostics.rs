@@ -0,0 +1,150 @@
+//! How the lookups of a scan went, per registry: how many packages resolved and why the others
+//! failed. Dashboards read this from the JSON report's `diagnostics` object.
+
+use reqwest::StatusCode;
+use serde::{ Deserialize, Serialize };
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::package::Package;
+
+/// The outcome of a package's registry lookup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResolutionStatus {
+    #[default]
+    Ok,
+    NotFound,
+    RateLimited,
+    AuthRequired, // The registry answered with a sign-in page
+    NetworkError,
+    Error, // Any other failure, e.g. a response that couldn't be parsed
+}
+
+impl ResolutionStatus {
+    /// The outcome of a package's lookup; a failure recorded without a status counts as Error
+    pub fn of(package: &Package) -> Self {
+        match (package.lookup_failed, package.resolution_status) {
+            (false, _) => ResolutionStatus::Ok,
+            (true, ResolutionStatus::Ok) => ResolutionStatus::Error,
+            (true, status) => status,
+        }
+    }
+
+    /// The failure an unsuccessful HTTP status stands for
+    pub fn from_http_status(status: StatusCode) -> Self {
+        match status.as_u16() {
+            401 | 403 => ResolutionStatus::AuthRequired,
+            404 | 410 => ResolutionStatus::NotFound,
+            429 => ResolutionStatus::RateLimited,
+            _ => ResolutionStatus::Error,
+        }
+    }
+
+    /// The failure behind an error a lookup passed up
+    pub fn of_error(error: &(dyn std::error::Error + 'static)) -> Self {
+        if let Some(error) = error.downcast_ref::<LookupError>() {
+            return error.status;
+        }
+        match error.downcast_ref::<reqwest::Error>() {
+            Some(error) if error.is_decode() => ResolutionStatus::Error,
+            Some(error) => error.status().map_or(ResolutionStatus::NetworkError, Self::from_http_status),
+            None => ResolutionStatus::Error,
+        }
+    }
+}
+
+/// A failed lookup passed up as an error, keeping how it failed
+#[derive(Debug)]
+pub struct LookupError {
+    pub status: ResolutionStatus,
+    pub message: String,
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for LookupError {}
+
+/// Lookup outcomes of the packages of one registry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RegistryDiagnostics {
+    pub ok: usize,
+    pub not_found: usize,
+    pub rate_limited: usize,
+    pub auth_required: usize,
+    pub network_error: usize,
+    pub error: usize,
+}
+
+impl RegistryDiagnostics {
+    fn count(&mut self, status: ResolutionStatus) {
+        let counter = match status {
+            ResolutionStatus::Ok => &mut self.ok,
+            ResolutionStatus::NotFound => &mut self.not_found,
+            ResolutionStatus::RateLimited => &mut self.rate_limited,
+            ResolutionStatus::AuthRequired => &mut self.auth_required,
+            ResolutionStatus::NetworkError => &mut self.network_error,
+            ResolutionStatus::Error => &mut self.error,
+        };
+        *counter += 1;
+    }
+}
+
+/// Lookup outcomes keyed by registry, in registry name order
+pub fn by_registry(packages: &[Package]) -> BTreeMap<String, RegistryDiagnostics> {
+    let mut diagnostics: BTreeMap<String, RegistryDiagnostics> = BTreeMap::new();
+    for package in packages {
+        diagnostics.entry(package.registry.clone()).or_default().count(ResolutionStatus::of(package));
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packages_report_their_recorded_status() {
+        let resolved = Package::new("left-pad".to_string(), "1.3.0".to_string(), String::new(), None);
+        assert_eq!(ResolutionStatus::of(&resolved), ResolutionStatus::Ok);
+
+        // The status is recorded where the lookup failed, whatever its message says
+        let failed = Package::with_error(
+            "left-pad".to_string(),
+            "1.3.0".to_string(),
+            "npm",
+            String::new(),
+            ResolutionStatus::RateLimited,
+            "npm registry returned status code 404: Not Found"
+        );
+        assert_eq!(ResolutionStatus::of(&failed), ResolutionStatus::RateLimited);
+
+        let mut unclassified = resolved.clone();
+        unclassified.lookup_failed = true;
+        assert_eq!(ResolutionStatus::of(&unclassified), ResolutionStatus::Error);
+    }
+
+    #[test]
+    fn test_lookup_errors_are_classified() {
+        assert_eq!(ResolutionStatus::from_http_status(StatusCode::NOT_FOUND), ResolutionStatus::NotFound);
+        assert_eq!(ResolutionStatus::from_http_status(StatusCode::TOO_MANY_REQUESTS), ResolutionStatus::RateLimited);
+        assert_eq!(ResolutionStatus::from_http_status(StatusCode::UNAUTHORIZED), ResolutionStatus::AuthRequired);
+        assert_eq!(ResolutionStatus::from_http_status(StatusCode::SERVICE_UNAVAILABLE), ResolutionStatus::Error);
+
+        let error: Box<dyn std::error::Error> = Box::new(LookupError {
+            status: ResolutionStatus::NotFound,
+            message: "status code 404: Not Found".to_string(),
+        });
+        assert_eq!(ResolutionStatus::of_error(&*error), ResolutionStatus::NotFound);
+        let error: Box<dyn std::error::Error> = "Failed to parse GitHub API response: EOF".into();
+        assert_eq!(ResolutionStatus::of_error(&*error), ResolutionStatus::Error);
+
+        // Nothing listens on port 1, so the request itself fails
+        let error = reqwest::blocking::get("http://127.0.0.1:1/").unwrap_err();
+        assert_eq!(ResolutionStatus::of_error(&error), ResolutionStatus::NetworkError);
+    }
+}
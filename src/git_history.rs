@@ -0,0 +1,342 @@
+use std::collections::{ HashMap, HashSet };
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::process::Command;
+
+use crate::baseline;
+use crate::license_checker::LicenseChecker;
+use crate::package::Package;
+use crate::version_overrides;
+
+/// The content of a file as committed at HEAD, or None when the file isn't in HEAD yet
+pub fn committed_content(path: &Path) -> Result<Option<String>, String> {
+    content_at(path, "HEAD")
+}
+
+/// The content of a file at a git revision, or None when the file didn't exist there
+pub fn content_at(path: &Path, revision: &str) -> Result<Option<String>, String> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("{} is not a file", path.display()))?
+        .to_string_lossy();
+
+    let in_repository = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--git-dir"])
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !in_repository.status.success() {
+        return Err(format!("{} is not inside a git repository", path.display()));
+    }
+
+    // Unborn HEAD is fine (nothing is committed yet), any other unknown revision is not
+    let known_revision = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", revision)])
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !known_revision.status.success() && revision != "HEAD" {
+        return Err(format!("Unknown git revision: {}", revision));
+    }
+
+    // REV:./name resolves relative to the directory git runs in
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["show", &format!("{}:./{}", revision, file_name)])
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+
+    // New files, and repositories without commits, have nothing at the revision
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// The packages each lockfile held at HEAD, keyed like Package::source_lockfiles. `scan` is
+/// given the lockfile and a copy of its committed content, next to the package.json committed
+/// with it. Lockfiles that are new since HEAD are left out.
+pub fn committed_packages<F>(lockfiles: &[PathBuf], scan: F) -> Result<HashMap<String, Vec<Package>>, String>
+    where F: Fn(&Path, &Path) -> Result<Vec<Package>, String>
+{
+    let temp_dir = tempfile::tempdir().map_err(|e| format!("Failed to create a temporary directory: {}", e))?;
+    let mut committed = HashMap::new();
+
+    for (index, lockfile) in lockfiles.iter().enumerate() {
+        let Some(content) = committed_content(lockfile)? else {
+            println!("INFO: {} is new since HEAD; all of its violations are new", lockfile.display());
+            continue;
+        };
+
+        // One directory per lockfile, so projects with the same lockfile name keep their own manifest
+        let project_dir = temp_dir.path().join(index.to_string());
+        let manifest = version_overrides::manifest_path(lockfile).filter(|manifest| manifest != lockfile);
+        if let Some(manifest) = manifest {
+            if let Some(manifest_content) = committed_content(&manifest)? {
+                let committed_manifest = project_dir.join(manifest.file_name().unwrap_or_default());
+                write_previous(&committed_manifest, &manifest_content)?;
+            }
+        }
+
+        let previous_path = project_dir.join(lockfile.file_name().unwrap_or_default());
+        write_previous(&previous_path, &content)?;
+        let packages = scan(lockfile, &previous_path).map_err(|e| {
+            format!("Failed to parse {} at HEAD: {}", lockfile.display(), e)
+        })?;
+        committed.insert(lockfile.display().to_string(), packages);
+    }
+
+    Ok(committed)
+}
+
+/// Write an earlier version of a file, creating its directory
+fn write_previous(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The packages of an earlier version of a lockfile
+fn previous_packages<F>(
+    temp_dir: &Path,
+    lockfile: &Path,
+    content: &str,
+    revision: &str,
+    parse: &F
+) -> Result<Vec<Package>, String>
+    where F: Fn(&Path) -> Result<Vec<Package>, String>
+{
+    // Parsers pick the format from the file name, so keep it
+    let previous_path = temp_dir.join(lockfile.file_name().unwrap_or_default());
+    write_previous(&previous_path, content)?;
+    parse(&previous_path).map_err(|e| format!("Failed to parse {} at {}: {}", lockfile.display(), revision, e))
+}
+
+/// The packages of a lockfile that its committed state at `revision` didn't have yet.
+/// A lockfile that didn't exist at the revision is new as a whole.
+pub fn packages_added_since<F>(
+    lockfile: &Path,
+    revision: &str,
+    packages: Vec<Package>,
+    parse: F
+) -> Result<Vec<Package>, String>
+    where F: Fn(&Path) -> Result<Vec<Package>, String>
+{
+    let Some(content) = content_at(lockfile, revision)? else {
+        println!("INFO: {} is new since {}; all of its packages are new", lockfile.display(), revision);
+        return Ok(packages);
+    };
+
+    let temp_dir = tempfile::tempdir().map_err(|e| format!("Failed to create a temporary directory: {}", e))?;
+    let previous: HashSet<String> = previous_packages(temp_dir.path(), lockfile, &content, revision, &parse)?
+        .iter()
+        .map(baseline::package_id)
+        .collect();
+    Ok(
+        packages
+            .into_iter()
+            .filter(|package| !previous.contains(&baseline::package_id(package)))
+            .collect()
+    )
+}
+
+/// Violations introduced since HEAD: disallowed packages whose name and license weren't already
+/// disallowed in the committed scan of a lockfile they were reached from. Versions aren't
+/// compared, so bumping a package that was already a violation doesn't make a new one.
+pub fn new_violations<'a>(
+    packages: &'a [Package],
+    license_checker: &LicenseChecker,
+    committed: &HashMap<String, Vec<Package>>
+) -> Vec<&'a Package> {
+    let committed_violations: HashMap<&str, HashSet<(&str, &str)>> = committed
+        .iter()
+        .map(|(lockfile, previous)| {
+            let violations = previous
+                .iter()
+                .filter(|p| !license_checker.is_package_allowed(p))
+                .map(|p| (p.name.as_str(), p.license.as_str()))
+                .collect();
+            (lockfile.as_str(), violations)
+        })
+        .collect();
+
+    packages
+        .iter()
+        .filter(|p| !license_checker.is_package_allowed(p))
+        .filter(|p| {
+            p.source_lockfiles.iter().any(|lockfile| {
+                !committed_violations
+                    .get(lockfile.as_str())
+                    .is_some_and(|previous| previous.contains(&(p.name.as_str(), p.license.as_str())))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn package_lock(names: &[&str]) -> String {
+        let entries: Vec<String> = names
+            .iter()
+            .map(|name| {
+                let (name, version) = name.split_once('@').unwrap_or((name, "1.0.0"));
+                format!("\"node_modules/{}\": {{ \"version\": \"{}\" }}", name, version)
+            })
+            .collect();
+        format!("{{ \"lockfileVersion\": 3, \"packages\": {{ {} }} }}", entries.join(", "))
+    }
+
+    fn resolved(name: &str, version: &str, license: &str, lockfile: &Path) -> Package {
+        let mut package = Package::new(name.to_string(), version.to_string(), String::new(), None);
+        package.license = license.to_string();
+        package.source_lockfiles = vec![lockfile.display().to_string()];
+        package
+    }
+
+    /// Stands in for a scan: licenses by name@version (else by name), and the GPL dependency the
+    /// registry reports for every version of old-gpl
+    fn scan(path: &Path, licenses: &[(&str, &str)]) -> Result<Vec<Package>, String> {
+        let mut packages = crate::lockfile_parser::parse_lockfile(path, &Default::default())?;
+        for package in &mut packages {
+            let id = baseline::package_id(package);
+            package.license = licenses
+                .iter()
+                .find(|(key, _)| *key == id || *key == package.name)
+                .map_or("MIT", |(_, license)| license)
+                .to_string();
+        }
+        if packages.iter().any(|p| p.name == "old-gpl") {
+            packages.push(resolved("gpl-dep", "1.0.0", "GPL-3.0", path));
+        }
+        Ok(packages)
+    }
+
+    fn new_names(results: &[Package], committed: &HashMap<String, Vec<Package>>) -> Vec<String> {
+        let license_checker = LicenseChecker::new(vec!["MIT".to_string()]);
+        new_violations(results, &license_checker, committed)
+            .iter()
+            .map(|p| baseline::package_id(p))
+            .collect()
+    }
+
+    #[test]
+    fn test_only_violations_added_since_head_are_new() {
+        let repo = tempfile::tempdir().unwrap();
+        let lockfile = repo.path().join("package-lock.json");
+        let new_lockfile = repo.path().join("tools").join("package-lock.json");
+
+        git(repo.path(), &["init", "-q"]);
+        fs::write(&lockfile, package_lock(&["left-pad", "old-gpl"])).unwrap();
+        git(repo.path(), &["add", "package-lock.json"]);
+        git(repo.path(), &["commit", "-q", "-m", "initial"]);
+
+        // The working tree adds a GPL package and a lockfile HEAD doesn't have
+        fs::write(&lockfile, package_lock(&["left-pad", "old-gpl", "new-gpl"])).unwrap();
+        fs::create_dir_all(new_lockfile.parent().unwrap()).unwrap();
+        fs::write(&new_lockfile, package_lock(&["tool-gpl"])).unwrap();
+
+        assert!(committed_content(&new_lockfile).unwrap().is_none());
+        let licenses = [("old-gpl", "GPL-3.0")];
+        let committed = committed_packages(&[lockfile.clone(), new_lockfile.clone()], |_, path| {
+            scan(path, &licenses)
+        }).unwrap();
+        assert_eq!(committed.len(), 1);
+        let previous = &committed[&lockfile.display().to_string()];
+        assert!(previous.iter().any(|p| p.name == "old-gpl" && p.license == "GPL-3.0"));
+
+        // gpl-dep isn't in the lockfile, but the committed scan reached it too
+        let results = vec![
+            resolved("left-pad", "1.0.0", "MIT", &lockfile),
+            resolved("old-gpl", "1.0.0", "GPL-3.0", &lockfile),
+            resolved("gpl-dep", "1.0.0", "GPL-3.0", &lockfile),
+            resolved("new-gpl", "1.0.0", "GPL-3.0", &lockfile),
+            resolved("tool-gpl", "1.0.0", "GPL-3.0", &new_lockfile)
+        ];
+        assert_eq!(new_names(&results, &committed), ["new-gpl@1.0.0", "tool-gpl@1.0.0"]);
+
+        let outside = tempfile::tempdir().unwrap();
+        assert!(committed_content(&outside.path().join("package-lock.json")).is_err());
+    }
+
+    #[test]
+    fn test_version_bumps_of_violations_are_not_new() {
+        let repo = tempfile::tempdir().unwrap();
+        let lockfile = repo.path().join("package-lock.json");
+
+        git(repo.path(), &["init", "-q"]);
+        fs::write(&lockfile, package_lock(&["left-pad@1.0.0", "old-gpl@1.0.0"])).unwrap();
+        git(repo.path(), &["add", "package-lock.json"]);
+        git(repo.path(), &["commit", "-q", "-m", "initial"]);
+        fs::write(&lockfile, package_lock(&["left-pad@2.0.0", "old-gpl@2.0.0"])).unwrap();
+
+        // old-gpl stays GPL in its new version; left-pad changes its license to GPL with the bump
+        let licenses = [("old-gpl", "GPL-3.0"), ("left-pad@2.0.0", "GPL-3.0")];
+        let committed = committed_packages(std::slice::from_ref(&lockfile), |_, path| scan(path, &licenses)).unwrap();
+        let results = scan(&lockfile, &licenses)
+            .unwrap()
+            .into_iter()
+            .map(|mut package| {
+                package.source_lockfiles = vec![lockfile.display().to_string()];
+                package
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(new_names(&results, &committed), ["left-pad@2.0.0"]);
+    }
+
+    #[test]
+    fn test_packages_added_since_a_revision() {
+        let repo = tempfile::tempdir().unwrap();
+        let lockfile = repo.path().join("package-lock.json");
+        let parse = |path: &Path| crate::lockfile_parser::parse_lockfile(path, &Default::default());
+
+        git(repo.path(), &["init", "-q"]);
+        fs::write(&lockfile, package_lock(&["left-pad", "lodash"])).unwrap();
+        git(repo.path(), &["add", "package-lock.json"]);
+        git(repo.path(), &["commit", "-q", "-m", "initial"]);
+        git(repo.path(), &["tag", "reviewed"]);
+
+        fs::write(&lockfile, package_lock(&["left-pad", "lodash", "is-odd"])).unwrap();
+        git(repo.path(), &["commit", "-q", "-am", "add is-odd"]);
+
+        let added = packages_added_since(&lockfile, "reviewed", parse(&lockfile).unwrap(), parse).unwrap();
+        let names: Vec<&str> = added
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["is-odd"]);
+
+        // Nothing was added since the latest commit
+        assert!(packages_added_since(&lockfile, "HEAD", parse(&lockfile).unwrap(), parse).unwrap().is_empty());
+
+        let err = packages_added_since(&lockfile, "no-such-ref", Vec::new(), parse).unwrap_err();
+        assert!(err.contains("Unknown git revision"), "{}", err);
+
+        // Outside of git there is nothing to compare with
+        let outside = tempfile::tempdir().unwrap();
+        let untracked = outside.path().join("package-lock.json");
+        fs::write(&untracked, package_lock(&["left-pad"])).unwrap();
+        assert!(packages_added_since(&untracked, "HEAD", Vec::new(), parse).is_err());
+    }
+}
@@ -1,15 +1,28 @@
-use reqwest::blocking::Client;
 use serde_json::Value;
 use std::error::Error;
 
-use crate::package::Package;
+use crate::diagnostics::ResolutionStatus;
+use crate::package::{ is_unknown_license, LicenseSource, Package, UNKNOWN_LICENSE };
 use crate::utils;
 
+/// Look up a GitHub-sourced package. Every route to a GitHub package ends here, so the npm
+/// registry is probed once and the GitHub API is only asked when npm doesn't have it.
 pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
-    let client = Client::new();
+    let client = crate::http_retry::metadata_client();
+    lookup(
+        package,
+        |package| crate::npm_api::try_npm_registry(&package.name, &package.version, client),
+        get_repository_info
+    )
+}
 
+fn lookup<N, G>(package: &Package, probe_npm: N, from_github: G) -> Result<Package, Box<dyn Error>>
+    where
+        N: FnOnce(&Package) -> Result<Option<Package>, Box<dyn Error>>,
+        G: FnOnce(&Package) -> Result<Package, Box<dyn Error>>
+{
     // First try to find the package on npm registry, since many GitHub packages are published there
-    match crate::npm_api::try_npm_registry(&package.name, &package.version, &client) {
+    match probe_npm(package) {
         Ok(Some(npm_package)) => {
             eprintln!("INFO: GitHub package {} found in npm registry", package.name);
             return Ok(npm_package);
@@ -26,25 +39,26 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
         }
     }
 
-    // If not found in npm, continue with GitHub API
+    from_github(package)
+}
+
+/// Read the license from the package's GitHub repository, without checking npm
+fn get_repository_info(package: &Package) -> Result<Package, Box<dyn Error>> {
+    let client = crate::http_retry::metadata_client();
 
     // Determine the GitHub repository URL from package info
-    let repo_url = if package.resolution.contains("github:") {
+    let archive_details = utils
+        ::archive_url_from_resolution(&package.resolution)
+        .and_then(|archive_url| utils::github_archive_details(&archive_url));
+    let repo_url = if let Some((owner, repo, ref_or_commit)) = archive_details {
+        // Yarn's __archiveUrl form, e.g. a codeload.github.com tarball
+        format!("https://github.com/{}/{}#{}", owner, repo, ref_or_commit)
+    } else if package.resolution.contains("github:") {
         // Extract GitHub repo from resolution
         extract_github_url_from_resolution(&package.resolution)?
     } else if package.name.starts_with("github:") {
         // Extract GitHub repo from name
         format!("https://github.com/{}", package.name.trim_start_matches("github:"))
-    } else if
-        package.resolution.contains("__archiveUrl=") &&
-        package.resolution.contains("github.com")
-    {
-        // Try to extract from archive URL
-        let start_idx = package.resolution.find("github.com").unwrap_or(0);
-        let end_idx = package.resolution[start_idx..]
-            .find(".git")
-            .unwrap_or(package.resolution.len() - start_idx);
-        package.resolution[start_idx..start_idx + end_idx].to_string()
     } else {
         return Err(
             format!("Could not determine GitHub repository from package: {}", package.name).into()
@@ -66,6 +80,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
                     package.version.clone(),
                     "github",
                     package.resolution.clone(),
+                    ResolutionStatus::Error,
                     &error_msg
                 )
             );
@@ -87,7 +102,9 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
     );
 
     // Try to get the package info
-    let response = match client.get(&api_url).header("User-Agent", "Dependency-Scanner").send() {
+    // Revalidated with the stored ETag; GitHub doesn't count 304 answers against the rate limit
+    let request = client.get(&api_url).header("User-Agent", "Dependency-Scanner");
+    let response = match crate::http_cache::send(&api_url, request) {
         Ok(resp) => resp,
         Err(e) => {
             let error_msg = format!("GitHub API network error: {}", e);
@@ -100,12 +117,34 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
                     package.version.clone(),
                     "github",
                     repo_url.clone(),
+                    ResolutionStatus::NetworkError,
                     &error_msg
                 )
             );
         }
     };
 
+    // Private repositories can answer with a sign-in page instead of the API's JSON
+    if response.is_html() {
+        let error_msg = format!(
+            "Authentication required: GitHub answered {} with an HTML page{} instead of JSON; the repository may be private",
+            api_url,
+            if response.redirected() { " after a redirect" } else { "" }
+        );
+        eprintln!("INFO: {}", error_msg);
+
+        return Ok(
+            Package::with_error(
+                package.name.clone(),
+                package.version.clone(),
+                "github",
+                repo_url.clone(),
+                ResolutionStatus::AuthRequired,
+                &error_msg
+            )
+        );
+    }
+
     if !response.status().is_success() {
         // Log status code issues
         let status_code = response.status().as_u16();
@@ -121,6 +160,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
                 package.version.clone(),
                 "github",
                 repo_url.clone(),
+                response.failure_status(),
                 &error_msg
             )
         );
@@ -139,6 +179,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
                     package.version.clone(),
                     "github",
                     repo_url.clone(),
+                    ResolutionStatus::Error,
                     &error_msg
                 )
             );
@@ -158,14 +199,15 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
                     package.version.clone(),
                     "github",
                     repo_url.clone(),
-                    &error_msg.to_string()
+                    ResolutionStatus::Error,
+                    error_msg
                 )
             );
         }
     };
 
     // Try to decode base64 content
-    let decoded_content = match base64::decode(&content_str.replace("\n", "")) {
+    let decoded_content = match base64::decode(content_str.replace("\n", "")) {
         Ok(bytes) => bytes,
         Err(e) => {
             let error_msg = format!("Failed to decode base64 content: {}", e);
@@ -177,6 +219,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
                     package.version.clone(),
                     "github",
                     repo_url.clone(),
+                    ResolutionStatus::Error,
                     &error_msg
                 )
             );
@@ -196,6 +239,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
                     package.version.clone(),
                     "github",
                     repo_url.clone(),
+                    ResolutionStatus::Error,
                     &error_msg
                 )
             );
@@ -205,24 +249,22 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
     // Extract license information
     let license_field = package_json["license"].as_str();
     let license = if let Some(lic) = license_field {
-        lic.to_string()
+        crate::license_detection::normalize_license_id(lic)
     } else {
-        "UNKNOWN".to_string()
+        UNKNOWN_LICENSE.to_string()
     };
 
-    let debug_info = if license == "UNKNOWN" {
-        Some(
-            format!(
-                "No license field in package.json; manual check needed at {}",
-                license_url.clone().unwrap_or_else(|| repo_url.clone())
-            )
-        )
+    let debug_info = if is_unknown_license(&license) {
+        Some(match &license_url {
+            Some(url) => format!("No license field in package.json; manual check needed at {}", url),
+            None => format!("No license field in package.json; no license file found in {}", repo_url),
+        })
     } else {
         None
     };
 
     // Get standard license URL if available, otherwise use repo license URL
-    let final_license_url = crate::license_urls::get_license_url(&license).or_else(|| license_url);
+    let final_license_url = crate::license_urls::get_license_url(&license).or(license_url);
 
     // Extract dependencies
     let mut dependencies = Vec::new();
@@ -230,51 +272,14 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
     // Process regular dependencies
     if let Some(deps) = package_json["dependencies"].as_object() {
         for (name, version_value) in deps {
-            let version_str = version_value.as_str().unwrap_or("").to_string();
-
-            // Create a package entry for each dependency
-            let dep = Package::new(
-                name.clone(),
-                version_str.clone(),
-                if version_str.starts_with("github:") {
-                    format!("https://github.com/{}", version_str.trim_start_matches("github:"))
-                } else {
-                    format!(
-                        "https://registry.npmjs.org/{}/-/{}-{}.tgz",
-                        name,
-                        name.replace('@', "").replace('/', "-"),
-                        version_str.trim_start_matches("^").trim_start_matches("~")
-                    )
-                },
-                None
-            );
-
-            dependencies.push(dep);
+            dependencies.push(manifest_dependency(name, version_value.as_str().unwrap_or("")));
         }
     }
 
     // Process dev dependencies (optional)
     if let Some(dev_deps) = package_json["devDependencies"].as_object() {
         for (name, version_value) in dev_deps {
-            let version_str = version_value.as_str().unwrap_or("").to_string();
-
-            let dep = Package::new(
-                name.clone(),
-                version_str.clone(),
-                if version_str.starts_with("github:") {
-                    format!("https://github.com/{}", version_str.trim_start_matches("github:"))
-                } else {
-                    format!(
-                        "https://registry.npmjs.org/{}/-/{}-{}.tgz",
-                        name,
-                        name.replace('@', "").replace('/', "-"),
-                        version_str.trim_start_matches("^").trim_start_matches("~")
-                    )
-                },
-                None
-            );
-
-            dependencies.push(dep);
+            dependencies.push(manifest_dependency(name, version_value.as_str().unwrap_or("")));
         }
     }
 
@@ -290,18 +295,22 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
     result_package.name = package.name.clone(); // Keep original package name
     result_package.registry = format!("github:{}/{}", owner, repo); // Store GitHub info in registry field
     result_package.license = license.clone(); // FIX: Clone license to avoid move
+    result_package.license_source = (!is_unknown_license(&license)).then_some(LicenseSource::Github);
+    result_package.confidence = crate::license_detection::declared_confidence(&license);
     result_package.license_expiration = None;
     result_package.url = repo_url;
     result_package.license_url = final_license_url.clone();
     result_package.debug_info = debug_info.clone(); // FIX: Clone if needed
 
     // When license is unknown but we have a license URL, try to download and detect license
-    if license == "UNKNOWN" && final_license_url.is_some() {
-        match crate::npm_api::try_detect_license_from_url(final_license_url.as_ref().unwrap()) {
-            Ok(Some(detected_license)) => {
+    if let Some(detect_url) = final_license_url.as_ref().filter(|_| is_unknown_license(&license)) {
+        match crate::npm_api::try_detect_license_from_url(detect_url) {
+            Ok(Some((detected_license, confidence))) => {
                 result_package.license = detected_license;
+                result_package.license_source = Some(LicenseSource::LicenseText);
+                result_package.confidence = confidence;
                 result_package.debug_info = Some(
-                    format!("License detected from URL: {}", final_license_url.as_ref().unwrap())
+                    format!("License detected from URL: {}", detect_url)
                 );
             }
             Ok(None) => {
@@ -310,7 +319,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
                     format!(
                         "{}; No license detected from URL: {}",
                         result_package.debug_info.unwrap_or_else(|| "Unknown license".to_string()),
-                        final_license_url.as_ref().unwrap()
+                        detect_url
                     )
                 );
             }
@@ -320,7 +329,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
                     format!(
                         "{}; Failed to download license from URL: {} ({})",
                         result_package.debug_info.unwrap_or_else(|| "Unknown license".to_string()),
-                        final_license_url.as_ref().unwrap(),
+                        detect_url,
                         e
                     )
                 );
@@ -328,46 +337,95 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
         }
     }
 
+    // "SEE LICENSE IN <file>" names a file in this repository
+    if let Some(file) = crate::license_detection::license_file_reference(&license) {
+        let file_url = utils::raw_github_file_url(&result_package.url, &ref_or_commit, file);
+        crate::npm_api::detect_license_from_referenced_file(&mut result_package, &file_url);
+    }
+
     result_package.dependencies = dependencies;
     result_package.processed = true;
 
     Ok(result_package)
 }
 
-fn extract_github_details(url: &str) -> Result<(String, String, String), Box<dyn Error>> {
+/// Extract the owner, repository and ref from the various GitHub reference formats:
+/// `https://github.com/owner/repo[/tree/ref][.git][#ref]`, `git+https://...`, `git://...`,
+/// `git@github.com:owner/repo.git` and the `github:owner/repo[#ref]` shorthand
+/// A dependency read from a package.json, queued with its range operator dropped
+pub fn manifest_dependency(name: &str, spec: &str) -> Package {
+    Package::new(name.to_string(), strip_range_operator(spec).to_string(), dependency_resolution(name, spec), None)
+}
+
+/// Where a manifest dependency is looked up: its GitHub or GitLab repository, or else the
+/// registry tarball of the version the range starts at
+pub fn dependency_resolution(name: &str, spec: &str) -> String {
+    if let Some(repo_path) = spec.strip_prefix("github:") {
+        format!("https://github.com/{}", repo_path)
+    } else if spec.starts_with("gitlab:") || spec.contains("://") {
+        spec.to_string()
+    } else {
+        npm_tarball_url(name, strip_range_operator(spec))
+    }
+}
+
+/// The npm registry tarball of a package version
+pub fn npm_tarball_url(name: &str, version: &str) -> String {
+    format!("https://registry.npmjs.org/{}/-/{}-{}.tgz", name, name.replace('@', "").replace('/', "-"), version)
+}
+
+/// A version range without its leading `^` / `~` operator, e.g. `1.2.0` for `^1.2.0`
+pub fn strip_range_operator(range: &str) -> &str {
+    range.trim_start_matches('^').trim_start_matches('~')
+}
+
+pub fn extract_github_details(url: &str) -> Result<(String, String, String), Box<dyn Error>> {
+    // Normalize the transport variations to a plain https URL
+    let normalized = url.trim().trim_start_matches("git+");
+    let normalized = if
+        let Some(rest) = normalized
+            .strip_prefix("git@github.com:")
+            .or_else(|| normalized.strip_prefix("ssh://git@github.com/"))
+    {
+        format!("https://github.com/{}", rest)
+    } else {
+        normalized.replace("git://github.com/", "https://github.com/").replace(
+            "http://github.com/",
+            "https://github.com/"
+        )
+    };
+
     // Handle different GitHub URL formats
-    if url.starts_with("https://github.com/") {
-        // Format: https://github.com/owner/repo/...
-        let parts: Vec<&str> = url.split('/').collect();
-        if parts.len() >= 5 {
+    if normalized.starts_with("https://github.com/") {
+        // Format: https://github.com/owner/repo/...#ref
+        let (path, fragment) = split_ref_fragment(&normalized);
+        let parts: Vec<&str> = path.split('/').collect();
+        if parts.len() >= 5 && !parts[3].is_empty() && !parts[4].is_empty() {
             let owner = parts[3].to_string();
-            let repo = parts[4].to_string();
+            let repo = parts[4].trim_end_matches(".git").to_string();
 
             // Determine ref or commit
-            let ref_or_commit = if parts.len() > 6 && (parts[5] == "tree" || parts[5] == "commit") {
-                parts[6].to_string()
+            let ref_or_commit = if let Some(fragment) = fragment {
+                fragment
+            } else if parts.len() > 6 && (parts[5] == "tree" || parts[5] == "commit") {
+                parts[6..].join("/")
             } else {
                 "main".to_string() // Default to main if not specified
             };
 
             return Ok((owner, repo, ref_or_commit));
         }
-    } else if url.starts_with("github:") {
+    } else if normalized.starts_with("github:") {
         // Format: github:owner/repo#ref
-        let url = url.trim_start_matches("github:");
-        let parts: Vec<&str> = url.split('#').collect();
+        let (path, fragment) = split_ref_fragment(normalized.trim_start_matches("github:"));
 
-        let repo_parts: Vec<&str> = parts[0].split('/').collect();
-        if repo_parts.len() >= 2 {
+        let repo_parts: Vec<&str> = path.split('/').collect();
+        if repo_parts.len() >= 2 && !repo_parts[0].is_empty() && !repo_parts[1].is_empty() {
             let owner = repo_parts[0].to_string();
-            let repo = repo_parts[1].to_string();
+            let repo = repo_parts[1].trim_end_matches(".git").to_string();
 
             // Get ref if specified, otherwise use main
-            let ref_or_commit = if parts.len() > 1 {
-                parts[1].to_string()
-            } else {
-                "main".to_string()
-            };
+            let ref_or_commit = fragment.unwrap_or_else(|| "main".to_string());
 
             return Ok((owner, repo, ref_or_commit));
         }
@@ -376,6 +434,81 @@ fn extract_github_details(url: &str) -> Result<(String, String, String), Box<dyn
     Err(format!("Could not extract GitHub details from URL: {}", url).into())
 }
 
+/// Owner, repository and ref of a package sourced from GitHub, from its resolution, its
+/// repository URL or a `github:` name; None for packages that don't come from GitHub
+pub fn repository_location(package: &Package) -> Option<(String, String, String)> {
+    if
+        let Some(details) = utils
+            ::archive_url_from_resolution(&package.resolution)
+            .and_then(|archive_url| utils::github_archive_details(&archive_url))
+    {
+        return Some(details);
+    }
+
+    let mut references = Vec::new();
+    if package.resolution.contains("github:") || package.resolution.contains("github.com") {
+        references.push(package.resolution.as_str());
+    }
+    if package.registry.starts_with("github") && package.url.contains("github.com") {
+        references.push(package.url.as_str());
+    }
+    if package.name.starts_with("github:") {
+        references.push(package.name.as_str());
+    }
+
+    references.into_iter().find_map(|reference| {
+        // Resolutions like "name@github:owner/repo#ref" carry the reference after the name
+        let reference = match reference.find("github:") {
+            Some(start) if !reference.contains("github.com") => &reference[start..],
+            _ => reference,
+        };
+        extract_github_details(reference).ok()
+    })
+}
+
+/// The license GitHub detects from a repository's LICENSE file, from its /license endpoint;
+/// None when the repository has no license file or GitHub doesn't recognize it
+pub fn repository_license(owner: &str, repo: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let client = crate::http_retry::metadata_client();
+    let api_url = format!("https://api.github.com/repos/{}/{}/license", owner, repo);
+    let request = client.get(&api_url).header("User-Agent", "Dependency-Scanner");
+    let response = crate::http_cache::send(&api_url, request)?;
+
+    if response.status().as_u16() == 404 {
+        return Ok(None);
+    }
+    if response.is_html() || !response.status().is_success() {
+        return Err(format!("GitHub API returned status code {} for {}", response.status().as_u16(), api_url).into());
+    }
+    Ok(detected_license(&response.json()?))
+}
+
+// GitHub reports licenses it can't identify as NOASSERTION
+fn detected_license(response: &Value) -> Option<String> {
+    response["license"]["spdx_id"]
+        .as_str()
+        .filter(|spdx_id| !spdx_id.is_empty() && *spdx_id != "NOASSERTION")
+        .map(str::to_string)
+}
+
+/// Split a `#ref` fragment off a git reference, unwrapping yarn's `#commit=...` form
+pub fn split_ref_fragment(reference: &str) -> (&str, Option<String>) {
+    match reference.split_once('#') {
+        Some((path, fragment)) => {
+            let fragment = fragment
+                .trim_start_matches("commit=")
+                .trim_start_matches("tag=")
+                .trim_start_matches("head=");
+            if fragment.is_empty() {
+                (path, None)
+            } else {
+                (path, Some(fragment.to_string()))
+            }
+        }
+        None => (reference, None),
+    }
+}
+
 fn extract_github_url_from_resolution(resolution: &str) -> Result<String, Box<dyn Error>> {
     if resolution.contains("github:") {
         if let Some(github_part) = resolution.split("github:").nth(1) {
@@ -387,3 +520,93 @@ fn extract_github_url_from_resolution(resolution: &str) -> Result<String, Box<dy
 
     Err(format!("Could not extract GitHub URL from resolution: {}", resolution).into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(url: &str) -> (String, String, String) {
+        extract_github_details(url).unwrap()
+    }
+
+    #[test]
+    fn test_extract_github_details_formats() {
+        let expected = ("owner".to_string(), "repo".to_string(), "abc123".to_string());
+        assert_eq!(details("github:owner/repo#abc123"), expected);
+        assert_eq!(details("https://github.com/owner/repo#abc123"), expected);
+        assert_eq!(details("https://github.com/owner/repo.git#commit=abc123"), expected);
+        assert_eq!(details("git+https://github.com/owner/repo.git#abc123"), expected);
+        assert_eq!(details("git://github.com/owner/repo.git#abc123"), expected);
+        assert_eq!(details("git@github.com:owner/repo.git#abc123"), expected);
+        assert_eq!(details("https://github.com/owner/repo/tree/abc123"), expected);
+    }
+
+    #[test]
+    fn test_extract_github_details_default_ref() {
+        assert_eq!(details("github:owner/repo").2, "main");
+        assert_eq!(details("https://github.com/owner/repo.git").2, "main");
+    }
+
+    #[test]
+    fn test_npm_is_probed_once_before_the_github_api() {
+        use std::cell::Cell;
+
+        let package = Package::new(
+            "widget".to_string(),
+            "1.0.0".to_string(),
+            "widget@github:acme/widget#abc123".to_string(),
+            None
+        );
+        let (probes, github_calls) = (Cell::new(0), Cell::new(0));
+        let probe_npm = |_: &Package| -> Result<Option<Package>, Box<dyn Error>> {
+            probes.set(probes.get() + 1);
+            Ok(None)
+        };
+        let from_github = |package: &Package| -> Result<Package, Box<dyn Error>> {
+            github_calls.set(github_calls.get() + 1);
+            let mut resolved = package.clone();
+            resolved.license = "MIT".to_string();
+            Ok(resolved)
+        };
+
+        // Not published to npm: one probe, then the repository
+        let resolved = lookup(&package, probe_npm, from_github).unwrap();
+        assert_eq!(resolved.license, "MIT");
+        assert_eq!((probes.get(), github_calls.get()), (1, 1));
+
+        // Published to npm: the GitHub API isn't asked at all
+        let published = lookup(&package, |package| Ok(Some(package.clone())), from_github).unwrap();
+        assert_eq!(published.name, "widget");
+        assert_eq!(github_calls.get(), 1);
+
+        // A failing probe isn't retried; the repository answers instead
+        let after_error = lookup(&package, |_| Err("connection refused".into()), from_github).unwrap();
+        assert_eq!(after_error.license, "MIT");
+        assert_eq!(github_calls.get(), 2);
+    }
+
+    #[test]
+    fn test_detected_license_skips_unrecognized() {
+        let response = serde_json::json!({ "license": { "key": "mit", "spdx_id": "MIT" } });
+        assert_eq!(detected_license(&response).as_deref(), Some("MIT"));
+        let response = serde_json::json!({ "license": { "key": "other", "spdx_id": "NOASSERTION" } });
+        assert_eq!(detected_license(&response), None);
+        assert_eq!(detected_license(&serde_json::json!({})), None);
+    }
+
+    #[test]
+    fn test_extract_github_details_rejects_incomplete() {
+        assert!(extract_github_details("github:owner").is_err());
+        assert!(extract_github_details("https://gitlab.com/owner/repo").is_err());
+    }
+
+    #[test]
+    fn test_manifest_dependencies_are_queued_without_range_operators() {
+        let dep = manifest_dependency("@scope/widget", "^1.2.0");
+        assert_eq!(dep.version, "1.2.0");
+        assert_eq!(dep.resolution, "https://registry.npmjs.org/@scope/widget/-/scope-widget-1.2.0.tgz");
+        assert_eq!(manifest_dependency("left-pad", "~1.3.0").version, "1.3.0");
+        assert_eq!(dependency_resolution("widget", "github:owner/widget"), "https://github.com/owner/widget");
+        assert_eq!(dependency_resolution("ui-kit", "gitlab:acme/ui-kit"), "gitlab:acme/ui-kit");
+    }
+}
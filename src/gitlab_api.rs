@@ -0,0 +1,406 @@
+//! Packages installed from GitLab repositories: `git+https://gitlab.com/group/repo.git#ref`
+//! resolutions and the `gitlab:group/repo` shorthand. The license comes from the repository's
+//! package.json, read through the GitLab API, or else from its LICENSE file. Self-hosted
+//! instances are named with --gitlab-host; GITLAB_TOKEN is sent to that host for private projects.
+
+use once_cell::sync::OnceCell;
+use reqwest::blocking::Client;
+use serde_json::Value;
+use std::error::Error;
+use urlencoding::encode;
+
+use crate::diagnostics::{ LookupError, ResolutionStatus };
+use crate::package::{ is_unknown_license, LicenseSource, Package, UNKNOWN_LICENSE };
+
+const DEFAULT_HOST: &str = "gitlab.com";
+
+/// Environment variable holding a personal or project access token
+const TOKEN_VAR: &str = "GITLAB_TOKEN";
+
+/// Ref of a resolution that doesn't name one; GitLab reads it as the default branch
+const DEFAULT_REF: &str = "HEAD";
+
+/// Files looked for when package.json doesn't declare a license, in order
+const LICENSE_FILES: &[&str] = &["LICENSE", "LICENSE.md", "LICENSE.txt", "COPYING"];
+
+static GITLAB_HOST: OnceCell<String> = OnceCell::new();
+
+/// Configure the self-hosted GitLab instance, e.g. `gitlab.example.com`, whose repositories are
+/// looked up alongside gitlab.com's. GITLAB_TOKEN is only sent to this host once it is set.
+pub fn configure_host(host: &str) -> Result<(), String> {
+    let host = normalize_host(host)?;
+    GITLAB_HOST.set(host).map_err(|_| "GitLab host is already configured".to_string())
+}
+
+fn normalize_host(host: &str) -> Result<String, String> {
+    let normalized = host
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/');
+    if normalized.is_empty() || normalized.contains(['/', '@', ' ']) {
+        return Err(format!("Invalid GitLab host \"{}\"; expected a host name like gitlab.example.com", host));
+    }
+    Ok(normalized.to_ascii_lowercase())
+}
+
+/// The host GITLAB_TOKEN belongs to
+fn token_host() -> &'static str {
+    GITLAB_HOST.get().map(String::as_str).unwrap_or(DEFAULT_HOST)
+}
+
+/// A project on a GitLab instance and the ref to read it at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitlabProject {
+    pub host: String,
+    pub path: String, // Namespace and name, e.g. group/subgroup/repo
+    pub git_ref: String,
+}
+
+impl GitlabProject {
+    pub fn web_url(&self) -> String {
+        format!("https://{}/{}", self.host, self.path)
+    }
+
+    fn file_api_url(&self, file: &str) -> String {
+        format!(
+            "https://{}/api/v4/projects/{}/repository/files/{}?ref={}",
+            self.host,
+            encode(&self.path),
+            encode(file),
+            encode(&self.git_ref)
+        )
+    }
+}
+
+/// The GitLab project a package is installed from, from its resolution or a `gitlab:` name;
+/// None for packages that don't come from gitlab.com or the configured host
+pub fn repository_location(package: &Package) -> Option<GitlabProject> {
+    [package.resolution.as_str(), package.name.as_str()].into_iter().find_map(project_for_url)
+}
+
+/// The GitLab project a reference points to, on gitlab.com or the configured host
+pub fn project_for_url(reference: &str) -> Option<GitlabProject> {
+    std::iter
+        ::once(DEFAULT_HOST)
+        .chain(GITLAB_HOST.get().map(String::as_str))
+        .find_map(|host| extract_gitlab_details(reference, host))
+}
+
+/// Extract the project path and ref from a reference to a repository on `host`:
+/// `https://host/group/repo[.git][/-/tree/ref][#ref]`, `git+https://...`, `git+ssh://git@host/...`,
+/// `git@host:group/repo.git`, and for gitlab.com the `gitlab:group/repo[#ref]` shorthand.
+/// Yarn's `name@` prefix and `#commit=` fragments are accepted.
+pub fn extract_gitlab_details(reference: &str, host: &str) -> Option<GitlabProject> {
+    let rest = match reference.find("gitlab:") {
+        Some(start) if host == DEFAULT_HOST && !reference.contains("://") => &reference[start + "gitlab:".len()..],
+        _ => after_host(reference, host)?,
+    };
+
+    let (path, fragment) = crate::github_api::split_ref_fragment(rest);
+    // "/-/" separates the project from a page of it: /-/tree/ref, /-/archive/ref/repo-ref.tar.gz
+    let (path, page) = match path.split_once("/-/") {
+        Some((path, page)) => (path, Some(page)),
+        None => (path, None),
+    };
+    let path = path.trim_matches('/').trim_end_matches(".git");
+
+    // API URLs, e.g. tarballs from a GitLab package registry, aren't repositories
+    let segments: Vec<&str> = path.split('/').collect();
+    if segments.len() < 2 || segments.iter().any(|segment| segment.is_empty()) || segments[0] == "api" {
+        return None;
+    }
+
+    let page_ref = page.and_then(|page| {
+        let mut parts = page.split('/');
+        match parts.next() {
+            Some("tree" | "commit" | "archive") => parts.next().filter(|part| !part.is_empty()),
+            _ => None,
+        }
+    });
+    let git_ref = fragment.or_else(|| page_ref.map(str::to_string)).unwrap_or_else(|| DEFAULT_REF.to_string());
+
+    Some(GitlabProject { host: host.to_string(), path: path.to_string(), git_ref })
+}
+
+/// The part of a reference after `host/` or `host:`, when the host isn't part of a longer name
+fn after_host<'a>(reference: &'a str, host: &str) -> Option<&'a str> {
+    let start = reference.find(host)?;
+    let boundary = start == 0 || reference[..start].ends_with(['/', '@']);
+    let rest = &reference[start + host.len()..];
+    match rest.chars().next() {
+        Some('/' | ':') if boundary => Some(&rest[1..]),
+        _ => None,
+    }
+}
+
+/// Look up a GitLab-sourced package
+pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
+    let project = repository_location(package).ok_or_else(|| {
+        format!("Could not determine GitLab project from package: {}", package.name)
+    })?;
+    let client = crate::http_retry::metadata_client();
+    let repo_url = project.web_url();
+
+    let failed = |status: ResolutionStatus, error_msg: String| {
+        eprintln!("INFO: {}", error_msg);
+        Ok(
+            Package::with_error(
+                package.name.clone(),
+                package.version.clone(),
+                "gitlab",
+                repo_url.clone(),
+                status,
+                &error_msg
+            )
+        )
+    };
+
+    let package_json: Option<Value> = match fetch_file(client, &project, "package.json") {
+        Ok(Some(content)) => {
+            match serde_json::from_str(&content) {
+                Ok(json) => Some(json),
+                Err(e) => {
+                    return failed(
+                        ResolutionStatus::Error,
+                        format!("Failed to parse package.json of {}: {}", repo_url, e)
+                    );
+                }
+            }
+        }
+        Ok(None) => None,
+        Err(e) => {
+            return failed(ResolutionStatus::of_error(e.as_ref()), e.to_string());
+        }
+    };
+
+    let mut result_package = Package::new(
+        package.name.clone(),
+        package.version.clone(),
+        package.resolution.clone(),
+        package.checksum.clone()
+    );
+    result_package.registry = "gitlab".to_string();
+    result_package.display_name = format!("{}@{}", package.name, package.version);
+    result_package.url = repo_url.clone();
+    result_package.license = package_json
+        .as_ref()
+        .and_then(|json| json["license"].as_str())
+        .map(crate::license_detection::normalize_license_id)
+        .unwrap_or_else(|| UNKNOWN_LICENSE.to_string());
+
+    if is_unknown_license(&result_package.license) {
+        detect_license_file(client, &project, &mut result_package);
+    } else {
+        result_package.license_source = Some(LicenseSource::Gitlab);
+        result_package.confidence = crate::license_detection::declared_confidence(&result_package.license);
+        result_package.license_url = crate::license_urls::get_license_url(&result_package.license);
+    }
+
+    if result_package.is_unknown() && result_package.debug_info.is_none() {
+        let private_hint = if std::env::var_os(TOKEN_VAR).is_none() {
+            format!("; if the project is private, set {}", TOKEN_VAR)
+        } else {
+            String::new()
+        };
+        result_package.debug_info = Some(match &package_json {
+            Some(_) => format!("No license field in package.json and no license file found in {}", repo_url),
+            None => format!("No package.json or license file found in {}{}", repo_url, private_hint),
+        });
+    }
+
+    if let Some(package_json) = &package_json {
+        result_package.dependencies = manifest_dependencies(package_json);
+    }
+    result_package.processed = true;
+
+    Ok(result_package)
+}
+
+/// The license detected from the project's license file, for --cross-check-licenses
+pub fn repository_license(project: &GitlabProject) -> Option<(String, f32)> {
+    let mut detected = Package::new(project.path.clone(), project.git_ref.clone(), project.web_url(), None);
+    detect_license_file(crate::http_retry::metadata_client(), project, &mut detected);
+    detected.license_source.map(|_| (detected.license, detected.confidence))
+}
+
+/// Detect the license from the first license file the repository has
+fn detect_license_file(client: &Client, project: &GitlabProject, package: &mut Package) {
+    for file in LICENSE_FILES {
+        let text = match fetch_file(client, project, file) {
+            Ok(Some(text)) => text,
+            Ok(None) => {
+                continue;
+            }
+            Err(e) => {
+                package.debug_info = Some(format!("Failed to read {} from {}: {}", file, project.web_url(), e));
+                return;
+            }
+        };
+
+        let file_url = format!("{}/-/blob/{}/{}", project.web_url(), project.git_ref, file);
+        match crate::license_detection::detect_license_from_text(&text) {
+            Some((license, confidence)) => {
+                package.license = license;
+                package.license_source = Some(LicenseSource::LicenseText);
+                package.confidence = confidence;
+                package.debug_info = Some(format!("License detected from {}", file_url));
+            }
+            None => {
+                package.debug_info = Some(format!("License file found but type unknown: {}", file_url));
+            }
+        }
+        package.license_url = Some(file_url);
+        return;
+    }
+}
+
+/// A file of the repository at the project's ref, or None when it doesn't exist
+fn fetch_file(client: &Client, project: &GitlabProject, file: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let api_url = project.file_api_url(file);
+    let request = client.get(&api_url).header("User-Agent", "Dependency-Scanner");
+    // Files fetched with the token may belong to a private project and are never cached
+    let token = std::env::var(TOKEN_VAR).ok().filter(|_| project.host == token_host());
+    let response = match token {
+        Some(token) => crate::http_cache::send_authenticated(&api_url, request.header("PRIVATE-TOKEN", token))?,
+        None => crate::http_cache::send(&api_url, request)?,
+    };
+
+    // Instances behind single sign-on answer with their login page
+    if response.is_html() {
+        return Err(
+            Box::new(LookupError {
+                status: ResolutionStatus::AuthRequired,
+                message: format!(
+                    "Authentication required: GitLab answered {} with an HTML page{} instead of JSON; set {} for private projects",
+                    api_url,
+                    if response.redirected() { " after a redirect" } else { "" },
+                    TOKEN_VAR
+                ),
+            })
+        );
+    }
+    if response.status().as_u16() == 404 {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(
+            Box::new(LookupError {
+                status: response.failure_status(),
+                message: format!(
+                    "GitLab API returned status code {}: {}",
+                    status.as_u16(),
+                    status.canonical_reason().unwrap_or("Unknown error")
+                ),
+            })
+        );
+    }
+
+    let content: Value = response.json()?;
+    let encoded = content["content"].as_str().ok_or("No content field in GitLab API response")?;
+    let decoded = base64::decode(encoded.replace('\n', ""))?;
+    Ok(Some(String::from_utf8_lossy(&decoded).into_owned()))
+}
+
+/// The packages a repository's package.json depends on; devDependencies aren't installed with it
+fn manifest_dependencies(package_json: &Value) -> Vec<Package> {
+    let Some(deps) = package_json["dependencies"].as_object() else {
+        return Vec::new();
+    };
+
+    deps.iter()
+        .filter_map(|(name, version_value)| Some(crate::github_api::manifest_dependency(name, version_value.as_str()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(host: &str, path: &str, git_ref: &str) -> Option<GitlabProject> {
+        Some(GitlabProject { host: host.to_string(), path: path.to_string(), git_ref: git_ref.to_string() })
+    }
+
+    #[test]
+    fn test_extract_gitlab_details_formats() {
+        let expected = project("gitlab.com", "group/repo", "abc123");
+        for reference in [
+            "git+https://gitlab.com/group/repo.git#abc123",
+            "git+ssh://git@gitlab.com/group/repo.git#abc123",
+            "git@gitlab.com:group/repo.git#abc123",
+            "widget@https://gitlab.com/group/repo.git#commit=abc123",
+            "https://gitlab.com/group/repo/-/tree/abc123",
+            "https://gitlab.com/group/repo/-/archive/abc123/repo-abc123.tar.gz",
+            "gitlab:group/repo#abc123",
+            "widget@gitlab:group/repo#abc123",
+        ] {
+            assert_eq!(extract_gitlab_details(reference, "gitlab.com"), expected, "{}", reference);
+        }
+
+        // Nested groups are part of the project path; without a ref the default branch is read
+        assert_eq!(
+            extract_gitlab_details("git+https://gitlab.com/group/subgroup/repo.git", "gitlab.com"),
+            project("gitlab.com", "group/subgroup/repo", "HEAD")
+        );
+        assert_eq!(
+            extract_gitlab_details("git+ssh://git@git.example.com/platform/ui-kit.git#v2.1.0", "git.example.com"),
+            project("git.example.com", "platform/ui-kit", "v2.1.0")
+        );
+    }
+
+    #[test]
+    fn test_extract_gitlab_details_rejects_other_references() {
+        for reference in [
+            "https://github.com/group/repo.git#abc123",
+            "https://notgitlab.com/group/repo.git",
+            "https://gitlab.com/group",
+            // A tarball from GitLab's npm package registry, not a repository
+            "https://gitlab.com/api/v4/projects/42/packages/npm/widget/-/widget-1.0.0.tgz",
+            "https://registry.npmjs.org/gitlab/-/gitlab-14.2.2.tgz",
+        ] {
+            assert_eq!(extract_gitlab_details(reference, "gitlab.com"), None, "{}", reference);
+        }
+        // The shorthand always means gitlab.com
+        assert_eq!(extract_gitlab_details("gitlab:group/repo", "git.example.com"), None);
+    }
+
+    #[test]
+    fn test_gitlab_host_and_api_urls() {
+        assert_eq!(normalize_host("https://GitLab.Example.com/").unwrap(), "gitlab.example.com");
+        assert!(normalize_host("https://example.com/gitlab").is_err());
+        assert!(normalize_host("").is_err());
+
+        let project = project("gitlab.com", "group/subgroup/repo", "v1.0.0").unwrap();
+        assert_eq!(
+            project.file_api_url("package.json"),
+            "https://gitlab.com/api/v4/projects/group%2Fsubgroup%2Frepo/repository/files/package.json?ref=v1.0.0"
+        );
+        assert_eq!(project.web_url(), "https://gitlab.com/group/subgroup/repo");
+    }
+
+    #[test]
+    fn test_manifest_dependencies() {
+        let package_json = serde_json::json!({
+            "dependencies": {
+                "left-pad": "^1.3.0",
+                "theme": "gitlab:group/theme#v2",
+                "widget": "github:acme/widget"
+            },
+            "devDependencies": { "jest": "^29.0.0" }
+        });
+        let dependencies = manifest_dependencies(&package_json);
+        let resolutions: Vec<(&str, &str)> = dependencies
+            .iter()
+            .map(|dep| (dep.name.as_str(), dep.resolution.as_str()))
+            .collect();
+        assert_eq!(
+            resolutions,
+            [
+                ("left-pad", "https://registry.npmjs.org/left-pad/-/left-pad-1.3.0.tgz"),
+                ("theme", "gitlab:group/theme#v2"),
+                ("widget", "https://github.com/acme/widget"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,360 @@
+use once_cell::sync::OnceCell;
+use reqwest::blocking::RequestBuilder;
+use reqwest::header::{ CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED };
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{ Deserialize, Serialize };
+use sha2::{ Digest, Sha256 };
+use std::error::Error;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::diagnostics::ResolutionStatus;
+use crate::utils::init_cache_dir;
+
+/// Most bytes kept in .cache/http; the least recently used entries are evicted beyond it
+pub const MAX_CACHE_BYTES: u64 = 100 * 1024 * 1024;
+
+static HTTP_CACHE: OnceCell<Option<HttpCache>> = OnceCell::new();
+
+/// Responses kept across runs for revalidation
+struct HttpCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    used_bytes: Mutex<u64>, // also serializes eviction between workers
+}
+
+/// A response body stored with the validators the server sent along with it
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredResponse {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+/// A response read in full, either fresh or replayed from the HTTP cache after a 304
+pub struct CachedResponse {
+    status: StatusCode,
+    content_type: Option<String>, // None for replayed bodies, which are always JSON
+    redirected: bool,
+    rate_limit_exhausted: bool, // GitHub answers 403 rather than 429 once the rate limit is used up
+    body: String,
+}
+
+impl CachedResponse {
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Whether the server answered with a web page instead of JSON, typically the login page
+    /// a private resource redirects to
+    pub fn is_html(&self) -> bool {
+        match &self.content_type {
+            Some(content_type) => content_type.to_ascii_lowercase().starts_with("text/html"),
+            None => self.body.trim_start().starts_with('<'),
+        }
+    }
+
+    /// How a lookup answered with this response failed
+    pub fn failure_status(&self) -> ResolutionStatus {
+        if self.is_html() {
+            ResolutionStatus::AuthRequired
+        } else if self.rate_limit_exhausted {
+            ResolutionStatus::RateLimited
+        } else {
+            ResolutionStatus::from_http_status(self.status)
+        }
+    }
+
+    /// Whether the request was redirected to another URL
+    pub fn redirected(&self) -> bool {
+        self.redirected
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_str(&self.body)
+    }
+}
+
+/// Keep revalidatable responses in .cache/http across runs (--http-cache). Without it nothing
+/// is stored and every request is answered by the server.
+pub fn configure(enabled: bool) -> Result<(), Box<dyn Error>> {
+    let cache = match enabled {
+        true => Some(HttpCache::open(init_cache_dir()?.join("http"), MAX_CACHE_BYTES)?),
+        false => None,
+    };
+    HTTP_CACHE.set(cache).map_err(|_| "HTTP cache is already configured".into())
+}
+
+fn http_cache() -> Option<&'static HttpCache> {
+    HTTP_CACHE.get_or_init(|| None).as_ref()
+}
+
+impl HttpCache {
+    /// Open the cache directory, evicting what an earlier run left beyond `max_bytes`
+    fn open(dir: PathBuf, max_bytes: u64) -> Result<Self, Box<dyn Error>> {
+        fs::create_dir_all(&dir)?;
+        let used_bytes = evict(&dir, max_bytes);
+        Ok(HttpCache { dir, max_bytes, used_bytes: Mutex::new(used_bytes) })
+    }
+
+    // Validators and bodies are stored apart from packages, under the URL they were fetched from
+    fn entry_file(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:x}.json", Sha256::digest(url.as_bytes())))
+    }
+
+    fn load(&self, url: &str) -> Option<StoredResponse> {
+        let entry_file = self.entry_file(url);
+        let content = fs::read_to_string(&entry_file).ok()?;
+        // Reading an entry counts as a use, so the entries still needed are the last evicted
+        if let Ok(file) = fs::File::options().append(true).open(&entry_file) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        serde_json::from_str(&content).ok()
+    }
+
+    fn store(&self, url: &str, response: &StoredResponse) -> Result<(), Box<dyn Error>> {
+        let content = serde_json::to_string(response)?;
+        let size = content.len() as u64;
+        if size > self.max_bytes {
+            return Ok(());
+        }
+        fs::write(self.entry_file(url), content)?;
+
+        // Evict down to three quarters of the cap, so that not every later store has to list the directory
+        let mut used_bytes = self.used_bytes.lock().unwrap_or_else(|e| e.into_inner());
+        *used_bytes += size;
+        if *used_bytes > self.max_bytes {
+            *used_bytes = evict(&self.dir, self.max_bytes / 4 * 3);
+        }
+        Ok(())
+    }
+}
+
+/// Remove the least recently used entries until at most `max_bytes` remain, returning the bytes kept
+fn evict(dir: &Path, max_bytes: u64) -> u64 {
+    let mut entries: Vec<(SystemTime, u64, PathBuf)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            metadata.is_file().then(|| (modified, metadata.len(), entry.path()))
+        })
+        .collect();
+    entries.sort();
+
+    let mut used_bytes: u64 = entries.iter().map(|(_, size, _)| size).sum();
+    for (_, size, path) in entries {
+        if used_bytes <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            used_bytes -= size;
+        }
+    }
+    used_bytes
+}
+
+/// Send a GET request for `url`, revalidating a previously stored response with
+/// If-None-Match / If-Modified-Since when --http-cache is on. A 304 answer replays the
+/// stored body as a 200. Bodies larger than --max-response-size are an error.
+pub fn send(url: &str, request: RequestBuilder) -> Result<CachedResponse, Box<dyn Error>> {
+    send_with(http_cache(), url, request)
+}
+
+/// Send a GET request that carries credentials. Its response is private to whoever holds
+/// them, so it is neither revalidated nor stored, even with --http-cache.
+pub fn send_authenticated(url: &str, request: RequestBuilder) -> Result<CachedResponse, Box<dyn Error>> {
+    send_with(None, url, request)
+}
+
+fn send_with(cache: Option<&HttpCache>, url: &str, request: RequestBuilder) -> Result<CachedResponse, Box<dyn Error>> {
+    let stored = cache.and_then(|cache| cache.load(url));
+
+    let mut request = request;
+    if let Some(stored) = &stored {
+        if let Some(etag) = &stored.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &stored.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request.send()?;
+    let status = response.status();
+    if status == StatusCode::NOT_MODIFIED {
+        if let Some(stored) = stored {
+            return Ok(CachedResponse {
+                status: StatusCode::OK,
+                content_type: None,
+                redirected: false,
+                rate_limit_exhausted: false,
+                body: stored.body,
+            });
+        }
+    }
+
+    let header = |name| {
+        response.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    let content_type = header(CONTENT_TYPE);
+    let redirected = response.url().as_str() != url;
+    let rate_limit_exhausted = response.headers()
+        .get("x-ratelimit-remaining")
+        .is_some_and(|remaining| remaining == "0");
+    let body = crate::http_retry::read_text(response)?;
+    let mut response = CachedResponse { status, content_type, redirected, rate_limit_exhausted, body };
+
+    // Only responses that can be revalidated are worth keeping; login pages never are
+    let revalidatable = status.is_success() && (etag.is_some() || last_modified.is_some()) && !response.is_html();
+    if let Some(cache) = cache.filter(|_| revalidatable) {
+        let entry = StoredResponse { etag, last_modified, body: response.body };
+        if let Err(e) = cache.store(url, &entry) {
+            eprintln!("Warning: Failed to cache response from {}: {}", url, e);
+        }
+        response.body = entry.body;
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::blocking::Client;
+    use std::io::{ Read, Write };
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_not_modified_reuses_the_stored_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/widget", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            let responses = [
+                "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 17\r\nConnection: close\r\n\r\n{\"license\":\"MIT\"}",
+                "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            ];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 4096];
+                let read = stream.read(&mut request).unwrap();
+                requests.push(String::from_utf8_lossy(&request[..read]).to_lowercase());
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = HttpCache::open(dir.path().to_path_buf(), MAX_CACHE_BYTES).unwrap();
+        let client = Client::builder().no_proxy().build().unwrap();
+        let first = send_with(Some(&cache), &url, client.get(&url)).unwrap();
+        let second = send_with(Some(&cache), &url, client.get(&url)).unwrap();
+        let requests = server.join().unwrap();
+
+        assert!(!requests[0].contains("if-none-match"));
+        assert!(requests[1].contains("if-none-match: \"v1\""), "{}", requests[1]);
+        assert_eq!(second.status(), StatusCode::OK);
+        assert_eq!(second.body, first.body);
+        assert_eq!(second.json::<serde_json::Value>().unwrap()["license"], "MIT");
+        assert!(!second.is_html());
+    }
+
+    #[test]
+    fn test_redirect_to_a_login_page_is_html() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/@private%2Fwidget", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let login_page = "<!DOCTYPE html><html><body>Sign in</body></html>";
+            let responses = [
+                "HTTP/1.1 302 Found\r\nLocation: /login\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nETag: \"login\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    login_page.len(),
+                    login_page
+                ),
+            ];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 4096];
+                let _ = stream.read(&mut request).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = HttpCache::open(dir.path().to_path_buf(), MAX_CACHE_BYTES).unwrap();
+        let client = Client::builder().no_proxy().build().unwrap();
+        let response = send_with(Some(&cache), &url, client.get(&url)).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.is_html());
+        assert!(response.redirected());
+        assert!(response.json::<serde_json::Value>().is_err());
+        // The login page is not kept for revalidation
+        assert!(cache.load(&url).is_none());
+    }
+
+    #[test]
+    fn test_authenticated_responses_are_not_stored() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/private%2Fwidget", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request).unwrap();
+            let response = "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}";
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = HttpCache::open(dir.path().to_path_buf(), MAX_CACHE_BYTES).unwrap();
+        let client = Client::builder().no_proxy().build().unwrap();
+        let response = send_authenticated(&url, client.get(&url).header("PRIVATE-TOKEN", "secret")).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(cache.load(&url).is_none());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_least_recently_used_entries_are_evicted_beyond_the_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = |body: &str| StoredResponse {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            body: body.to_string(),
+        };
+        let size = serde_json::to_string(&entry(&"x".repeat(100))).unwrap().len() as u64;
+        let cache = HttpCache::open(dir.path().to_path_buf(), size * 3).unwrap();
+
+        for url in ["https://a", "https://b", "https://c"] {
+            cache.store(url, &entry(&"x".repeat(100))).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        // Reading "a" makes "b" the least recently used entry
+        assert!(cache.load("https://a").is_some());
+        cache.store("https://d", &entry(&"x".repeat(100))).unwrap();
+
+        assert!(cache.load("https://b").is_none());
+        assert!(cache.load("https://a").is_some());
+        assert!(cache.load("https://d").is_some());
+        assert!(*cache.used_bytes.lock().unwrap() <= size * 3);
+
+        // A body bigger than the whole cache is never written
+        cache.store("https://e", &entry(&"x".repeat(1000))).unwrap();
+        assert!(cache.load("https://e").is_none());
+    }
+}
@@ -0,0 +1,383 @@
+use chrono::{ DateTime, Utc };
+use once_cell::sync::{ Lazy, OnceCell };
+use reqwest::blocking::{ Client, ClientBuilder, RequestBuilder, Response };
+use reqwest::header::{ HeaderMap, RETRY_AFTER };
+use reqwest::StatusCode;
+use std::error::Error;
+use std::io::Read;
+use std::time::Duration;
+
+// Upper bound for random delay added to each wait, so workers don't retry in lockstep
+const MAX_JITTER_MS: u64 = 500;
+
+// Never wait longer than this, whatever the server advises
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+const USER_AGENT: &str = "Dependency-Scanner/1.0";
+
+pub const DEFAULT_METADATA_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_DOWNLOAD_TIMEOUT_SECS: u64 = 60;
+
+/// Largest API response body read, in megabytes; metadata for the biggest npm packages is a
+/// few megabytes, so anything near this is a misbehaving server
+pub const DEFAULT_MAX_RESPONSE_SIZE_MB: u64 = 50;
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Request timeouts: registry metadata answers quickly, archive downloads can be large
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    pub metadata: Duration,
+    pub download: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            metadata: Duration::from_secs(DEFAULT_METADATA_TIMEOUT_SECS),
+            download: Duration::from_secs(DEFAULT_DOWNLOAD_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl Timeouts {
+    /// Builder for clients that query package registries and the GitHub API
+    pub fn metadata_client_builder(&self) -> ClientBuilder {
+        Client::builder().user_agent(USER_AGENT).timeout(self.metadata)
+    }
+
+    /// Builder for clients that download package archives
+    pub fn download_client_builder(&self) -> ClientBuilder {
+        Client::builder().user_agent(USER_AGENT).timeout(self.download)
+    }
+}
+
+/// How the shared clients make requests and read their responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientSettings {
+    pub timeouts: Timeouts,
+    pub max_response_size_mb: u64, // Largest response body read (--max-response-size)
+}
+
+impl Default for ClientSettings {
+    fn default() -> Self {
+        ClientSettings { timeouts: Timeouts::default(), max_response_size_mb: DEFAULT_MAX_RESPONSE_SIZE_MB }
+    }
+}
+
+impl ClientSettings {
+    fn max_response_bytes(&self) -> u64 {
+        self.max_response_size_mb.saturating_mul(BYTES_PER_MB)
+    }
+}
+
+static CLIENT_SETTINGS: OnceCell<ClientSettings> = OnceCell::new();
+
+/// Set how the clients make requests; must happen before the first client is used
+pub fn configure(settings: ClientSettings) -> Result<(), String> {
+    CLIENT_SETTINGS.set(settings).map_err(|_| "HTTP clients are already configured".to_string())
+}
+
+fn client_settings() -> &'static ClientSettings {
+    CLIENT_SETTINGS.get_or_init(ClientSettings::default)
+}
+
+fn timeouts() -> &'static Timeouts {
+    &client_settings().timeouts
+}
+
+/// Timeout for a single archive download, also applied to callers' own clients
+pub fn download_timeout() -> Duration {
+    timeouts().download
+}
+
+// One client each for metadata and downloads, so requests share connections, proxy settings
+// (HTTP_PROXY, HTTPS_PROXY, NO_PROXY) and the user-agent
+static METADATA_CLIENT: Lazy<Client> = Lazy::new(|| {
+    timeouts().metadata_client_builder().build().unwrap_or_default()
+});
+
+static SHARED_CLIENT: Lazy<Client> = Lazy::new(|| {
+    timeouts().download_client_builder().build().unwrap_or_default()
+});
+
+/// The client shared by registry and GitHub API requests
+pub fn metadata_client() -> &'static Client {
+    &METADATA_CLIENT
+}
+
+/// The client shared by downloads; cheap to clone
+pub fn shared_client() -> &'static Client {
+    &SHARED_CLIENT
+}
+
+/// Read an API response body, giving up once it grows past --max-response-size
+pub fn read_text(response: Response) -> Result<String, Box<dyn Error>> {
+    read_text_limited(response, client_settings().max_response_bytes())
+}
+
+fn read_text_limited(response: Response, limit: u64) -> Result<String, Box<dyn Error>> {
+    let too_large = format!(
+        "response from {} is larger than the {} limit (--max-response-size)",
+        response.url(),
+        format_size(limit)
+    );
+
+    // The declared length turns a huge body down before any of it is read
+    if response.content_length().is_some_and(|length| length > limit) {
+        return Err(too_large.into());
+    }
+
+    // Chunked responses declare no length, so read one byte past the limit to notice it
+    let mut body = Vec::new();
+    response.take(limit + 1).read_to_end(&mut body)?;
+    if body.len() as u64 > limit {
+        return Err(too_large.into());
+    }
+
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= BYTES_PER_MB && bytes.is_multiple_of(BYTES_PER_MB) {
+        format!("{} MB", bytes / BYTES_PER_MB)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+/// Send a request, retrying network errors, 429 and 5xx responses for at most `max_attempts`
+/// attempts in all. Waits as long as the server's Retry-After header advises, plus jitter.
+/// Returns the last response when the attempts run out, so callers can report its status.
+pub fn send_with_retry<F>(build_request: F, max_attempts: usize) -> Result<Response, reqwest::Error>
+    where F: Fn() -> RequestBuilder
+{
+    let mut attempt = 0;
+
+    loop {
+        let result = build_request().send();
+
+        let delay = match &result {
+            Ok(response) if !should_retry(response.status()) => {
+                return result;
+            }
+            Ok(response) => {
+                retry_after_delay(response.headers(), Utc::now()).unwrap_or_else(|| {
+                    fallback_delay(response.status(), attempt)
+                })
+            }
+            Err(_) => fallback_delay(StatusCode::OK, attempt),
+        };
+
+        if attempt + 1 >= max_attempts {
+            return result;
+        }
+
+        let delay = delay + jitter();
+        eprintln!("INFO: Request failed, retrying in {:.1}s", delay.as_secs_f64());
+        std::thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+fn should_retry(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Backoff when the server gives no advice: rate limits wait longer than other failures
+fn fallback_delay(status: StatusCode, attempt: usize) -> Duration {
+    let attempt = (attempt + 1) as u64;
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        Duration::from_secs(5 * attempt)
+    } else {
+        Duration::from_secs(attempt)
+    }
+}
+
+/// Read the Retry-After header, which is either a number of seconds or an HTTP-date
+pub fn retry_after_delay(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    let delay = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let date = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+            // A date in the past means we may retry right away
+            (date - now).to_std().unwrap_or(Duration::ZERO)
+        }
+    };
+
+    Some(delay.min(MAX_RETRY_AFTER))
+}
+
+fn jitter() -> Duration {
+    Duration::from_millis(fastrand::u64(0..=MAX_JITTER_MS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use std::io::{ Read, Write };
+    use std::net::TcpListener;
+    use std::sync::atomic::{ AtomicBool, Ordering };
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    fn headers_with_retry_after(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z").unwrap().with_timezone(&Utc);
+
+        let seconds = headers_with_retry_after("2");
+        assert_eq!(retry_after_delay(&seconds, now), Some(Duration::from_secs(2)));
+
+        let date = headers_with_retry_after("Wed, 21 Oct 2015 07:28:30 GMT");
+        assert_eq!(retry_after_delay(&date, now), Some(Duration::from_secs(30)));
+
+        let past = headers_with_retry_after("Wed, 21 Oct 2015 07:00:00 GMT");
+        assert_eq!(retry_after_delay(&past, now), Some(Duration::ZERO));
+
+        assert_eq!(retry_after_delay(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn test_retry_after_is_honored() {
+        // Serve a 429 with Retry-After: 2, then a success
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/package", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let responses = [
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 2\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+            ];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let client = reqwest::blocking::Client::builder().no_proxy().build().unwrap();
+        let started = Instant::now();
+        let response = send_with_retry(|| client.get(&url), 3).unwrap();
+        let elapsed = started.elapsed();
+        server.join().unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(elapsed >= Duration::from_secs(2), "waited only {:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(2000 + MAX_JITTER_MS + 1000), "waited {:?}", elapsed);
+    }
+
+    #[test]
+    fn test_attempts_stop_at_the_maximum() {
+        // An outage: every request gets a 503 that may be retried at once
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("http://{}/package", listener.local_addr().unwrap());
+        let done = Arc::new(AtomicBool::new(false));
+        let server = {
+            let done = Arc::clone(&done);
+            std::thread::spawn(move || {
+                let mut requests = 0;
+                while !done.load(Ordering::SeqCst) {
+                    let Ok((mut stream, _)) = listener.accept() else {
+                        std::thread::sleep(Duration::from_millis(10));
+                        continue;
+                    };
+                    stream.set_nonblocking(false).unwrap();
+                    let mut request = [0u8; 1024];
+                    let _ = stream.read(&mut request);
+                    let response = "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\n\r\n";
+                    let _ = stream.write_all(response.as_bytes());
+                    requests += 1;
+                }
+                requests
+            })
+        };
+
+        let client = reqwest::blocking::Client::builder().no_proxy().pool_max_idle_per_host(0).build().unwrap();
+        let response = send_with_retry(|| client.get(&url), 3).unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        done.store(true, Ordering::SeqCst);
+        assert_eq!(server.join().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_timeouts_apply_to_their_clients() {
+        // Answer each request only after half a second
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/package", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request);
+                std::thread::sleep(Duration::from_millis(500));
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok");
+            }
+        });
+
+        let timeouts = Timeouts { metadata: Duration::from_millis(100), download: Duration::from_secs(5) };
+
+        let metadata_client = timeouts.metadata_client_builder().no_proxy().build().unwrap();
+        let err = metadata_client.get(&url).send().unwrap_err();
+        assert!(err.is_timeout(), "{}", err);
+
+        let download_client = timeouts.download_client_builder().no_proxy().build().unwrap();
+        let response = download_client.get(&url).send().unwrap();
+        assert_eq!(response.text().unwrap(), "ok");
+        server.join().unwrap();
+
+        assert_eq!(Timeouts::default().metadata, Duration::from_secs(DEFAULT_METADATA_TIMEOUT_SECS));
+        assert_eq!(Timeouts::default().download, Duration::from_secs(DEFAULT_DOWNLOAD_TIMEOUT_SECS));
+    }
+
+    #[test]
+    fn test_oversized_responses_are_rejected() {
+        // A body within the limit, one whose Content-Length is over it and a chunked one that
+        // only turns out to be too large while it is read
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/package", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let oversized = "x".repeat(64);
+            let responses = [
+                "HTTP/1.1 200 OK\r\nContent-Length: 17\r\nConnection: close\r\n\r\n{\"license\":\"MIT\"}".to_string(),
+                format!("HTTP/1.1 200 OK\r\nContent-Length: 64\r\nConnection: close\r\n\r\n{}", oversized),
+                format!(
+                    "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n40\r\n{}\r\n0\r\n\r\n",
+                    oversized
+                ),
+            ];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        let client = reqwest::blocking::Client::builder().no_proxy().build().unwrap();
+        let body = read_text_limited(client.get(&url).send().unwrap(), 32).unwrap();
+        assert_eq!(body, "{\"license\":\"MIT\"}");
+
+        let err = read_text_limited(client.get(&url).send().unwrap(), 32).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("response from {} is larger than the 32 bytes limit (--max-response-size)", url)
+        );
+
+        let response = client.get(&url).send().unwrap();
+        assert_eq!(response.content_length(), None);
+        assert!(read_text_limited(response, 32).unwrap_err().to_string().contains("--max-response-size"));
+        server.join().unwrap();
+
+        assert_eq!(format_size(DEFAULT_MAX_RESPONSE_SIZE_MB * BYTES_PER_MB), "50 MB");
+    }
+}
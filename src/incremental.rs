@@ -0,0 +1,180 @@
+use serde::{ Deserialize, Serialize };
+use sha2::{ Digest, Sha256 };
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use crate::package::Package;
+use crate::utils::generate_package_hash;
+use crate::version_overrides;
+
+/// The resolved packages of one lockfile, stored so an unchanged lockfile can be replayed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LockfileSnapshot {
+    pub packages: Vec<Package>,
+    pub dependency_tree: HashMap<String, Vec<String>>,
+}
+
+impl LockfileSnapshot {
+    /// Collect the packages reached from `source`, with the tree edges leaving them
+    pub fn from_results(
+        source: &str,
+        packages: &[Package],
+        dependency_tree: &HashMap<String, Vec<String>>
+    ) -> Self {
+        let packages: Vec<Package> = packages
+            .iter()
+            .filter(|p| p.source_lockfiles.iter().any(|lockfile| lockfile == source))
+            .map(|p| {
+                let mut package = p.clone();
+                package.source_lockfiles = vec![source.to_string()];
+                package
+            })
+            .collect();
+
+        let dependency_tree = packages
+            .iter()
+            .filter_map(|p| {
+                let id = format!("{}@{}", p.name, p.version);
+                dependency_tree.get(&id).map(|children| (id, children.clone()))
+            })
+            .collect();
+
+        LockfileSnapshot { packages, dependency_tree }
+    }
+}
+
+/// Snapshots of previous reports, keyed by lockfile content and the settings that shape a scan
+pub struct IncrementalCache {
+    dir: PathBuf,
+    settings: String,
+}
+
+impl IncrementalCache {
+    pub fn new(dir: PathBuf, settings: String) -> Self {
+        IncrementalCache { dir, settings }
+    }
+
+    /// Hash a lockfile's content together with the scan settings and the project's
+    /// package.json, whose resolutions and overrides change the versions scanned
+    pub fn key(&self, lockfile: &Path) -> std::io::Result<String> {
+        let content = fs::read(lockfile)?;
+        let mut hasher = Sha256::new();
+        hasher.update(&content);
+        hasher.update(self.settings.as_bytes());
+        if let Some(manifest) = version_overrides::manifest_path(lockfile).and_then(|path| fs::read(path).ok()) {
+            hasher.update(&manifest);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Load the snapshot stored for a key, if any
+    pub fn load(&self, key: &str) -> Option<LockfileSnapshot> {
+        let content = fs::read_to_string(self.snapshot_path(key)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn save(&self, key: &str, snapshot: &LockfileSnapshot) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.snapshot_path(key), serde_json::to_string(snapshot)?)?;
+        Ok(())
+    }
+
+    fn snapshot_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+/// Add replayed packages to the results; packages already present gain the replayed lockfiles
+pub fn merge_packages(results: &mut Vec<Package>, replayed: Vec<Package>) {
+    let mut index: HashMap<String, usize> = results
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (generate_package_hash(p), i))
+        .collect();
+
+    for package in replayed {
+        let package_hash = generate_package_hash(&package);
+        match index.get(&package_hash) {
+            Some(&i) => {
+                let existing = &mut results[i];
+                for lockfile in package.source_lockfiles {
+                    if !existing.source_lockfiles.contains(&lockfile) {
+                        existing.source_lockfiles.push(lockfile);
+                    }
+                }
+            }
+            None => {
+                index.insert(package_hash, results.len());
+                results.push(package);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: &str, lockfiles: &[&str]) -> Package {
+        let mut package = Package::new(name.to_string(), version.to_string(), String::new(), None);
+        package.license = "MIT".to_string();
+        package.source_lockfiles = lockfiles
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        package
+    }
+
+    #[test]
+    fn test_unchanged_lockfile_is_replayed() {
+        let dir = tempfile::tempdir().unwrap();
+        let lockfile = dir.path().join("yarn.lock");
+        fs::write(&lockfile, "left-pad@^1.3.0:\n  version \"1.3.0\"\n").unwrap();
+        let source = lockfile.display().to_string();
+
+        let cache = IncrementalCache::new(dir.path().join("incremental"), "settings".to_string());
+        let key = cache.key(&lockfile).unwrap();
+        assert!(cache.load(&key).is_none());
+
+        let results = vec![
+            package("left-pad", "1.3.0", &[&source, "other/yarn.lock"]),
+            package("unrelated", "2.0.0", &["other/yarn.lock"])
+        ];
+        let tree = HashMap::from([("left-pad@1.3.0".to_string(), vec!["dep@1.0.0".to_string()])]);
+        cache.save(&key, &LockfileSnapshot::from_results(&source, &results, &tree)).unwrap();
+
+        // The same content replays the stored packages, no network involved
+        let replayed = cache.load(&cache.key(&lockfile).unwrap()).unwrap();
+        assert_eq!(replayed.packages.len(), 1);
+        assert_eq!(replayed.packages[0].name, "left-pad");
+        assert_eq!(replayed.packages[0].source_lockfiles, vec![source.clone()]);
+        assert_eq!(replayed.dependency_tree["left-pad@1.3.0"], vec!["dep@1.0.0"]);
+
+        // Changed content or settings miss the snapshot
+        fs::write(&lockfile, "left-pad@^1.3.0:\n  version \"1.3.1\"\n").unwrap();
+        assert!(cache.load(&cache.key(&lockfile).unwrap()).is_none());
+        let other_settings = IncrementalCache::new(dir.path().join("incremental"), "deep".to_string());
+        fs::write(&lockfile, "left-pad@^1.3.0:\n  version \"1.3.0\"\n").unwrap();
+        assert_ne!(other_settings.key(&lockfile).unwrap(), key);
+
+        // So do new resolutions in the project's package.json
+        assert_eq!(cache.key(&lockfile).unwrap(), key);
+        fs::write(dir.path().join("package.json"), r#"{ "resolutions": { "left-pad": "1.3.1" } }"#).unwrap();
+        assert_ne!(cache.key(&lockfile).unwrap(), key);
+    }
+
+    #[test]
+    fn test_merge_packages_combines_lockfiles() {
+        let mut results = vec![package("left-pad", "1.3.0", &["a/yarn.lock"])];
+        merge_packages(
+            &mut results,
+            vec![package("left-pad", "1.3.0", &["b/yarn.lock"]), package("is-odd", "3.0.1", &["b/yarn.lock"])]
+        );
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].source_lockfiles, vec!["a/yarn.lock", "b/yarn.lock"]);
+        assert_eq!(results[1].name, "is-odd");
+    }
+}
@@ -0,0 +1,128 @@
+use serde::{ Deserialize, Serialize };
+use sha1::Sha1;
+use sha2::{ Digest, Sha256, Sha384, Sha512 };
+
+/// Hash algorithms allowed in Subresource Integrity strings, weakest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha1" => Some(HashAlgorithm::Sha1),
+            "sha256" => Some(HashAlgorithm::Sha256),
+            "sha384" => Some(HashAlgorithm::Sha384),
+            "sha512" => Some(HashAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha384 => "sha384",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+
+    fn digest(&self, content: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha1 => Sha1::digest(content).to_vec(),
+            HashAlgorithm::Sha256 => Sha256::digest(content).to_vec(),
+            HashAlgorithm::Sha384 => Sha384::digest(content).to_vec(),
+            HashAlgorithm::Sha512 => Sha512::digest(content).to_vec(),
+        }
+    }
+}
+
+/// One `algorithm-digest` entry of an SRI string; the digest is base64 as written in the lockfile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityHash {
+    pub algorithm: HashAlgorithm,
+    pub digest: String,
+}
+
+impl IntegrityHash {
+    /// Check downloaded content against this hash
+    pub fn verify(&self, content: &[u8]) -> Result<(), String> {
+        let actual = base64::encode(self.algorithm.digest(content));
+        if actual == self.digest {
+            Ok(())
+        } else {
+            Err(
+                format!(
+                    "Integrity check failed: expected {}-{}, got {}-{}",
+                    self.algorithm.as_str(),
+                    self.digest,
+                    self.algorithm.as_str(),
+                    actual
+                )
+            )
+        }
+    }
+}
+
+/// Parse an SRI string such as "sha512-... sha1-...". Entries with unknown algorithms are
+/// skipped, as the SRI spec requires, and `?options` suffixes are ignored.
+pub fn parse_sri(value: &str) -> Vec<IntegrityHash> {
+    value
+        .split_whitespace()
+        .filter_map(|entry| {
+            let (name, digest) = entry.split_once('-')?;
+            let digest = digest.split('?').next().unwrap_or(digest);
+            let algorithm = HashAlgorithm::from_name(name)?;
+            if digest.is_empty() {
+                return None;
+            }
+            Some(IntegrityHash { algorithm, digest: digest.to_string() })
+        })
+        .collect()
+}
+
+/// The hash to verify against: the one with the strongest algorithm
+pub fn strongest(hashes: &[IntegrityHash]) -> Option<&IntegrityHash> {
+    hashes.iter().max_by_key(|hash| hash.algorithm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strongest_hash_is_selected() {
+        let hashes = parse_sri(
+            "sha1-Mab4PeQVl4GeUJ4XBFdmExfM6oM= sha512-HV1Cm0Q3ZrpCR93tkWOYiuYIgLxZXZFVG2VgK+MBWjUqZTundupbfx2aXarXuw5Ko5aMcjtJgbSs4vUGBS5v6g== md5-ignored sha256-abc?opt"
+        );
+
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes[2], IntegrityHash { algorithm: HashAlgorithm::Sha256, digest: "abc".to_string() });
+
+        let best = strongest(&hashes).unwrap();
+        assert_eq!(best.algorithm, HashAlgorithm::Sha512);
+        assert!(best.digest.starts_with("HV1Cm0Q3"));
+
+        assert!(parse_sri("").is_empty());
+        assert!(strongest(&[]).is_none());
+    }
+
+    #[test]
+    fn test_verify() {
+        let content = b"hello";
+        let hash = IntegrityHash {
+            algorithm: HashAlgorithm::Sha256,
+            digest: base64::encode(Sha256::digest(content)),
+        };
+        assert!(hash.verify(content).is_ok());
+        assert!(hash.verify(b"tampered").is_err());
+
+        let sha1 = parse_sri("sha1-qvTGHdzF6KLavt4PO0gs2a6pQ00=");
+        assert!(sha1[0].verify(content).is_ok());
+    }
+}
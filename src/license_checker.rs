@@ -1,29 +1,325 @@
 use regex::Regex;
+use serde::{ Deserialize, Serialize };
+use std::collections::BTreeMap;
+
+use crate::license_compat::LicenseExpression;
+use crate::package::Package;
+
+/// A reviewed package approved to use a license the allowed patterns reject
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LicenseException {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>, // None approves every version
+    pub license: String,
+}
+
+impl LicenseException {
+    /// Parse `name@version:license`, or `name:license` for any version
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (package, license) = spec
+            .rsplit_once(':')
+            .ok_or_else(|| format!("Exception \"{}\" must have the form name@version:license", spec))?;
+        let (package, license) = (package.trim(), license.trim());
+
+        // Scoped names start with '@', so only a later '@' separates the version
+        let (name, version) = match package.rfind('@').filter(|&pos| pos > 0) {
+            Some(pos) => (&package[..pos], Some(&package[pos + 1..])),
+            None => (package, None),
+        };
+
+        if name.is_empty() || license.is_empty() {
+            return Err(format!("Exception \"{}\" is missing a package name or license", spec));
+        }
+
+        Ok(LicenseException {
+            name: name.to_string(),
+            version: version.filter(|v| !v.is_empty() && *v != "*").map(|v| v.to_string()),
+            license: license.to_string(),
+        })
+    }
+
+    fn matches(&self, package: &Package) -> bool {
+        self.name == package.name &&
+            self.version.as_ref().is_none_or(|version| *version == package.version) &&
+            self.license == package.license
+    }
+}
+
+/// How much a license matching a policy rule matters, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    Info, // Allowed, with the rule shown in verbose reports
+    Warn, // Allowed, but highlighted; fails the run only with --fail-on warn
+    Error, // A violation
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A license pattern with the severity of the licenses it matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeverityRule {
+    pub pattern: String,
+    pub severity: Severity,
+}
+
+impl SeverityRule {
+    /// Parse `PATTERN=LEVEL`, e.g. `LGPL-*=warn`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (pattern, level) = spec
+            .rsplit_once('=')
+            .ok_or_else(|| format!("Severity rule \"{}\" must have the form PATTERN=error|warn|info", spec))?;
+        let severity = match level.trim() {
+            "error" => Severity::Error,
+            "warn" => Severity::Warn,
+            "info" => Severity::Info,
+            other => {
+                return Err(format!("Unknown severity \"{}\" in \"{}\"; use error, warn or info", other, spec));
+            }
+        };
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Err(format!("Severity rule \"{}\" is missing a license pattern", spec));
+        }
+
+        Ok(SeverityRule { pattern: pattern.to_string(), severity })
+    }
+}
+
+/// The outcome of checking a license, with the rule that decided it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Decision {
+    pub allowed: bool,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>, // Error for violations, the rule's severity for rule matches
+}
+
+impl Decision {
+    fn allow(reason: String) -> Self {
+        Decision { allowed: true, reason, severity: None }
+    }
+
+    fn deny(reason: String) -> Self {
+        Decision { allowed: false, reason, severity: Some(Severity::Error) }
+    }
+
+    fn from_rule(rule: &SeverityRule) -> Self {
+        Decision {
+            allowed: rule.severity != Severity::Error,
+            reason: format!("matched {} pattern {}", rule.severity.as_str(), rule.pattern),
+            severity: Some(rule.severity),
+        }
+    }
+
+    /// Whether the license is allowed but matched a warn rule
+    pub fn is_warning(&self) -> bool {
+        self.allowed && self.severity == Some(Severity::Warn)
+    }
+}
 
 pub struct LicenseChecker {
     allowed_patterns: Vec<String>,
+    registry_patterns: BTreeMap<String, Vec<String>>, // Replace allowed_patterns for a registry
+    exceptions: Vec<LicenseException>,
+    severity_rules: Vec<SeverityRule>, // Checked in order before the allow-lists
+    forbidden_licenses: Vec<String>, // SPDX ids that fail whatever the other rules say
+    strict_spdx: bool, // Deprecated SPDX ids fail the check
+    min_confidence: Option<f32>, // Known licenses below this confidence need review
 }
 
 impl LicenseChecker {
     pub fn new(allowed_licenses: Vec<String>) -> Self {
         LicenseChecker {
-            allowed_patterns: allowed_licenses,
+            allowed_patterns: crate::license_groups::expand(allowed_licenses),
+            registry_patterns: BTreeMap::new(),
+            exceptions: Vec::new(),
+            severity_rules: Vec::new(),
+            forbidden_licenses: Vec::new(),
+            strict_spdx: false,
+            min_confidence: None,
         }
     }
 
-    pub fn is_allowed(&self, license: &str) -> bool {
-        // If no patterns specified, all licenses are allowed
-        if self.allowed_patterns.is_empty() {
-            return true;
+    /// Approve specific packages whose license would otherwise be rejected
+    pub fn with_exceptions(mut self, exceptions: Vec<LicenseException>) -> Self {
+        self.exceptions = exceptions;
+        self
+    }
+
+    /// Use a separate allow-list for packages from these registries (e.g. "npm", "github");
+    /// packages from other registries are checked against the global list
+    pub fn with_registry_allowed(mut self, registry_patterns: BTreeMap<String, Vec<String>>) -> Self {
+        self.registry_patterns = registry_patterns
+            .into_iter()
+            .map(|(registry, patterns)| (registry, crate::license_groups::expand(patterns)))
+            .collect();
+        self
+    }
+
+    /// Give licenses matching these patterns a severity instead of a plain allow or deny;
+    /// the first matching rule wins over the allow-lists
+    pub fn with_severity_rules(mut self, severity_rules: Vec<SeverityRule>) -> Self {
+        self.severity_rules = severity_rules;
+        self
+    }
+
+    /// Fail licenses that can only be used under one of these SPDX ids, whatever the allow-lists,
+    /// severity rules and exceptions say
+    pub fn with_forbidden_licenses(mut self, forbidden_licenses: Vec<String>) -> Self {
+        self.forbidden_licenses = forbidden_licenses;
+        self
+    }
+
+    /// Treat licenses that use deprecated SPDX ids as violations
+    pub fn with_strict_spdx(mut self, strict_spdx: bool) -> Self {
+        self.strict_spdx = strict_spdx;
+        self
+    }
+
+    /// Flag known licenses whose confidence is below the threshold as needing review
+    pub fn with_min_confidence(mut self, min_confidence: Option<f32>) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// Whether a package's license was found too unreliably to trust without a manual check
+    pub fn needs_review(&self, package: &Package) -> bool {
+        !package.is_unknown() &&
+            self.min_confidence.is_some_and(|min_confidence| package.confidence < min_confidence)
+    }
+
+    /// Whether a package passes the check, either by license or by an approved exception
+    pub fn is_package_allowed(&self, package: &Package) -> bool {
+        self.evaluate_package(package).allowed
+    }
+
+    /// Whether a package's license is allowed but matched a warn rule
+    pub fn is_warning(&self, package: &Package) -> bool {
+        self.evaluate_package(package).is_warning()
+    }
+
+    /// Whether a package is only allowed because of an approved exception
+    pub fn is_approved_exception(&self, package: &Package) -> bool {
+        !self.is_forbidden(package) &&
+            !self.license_passes(package).allowed &&
+            self.exceptions.iter().any(|e| e.matches(package))
+    }
+
+    /// Whether a package's license is one of the forbidden ids
+    pub fn is_forbidden(&self, package: &Package) -> bool {
+        self.is_forbidden_license(&package.license)
+    }
+
+    /// Whether a license (possibly an SPDX expression) can't be used without a forbidden id:
+    /// every OR alternative has a forbidden AND component
+    pub fn is_forbidden_license(&self, license: &str) -> bool {
+        if self.forbidden_licenses.is_empty() {
+            return false;
+        }
+
+        match LicenseExpression::parse(license) {
+            Some(expression) => self.is_forbidden_expression(&expression),
+            // A malformed expression can't be shown to avoid a forbidden id it names
+            None => crate::license_compat::tokenize(license)
+                .iter()
+                .any(|id| self.forbidden_licenses.iter().any(|forbidden| Self::same_license_id(forbidden, id))),
+        }
+    }
+
+    fn is_forbidden_expression(&self, expression: &LicenseExpression) -> bool {
+        match expression {
+            LicenseExpression::License { id, .. } => {
+                self.forbidden_licenses.iter().any(|forbidden| Self::same_license_id(forbidden, id))
+            }
+            LicenseExpression::And(components) => components.iter().any(|c| self.is_forbidden_expression(c)),
+            LicenseExpression::Or(alternatives) => alternatives.iter().all(|a| self.is_forbidden_expression(a)),
+        }
+    }
+
+    // GPL-3.0 also covers GPL-3.0-only, GPL-3.0-or-later and GPL-3.0+, in any case
+    fn same_license_id(a: &str, b: &str) -> bool {
+        let base = |id: &str| {
+            let id = id.trim();
+            ["-only", "-or-later", "+"]
+                .iter()
+                .find_map(|suffix| id.strip_suffix(suffix))
+                .unwrap_or(id)
+                .to_ascii_lowercase()
+        };
+        !b.trim().is_empty() && base(a) == base(b)
+    }
+
+    /// Check a package against forbidden ids, its registry's allow-list, strict SPDX ids and
+    /// approved exceptions
+    pub fn evaluate_package(&self, package: &Package) -> Decision {
+        if self.is_forbidden(package) {
+            return Decision::deny("forbidden by --fail-on-license".to_string());
+        }
+
+        let decision = self.license_passes(package);
+        if !decision.allowed && self.exceptions.iter().any(|e| e.matches(package)) {
+            return Decision::allow(format!("approved exception for {}@{}", package.name, package.version));
         }
+        decision
+    }
 
-        for pattern in &self.allowed_patterns {
-            if Self::matches_pattern(license, pattern) {
-                return true;
+    fn license_passes(&self, package: &Package) -> Decision {
+        let license = &package.license;
+        let decision = self.evaluate_rules(license, self.patterns_for(&package.registry));
+        if decision.allowed && self.strict_spdx {
+            if let Some((deprecated, _)) = crate::spdx_deprecations::declared_deprecated_ids(package).first() {
+                return Decision::deny(format!("deprecated SPDX id {}", deprecated));
             }
         }
+        decision
+    }
+
+    /// Allow-list for a registry; GitHub packages record theirs as "github:owner/repo"
+    fn patterns_for(&self, registry: &str) -> &[String] {
+        let registry = registry.split(':').next().unwrap_or(registry);
+        self.registry_patterns.get(registry).unwrap_or(&self.allowed_patterns)
+    }
 
-        false
+    /// Whether a license passes the global allow-list
+    #[cfg(test)]
+    pub fn is_allowed(&self, license: &str) -> bool {
+        self.evaluate(license).allowed
+    }
+
+    /// Check a license against the severity rules and the global allow-list
+    #[cfg(test)]
+    pub fn evaluate(&self, license: &str) -> Decision {
+        self.evaluate_rules(license, &self.allowed_patterns)
+    }
+
+    fn evaluate_rules(&self, license: &str, patterns: &[String]) -> Decision {
+        match self.severity_rules.iter().find(|rule| Self::matches_pattern(license, &rule.pattern)) {
+            Some(rule) => Decision::from_rule(rule),
+            None => Self::evaluate_patterns(license, patterns),
+        }
+    }
+
+    fn evaluate_patterns(license: &str, patterns: &[String]) -> Decision {
+        // If no patterns specified, all licenses are allowed
+        if patterns.is_empty() {
+            return Decision::allow("no allow-list configured".to_string());
+        }
+
+        match patterns.iter().find(|pattern| Self::matches_pattern(license, pattern)) {
+            Some(pattern) => Decision::allow(format!("matched pattern {}", pattern)),
+            None => Decision::deny("no matching allow pattern".to_string()),
+        }
     }
 
     // Match license string against a pattern, supporting wildcards
@@ -47,6 +343,7 @@ impl LicenseChecker {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::package::LicenseSource;
 
     #[test]
     fn test_exact_match() {
@@ -55,6 +352,18 @@ mod tests {
         assert!(!checker.is_allowed("Apache-2.0"));
     }
 
+    #[test]
+    fn test_license_list_groups() {
+        let checker = LicenseChecker::new(vec!["@osi-approved".to_string(), "LicenseRef-Acme-*".to_string()]);
+        assert!(checker.is_allowed("MIT"));
+        assert!(checker.is_allowed("Apache-2.0"));
+        assert!(checker.is_allowed("LicenseRef-Acme-Internal"));
+        assert!(!checker.is_allowed("PROPRIETARY"));
+        assert!(!checker.is_allowed("LicenseRef-Vendor-EULA"));
+        // FSF libre, but not OSI approved
+        assert!(!checker.is_allowed("WTFPL"));
+    }
+
     #[test]
     fn test_wildcard_match() {
         let checker = LicenseChecker::new(vec!["Apache*".to_string()]);
@@ -77,4 +386,224 @@ mod tests {
         assert!(checker.is_allowed("MIT"));
         assert!(checker.is_allowed("Any-License"));
     }
+
+    #[test]
+    fn test_package_exception() {
+        let exception = LicenseException::parse("readline-sync@1.4.10:GPL-3.0").unwrap();
+        let checker = LicenseChecker::new(vec!["MIT".to_string()]).with_exceptions(vec![exception]);
+
+        let package = |name: &str, version: &str, license: &str| {
+            let mut package = Package::new(name.to_string(), version.to_string(), String::new(), None);
+            package.license = license.to_string();
+            package
+        };
+
+        // GPL stays denied globally
+        assert!(!checker.is_allowed("GPL-3.0"));
+        assert!(checker.is_package_allowed(&package("readline-sync", "1.4.10", "GPL-3.0")));
+        assert!(checker.is_approved_exception(&package("readline-sync", "1.4.10", "GPL-3.0")));
+        assert!(!checker.is_package_allowed(&package("readline-sync", "1.5.0", "GPL-3.0")));
+        assert!(!checker.is_package_allowed(&package("other-gpl", "1.4.10", "GPL-3.0")));
+        assert!(!checker.is_approved_exception(&package("left-pad", "1.3.0", "MIT")));
+    }
+
+    #[test]
+    fn test_registry_allow_lists() {
+        let checker = LicenseChecker::new(vec!["MIT".to_string(), "GPL-3.0".to_string()])
+            .with_registry_allowed(BTreeMap::from([("github".to_string(), vec!["MIT".to_string()])]));
+
+        let package = |registry: &str, license: &str| {
+            let mut package = Package::new("tool".to_string(), "1.0.0".to_string(), String::new(), None);
+            package.registry = registry.to_string();
+            package.license = license.to_string();
+            package
+        };
+
+        // GPL is fine from npm but not from an unvetted GitHub source
+        assert!(checker.is_package_allowed(&package("npm", "GPL-3.0")));
+        assert!(!checker.is_package_allowed(&package("github:acme/tool", "GPL-3.0")));
+        assert!(!checker.is_package_allowed(&package("github", "GPL-3.0")));
+        assert!(checker.is_package_allowed(&package("github:acme/tool", "MIT")));
+
+        // Registries without their own list use the global one
+        assert!(checker.is_package_allowed(&package("pypi", "GPL-3.0")));
+        assert!(checker.is_allowed("GPL-3.0"));
+    }
+
+    #[test]
+    fn test_decision_reasons() {
+        let exception = LicenseException::parse("readline-sync:GPL-3.0-only").unwrap();
+        let checker = LicenseChecker::new(vec!["MIT".to_string(), "GPL-*".to_string()])
+            .with_strict_spdx(true)
+            .with_exceptions(vec![exception]);
+
+        let package = |name: &str, license: &str| {
+            let mut package = Package::new(name.to_string(), "1.0.0".to_string(), String::new(), None);
+            package.license = license.to_string();
+            package
+        };
+
+        assert_eq!(
+            checker.evaluate("MIT"),
+            Decision { allowed: true, reason: "matched pattern MIT".to_string(), severity: None }
+        );
+        assert_eq!(checker.evaluate("GPL-3.0-only").reason, "matched pattern GPL-*");
+        assert_eq!(
+            checker.evaluate("Apache-2.0"),
+            Decision {
+                allowed: false,
+                reason: "no matching allow pattern".to_string(),
+                severity: Some(Severity::Error),
+            }
+        );
+        assert_eq!(LicenseChecker::new(Vec::new()).evaluate("Apache-2.0").reason, "no allow-list configured");
+
+        // Denied despite matching a pattern, and allowed despite matching none
+        assert_eq!(
+            checker.evaluate_package(&package("old-gpl", "GPL-3.0")),
+            Decision {
+                allowed: false,
+                reason: "deprecated SPDX id GPL-3.0".to_string(),
+                severity: Some(Severity::Error),
+            }
+        );
+        assert_eq!(
+            checker.evaluate_package(&package("readline-sync", "GPL-3.0-only")).reason,
+            "matched pattern GPL-*"
+        );
+        let exception_checker = LicenseChecker::new(vec!["MIT".to_string()]).with_exceptions(vec![
+            LicenseException::parse("readline-sync:GPL-3.0-only").unwrap()
+        ]);
+        assert_eq!(
+            exception_checker.evaluate_package(&package("readline-sync", "GPL-3.0-only")),
+            Decision {
+                allowed: true,
+                reason: "approved exception for readline-sync@1.0.0".to_string(),
+                severity: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_exception() {
+        let scoped = LicenseException::parse("@acme/tool:AGPL-3.0").unwrap();
+        assert_eq!(scoped.name, "@acme/tool");
+        assert_eq!(scoped.version, None);
+        assert_eq!(scoped.license, "AGPL-3.0");
+
+        let versioned = LicenseException::parse("@acme/tool@2.0.0:AGPL-3.0").unwrap();
+        assert_eq!(versioned.name, "@acme/tool");
+        assert_eq!(versioned.version.as_deref(), Some("2.0.0"));
+
+        assert!(LicenseException::parse("no-license").is_err());
+        assert!(LicenseException::parse("pkg@1.0.0:").is_err());
+    }
+
+    #[test]
+    fn test_severity_rules() {
+        let rules = ["LGPL-*=warn", "AGPL-*=error", "Unlicense=info"]
+            .iter()
+            .map(|spec| SeverityRule::parse(spec).unwrap())
+            .collect();
+        let checker = LicenseChecker::new(vec!["MIT".to_string(), "*GPL-*".to_string()]).with_severity_rules(rules);
+
+        let lgpl = checker.evaluate("LGPL-3.0-only");
+        assert_eq!(lgpl.severity, Some(Severity::Warn));
+        assert!(lgpl.allowed && lgpl.is_warning());
+        assert_eq!(lgpl.reason, "matched warn pattern LGPL-*");
+
+        // Rules come before the allow-list, which would accept the AGPL
+        assert_eq!(
+            checker.evaluate("AGPL-3.0-only"),
+            Decision {
+                allowed: false,
+                reason: "matched error pattern AGPL-*".to_string(),
+                severity: Some(Severity::Error),
+            }
+        );
+        assert!(checker.is_allowed("Unlicense"));
+        assert_eq!(checker.evaluate("Unlicense").severity, Some(Severity::Info));
+        assert_eq!(checker.evaluate("MIT").severity, None);
+        assert_eq!(checker.evaluate("Apache-2.0").severity, Some(Severity::Error));
+
+        let mut package = Package::new("glib-binding".to_string(), "1.0.0".to_string(), String::new(), None);
+        package.license = "LGPL-2.1".to_string();
+        assert!(checker.is_package_allowed(&package));
+        assert!(checker.is_warning(&package));
+
+        assert!(SeverityRule::parse("GPL-*=fatal").is_err());
+        assert!(SeverityRule::parse("=warn").is_err());
+        assert!(SeverityRule::parse("GPL-*").is_err());
+    }
+
+    #[test]
+    fn test_forbidden_single_id() {
+        let checker = LicenseChecker::new(vec!["*".to_string()])
+            .with_exceptions(vec![LicenseException::parse("readline-sync:GPL-3.0").unwrap()])
+            .with_forbidden_licenses(vec!["GPL-3.0".to_string(), "AGPL-3.0".to_string()]);
+
+        assert!(checker.is_forbidden_license("GPL-3.0"));
+        assert!(checker.is_forbidden_license("GPL-3.0-only"));
+        assert!(checker.is_forbidden_license("agpl-3.0-or-later"));
+        assert!(checker.is_forbidden_license("GPL-3.0+ WITH GCC-exception-3.1"));
+        assert!(!checker.is_forbidden_license("LGPL-3.0-only"));
+        assert!(!checker.is_forbidden_license("GPL-2.0-only"));
+        assert!(!checker.is_forbidden_license("UNKNOWN"));
+
+        // Neither the allow-list nor an approved exception lets it through
+        let mut package = Package::new("readline-sync".to_string(), "1.4.10".to_string(), String::new(), None);
+        package.license = "GPL-3.0".to_string();
+        assert!(checker.is_forbidden(&package));
+        assert_eq!(checker.evaluate_package(&package), Decision::deny("forbidden by --fail-on-license".to_string()));
+        assert!(!checker.is_approved_exception(&package));
+
+        assert!(!LicenseChecker::new(Vec::new()).is_forbidden(&package));
+    }
+
+    #[test]
+    fn test_forbidden_or_expression() {
+        let checker = LicenseChecker::new(Vec::new()).with_forbidden_licenses(vec!["GPL-3.0".to_string()]);
+
+        // One acceptable alternative is enough
+        assert!(!checker.is_forbidden_license("(MIT OR GPL-3.0)"));
+        assert!(!checker.is_forbidden_license("GPL-3.0-only OR Apache-2.0"));
+        // Every alternative needs the forbidden license
+        assert!(checker.is_forbidden_license("(MIT AND GPL-3.0) OR GPL-3.0-or-later"));
+        assert!(checker.is_forbidden_license("MIT AND GPL-3.0-only"));
+        // Grouping is kept, and operators match in any case
+        assert!(checker.is_forbidden_license("GPL-3.0 AND (MIT OR Apache-2.0)"));
+        assert!(!checker.is_forbidden_license("(GPL-3.0 AND MIT) OR Apache-2.0"));
+        assert!(!checker.is_forbidden_license("mit or gpl-3.0"));
+        assert!(checker.is_forbidden_license("mit and gpl-3.0"));
+
+        let both = LicenseChecker::new(Vec::new())
+            .with_forbidden_licenses(vec!["GPL-3.0".to_string(), "MIT".to_string()]);
+        assert!(both.is_forbidden_license("(MIT OR GPL-3.0)"));
+    }
+
+    #[test]
+    fn test_strict_spdx() {
+        let mut package = Package::new("old-gpl".to_string(), "1.0.0".to_string(), String::new(), None);
+        package.license = "GPL-3.0".to_string();
+
+        let lenient = LicenseChecker::new(vec!["GPL-*".to_string()]);
+        assert!(lenient.is_package_allowed(&package));
+
+        let strict = LicenseChecker::new(vec!["GPL-*".to_string()]).with_strict_spdx(true);
+        assert!(!strict.is_package_allowed(&package));
+        package.license = "GPL-3.0-only".to_string();
+        assert!(strict.is_package_allowed(&package));
+
+        // Detection names GPL licenses by their deprecated ids, which the package didn't declare
+        for (source, allowed) in [
+            (LicenseSource::LicenseFile, true),
+            (LicenseSource::LicenseText, true),
+            (LicenseSource::Registry, false),
+            (LicenseSource::PackageManifest, false),
+        ] {
+            package.license = "GPL-3.0".to_string();
+            package.license_source = Some(source);
+            assert_eq!(strict.is_package_allowed(&package), allowed, "{:?}", source);
+        }
+    }
 }
@@ -0,0 +1,339 @@
+//! Coarse license compatibility checks against the license a project is distributed under.
+//!
+//! Licenses are grouped into broad families and compared with a fixed matrix. This catches
+//! obvious conflicts (a GPL dependency in an MIT project) but is not legal analysis:
+//! - linking style is ignored, so weak copyleft dependencies (LGPL, MPL, EPL) in
+//!   non-copyleft projects are reported as needing review rather than compatible
+//! - EPL, CDDL and MPL-1.x are incompatible with every GPL version; an EPL-2.0 package that
+//!   names GPL as a Secondary License is not told apart
+//! - license exceptions (`WITH ...`) are ignored, and a malformed expression needs review
+//! - licenses outside the known families, including custom and UNKNOWN ones, need review
+//! - an unrecognized project license is treated as proprietary
+
+use serde::Serialize;
+
+/// How a dependency license relates to the project license
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Compatibility {
+    // Ordered from worst to best so expressions can take the min/max
+    Incompatible,
+    ReviewNeeded,
+    Compatible,
+}
+
+/// License families used by the compatibility matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LicenseFamily {
+    Permissive,
+    Apache2,
+    WeakCopyleft,
+    GplIncompatibleCopyleft, // Weak copyleft whose terms conflict with the GPL
+    Gpl2Only,
+    Gpl2OrLater,
+    Gpl3,
+    Agpl3,
+    Other, // Proprietary, custom, or unknown
+}
+
+impl LicenseFamily {
+    fn name(&self) -> &'static str {
+        match self {
+            LicenseFamily::Permissive => "permissive",
+            LicenseFamily::Apache2 => "apache-2.0",
+            LicenseFamily::WeakCopyleft => "weak copyleft",
+            LicenseFamily::GplIncompatibleCopyleft => "weak copyleft (GPL-incompatible)",
+            LicenseFamily::Gpl2Only => "gpl-2.0-only",
+            LicenseFamily::Gpl2OrLater => "gpl-2.0-or-later",
+            LicenseFamily::Gpl3 => "gpl-3.0",
+            LicenseFamily::Agpl3 => "agpl-3.0",
+            LicenseFamily::Other => "other",
+        }
+    }
+}
+
+const PERMISSIVE_LICENSES: &[&str] = &[
+    "MIT",
+    "MIT-0",
+    "ISC",
+    "0BSD",
+    "BSD-2-Clause",
+    "BSD-3-Clause",
+    "Unlicense",
+    "CC0-1.0",
+    "CC-BY-3.0",
+    "CC-BY-4.0",
+    "Zlib",
+    "WTFPL",
+    "BSL-1.0",
+    "Python-2.0",
+    "PSF-2.0",
+    "BlueOak-1.0.0",
+    "X11",
+];
+
+fn classify(license: &str) -> LicenseFamily {
+    let license = crate::license_detection::normalize_license_id(license);
+    let upper = license.to_uppercase();
+
+    if PERMISSIVE_LICENSES.iter().any(|id| id.eq_ignore_ascii_case(&license)) {
+        LicenseFamily::Permissive
+    } else if upper == "APACHE-2.0" {
+        LicenseFamily::Apache2
+    } else if upper.starts_with("EPL-") || upper.starts_with("CDDL-") || upper.starts_with("MPL-1.") {
+        LicenseFamily::GplIncompatibleCopyleft
+    } else if upper.starts_with("LGPL-") || upper.starts_with("MPL-") {
+        LicenseFamily::WeakCopyleft
+    } else if upper.starts_with("AGPL-3.0") {
+        LicenseFamily::Agpl3
+    } else if upper.starts_with("GPL-3.0") {
+        LicenseFamily::Gpl3
+    } else if upper == "GPL-2.0+" || upper == "GPL-2.0-OR-LATER" {
+        LicenseFamily::Gpl2OrLater
+    } else if upper == "GPL-2.0" || upper == "GPL-2.0-ONLY" {
+        LicenseFamily::Gpl2Only
+    } else {
+        LicenseFamily::Other
+    }
+}
+
+/// The family a single license id is grouped into, e.g. "permissive" or "weak copyleft"
+pub fn category(license: &str) -> &'static str {
+    classify(license).name()
+}
+
+/// Whether the project license is one the matrix knows about
+pub fn is_known_project_license(license: &str) -> bool {
+    classify(license) != LicenseFamily::Other
+}
+
+/// Check whether a dependency license (possibly an SPDX expression) can be used in a
+/// project distributed under `project_license`
+pub fn check_compatibility(project_license: &str, dependency_license: &str) -> Compatibility {
+    match LicenseExpression::parse(dependency_license) {
+        Some(expression) => expression.compatibility(classify(project_license)),
+        None => Compatibility::ReviewNeeded,
+    }
+}
+
+/// Split an expression into parentheses, operators and license ids. Words between operators
+/// make up one id, so unnormalized names like "Apache 2.0" stay whole.
+pub(crate) fn tokenize(expression: &str) -> Vec<String> {
+    let spaced = expression.replace('(', " ( ").replace(')', " ) ");
+    let mut tokens: Vec<String> = Vec::new();
+    let mut in_id = false;
+    for word in spaced.split_whitespace() {
+        let is_id_word = !matches!(word, "(" | ")") && !is_operator(word);
+        match tokens.last_mut() {
+            Some(id) if in_id && is_id_word => {
+                id.push(' ');
+                id.push_str(word);
+            }
+            _ => tokens.push(word.to_string()),
+        }
+        in_id = is_id_word;
+    }
+    tokens
+}
+
+fn is_operator(word: &str) -> bool {
+    ["AND", "OR", "WITH"].iter().any(|operator| operator.eq_ignore_ascii_case(word))
+}
+
+/// A parsed SPDX expression. Operators match in any case, AND binds tighter than OR, and a
+/// `WITH` exception stays with its license id.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LicenseExpression {
+    License {
+        id: String,
+        exception: Option<String>,
+    },
+    And(Vec<LicenseExpression>),
+    Or(Vec<LicenseExpression>),
+}
+
+impl LicenseExpression {
+    /// Parse an expression, or None when it is malformed (unbalanced parentheses, a missing id)
+    pub(crate) fn parse(expression: &str) -> Option<Self> {
+        let mut parser = Parser { tokens: tokenize(expression), position: 0 };
+        let parsed = parser.or_expression()?;
+        (parser.position == parser.tokens.len()).then_some(parsed)
+    }
+
+    /// Any OR alternative may be chosen and every AND component must be satisfied
+    fn compatibility(&self, project: LicenseFamily) -> Compatibility {
+        match self {
+            LicenseExpression::License { id, .. } => family_compatibility(project, classify(id)),
+            LicenseExpression::And(components) => components
+                .iter()
+                .map(|component| component.compatibility(project))
+                .min()
+                .unwrap_or(Compatibility::ReviewNeeded),
+            LicenseExpression::Or(alternatives) => alternatives
+                .iter()
+                .map(|alternative| alternative.compatibility(project))
+                .max()
+                .unwrap_or(Compatibility::ReviewNeeded),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    position: usize,
+}
+
+impl Parser {
+    fn next_is(&self, operator: &str) -> bool {
+        self.tokens.get(self.position).is_some_and(|token| token.eq_ignore_ascii_case(operator))
+    }
+
+    fn or_expression(&mut self) -> Option<LicenseExpression> {
+        let mut alternatives = vec![self.and_expression()?];
+        while self.next_is("OR") {
+            self.position += 1;
+            alternatives.push(self.and_expression()?);
+        }
+        Some(if alternatives.len() == 1 { alternatives.remove(0) } else { LicenseExpression::Or(alternatives) })
+    }
+
+    fn and_expression(&mut self) -> Option<LicenseExpression> {
+        let mut components = vec![self.term()?];
+        while self.next_is("AND") {
+            self.position += 1;
+            components.push(self.term()?);
+        }
+        Some(if components.len() == 1 { components.remove(0) } else { LicenseExpression::And(components) })
+    }
+
+    /// A parenthesized expression, or a license id with an optional `WITH` exception
+    fn term(&mut self) -> Option<LicenseExpression> {
+        let token = self.tokens.get(self.position)?.clone();
+        self.position += 1;
+        if token == "(" {
+            let expression = self.or_expression()?;
+            if !self.next_is(")") {
+                return None;
+            }
+            self.position += 1;
+            return Some(expression);
+        }
+        if token == ")" || is_operator(&token) {
+            return None;
+        }
+
+        let mut exception = None;
+        if self.next_is("WITH") {
+            let id = self.tokens.get(self.position + 1)?;
+            if id == "(" || id == ")" || is_operator(id) {
+                return None;
+            }
+            exception = Some(id.clone());
+            self.position += 2;
+        }
+        Some(LicenseExpression::License { id: token, exception })
+    }
+}
+
+fn family_compatibility(project: LicenseFamily, dependency: LicenseFamily) -> Compatibility {
+    use Compatibility::*;
+    use LicenseFamily::*;
+
+    match (dependency, project) {
+        (Other, _) => ReviewNeeded,
+        (Permissive, _) => Compatible,
+
+        // Apache-2.0's patent terms conflict with GPL-2.0-only
+        (Apache2, Gpl2Only) => Incompatible,
+        (Apache2, Gpl2OrLater) => ReviewNeeded,
+        (Apache2, _) => Compatible,
+
+        // Fine when dynamically linked or kept in separate files, which we can't tell
+        (WeakCopyleft, Permissive | Apache2 | Other) => ReviewNeeded,
+        (WeakCopyleft, _) => Compatible,
+
+        (GplIncompatibleCopyleft, Gpl2Only | Gpl2OrLater | Gpl3 | Agpl3) => Incompatible,
+        (GplIncompatibleCopyleft, _) => ReviewNeeded,
+
+        (Gpl2Only, Gpl2Only | Gpl2OrLater) => Compatible,
+        (Gpl2Only, _) => Incompatible,
+
+        (Gpl2OrLater, Gpl2Only | Gpl2OrLater | Gpl3 | Agpl3) => Compatible,
+        (Gpl2OrLater, _) => Incompatible,
+
+        (Gpl3, Gpl3 | Agpl3) => Compatible,
+        (Gpl3, Gpl2OrLater) => ReviewNeeded, // Only if the project is distributed as GPL-3.0
+        (Gpl3, _) => Incompatible,
+
+        (Agpl3, Agpl3) => Compatible,
+        (Agpl3, Gpl3) => ReviewNeeded, // Allowed by GPL-3.0 section 13, with AGPL terms applying
+        (Agpl3, _) => Incompatible,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_pairs() {
+        assert_eq!(check_compatibility("GPL-3.0", "MIT"), Compatibility::Compatible);
+        assert_eq!(check_compatibility("MIT", "GPL-3.0"), Compatibility::Incompatible);
+        assert_eq!(check_compatibility("GPL-2.0-only", "Apache-2.0"), Compatibility::Incompatible);
+        assert_eq!(check_compatibility("GPL-3.0-or-later", "Apache-2.0"), Compatibility::Compatible);
+        assert_eq!(check_compatibility("GPL-3.0", "GPL-2.0-only"), Compatibility::Incompatible);
+        assert_eq!(check_compatibility("GPL-3.0", "GPL-2.0+"), Compatibility::Compatible);
+        assert_eq!(check_compatibility("Apache-2.0", "LGPL-2.1"), Compatibility::ReviewNeeded);
+        assert_eq!(check_compatibility("MIT", "AGPL-3.0"), Compatibility::Incompatible);
+        assert_eq!(check_compatibility("MIT", "UNKNOWN"), Compatibility::ReviewNeeded);
+    }
+
+    #[test]
+    fn test_gpl_incompatible_weak_copyleft() {
+        assert_eq!(check_compatibility("GPL-3.0", "EPL-1.0"), Compatibility::Incompatible);
+        assert_eq!(check_compatibility("GPL-2.0-or-later", "CDDL-1.0"), Compatibility::Incompatible);
+        assert_eq!(check_compatibility("AGPL-3.0", "EPL-2.0"), Compatibility::Incompatible);
+        assert_eq!(check_compatibility("MIT", "CDDL-1.0"), Compatibility::ReviewNeeded);
+        // MPL-2.0 names the GPL as a compatible secondary license, unlike MPL-1.1
+        assert_eq!(check_compatibility("GPL-3.0", "MPL-2.0"), Compatibility::Compatible);
+        assert_eq!(check_compatibility("GPL-3.0", "MPL-1.1"), Compatibility::Incompatible);
+        // A GPL-compatible alternative can still be chosen
+        assert_eq!(check_compatibility("GPL-2.0", "CDDL-1.0 OR GPL-2.0"), Compatibility::Compatible);
+    }
+
+    #[test]
+    fn test_expressions() {
+        // A permissive alternative makes a dual license usable
+        assert_eq!(check_compatibility("MIT", "(MIT OR GPL-3.0)"), Compatibility::Compatible);
+        // Every AND component has to be compatible
+        assert_eq!(check_compatibility("MIT", "MIT AND GPL-3.0"), Compatibility::Incompatible);
+        assert_eq!(
+            check_compatibility("GPL-2.0", "GPL-2.0 WITH Classpath-exception-2.0"),
+            Compatibility::Compatible
+        );
+        assert_eq!(check_compatibility("MIT", "Apache 2.0"), Compatibility::Compatible);
+    }
+
+    #[test]
+    fn test_parenthesized_expressions_keep_their_grouping() {
+        // The GPL component applies whichever alternative is chosen
+        assert_eq!(check_compatibility("MIT", "(MIT OR Apache-2.0) AND GPL-3.0"), Compatibility::Incompatible);
+        assert_eq!(check_compatibility("MIT", "MIT OR (Apache-2.0 AND GPL-3.0)"), Compatibility::Compatible);
+        // AND binds tighter than OR
+        assert_eq!(check_compatibility("MIT", "GPL-3.0 AND LGPL-2.1 OR MIT"), Compatibility::Compatible);
+        assert_eq!(check_compatibility("MIT", "((MIT) and (ISC or GPL-2.0-only))"), Compatibility::Compatible);
+        assert_eq!(check_compatibility("GPL-3.0", "(MIT AND (BSD-3-Clause OR EPL-1.0))"), Compatibility::Compatible);
+
+        // Malformed expressions need review
+        for expression in ["(MIT OR GPL-3.0", "MIT OR", "MIT AND ) ISC", "MIT WITH", ""] {
+            assert_eq!(check_compatibility("MIT", expression), Compatibility::ReviewNeeded, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn test_project_license_recognition() {
+        assert!(is_known_project_license("mit"));
+        assert!(is_known_project_license("GPL-3.0-only"));
+        assert!(!is_known_project_license("LicenseRef-Proprietary"));
+    }
+}
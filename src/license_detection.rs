@@ -1,95 +1,136 @@
 use regex::Regex;
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
-
-// Common license text patterns to match against license files when license identifier is unknown
-static LICENSE_PATTERNS: Lazy<HashMap<&'static str, Regex>> = Lazy::new(|| {
-    let mut patterns = HashMap::new();
-
-    // MIT License pattern - enhance with more variations
-    patterns.insert(
-        "MIT",
-        Regex::new(
-            r"(?i)(Permission is hereby granted, free of charge,.*MIT License|The MIT License \(MIT\)|MIT License Copyright|Permission is hereby granted, free of charge,.*subject to the following conditions)"
-        ).unwrap()
-    );
-
-    // Apache 2.0 pattern - make more robust
-    patterns.insert(
-        "Apache-2.0",
-        Regex::new(
-            r"(?i)(Apache License.*Version 2\.0|Licensed under the Apache License, Version 2\.0)"
-        ).unwrap()
-    );
-
-    // GPL patterns
-    patterns.insert("GPL-3.0", Regex::new(r"(?i)GNU General Public License.*Version 3").unwrap());
-    patterns.insert("GPL-2.0", Regex::new(r"(?i)GNU General Public License.*Version 2").unwrap());
-
-    // BSD patterns - improve matching
-    patterns.insert(
-        "BSD-3-Clause",
-        Regex::new(
-            r"(?i)(redistribution and use.*permitted provided that.*conditions are met.*neither the name.*nor the names of|The 3-Clause BSD License|3-Clause BSD License|3-clause BSD license)"
-        ).unwrap()
-    );
-    patterns.insert(
-        "BSD-2-Clause",
-        Regex::new(
-            r"(?i)redistribution and use.*permitted provided that.*conditions are met.*binary form must"
-        ).unwrap()
-    );
-
-    // ISC
-    patterns.insert(
-        "ISC",
-        Regex::new(r"(?i)ISC License.*Permission to use, copy, modify, and/or distribute").unwrap()
-    );
-
-    // Unlicense
-    patterns.insert(
-        "Unlicense",
-        Regex::new(
-            r"(?i)This is free and unencumbered software released into the public domain"
-        ).unwrap()
-    );
-
-    // Add more patterns for common licenses
-    patterns.insert(
-        "MPL-2.0",
-        Regex::new(r"(?i)(Mozilla Public License.*Version 2\.0|MPL 2\.0)").unwrap()
-    );
-
-    patterns.insert(
-        "LGPL-2.1",
-        Regex::new(r"(?i)(GNU Lesser General Public License.*Version 2\.1)").unwrap()
-    );
-
-    patterns.insert(
-        "LGPL-3.0",
-        Regex::new(r"(?i)(GNU Lesser General Public License.*Version 3)").unwrap()
-    );
-
-    patterns.insert(
-        "CC0-1.0",
-        Regex::new(
-            r"(?i)(Creative Commons Legal Code.*CC0 1\.0|CC0 1\.0 Universal|The person.*waives all of his or her rights)"
-        ).unwrap()
-    );
-
-    patterns.insert("EPL-2.0", Regex::new(r"(?i)(Eclipse Public License.*2\.0|EPL-2\.0)").unwrap());
+use crate::package::is_unknown_license;
+
+// Confidence in a license declared in package metadata as a valid SPDX expression
+pub const DECLARED_SPDX_CONFIDENCE: f32 = 1.0;
+
+// Confidence in a free-form license declared in package metadata, e.g. "BSD" or "SEE LICENSE IN"
+pub const DECLARED_FREE_TEXT_CONFIDENCE: f32 = 0.9;
+
+// License reported for packages npm marks "UNLICENSED": private code nobody may use
+pub const PROPRIETARY: &str = "PROPRIETARY";
+
+// Confidence in text detection, by how specific the matched pattern is
+const FULL_TEXT_CONFIDENCE: f32 = 0.75; // Distinctive wording from the license body
+const TITLE_CONFIDENCE: f32 = 0.5; // Only a license title or short notice
+
+// Common license text patterns to match against license files when license identifier is unknown.
+// Order matters when patterns tie: more specific licenses (LGPL, BSD-3-Clause) come before
+// the licenses whose patterns they also match (GPL, BSD-2-Clause).
+static LICENSE_PATTERNS: Lazy<Vec<(&'static str, Regex, f32)>> = Lazy::new(|| {
+    let patterns: &[(&str, &str, f32)] = &[
+        // MIT License
+        (
+            "MIT",
+            r"(?i)Permission is hereby granted, free of charge,.*(MIT License|subject to the following conditions)",
+            FULL_TEXT_CONFIDENCE,
+        ),
+        ("MIT", r"(?i)(The MIT License \(MIT\)|MIT License Copyright)", TITLE_CONFIDENCE),
+
+        // Apache 2.0
+        (
+            "Apache-2.0",
+            r"(?i)(Apache License.*Version 2\.0|Licensed under the Apache License, Version 2\.0)",
+            TITLE_CONFIDENCE,
+        ),
+
+        // LGPL before GPL, since LGPL texts also mention the GNU General Public License
+        ("LGPL-2.1", r"(?i)(GNU Lesser General Public License.*Version 2\.1)", TITLE_CONFIDENCE),
+        ("LGPL-3.0", r"(?i)(GNU Lesser General Public License.*Version 3)", TITLE_CONFIDENCE),
+        ("GPL-3.0", r"(?i)GNU General Public License.*Version 3", TITLE_CONFIDENCE),
+        ("GPL-2.0", r"(?i)GNU General Public License.*Version 2", TITLE_CONFIDENCE),
+
+        // BSD-3-Clause before BSD-2-Clause, since its text contains every BSD-2-Clause condition
+        (
+            "BSD-3-Clause",
+            r"(?i)redistribution and use.*permitted provided that.*conditions are met.*neither the name.*nor the names of",
+            FULL_TEXT_CONFIDENCE,
+        ),
+        ("BSD-3-Clause", r"(?i)(The 3-Clause BSD License|3-Clause BSD License)", TITLE_CONFIDENCE),
+        (
+            "BSD-2-Clause",
+            r"(?i)redistribution and use.*permitted provided that.*conditions are met.*binary form must",
+            FULL_TEXT_CONFIDENCE,
+        ),
+
+        // ISC
+        (
+            "ISC",
+            r"(?i)ISC License.*Permission to use, copy, modify, and/or distribute",
+            FULL_TEXT_CONFIDENCE,
+        ),
+
+        // Unlicense
+        (
+            "Unlicense",
+            r"(?i)This is free and unencumbered software released into the public domain",
+            FULL_TEXT_CONFIDENCE,
+        ),
+
+        // Add more patterns for common licenses
+        ("MPL-2.0", r"(?i)(Mozilla Public License.*Version 2\.0|MPL 2\.0)", TITLE_CONFIDENCE),
+        ("CC0-1.0", r"(?i)The person.*waives all of his or her rights", FULL_TEXT_CONFIDENCE),
+        ("CC0-1.0", r"(?i)(Creative Commons Legal Code.*CC0 1\.0|CC0 1\.0 Universal)", TITLE_CONFIDENCE),
+        ("EPL-2.0", r"(?i)(Eclipse Public License.*2\.0|EPL-2\.0)", TITLE_CONFIDENCE),
+    ];
 
     patterns
+        .iter()
+        .map(|(license_type, pattern, confidence)| (*license_type, Regex::new(pattern).unwrap(), *confidence))
+        .collect()
 });
 
-/// Attempt to detect license type from license text
-pub fn detect_license_from_text(text: &str) -> Option<String> {
-    for (license_type, pattern) in LICENSE_PATTERNS.iter() {
-        if pattern.is_match(text) {
-            return Some(license_type.to_string());
+/// Attempt to detect license type from license text.
+/// Returns the license id with a confidence reflecting how specific the matched pattern is.
+pub fn detect_license_from_text(text: &str) -> Option<(String, f32)> {
+    let mut best: Option<(&str, f32)> = None;
+
+    for (license_type, pattern, confidence) in LICENSE_PATTERNS.iter() {
+        let is_better = best.is_none_or(|(_, best_confidence)| *confidence > best_confidence);
+        if is_better && pattern.is_match(text) {
+            best = Some((license_type, *confidence));
         }
     }
-    None
+
+    best.map(|(license_type, confidence)| (license_type.to_string(), confidence))
+}
+
+/// Confidence in a license declared in package metadata (0 when unknown)
+pub fn declared_confidence(license: &str) -> f32 {
+    if is_unknown_license(license) {
+        0.0
+    } else if is_spdx_expression(license) {
+        DECLARED_SPDX_CONFIDENCE
+    } else {
+        DECLARED_FREE_TEXT_CONFIDENCE
+    }
+}
+
+/// Whether a license string is syntactically a valid SPDX license expression
+pub fn is_spdx_expression(license: &str) -> bool {
+    if is_unknown_license(license) {
+        return false;
+    }
+
+    // Expressions alternate between license ids and AND/OR/WITH operators
+    let spaced = license.replace('(', " ( ").replace(')', " ) ");
+    let mut expect_id = true;
+    for token in spaced.split_whitespace().filter(|t| *t != "(" && *t != ")") {
+        let is_operator = matches!(token, "AND" | "OR" | "WITH");
+        if expect_id {
+            let is_id = token.chars().all(|c| c.is_ascii_alphanumeric() || ".-+:".contains(c));
+            if is_operator || !is_id {
+                return false;
+            }
+        } else if !is_operator {
+            return false;
+        }
+        expect_id = !expect_id;
+    }
+
+    // An empty expression or a trailing operator leaves us still expecting an id
+    !expect_id
 }
 
 /// Clean up commonly found license variations
@@ -104,6 +145,136 @@ pub fn normalize_license_id(license: &str) -> String {
         "gpl2" | "gplv2" | "gpl-2" => "GPL-2.0".to_string(),
         "isc license" => "ISC".to_string(),
         "public domain" => "Unlicense".to_string(),
+        "unlicensed" => PROPRIETARY.to_string(), // npm's private marker, not the Unlicense
         _ => license.to_string(),
     }
 }
+
+/// Deprecated SPDX ids, which normalization keeps as packages declare them so allow-lists
+/// still match, and the current ids they canonicalize to
+const SPDX_ID_REPLACEMENTS: &[(&str, &str)] = &[
+    ("AGPL-1.0", "AGPL-1.0-only"),
+    ("AGPL-3.0", "AGPL-3.0-only"),
+    ("GFDL-1.1", "GFDL-1.1-only"),
+    ("GFDL-1.2", "GFDL-1.2-only"),
+    ("GFDL-1.3", "GFDL-1.3-only"),
+    ("GPL-1.0", "GPL-1.0-only"),
+    ("GPL-1.0+", "GPL-1.0-or-later"),
+    ("GPL-2.0", "GPL-2.0-only"),
+    ("GPL-2.0+", "GPL-2.0-or-later"),
+    ("GPL-2.0-with-classpath-exception", "GPL-2.0-only WITH Classpath-exception-2.0"),
+    ("GPL-2.0-with-font-exception", "GPL-2.0-only WITH Font-exception-2.0"),
+    ("GPL-3.0", "GPL-3.0-only"),
+    ("GPL-3.0+", "GPL-3.0-or-later"),
+    ("LGPL-2.0", "LGPL-2.0-only"),
+    ("LGPL-2.0+", "LGPL-2.0-or-later"),
+    ("LGPL-2.1", "LGPL-2.1-only"),
+    ("LGPL-2.1+", "LGPL-2.1-or-later"),
+    ("LGPL-3.0", "LGPL-3.0-only"),
+    ("LGPL-3.0+", "LGPL-3.0-or-later"),
+    ("BSD-2-Clause-FreeBSD", "BSD-2-Clause"),
+    ("BSD-2-Clause-NetBSD", "BSD-2-Clause"),
+    ("bzip2-1.0.5", "bzip2-1.0.6"),
+    ("eCos-2.0", "GPL-2.0-or-later WITH eCos-exception-2.0"),
+    ("Nunit", "zlib-acknowledgement"),
+    ("StandardML-NJ", "SMLNJ"),
+    ("wxWindows", "GPL-2.0-or-later WITH WxWindows-exception-3.1"),
+];
+
+/// The current SPDX id for a deprecated one, e.g. GPL-3.0-only for GPL-3.0 (ids compare
+/// case-insensitively)
+pub fn canonical_spdx_id(id: &str) -> Option<&'static str> {
+    SPDX_ID_REPLACEMENTS
+        .iter()
+        .find(|(deprecated, _)| deprecated.eq_ignore_ascii_case(id))
+        .map(|(_, replacement)| *replacement)
+}
+
+/// File named by an npm `SEE LICENSE IN <file>` declaration
+pub fn license_file_reference(license: &str) -> Option<&str> {
+    const MARKER: &str = "SEE LICENSE IN ";
+    let license = license.trim();
+    let prefix = license.get(..MARKER.len())?;
+    if !prefix.eq_ignore_ascii_case(MARKER) {
+        return None;
+    }
+    let file = license[MARKER.len()..].trim().trim_start_matches("./");
+    (!file.is_empty()).then_some(file)
+}
+
+/// Whether a license detected from license text is one the declared license (possibly an
+/// SPDX expression) names. Version suffixes are ignored, since detection only finds the family
+/// and version, e.g. a GPL-3.0 text matches a GPL-3.0-only declaration.
+pub fn declared_matches_detected(declared: &str, detected: &str) -> bool {
+    let base_id = |id: &str| {
+        id.trim_end_matches('+')
+            .trim_end_matches("-only")
+            .trim_end_matches("-or-later")
+            .to_lowercase()
+    };
+    let detected = base_id(detected);
+
+    declared
+        .replace(['(', ')'], " ")
+        .split_whitespace()
+        .filter(|token| !matches!(*token, "OR" | "AND" | "WITH"))
+        .any(|id| base_id(id) == detected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_license_markers() {
+        assert_eq!(normalize_license_id("UNLICENSED"), PROPRIETARY);
+        assert_eq!(normalize_license_id("Unlicense"), "Unlicense");
+
+        assert_eq!(license_file_reference("SEE LICENSE IN LICENSE.md"), Some("LICENSE.md"));
+        assert_eq!(license_file_reference("see license in ./docs/EULA.txt"), Some("docs/EULA.txt"));
+        assert_eq!(license_file_reference("SEE LICENSE IN "), None);
+        assert_eq!(license_file_reference("MIT"), None);
+    }
+
+    #[test]
+    fn test_declared_matches_detected() {
+        assert!(declared_matches_detected("MIT", "MIT"));
+        assert!(declared_matches_detected("(MIT OR Apache-2.0)", "Apache-2.0"));
+        assert!(declared_matches_detected("GPL-3.0-only", "GPL-3.0"));
+        assert!(declared_matches_detected("GPL-2.0+", "GPL-2.0"));
+        assert!(!declared_matches_detected("MIT", "GPL-3.0"));
+        assert!(!declared_matches_detected("LGPL-3.0", "GPL-3.0"));
+    }
+
+    #[test]
+    fn test_declared_confidence() {
+        assert_eq!(declared_confidence("MIT"), DECLARED_SPDX_CONFIDENCE);
+        assert_eq!(declared_confidence("(MIT OR Apache-2.0)"), DECLARED_SPDX_CONFIDENCE);
+        assert_eq!(declared_confidence("SEE LICENSE IN LICENSE.md"), DECLARED_FREE_TEXT_CONFIDENCE);
+        assert_eq!(declared_confidence("UNKNOWN"), 0.0);
+    }
+
+    #[test]
+    fn test_text_confidence_by_specificity() {
+        let body = "Permission is hereby granted, free of charge, to any person obtaining a copy, \
+                    subject to the following conditions:";
+        let (license, body_confidence) = detect_license_from_text(body).unwrap();
+        assert_eq!(license, "MIT");
+
+        let (license, title_confidence) = detect_license_from_text("The MIT License (MIT)").unwrap();
+        assert_eq!(license, "MIT");
+
+        // Text detection never beats a license declared in metadata
+        assert!(title_confidence < body_confidence);
+        assert!(body_confidence < DECLARED_FREE_TEXT_CONFIDENCE);
+
+        assert_eq!(detect_license_from_text("All rights reserved."), None);
+    }
+
+    #[test]
+    fn test_more_specific_license_wins_ties() {
+        let lgpl = "GNU Lesser General Public License Version 3, which supplements the \
+                    GNU General Public License Version 3";
+        assert_eq!(detect_license_from_text(lgpl).unwrap().0, "LGPL-3.0");
+    }
+}
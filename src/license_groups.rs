@@ -0,0 +1,801 @@
+//! SPDX license-list categories usable in allow-lists as `@osi-approved` and `@fsf-libre`,
+//! expanded to the ids the SPDX license list flags with `isOsiApproved` / `isFsfLibre`.
+
+/// Release of the SPDX license list SPDX_LICENSE_FLAGS was generated from
+pub const SPDX_LICENSE_LIST_VERSION: &str = "3.27.0";
+
+/// Every id of the SPDX license list, deprecated ones included, with its (OSI approved, FSF
+/// libre) flags. Generated by tools/spdx_license_flags.py; don't edit by hand.
+const SPDX_LICENSE_FLAGS: &[(&str, bool, bool)] = &[
+    ("0BSD", true, false),
+    ("3D-Slicer-1.0", false, false),
+    ("AAL", true, false),
+    ("Abstyles", false, false),
+    ("AdaCore-doc", false, false),
+    ("Adobe-2006", false, false),
+    ("Adobe-Display-PostScript", false, false),
+    ("Adobe-Glyph", false, false),
+    ("Adobe-Utopia", false, false),
+    ("ADSL", false, false),
+    ("AFL-1.1", true, true),
+    ("AFL-1.2", true, true),
+    ("AFL-2.0", true, true),
+    ("AFL-2.1", true, true),
+    ("AFL-3.0", true, true),
+    ("Afmparse", false, false),
+    ("AGPL-1.0", false, true),
+    ("AGPL-1.0-only", false, false),
+    ("AGPL-1.0-or-later", false, false),
+    ("AGPL-3.0", true, true),
+    ("AGPL-3.0-only", true, true),
+    ("AGPL-3.0-or-later", true, true),
+    ("Aladdin", false, false),
+    ("AMD-newlib", false, false),
+    ("AMDPLPA", false, false),
+    ("AML", false, false),
+    ("AML-glslang", false, false),
+    ("AMPAS", false, false),
+    ("ANTLR-PD", false, false),
+    ("ANTLR-PD-fallback", false, false),
+    ("any-OSI", false, false),
+    ("any-OSI-perl-modules", false, false),
+    ("Apache-1.0", false, true),
+    ("Apache-1.1", true, true),
+    ("Apache-2.0", true, true),
+    ("APAFML", false, false),
+    ("APL-1.0", true, false),
+    ("App-s2p", false, false),
+    ("APSL-1.0", true, false),
+    ("APSL-1.1", true, false),
+    ("APSL-1.2", true, false),
+    ("APSL-2.0", true, true),
+    ("Arphic-1999", false, false),
+    ("Artistic-1.0", true, false),
+    ("Artistic-1.0-cl8", true, false),
+    ("Artistic-1.0-Perl", true, false),
+    ("Artistic-2.0", true, true),
+    ("Artistic-dist", false, false),
+    ("Aspell-RU", false, false),
+    ("ASWF-Digital-Assets-1.0", false, false),
+    ("ASWF-Digital-Assets-1.1", false, false),
+    ("Baekmuk", false, false),
+    ("Bahyph", false, false),
+    ("Barr", false, false),
+    ("bcrypt-Solar-Designer", false, false),
+    ("Beerware", false, false),
+    ("Bitstream-Charter", false, false),
+    ("Bitstream-Vera", false, false),
+    ("BitTorrent-1.0", false, false),
+    ("BitTorrent-1.1", false, true),
+    ("blessing", false, false),
+    ("BlueOak-1.0.0", true, false),
+    ("Boehm-GC", false, false),
+    ("Boehm-GC-without-fee", false, false),
+    ("Borceux", false, false),
+    ("Brian-Gladman-2-Clause", false, false),
+    ("Brian-Gladman-3-Clause", false, false),
+    ("BSD-1-Clause", true, false),
+    ("BSD-2-Clause", true, true),
+    ("BSD-2-Clause-Darwin", false, false),
+    ("BSD-2-Clause-first-lines", false, false),
+    ("BSD-2-Clause-FreeBSD", false, true),
+    ("BSD-2-Clause-NetBSD", false, true),
+    ("BSD-2-Clause-Patent", true, false),
+    ("BSD-2-Clause-pkgconf-disclaimer", false, false),
+    ("BSD-2-Clause-Views", false, false),
+    ("BSD-3-Clause", true, true),
+    ("BSD-3-Clause-acpica", false, false),
+    ("BSD-3-Clause-Attribution", false, false),
+    ("BSD-3-Clause-Clear", false, true),
+    ("BSD-3-Clause-flex", false, false),
+    ("BSD-3-Clause-HP", false, false),
+    ("BSD-3-Clause-LBNL", true, false),
+    ("BSD-3-Clause-Modification", false, false),
+    ("BSD-3-Clause-No-Military-License", false, false),
+    ("BSD-3-Clause-No-Nuclear-License", false, false),
+    ("BSD-3-Clause-No-Nuclear-License-2014", false, false),
+    ("BSD-3-Clause-No-Nuclear-Warranty", false, false),
+    ("BSD-3-Clause-Open-MPI", false, false),
+    ("BSD-3-Clause-Sun", false, false),
+    ("BSD-4-Clause", false, true),
+    ("BSD-4-Clause-Shortened", false, false),
+    ("BSD-4-Clause-UC", false, false),
+    ("BSD-4.3RENO", false, false),
+    ("BSD-4.3TAHOE", false, false),
+    ("BSD-Advertising-Acknowledgement", false, false),
+    ("BSD-Attribution-HPND-disclaimer", false, false),
+    ("BSD-Inferno-Nettverk", false, false),
+    ("BSD-Protection", false, false),
+    ("BSD-Source-beginning-file", false, false),
+    ("BSD-Source-Code", false, false),
+    ("BSD-Systemics", false, false),
+    ("BSD-Systemics-W3Works", false, false),
+    ("BSL-1.0", true, true),
+    ("BUSL-1.1", false, false),
+    ("bzip2-1.0.5", false, false),
+    ("bzip2-1.0.6", false, false),
+    ("C-UDA-1.0", false, false),
+    ("CAL-1.0", true, false),
+    ("CAL-1.0-Combined-Work-Exception", true, false),
+    ("Caldera", false, false),
+    ("Caldera-no-preamble", false, false),
+    ("Catharon", false, false),
+    ("CATOSL-1.1", true, false),
+    ("CC-BY-1.0", false, false),
+    ("CC-BY-2.0", false, false),
+    ("CC-BY-2.5", false, false),
+    ("CC-BY-2.5-AU", false, false),
+    ("CC-BY-3.0", false, false),
+    ("CC-BY-3.0-AT", false, false),
+    ("CC-BY-3.0-AU", false, false),
+    ("CC-BY-3.0-DE", false, false),
+    ("CC-BY-3.0-IGO", false, false),
+    ("CC-BY-3.0-NL", false, false),
+    ("CC-BY-3.0-US", false, false),
+    ("CC-BY-4.0", false, true),
+    ("CC-BY-NC-1.0", false, false),
+    ("CC-BY-NC-2.0", false, false),
+    ("CC-BY-NC-2.5", false, false),
+    ("CC-BY-NC-3.0", false, false),
+    ("CC-BY-NC-3.0-DE", false, false),
+    ("CC-BY-NC-4.0", false, false),
+    ("CC-BY-NC-ND-1.0", false, false),
+    ("CC-BY-NC-ND-2.0", false, false),
+    ("CC-BY-NC-ND-2.5", false, false),
+    ("CC-BY-NC-ND-3.0", false, false),
+    ("CC-BY-NC-ND-3.0-DE", false, false),
+    ("CC-BY-NC-ND-3.0-IGO", false, false),
+    ("CC-BY-NC-ND-4.0", false, false),
+    ("CC-BY-NC-SA-1.0", false, false),
+    ("CC-BY-NC-SA-2.0", false, false),
+    ("CC-BY-NC-SA-2.0-DE", false, false),
+    ("CC-BY-NC-SA-2.0-FR", false, false),
+    ("CC-BY-NC-SA-2.0-UK", false, false),
+    ("CC-BY-NC-SA-2.5", false, false),
+    ("CC-BY-NC-SA-3.0", false, false),
+    ("CC-BY-NC-SA-3.0-DE", false, false),
+    ("CC-BY-NC-SA-3.0-IGO", false, false),
+    ("CC-BY-NC-SA-4.0", false, false),
+    ("CC-BY-ND-1.0", false, false),
+    ("CC-BY-ND-2.0", false, false),
+    ("CC-BY-ND-2.5", false, false),
+    ("CC-BY-ND-3.0", false, false),
+    ("CC-BY-ND-3.0-DE", false, false),
+    ("CC-BY-ND-4.0", false, false),
+    ("CC-BY-SA-1.0", false, false),
+    ("CC-BY-SA-2.0", false, false),
+    ("CC-BY-SA-2.0-UK", false, false),
+    ("CC-BY-SA-2.1-JP", false, false),
+    ("CC-BY-SA-2.5", false, false),
+    ("CC-BY-SA-3.0", false, false),
+    ("CC-BY-SA-3.0-AT", false, false),
+    ("CC-BY-SA-3.0-DE", false, false),
+    ("CC-BY-SA-3.0-IGO", false, false),
+    ("CC-BY-SA-4.0", false, true),
+    ("CC-PDDC", false, false),
+    ("CC-PDM-1.0", false, false),
+    ("CC-SA-1.0", false, false),
+    ("CC0-1.0", false, true),
+    ("CDDL-1.0", true, true),
+    ("CDDL-1.1", false, false),
+    ("CDL-1.0", false, false),
+    ("CDLA-Permissive-1.0", false, false),
+    ("CDLA-Permissive-2.0", false, false),
+    ("CDLA-Sharing-1.0", false, false),
+    ("CECILL-1.0", false, false),
+    ("CECILL-1.1", false, false),
+    ("CECILL-2.0", false, true),
+    ("CECILL-2.1", true, false),
+    ("CECILL-B", false, true),
+    ("CECILL-C", false, true),
+    ("CERN-OHL-1.1", false, false),
+    ("CERN-OHL-1.2", false, false),
+    ("CERN-OHL-P-2.0", true, false),
+    ("CERN-OHL-S-2.0", true, false),
+    ("CERN-OHL-W-2.0", true, false),
+    ("CFITSIO", false, false),
+    ("check-cvs", false, false),
+    ("checkmk", false, false),
+    ("ClArtistic", false, true),
+    ("Clips", false, false),
+    ("CMU-Mach", false, false),
+    ("CMU-Mach-nodoc", false, false),
+    ("CNRI-Jython", false, false),
+    ("CNRI-Python", true, false),
+    ("CNRI-Python-GPL-Compatible", false, false),
+    ("COIL-1.0", false, false),
+    ("Community-Spec-1.0", false, false),
+    ("Condor-1.1", false, true),
+    ("copyleft-next-0.3.0", false, false),
+    ("copyleft-next-0.3.1", false, false),
+    ("Cornell-Lossless-JPEG", false, false),
+    ("CPAL-1.0", true, true),
+    ("CPL-1.0", true, true),
+    ("CPOL-1.02", false, false),
+    ("Cronyx", false, false),
+    ("Crossword", false, false),
+    ("CryptoSwift", false, false),
+    ("CrystalStacker", false, false),
+    ("CUA-OPL-1.0", true, false),
+    ("Cube", false, false),
+    ("curl", false, false),
+    ("cve-tou", false, false),
+    ("D-FSL-1.0", false, false),
+    ("DEC-3-Clause", false, false),
+    ("diffmark", false, false),
+    ("DL-DE-BY-2.0", false, false),
+    ("DL-DE-ZERO-2.0", false, false),
+    ("DOC", false, false),
+    ("DocBook-DTD", false, false),
+    ("DocBook-Schema", false, false),
+    ("DocBook-Stylesheet", false, false),
+    ("DocBook-XML", false, false),
+    ("Dotseqn", false, false),
+    ("DRL-1.0", false, false),
+    ("DRL-1.1", false, false),
+    ("DSDP", false, false),
+    ("dtoa", false, false),
+    ("dvipdfm", false, false),
+    ("ECL-1.0", true, false),
+    ("ECL-2.0", true, true),
+    ("eCos-2.0", false, true),
+    ("EFL-1.0", true, false),
+    ("EFL-2.0", true, true),
+    ("eGenix", false, false),
+    ("Elastic-2.0", false, false),
+    ("Entessa", true, false),
+    ("EPICS", false, false),
+    ("EPL-1.0", true, true),
+    ("EPL-2.0", true, true),
+    ("ErlPL-1.1", false, false),
+    ("etalab-2.0", false, false),
+    ("EUDatagrid", true, true),
+    ("EUPL-1.0", false, false),
+    ("EUPL-1.1", true, true),
+    ("EUPL-1.2", true, true),
+    ("Eurosym", false, false),
+    ("Fair", true, false),
+    ("FBM", false, false),
+    ("FDK-AAC", false, false),
+    ("Ferguson-Twofish", false, false),
+    ("Frameworx-1.0", true, false),
+    ("FreeBSD-DOC", false, false),
+    ("FreeImage", false, false),
+    ("FSFAP", false, true),
+    ("FSFAP-no-warranty-disclaimer", false, false),
+    ("FSFUL", false, false),
+    ("FSFULLR", false, false),
+    ("FSFULLRSD", false, false),
+    ("FSFULLRWD", false, false),
+    ("FSL-1.1-ALv2", false, false),
+    ("FSL-1.1-MIT", false, false),
+    ("FTL", false, true),
+    ("Furuseth", false, false),
+    ("fwlw", false, false),
+    ("Game-Programming-Gems", false, false),
+    ("GCR-docs", false, false),
+    ("GD", false, false),
+    ("generic-xts", false, false),
+    ("GFDL-1.1", false, true),
+    ("GFDL-1.1-invariants", false, false),
+    ("GFDL-1.1-invariants-only", false, false),
+    ("GFDL-1.1-invariants-or-later", false, false),
+    ("GFDL-1.1-no-invariants", false, false),
+    ("GFDL-1.1-no-invariants-only", false, false),
+    ("GFDL-1.1-no-invariants-or-later", false, false),
+    ("GFDL-1.1-only", false, true),
+    ("GFDL-1.1-or-later", false, true),
+    ("GFDL-1.2", false, true),
+    ("GFDL-1.2-invariants", false, false),
+    ("GFDL-1.2-invariants-only", false, false),
+    ("GFDL-1.2-invariants-or-later", false, false),
+    ("GFDL-1.2-no-invariants", false, false),
+    ("GFDL-1.2-no-invariants-only", false, false),
+    ("GFDL-1.2-no-invariants-or-later", false, false),
+    ("GFDL-1.2-only", false, true),
+    ("GFDL-1.2-or-later", false, true),
+    ("GFDL-1.3", false, true),
+    ("GFDL-1.3-invariants", false, false),
+    ("GFDL-1.3-invariants-only", false, false),
+    ("GFDL-1.3-invariants-or-later", false, false),
+    ("GFDL-1.3-no-invariants", false, false),
+    ("GFDL-1.3-no-invariants-only", false, false),
+    ("GFDL-1.3-no-invariants-or-later", false, false),
+    ("GFDL-1.3-only", false, true),
+    ("GFDL-1.3-or-later", false, true),
+    ("Giftware", false, false),
+    ("GL2PS", false, false),
+    ("Glide", false, false),
+    ("Glulxe", false, false),
+    ("GLWTPL", false, false),
+    ("gnuplot", false, true),
+    ("GPL-1.0", false, false),
+    ("GPL-1.0+", false, false),
+    ("GPL-1.0-only", false, false),
+    ("GPL-1.0-or-later", false, false),
+    ("GPL-2.0", true, true),
+    ("GPL-2.0+", true, true),
+    ("GPL-2.0-only", true, true),
+    ("GPL-2.0-or-later", true, true),
+    ("GPL-2.0-with-autoconf-exception", false, false),
+    ("GPL-2.0-with-bison-exception", false, false),
+    ("GPL-2.0-with-classpath-exception", false, false),
+    ("GPL-2.0-with-font-exception", false, false),
+    ("GPL-2.0-with-GCC-exception", false, false),
+    ("GPL-3.0", true, true),
+    ("GPL-3.0+", true, true),
+    ("GPL-3.0-only", true, true),
+    ("GPL-3.0-or-later", true, true),
+    ("GPL-3.0-with-autoconf-exception", false, false),
+    ("GPL-3.0-with-GCC-exception", true, false),
+    ("Graphics-Gems", false, false),
+    ("gSOAP-1.3b", false, false),
+    ("gtkbook", false, false),
+    ("Gutmann", false, false),
+    ("HaskellReport", false, false),
+    ("HDF5", false, false),
+    ("hdparm", false, false),
+    ("HIDAPI", false, false),
+    ("Hippocratic-2.1", false, false),
+    ("HP-1986", false, false),
+    ("HP-1989", false, false),
+    ("HPND", true, true),
+    ("HPND-DEC", false, false),
+    ("HPND-doc", false, false),
+    ("HPND-doc-sell", false, false),
+    ("HPND-export-US", false, false),
+    ("HPND-export-US-acknowledgement", false, false),
+    ("HPND-export-US-modify", false, false),
+    ("HPND-export2-US", false, false),
+    ("HPND-Fenneberg-Livingston", false, false),
+    ("HPND-INRIA-IMAG", false, false),
+    ("HPND-Intel", false, false),
+    ("HPND-Kevlin-Henney", false, false),
+    ("HPND-Markus-Kuhn", false, false),
+    ("HPND-merchantability-variant", false, false),
+    ("HPND-MIT-disclaimer", false, false),
+    ("HPND-Netrek", false, false),
+    ("HPND-Pbmplus", false, false),
+    ("HPND-sell-MIT-disclaimer-xserver", false, false),
+    ("HPND-sell-regexpr", false, false),
+    ("HPND-sell-variant", false, false),
+    ("HPND-sell-variant-MIT-disclaimer", false, false),
+    ("HPND-sell-variant-MIT-disclaimer-rev", false, false),
+    ("HPND-UC", false, false),
+    ("HPND-UC-export-US", false, false),
+    ("HTMLTIDY", false, false),
+    ("IBM-pibs", false, false),
+    ("ICU", true, false),
+    ("IEC-Code-Components-EULA", false, false),
+    ("IJG", false, true),
+    ("IJG-short", false, false),
+    ("ImageMagick", false, false),
+    ("iMatix", false, true),
+    ("Imlib2", false, true),
+    ("Info-ZIP", false, false),
+    ("Inner-Net-2.0", false, false),
+    ("InnoSetup", false, false),
+    ("Intel", true, true),
+    ("Intel-ACPI", false, false),
+    ("Interbase-1.0", false, false),
+    ("IPA", true, true),
+    ("IPL-1.0", true, true),
+    ("ISC", true, true),
+    ("ISC-Veillard", false, false),
+    ("Jam", true, false),
+    ("JasPer-2.0", false, false),
+    ("jove", false, false),
+    ("JPL-image", false, false),
+    ("JPNIC", false, false),
+    ("JSON", false, false),
+    ("Kastrup", false, false),
+    ("Kazlib", false, false),
+    ("Knuth-CTAN", false, false),
+    ("LAL-1.2", false, false),
+    ("LAL-1.3", false, false),
+    ("Latex2e", false, false),
+    ("Latex2e-translated-notice", false, false),
+    ("Leptonica", false, false),
+    ("LGPL-2.0", true, false),
+    ("LGPL-2.0+", true, false),
+    ("LGPL-2.0-only", true, false),
+    ("LGPL-2.0-or-later", true, false),
+    ("LGPL-2.1", true, true),
+    ("LGPL-2.1+", true, true),
+    ("LGPL-2.1-only", true, true),
+    ("LGPL-2.1-or-later", true, true),
+    ("LGPL-3.0", true, true),
+    ("LGPL-3.0+", true, true),
+    ("LGPL-3.0-only", true, true),
+    ("LGPL-3.0-or-later", true, true),
+    ("LGPLLR", false, false),
+    ("Libpng", false, false),
+    ("libpng-1.6.35", false, false),
+    ("libpng-2.0", false, false),
+    ("libselinux-1.0", false, false),
+    ("libtiff", false, false),
+    ("libutil-David-Nugent", false, false),
+    ("LiLiQ-P-1.1", true, false),
+    ("LiLiQ-R-1.1", true, false),
+    ("LiLiQ-Rplus-1.1", true, false),
+    ("Linux-man-pages-1-para", false, false),
+    ("Linux-man-pages-copyleft", false, false),
+    ("Linux-man-pages-copyleft-2-para", false, false),
+    ("Linux-man-pages-copyleft-var", false, false),
+    ("Linux-OpenIB", false, false),
+    ("LOOP", false, false),
+    ("LPD-document", false, false),
+    ("LPL-1.0", true, false),
+    ("LPL-1.02", true, true),
+    ("LPPL-1.0", false, false),
+    ("LPPL-1.1", false, false),
+    ("LPPL-1.2", false, true),
+    ("LPPL-1.3a", false, true),
+    ("LPPL-1.3c", true, false),
+    ("lsof", false, false),
+    ("Lucida-Bitmap-Fonts", false, false),
+    ("LZMA-SDK-9.11-to-9.20", false, false),
+    ("LZMA-SDK-9.22", false, false),
+    ("Mackerras-3-Clause", false, false),
+    ("Mackerras-3-Clause-acknowledgment", false, false),
+    ("magaz", false, false),
+    ("mailprio", false, false),
+    ("MakeIndex", false, false),
+    ("man2html", false, false),
+    ("Martin-Birgmeier", false, false),
+    ("McPhee-slideshow", false, false),
+    ("metamail", false, false),
+    ("Minpack", false, false),
+    ("MIPS", false, false),
+    ("MirOS", true, false),
+    ("MIT", true, true),
+    ("MIT-0", true, false),
+    ("MIT-advertising", false, false),
+    ("MIT-Click", false, false),
+    ("MIT-CMU", false, false),
+    ("MIT-enna", false, false),
+    ("MIT-feh", false, false),
+    ("MIT-Festival", false, false),
+    ("MIT-Khronos-old", false, false),
+    ("MIT-Modern-Variant", true, false),
+    ("MIT-open-group", false, false),
+    ("MIT-testregex", false, false),
+    ("MIT-Wu", false, false),
+    ("MITNFA", false, false),
+    ("MMIXware", false, false),
+    ("Motosoto", true, false),
+    ("MPEG-SSG", false, false),
+    ("mpi-permissive", false, false),
+    ("mpich2", false, false),
+    ("MPL-1.0", true, false),
+    ("MPL-1.1", true, true),
+    ("MPL-2.0", true, true),
+    ("MPL-2.0-no-copyleft-exception", true, false),
+    ("mplus", false, false),
+    ("MS-LPL", false, false),
+    ("MS-PL", true, true),
+    ("MS-RL", true, true),
+    ("MTLL", false, false),
+    ("MulanPSL-1.0", false, false),
+    ("MulanPSL-2.0", true, false),
+    ("Multics", true, false),
+    ("Mup", false, false),
+    ("NAIST-2003", false, false),
+    ("NASA-1.3", true, false),
+    ("Naumen", true, false),
+    ("NBPL-1.0", false, false),
+    ("NCBI-PD", false, false),
+    ("NCGL-UK-2.0", false, false),
+    ("NCL", false, false),
+    ("NCSA", true, true),
+    ("Net-SNMP", false, false),
+    ("NetCDF", false, false),
+    ("Newsletr", false, false),
+    ("NGPL", true, false),
+    ("ngrep", false, false),
+    ("NICTA-1.0", false, false),
+    ("NIST-PD", false, false),
+    ("NIST-PD-fallback", false, false),
+    ("NIST-Software", false, false),
+    ("NLOD-1.0", false, false),
+    ("NLOD-2.0", false, false),
+    ("NLPL", false, false),
+    ("NOASSERTION", false, false),
+    ("Nokia", true, true),
+    ("NOSL", false, true),
+    ("Noweb", false, false),
+    ("NPL-1.0", false, true),
+    ("NPL-1.1", false, true),
+    ("NPOSL-3.0", true, false),
+    ("NRL", false, false),
+    ("NTIA-PD", false, false),
+    ("NTP", true, false),
+    ("NTP-0", false, false),
+    ("Nunit", false, true),
+    ("O-UDA-1.0", false, false),
+    ("OAR", false, false),
+    ("OCCT-PL", false, false),
+    ("OCLC-2.0", true, false),
+    ("ODbL-1.0", false, true),
+    ("ODC-By-1.0", false, false),
+    ("OFFIS", false, false),
+    ("OFL-1.0", false, true),
+    ("OFL-1.0-no-RFN", false, false),
+    ("OFL-1.0-RFN", false, false),
+    ("OFL-1.1", true, true),
+    ("OFL-1.1-no-RFN", true, false),
+    ("OFL-1.1-RFN", true, false),
+    ("OGC-1.0", false, false),
+    ("OGDL-Taiwan-1.0", false, false),
+    ("OGL-Canada-2.0", false, false),
+    ("OGL-UK-1.0", false, false),
+    ("OGL-UK-2.0", false, false),
+    ("OGL-UK-3.0", false, false),
+    ("OGTSL", true, false),
+    ("OLDAP-1.1", false, false),
+    ("OLDAP-1.2", false, false),
+    ("OLDAP-1.3", false, false),
+    ("OLDAP-1.4", false, false),
+    ("OLDAP-2.0", false, false),
+    ("OLDAP-2.0.1", false, false),
+    ("OLDAP-2.1", false, false),
+    ("OLDAP-2.2", false, false),
+    ("OLDAP-2.2.1", false, false),
+    ("OLDAP-2.2.2", false, false),
+    ("OLDAP-2.3", false, true),
+    ("OLDAP-2.4", false, false),
+    ("OLDAP-2.5", false, false),
+    ("OLDAP-2.6", false, false),
+    ("OLDAP-2.7", false, true),
+    ("OLDAP-2.8", true, false),
+    ("OLFL-1.3", true, false),
+    ("OML", false, false),
+    ("OpenPBS-2.3", false, false),
+    ("OpenSSL", false, true),
+    ("OpenSSL-standalone", false, false),
+    ("OpenVision", false, false),
+    ("OPL-1.0", false, false),
+    ("OPL-UK-3.0", false, false),
+    ("OPUBL-1.0", false, false),
+    ("OSET-PL-2.1", true, false),
+    ("OSL-1.0", true, true),
+    ("OSL-1.1", false, true),
+    ("OSL-2.0", true, true),
+    ("OSL-2.1", true, true),
+    ("OSL-3.0", true, true),
+    ("PADL", false, false),
+    ("Parity-6.0.0", false, false),
+    ("Parity-7.0.0", false, false),
+    ("PDDL-1.0", false, false),
+    ("PHP-3.0", true, false),
+    ("PHP-3.01", true, true),
+    ("Pixar", false, false),
+    ("pkgconf", false, false),
+    ("Plexus", false, false),
+    ("pnmstitch", false, false),
+    ("PolyForm-Noncommercial-1.0.0", false, false),
+    ("PolyForm-Small-Business-1.0.0", false, false),
+    ("PostgreSQL", true, false),
+    ("PPL", false, false),
+    ("PSF-2.0", false, false),
+    ("psfrag", false, false),
+    ("psutils", false, false),
+    ("Python-2.0", true, true),
+    ("Python-2.0.1", false, false),
+    ("python-ldap", false, false),
+    ("Qhull", false, false),
+    ("QPL-1.0", true, true),
+    ("QPL-1.0-INRIA-2004", false, false),
+    ("radvd", false, false),
+    ("Rdisc", false, false),
+    ("RHeCos-1.1", false, false),
+    ("RPL-1.1", true, false),
+    ("RPL-1.5", true, false),
+    ("RPSL-1.0", true, true),
+    ("RSA-MD", false, false),
+    ("RSCPL", true, false),
+    ("Ruby", false, true),
+    ("Ruby-pty", false, false),
+    ("SAX-PD", false, false),
+    ("SAX-PD-2.0", false, false),
+    ("Saxpath", false, false),
+    ("SCEA", false, false),
+    ("SchemeReport", false, false),
+    ("Sendmail", false, false),
+    ("Sendmail-8.23", false, false),
+    ("Sendmail-Open-Source-1.1", false, false),
+    ("SGI-B-1.0", false, false),
+    ("SGI-B-1.1", false, false),
+    ("SGI-B-2.0", false, true),
+    ("SGI-OpenGL", false, false),
+    ("SGP4", false, false),
+    ("SHL-0.5", false, false),
+    ("SHL-0.51", false, false),
+    ("SimPL-2.0", true, false),
+    ("SISSL", true, true),
+    ("SISSL-1.2", false, false),
+    ("SL", false, false),
+    ("Sleepycat", true, true),
+    ("SMAIL-GPL", false, false),
+    ("SMLNJ", false, true),
+    ("SMPPL", false, false),
+    ("SNIA", false, false),
+    ("snprintf", false, false),
+    ("SOFA", false, false),
+    ("softSurfer", false, false),
+    ("Soundex", false, false),
+    ("Spencer-86", false, false),
+    ("Spencer-94", false, false),
+    ("Spencer-99", false, false),
+    ("SPL-1.0", true, true),
+    ("ssh-keyscan", false, false),
+    ("SSH-OpenSSH", false, false),
+    ("SSH-short", false, false),
+    ("SSLeay-standalone", false, false),
+    ("SSPL-1.0", false, false),
+    ("StandardML-NJ", false, true),
+    ("SugarCRM-1.1.3", false, false),
+    ("SUL-1.0", false, false),
+    ("Sun-PPP", false, false),
+    ("Sun-PPP-2000", false, false),
+    ("SunPro", false, false),
+    ("SWL", false, false),
+    ("swrule", false, false),
+    ("Symlinks", false, false),
+    ("TAPR-OHL-1.0", false, false),
+    ("TCL", false, false),
+    ("TCP-wrappers", false, false),
+    ("TermReadKey", false, false),
+    ("TGPPL-1.0", false, false),
+    ("ThirdEye", false, false),
+    ("threeparttable", false, false),
+    ("TMate", false, false),
+    ("TORQUE-1.1", false, false),
+    ("TOSL", false, false),
+    ("TPDL", false, false),
+    ("TPL-1.0", false, false),
+    ("TrustedQSL", false, false),
+    ("TTWL", false, false),
+    ("TTYP0", false, false),
+    ("TU-Berlin-1.0", false, false),
+    ("TU-Berlin-2.0", false, false),
+    ("Ubuntu-font-1.0", false, false),
+    ("UCAR", false, false),
+    ("UCL-1.0", true, false),
+    ("ulem", false, false),
+    ("UMich-Merit", false, false),
+    ("Unicode-3.0", true, false),
+    ("Unicode-DFS-2015", false, false),
+    ("Unicode-DFS-2016", true, false),
+    ("Unicode-TOU", false, false),
+    ("UnixCrypt", false, false),
+    ("Unlicense", true, true),
+    ("Unlicense-libtelnet", false, false),
+    ("Unlicense-libwhirlpool", false, false),
+    ("UPL-1.0", true, true),
+    ("URT-RLE", false, false),
+    ("Vim", false, true),
+    ("VOSTROM", false, false),
+    ("VSL-1.0", true, false),
+    ("W3C", true, true),
+    ("W3C-19980720", false, false),
+    ("W3C-20150513", true, false),
+    ("w3m", false, false),
+    ("Watcom-1.0", true, false),
+    ("Widget-Workshop", false, false),
+    ("Wsuipa", false, false),
+    ("WTFPL", false, true),
+    ("wwl", false, false),
+    ("wxWindows", true, false),
+    ("X11", false, true),
+    ("X11-distribute-modifications-variant", false, false),
+    ("X11-swapped", false, false),
+    ("Xdebug-1.03", false, false),
+    ("Xerox", false, false),
+    ("Xfig", false, false),
+    ("XFree86-1.1", false, true),
+    ("xinetd", false, true),
+    ("xkeyboard-config-Zinoviev", false, false),
+    ("xlock", false, false),
+    ("Xnet", true, false),
+    ("xpp", false, false),
+    ("XSkat", false, false),
+    ("xzoom", false, false),
+    ("YPL-1.0", false, false),
+    ("YPL-1.1", false, true),
+    ("Zed", false, false),
+    ("Zeeff", false, false),
+    ("Zend-2.0", false, true),
+    ("Zimbra-1.3", false, true),
+    ("Zimbra-1.4", false, false),
+    ("Zlib", true, true),
+    ("zlib-acknowledgement", false, false),
+    ("ZPL-1.1", false, false),
+    ("ZPL-2.0", true, true),
+    ("ZPL-2.1", true, true),
+];
+
+/// Group names accepted after `@`
+pub const GROUPS: &[&str] = &["osi-approved", "fsf-libre"];
+
+/// An id as the SPDX license list spells it, with its (OSI approved, FSF libre) flags. Ids
+/// compare case-insensitively.
+pub fn spdx_flags(id: &str) -> Option<(&'static str, bool, bool)> {
+    SPDX_LICENSE_FLAGS.iter().find(|(known, _, _)| known.eq_ignore_ascii_case(id)).copied()
+}
+
+/// The ids in a group, or None for an unknown group name
+fn group_ids(group: &str) -> Option<Vec<&'static str>> {
+    let flag: fn(&(&str, bool, bool)) -> bool = match group.to_lowercase().as_str() {
+        "osi-approved" => |(_, osi, _)| *osi,
+        "fsf-libre" => |(_, _, fsf)| *fsf,
+        _ => {
+            return None;
+        }
+    };
+    Some(
+        SPDX_LICENSE_FLAGS.iter()
+            .filter(|entry| flag(entry))
+            .map(|(id, _, _)| *id)
+            .collect()
+    )
+}
+
+/// Replace `@group` entries in an allow-list with the ids of the group. Literal ids and
+/// wildcards are kept as they are, and so are unknown groups, which match no license.
+pub fn expand(patterns: Vec<String>) -> Vec<String> {
+    let mut expanded: Vec<String> = Vec::new();
+    for pattern in patterns {
+        let ids = pattern.strip_prefix('@').and_then(group_ids);
+        match ids {
+            Some(ids) => expanded.extend(ids.into_iter().map(str::to_string)),
+            None => expanded.push(pattern),
+        }
+    }
+    expanded
+}
+
+/// Reject `@group` entries that don't name a known group
+pub fn validate(patterns: &[String]) -> Result<(), String> {
+    match patterns.iter().find(|pattern| pattern.strip_prefix('@').is_some_and(|group| group_ids(group).is_none())) {
+        Some(pattern) => {
+            let known: Vec<String> = GROUPS.iter().map(|group| format!("@{}", group)).collect();
+            Err(format!("unknown license group {} (known groups: {})", pattern, known.join(", ")))
+        }
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_expand_alongside_literals() {
+        let expanded = expand(vec!["@osi-approved".to_string(), "LicenseRef-*".to_string()]);
+        assert!(expanded.iter().any(|id| id == "MIT"));
+        assert!(expanded.iter().any(|id| id == "0BSD"));
+        assert!(!expanded.iter().any(|id| id == "WTFPL"));
+        assert_eq!(expanded.last().map(String::as_str), Some("LicenseRef-*"));
+
+        let fsf = expand(vec!["@FSF-Libre".to_string()]);
+        assert!(fsf.iter().any(|id| id == "WTFPL"));
+        assert!(!fsf.iter().any(|id| id == "0BSD"));
+
+        assert!(validate(&["@osi-approved".to_string(), "MIT".to_string()]).is_ok());
+        let err = validate(&["@oss".to_string()]).unwrap_err();
+        assert_eq!(err, "unknown license group @oss (known groups: @osi-approved, @fsf-libre)");
+    }
+
+    #[test]
+    fn test_every_spdx_id_is_known() {
+        assert_eq!(SPDX_LICENSE_FLAGS.len(), 706);
+        for id in ["CERN-OHL-P-2.0", "CERN-OHL-S-2.0", "CERN-OHL-W-2.0", "ICU", "Jam", "MIT-Modern-Variant"] {
+            assert_eq!(spdx_flags(id), Some((id, true, false)));
+        }
+        // Ids with neither flag are known too, and stay out of both groups
+        assert_eq!(spdx_flags("busl-1.1"), Some(("BUSL-1.1", false, false)));
+        let groups = expand(vec!["@osi-approved".to_string(), "@fsf-libre".to_string()]);
+        assert!(!groups.iter().any(|id| id == "BUSL-1.1"));
+    }
+}
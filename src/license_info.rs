@@ -0,0 +1,171 @@
+//! --normalize: show what the scanner makes of a raw license string, to debug why a license
+//! does or doesn't match an allow-list.
+
+use crate::license_checker::LicenseChecker;
+use crate::license_compat;
+use crate::license_detection;
+use crate::license_groups;
+use crate::package::Package;
+
+const OPERATORS: &[&str] = &["AND", "OR", "WITH"];
+
+/// A raw license string after normalization
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedLicense {
+    pub normalized: String, // The license as packages report it; allow-lists match this
+    pub canonical: String, // Spelled as the SPDX license list does, with deprecated ids replaced
+    pub spdx_expression: bool,
+    pub osi_approved: bool, // Every license id in the expression is OSI approved
+    pub fsf_libre: bool, // Every license id in the expression is FSF libre
+    pub categories: Vec<&'static str>, // Compatibility family of each license id, without repeats
+}
+
+pub fn normalize(raw: &str) -> NormalizedLicense {
+    let normalized = license_detection::normalize_license_id(raw.trim());
+    let ids = license_ids(&normalized);
+    let flags: Vec<Option<(&str, bool, bool)>> = ids
+        .iter()
+        .map(|id| license_groups::spdx_flags(id))
+        .collect();
+
+    let mut categories = Vec::new();
+    for id in &ids {
+        let category = license_compat::category(id);
+        if !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+
+    NormalizedLicense {
+        canonical: canonical_expression(&normalized),
+        spdx_expression: license_detection::is_spdx_expression(&normalized),
+        osi_approved: !ids.is_empty() && flags.iter().all(|flag| flag.is_some_and(|(_, osi, _)| osi)),
+        fsf_libre: !ids.is_empty() && flags.iter().all(|flag| flag.is_some_and(|(_, _, fsf)| fsf)),
+        categories,
+        normalized,
+    }
+}
+
+/// The --normalize report: the normalized forms, the license's flags and whether the
+/// configured policy allows it
+pub fn describe(raw: &str, license_checker: &LicenseChecker) -> String {
+    let license = normalize(raw);
+    let mut package = Package::new(String::new(), String::new(), String::new(), None);
+    package.license = license.normalized.clone();
+    let decision = license_checker.evaluate_package(&package);
+
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+    let rows = [
+        ("Raw", raw.to_string()),
+        ("Normalized", license.normalized.clone()),
+        ("Canonical SPDX", license.canonical.clone()),
+        ("SPDX expression", yes_no(license.spdx_expression).to_string()),
+        ("OSI approved", yes_no(license.osi_approved).to_string()),
+        ("FSF libre", yes_no(license.fsf_libre).to_string()),
+        ("Category", license.categories.join(", ")),
+        ("Allowed", format!("{} ({})", yes_no(decision.allowed), decision.reason)),
+    ];
+    rows.iter()
+        .map(|(label, value)| format!("{:<16} {}\n", format!("{}:", label), value))
+        .collect()
+}
+
+/// The license ids of an expression, leaving out parentheses, operators and `WITH` exceptions
+fn license_ids(license: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut after_with = false;
+    for token in license_compat::tokenize(license) {
+        if token == "(" || token == ")" {
+            continue;
+        }
+        if OPERATORS.iter().any(|operator| operator.eq_ignore_ascii_case(&token)) {
+            after_with = token.eq_ignore_ascii_case("WITH");
+        } else if after_with {
+            after_with = false;
+        } else {
+            ids.push(token);
+        }
+    }
+    ids
+}
+
+/// The expression with each id in its SPDX spelling and deprecated ids replaced
+fn canonical_expression(license: &str) -> String {
+    let mut canonical = String::new();
+    let mut token = String::new();
+    for c in license.chars() {
+        if c.is_whitespace() || c == '(' || c == ')' {
+            canonical.push_str(&canonical_token(&token));
+            token.clear();
+            canonical.push(c);
+        } else {
+            token.push(c);
+        }
+    }
+    canonical.push_str(&canonical_token(&token));
+    canonical
+}
+
+fn canonical_token(token: &str) -> String {
+    if let Some(operator) = OPERATORS.iter().find(|operator| operator.eq_ignore_ascii_case(token)) {
+        return operator.to_string();
+    }
+    let id = license_groups::spdx_flags(token).map_or(token, |(id, _, _)| id);
+    license_detection::canonical_spdx_id(id).unwrap_or(id).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_forms_normalize() {
+        let cases = [
+            ("mit", "MIT", "MIT", true, "permissive"),
+            (" Apache 2.0 ", "Apache-2.0", "Apache-2.0", true, "apache-2.0"),
+            ("GPL-3.0", "GPL-3.0", "GPL-3.0-only", true, "gpl-3.0"),
+            ("gplv2", "GPL-2.0", "GPL-2.0-only", true, "gpl-2.0-only"),
+            ("bsd", "BSD-3-Clause", "BSD-3-Clause", true, "permissive"),
+            ("(mit or lgpl-2.1)", "(mit or lgpl-2.1)", "(MIT OR LGPL-2.1-only)", true, "permissive, weak copyleft"),
+            ("WTFPL", "WTFPL", "WTFPL", false, "permissive"),
+            ("UNLICENSED", "PROPRIETARY", "PROPRIETARY", false, "other"),
+        ];
+        for (raw, normalized, canonical, osi_approved, categories) in cases {
+            let license = normalize(raw);
+            assert_eq!(license.normalized, normalized, "{}", raw);
+            assert_eq!(license.canonical, canonical, "{}", raw);
+            assert_eq!(license.osi_approved, osi_approved, "{}", raw);
+            assert_eq!(license.categories.join(", "), categories, "{}", raw);
+        }
+
+        let exception = normalize("GPL-2.0 WITH Classpath-exception-2.0");
+        assert_eq!(exception.canonical, "GPL-2.0-only WITH Classpath-exception-2.0");
+        assert!(exception.osi_approved);
+        assert!(!normalize("Copyright Acme Corp").spdx_expression);
+    }
+
+    #[test]
+    fn test_license_ids_follow_the_expression_tokenizer() {
+        assert_eq!(
+            license_ids("((MIT or Apache-2.0) AND GPL-2.0 with Classpath-exception-2.0)"),
+            vec!["MIT", "Apache-2.0", "GPL-2.0"]
+        );
+        // Words between operators are one id, as the compatibility check reads them
+        assert_eq!(license_ids("Copyright Acme Corp OR MIT"), vec!["Copyright Acme Corp", "MIT"]);
+    }
+
+    #[test]
+    fn test_describe_shows_the_allow_list_decision() {
+        let checker = LicenseChecker::new(vec!["MIT".to_string(), "Apache-*".to_string()]);
+        let report = describe("apache2", &checker);
+        assert!(report.contains("Normalized:      Apache-2.0\n"), "{}", report);
+        assert!(report.contains("OSI approved:    yes\n"), "{}", report);
+        assert!(report.contains("Allowed:         yes (matched pattern Apache-*)\n"), "{}", report);
+
+        // The allow-list matches the normalized form, so GPL-3.0-only doesn't match GPL-3.0
+        let checker = LicenseChecker::new(vec!["GPL-3.0-only".to_string()]);
+        let report = describe("gpl3", &checker);
+        assert!(report.contains("Canonical SPDX:  GPL-3.0-only\n"), "{}", report);
+        assert!(report.contains("Allowed:         no (no matching allow pattern)\n"), "{}", report);
+    }
+}
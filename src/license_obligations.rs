@@ -0,0 +1,155 @@
+//! What a license asks of those who distribute software that uses it.
+//!
+//! Covers the licenses in `license_urls` plus the AGPL. This is guidance for a compliance
+//! review, not legal advice: obligations are reduced to a few flags and license exceptions
+//! (`WITH ...`) are ignored.
+
+use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::license_compat::LicenseExpression;
+
+/// Obligations a license imposes when the software is distributed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Obligations {
+    pub attribution_required: bool, // Keep the copyright notice and license text
+    pub notice_required: bool, // Pass on the NOTICE file
+    pub source_disclosure: bool, // Offer the source of the licensed (or combined) work
+    pub network_use_triggers: bool, // Source disclosure also applies to users over a network
+}
+
+impl Obligations {
+    const NONE: Obligations = Obligations {
+        attribution_required: false,
+        notice_required: false,
+        source_disclosure: false,
+        network_use_triggers: false,
+    };
+    const ATTRIBUTION: Obligations = Obligations { attribution_required: true, ..Obligations::NONE };
+    const COPYLEFT: Obligations = Obligations { source_disclosure: true, ..Obligations::ATTRIBUTION };
+
+    /// Short descriptions of the obligations that apply, for text reports
+    pub fn describe(&self) -> Vec<&'static str> {
+        [
+            (self.attribution_required, "attribution"),
+            (self.notice_required, "NOTICE file"),
+            (self.source_disclosure, "source disclosure"),
+            (self.network_use_triggers, "network use triggers disclosure"),
+        ]
+            .into_iter()
+            .filter(|(applies, _)| *applies)
+            .map(|(_, description)| description)
+            .collect()
+    }
+
+    fn count(&self) -> usize {
+        self.describe().len()
+    }
+
+    fn union(self, other: Obligations) -> Obligations {
+        Obligations {
+            attribution_required: self.attribution_required || other.attribution_required,
+            notice_required: self.notice_required || other.notice_required,
+            source_disclosure: self.source_disclosure || other.source_disclosure,
+            network_use_triggers: self.network_use_triggers || other.network_use_triggers,
+        }
+    }
+}
+
+// Map of SPDX license identifiers to their obligations
+pub static OBLIGATIONS: Lazy<HashMap<&'static str, Obligations>> = Lazy::new(|| {
+    let mut map = HashMap::new();
+
+    // Permissive licenses only ask to keep the notice
+    for id in ["MIT", "ISC", "BSD-2-Clause", "BSD-3-Clause", "Zlib"] {
+        map.insert(id, Obligations::ATTRIBUTION);
+    }
+    map.insert("Apache-2.0", Obligations { notice_required: true, ..Obligations::ATTRIBUTION });
+
+    // Public domain dedications and licenses without conditions
+    for id in ["0BSD", "CC0-1.0", "Unlicense", "WTFPL"] {
+        map.insert(id, Obligations::NONE);
+    }
+
+    // Copyleft, weak (file or library level) and strong alike
+    for id in [
+        "GPL-2.0",
+        "GPL-2.0-only",
+        "GPL-2.0-or-later",
+        "GPL-3.0",
+        "GPL-3.0-only",
+        "GPL-3.0-or-later",
+        "LGPL-2.1",
+        "LGPL-3.0",
+        "MPL-2.0",
+        "CDDL-1.0",
+        "EPL-2.0",
+    ] {
+        map.insert(id, Obligations::COPYLEFT);
+    }
+    for id in ["AGPL-3.0", "AGPL-3.0-only", "AGPL-3.0-or-later"] {
+        map.insert(id, Obligations { network_use_triggers: true, ..Obligations::COPYLEFT });
+    }
+
+    map
+});
+
+fn obligations_for_id(license: &str) -> Option<Obligations> {
+    let license = crate::license_detection::normalize_license_id(license);
+    OBLIGATIONS.iter()
+        .find(|(id, _)| id.eq_ignore_ascii_case(&license))
+        .map(|(_, obligations)| *obligations)
+}
+
+/// The obligations of a license or SPDX expression, or None when part of it is unknown.
+/// Every AND component applies; of OR alternatives, the one with the fewest obligations
+/// is assumed to be chosen.
+pub fn obligations_for(license: &str) -> Option<Obligations> {
+    expression_obligations(&LicenseExpression::parse(license)?)
+}
+
+fn expression_obligations(expression: &LicenseExpression) -> Option<Obligations> {
+    match expression {
+        LicenseExpression::License { id, .. } => obligations_for_id(id),
+        LicenseExpression::And(components) => components
+            .iter()
+            .try_fold(Obligations::NONE, |all, component| Some(all.union(expression_obligations(component)?))),
+        LicenseExpression::Or(alternatives) => alternatives
+            .iter()
+            .map(expression_obligations)
+            .collect::<Option<Vec<Obligations>>>()?
+            .into_iter()
+            .min_by_key(Obligations::count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_license_obligations() {
+        let agpl = obligations_for("AGPL-3.0-only").unwrap();
+        assert!(agpl.network_use_triggers);
+        assert!(agpl.source_disclosure);
+
+        let mit = obligations_for("MIT").unwrap();
+        assert!(mit.attribution_required);
+        assert!(!mit.source_disclosure && !mit.network_use_triggers && !mit.notice_required);
+        assert_eq!(mit.describe(), vec!["attribution"]);
+
+        assert!(obligations_for("Apache-2.0").unwrap().notice_required);
+        assert_eq!(obligations_for("Unlicense"), Some(Obligations::NONE));
+
+        // AND adds up, OR takes the lighter alternative
+        assert_eq!(obligations_for("MIT AND Apache-2.0"), obligations_for("Apache-2.0"));
+        assert_eq!(obligations_for("(MIT OR GPL-3.0)"), Some(mit));
+        assert_eq!(obligations_for("MIT OR Custom-License"), None);
+
+        // Grouping is kept: GPL-3.0 applies whichever alternative is chosen
+        let grouped = obligations_for("GPL-3.0-only AND (MIT OR Apache-2.0)").unwrap();
+        assert_eq!(grouped, obligations_for("GPL-3.0-only").unwrap().union(mit));
+        assert_eq!(obligations_for("UNKNOWN"), None);
+    }
+}
@@ -0,0 +1,211 @@
+//! Licenses assigned by hand to packages whose license the registries don't give, kept in the
+//! config file's `license-overrides` list (written by --interactive) so later scans use them.
+
+use serde::{ Deserialize, Serialize };
+use std::fs;
+use std::path::Path;
+
+use crate::config::{ Config, ConfigFormat };
+use crate::package::{ parse_license_expiration, Package };
+
+const CONFIG_KEY: &str = "license-overrides";
+
+/// The license to report for a package, whatever its lookup found
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LicenseOverride {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>, // None sets the license of every version
+    pub license: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>, // When a time-limited (e.g. commercial) license runs out, as YYYY-MM-DD
+}
+
+impl LicenseOverride {
+    fn matches(&self, package: &Package) -> bool {
+        self.name == package.name && self.version.as_ref().is_none_or(|version| *version == package.version)
+    }
+}
+
+/// Set the license of every package an override names; the first matching override wins
+pub fn apply(packages: &mut [Package], overrides: &[LicenseOverride]) {
+    for package in packages {
+        let Some(license_override) = overrides.iter().find(|o| o.matches(package)) else {
+            continue;
+        };
+        package.license = license_override.license.clone();
+        package.license_url = crate::license_urls::get_license_url(&package.license);
+        package.license_source = None;
+        package.confidence = 1.0;
+        package.debug_info = Some("License set by a license-overrides entry in the config file".to_string());
+        package.license_expiration = license_override.expires.as_deref().and_then(|expires| {
+            let date = parse_license_expiration(expires);
+            if date.is_none() {
+                eprintln!(
+                    "Warning: Ignoring license-overrides expiration '{}' for {}: not a date",
+                    expires,
+                    package.name
+                );
+            }
+            date.map(|date| date.format("%Y-%m-%d").to_string())
+        });
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct OverridesTable<'a> {
+    license_overrides: &'a [LicenseOverride],
+}
+
+/// Add overrides to a config file, creating it if needed. The entries are appended to the text,
+/// so the rest of the file, comments included, is kept as it was.
+pub fn append_to_config(path: &Path, overrides: &[LicenseOverride]) -> Result<(), String> {
+    let format = ConfigFormat::from_path(path)?;
+    let existing = if path.exists() {
+        fs::read_to_string(path).map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?
+    } else {
+        String::new()
+    };
+
+    let updated = match format {
+        ConfigFormat::Toml => {
+            let entries = toml::to_string(&OverridesTable { license_overrides: overrides }).map_err(|e| e.to_string())?;
+            format!("{}\n{}", with_trailing_newline(&existing), entries)
+        }
+        ConfigFormat::Yaml => append_yaml_entries(&existing, overrides)?,
+    };
+
+    // A file that lists its overrides in another form, e.g. an inline array, isn't rewritten
+    Config::parse(&updated, format).map_err(|e| {
+        format!("Could not add {} to {}: {}", CONFIG_KEY, path.display(), e)
+    })?;
+    fs::write(path, updated).map_err(|e| format!("Failed to write config file {}: {}", path.display(), e))
+}
+
+/// Insert the entries at the top of an existing `license-overrides:` list, or start the list
+fn append_yaml_entries(existing: &str, overrides: &[LicenseOverride]) -> Result<String, String> {
+    let entries = serde_yaml::to_string(overrides).map_err(|e| e.to_string())?;
+    let lines: Vec<&str> = existing.lines().collect();
+    let key_line = format!("{}:", CONFIG_KEY);
+
+    let Some(key_index) = lines.iter().position(|line| line.trim_end() == key_line) else {
+        return Ok(format!("{}{}\n{}", with_trailing_newline(existing), key_line, indent(&entries, "  ")));
+    };
+
+    // Entries go at the indentation the list already uses
+    let item_indent: String = lines[key_index + 1..]
+        .iter()
+        .find(|line| !line.trim().is_empty())
+        .filter(|line| line.trim_start().starts_with("- "))
+        .map(|line| line.chars().take_while(|c| *c == ' ').collect())
+        .unwrap_or_default();
+
+    let mut updated: Vec<String> = lines[..=key_index].iter().map(|line| line.to_string()).collect();
+    updated.push(indent(&entries, &item_indent).trim_end().to_string());
+    updated.extend(lines[key_index + 1..].iter().map(|line| line.to_string()));
+    Ok(format!("{}\n", updated.join("\n")))
+}
+
+fn indent(text: &str, prefix: &str) -> String {
+    text.lines().map(|line| format!("{}{}\n", prefix, line)).collect()
+}
+
+fn with_trailing_newline(text: &str) -> String {
+    if text.is_empty() || text.ends_with('\n') { text.to_string() } else { format!("{}\n", text) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn license_override(name: &str, version: Option<&str>, license: &str) -> LicenseOverride {
+        LicenseOverride {
+            name: name.to_string(),
+            version: version.map(str::to_string),
+            license: license.to_string(),
+            expires: None,
+        }
+    }
+
+    #[test]
+    fn test_overrides_set_the_license_of_matching_packages() {
+        let mut packages: Vec<Package> = [("left-pad", "1.3.0"), ("left-pad", "1.2.0"), ("internal-ui", "2.0.0")]
+            .iter()
+            .map(|(name, version)| Package::new(name.to_string(), version.to_string(), String::new(), None))
+            .collect();
+        let overrides = [
+            license_override("left-pad", Some("1.3.0"), "WTFPL"),
+            license_override("internal-ui", None, "LicenseRef-Proprietary"),
+        ];
+
+        apply(&mut packages, &overrides);
+        assert_eq!(packages[0].license, "WTFPL");
+        assert!(packages[0].license_url.is_some());
+        assert!(packages[1].is_unknown());
+        assert_eq!(packages[2].license, "LicenseRef-Proprietary");
+        assert_eq!(packages[2].confidence, 1.0);
+    }
+
+    #[test]
+    fn test_overrides_set_the_license_expiration() {
+        let today = chrono::NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let mut packages: Vec<Package> = ["Vendor.Grid", "Vendor.Charts", "Vendor.Maps"]
+            .iter()
+            .map(|name| Package::new(name.to_string(), "4.2.0".to_string(), String::new(), None))
+            .collect();
+        let expiring = |name: &str, expires: &str| LicenseOverride {
+            expires: Some(expires.to_string()),
+            ..license_override(name, None, "LicenseRef-Vendor-EULA")
+        };
+
+        apply(
+            &mut packages,
+            &[
+                expiring("Vendor.Grid", "2025-03-31T00:00:00Z"),
+                expiring("Vendor.Charts", "2030-01-01"),
+                expiring("Vendor.Maps", "next spring"),
+            ]
+        );
+        assert_eq!(packages[0].license_expiration.as_deref(), Some("2025-03-31"));
+        assert!(packages[0].license_expired(today));
+        assert_eq!(packages[1].license_expiration.as_deref(), Some("2030-01-01"));
+        assert!(!packages[1].license_expired(today));
+        // Unparseable dates are ignored rather than guessed at
+        assert_eq!(packages[2].license_expiration, None);
+        assert!(!packages[2].license_expired(today));
+    }
+
+    #[test]
+    fn test_append_keeps_the_rest_of_the_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let decisions = [license_override("left-pad", Some("1.3.0"), "MIT")];
+
+        let toml_path = dir.path().join(".licenserc.toml");
+        fs::write(&toml_path, "# Team policy\nallowed = [\"MIT\"]").unwrap();
+        append_to_config(&toml_path, &decisions).unwrap();
+        append_to_config(&toml_path, &[license_override("ui-kit", None, "ISC")]).unwrap();
+        let content = fs::read_to_string(&toml_path).unwrap();
+        assert!(content.starts_with("# Team policy\nallowed = [\"MIT\"]\n"));
+        let config = Config::load(&toml_path).unwrap();
+        assert_eq!(config.allowed, ["MIT"]);
+        assert_eq!(config.license_overrides, [decisions[0].clone(), license_override("ui-kit", None, "ISC")]);
+
+        // YAML: a new list, then entries added to the existing one
+        let yaml_path = dir.path().join(".licenserc.yml");
+        append_to_config(&yaml_path, &decisions).unwrap();
+        let yaml_existing = "allowed: [MIT]\nlicense-overrides:\n- name: ui-kit\n  license: ISC\ndeep-scan: true\n";
+        fs::write(&yaml_path, yaml_existing).unwrap();
+        append_to_config(&yaml_path, &decisions).unwrap();
+        let config = Config::load(&yaml_path).unwrap();
+        assert_eq!(config.license_overrides, [decisions[0].clone(), license_override("ui-kit", None, "ISC")]);
+        assert!(config.deep_scan);
+
+        // Overrides written inline can't be extended by appending
+        let inline_path = dir.path().join("inline.toml");
+        fs::write(&inline_path, "license-overrides = []\n").unwrap();
+        let err = append_to_config(&inline_path, &decisions).unwrap_err();
+        assert!(err.starts_with("Could not add license-overrides to"), "{}", err);
+        assert_eq!(fs::read_to_string(&inline_path).unwrap(), "license-overrides = []\n");
+    }
+}
@@ -0,0 +1,203 @@
+//! Cross-checking a package's license across every source that declares or detects one
+//! (--cross-check-licenses): the resolver's lookup, the package archive's package.json and
+//! LICENSE file, and the license GitHub or GitLab detects in its repository. Sources that
+//! disagree are a conflict to review; the license from the most confident source is the one
+//! reported.
+
+use crate::license_detection;
+use crate::package::{ LicenseCandidate, LicenseSource, Package };
+
+/// Confidence in GitHub's license detection, which matches the whole LICENSE text: above our
+/// own pattern matches, below a declaration
+pub const GITHUB_DETECTED_CONFIDENCE: f32 = 0.8;
+
+/// Whether two licenses name the same license, e.g. `MIT` and `(MIT OR Apache-2.0)`
+fn agree(a: &str, b: &str) -> bool {
+    license_detection::declared_matches_detected(a, b) || license_detection::declared_matches_detected(b, a)
+}
+
+/// Whether any two sources give licenses that don't agree
+pub fn sources_conflict(candidates: &[LicenseCandidate]) -> bool {
+    candidates
+        .iter()
+        .enumerate()
+        .any(|(i, a)| candidates[i + 1..].iter().any(|b| !agree(&a.license, &b.license)))
+}
+
+/// The most confident candidate; ties go to the more authoritative source
+pub fn best_candidate(candidates: &[LicenseCandidate]) -> Option<&LicenseCandidate> {
+    candidates
+        .iter()
+        .max_by(|a, b| a.confidence.total_cmp(&b.confidence).then_with(|| b.source.cmp(&a.source)))
+}
+
+/// Record the candidates on a resolved package and report the best one's license
+pub fn apply_candidates(package: &mut Package, candidates: Vec<LicenseCandidate>) {
+    if let Some(best) = best_candidate(&candidates) {
+        if best.license != package.license {
+            package.license_url = crate::license_urls::get_license_url(&best.license).or(package.license_url.take());
+        }
+        package.license = best.license.clone();
+        package.license_source = Some(best.source);
+        package.confidence = best.confidence;
+    }
+    package.license_candidates = candidates;
+}
+
+/// Whether a cached result predates --cross-check-licenses and has to be looked up again
+pub fn needs_cross_check(package: &Package) -> bool {
+    package.license_source.is_some() && package.license_candidates.is_empty()
+}
+
+/// The license every source gives for a resolved package: the resolver's own, the package
+/// archive's, and the one detected in its GitHub or GitLab repository
+pub fn gather_candidates(resolved: &Package) -> Vec<LicenseCandidate> {
+    let mut candidates: Vec<LicenseCandidate> = resolved.license_source
+        .map(|source| LicenseCandidate { source, license: resolved.license.clone(), confidence: resolved.confidence })
+        .into_iter()
+        .collect();
+
+    if let Some(url) = archive_url(resolved) {
+        let client = crate::http_retry::shared_client();
+        let _permit = crate::archive_handler::download_permit();
+        match crate::archive_handler::extract_info_from_archive(client, &url, resolved.strongest_integrity()) {
+            Ok(info) => candidates.extend(info.candidates),
+            Err(e) => eprintln!("INFO: Failed to scan package archive {} for its license: {}", url, e),
+        }
+    }
+
+    let repository = resolved.repository.as_deref().unwrap_or(&resolved.url);
+    if let Ok((owner, repo, _)) = crate::github_api::extract_github_details(repository) {
+        match crate::github_api::repository_license(&owner, &repo) {
+            Ok(Some(license)) => {
+                candidates.push(LicenseCandidate {
+                    source: LicenseSource::Github,
+                    license,
+                    confidence: GITHUB_DETECTED_CONFIDENCE,
+                });
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("INFO: Failed to get the GitHub license of {}/{}: {}", owner, repo, e),
+        }
+    } else if let Some(project) = crate::gitlab_api::project_for_url(repository) {
+        if let Some((license, confidence)) = crate::gitlab_api::repository_license(&project) {
+            candidates.push(LicenseCandidate { source: LicenseSource::LicenseText, license, confidence });
+        }
+    }
+
+    // A source read twice, e.g. an archive the lookup already fell back to, counts once
+    let mut seen = Vec::new();
+    candidates.retain(|candidate| {
+        let key = (candidate.source, candidate.license.clone());
+        let first = !seen.contains(&key);
+        seen.push(key);
+        first
+    });
+    candidates
+}
+
+/// The archive a package is published as: its resolution when that is an archive URL, or the
+/// registry tarball of an npm package
+fn archive_url(package: &Package) -> Option<String> {
+    // Yarn resolutions carry the checksum after a '#'
+    let resolution = package.resolution.split('#').next().unwrap_or_default();
+    if resolution.starts_with("http") && crate::archive_handler::is_archive_url(resolution) {
+        Some(resolution.to_string())
+    } else if package.registry == "npm" {
+        Some(crate::github_api::npm_tarball_url(&package.name, &package.version))
+    } else {
+        None
+    }
+}
+
+/// Each source with its license, e.g. "registry: MIT, license-file: ISC"
+pub fn describe(candidates: &[LicenseCandidate]) -> String {
+    candidates
+        .iter()
+        .map(|candidate| format!("{}: {}", candidate.source.as_str(), candidate.license))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The --warn-on-network-fallback warning for a license found past the resolver's primary lookup
+pub fn fallback_warning(package: &Package) -> Option<String> {
+    let source = package.license_source.filter(|_| package.license_fallback)?;
+    Some(
+        format!(
+            "Warning: the license of {}@{} ({}) was found through a fallback ({}), not the primary lookup",
+            package.name,
+            package.version,
+            package.license,
+            source.as_str()
+        )
+    )
+}
+
+/// A candidate for a license declared in metadata, or None when the source has no license
+pub fn declared(source: LicenseSource, license: &str) -> Option<LicenseCandidate> {
+    let license = license_detection::normalize_license_id(license);
+    let confidence = license_detection::declared_confidence(&license);
+    (confidence > 0.0).then_some(LicenseCandidate { source, license, confidence })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(source: LicenseSource, license: &str, confidence: f32) -> LicenseCandidate {
+        LicenseCandidate { source, license: license.to_string(), confidence }
+    }
+
+    #[test]
+    fn test_disagreeing_sources_conflict() {
+        let candidates = vec![
+            candidate(LicenseSource::LicenseFile, "BSD-3-Clause", 0.75),
+            candidate(LicenseSource::Registry, "MIT", 1.0),
+            candidate(LicenseSource::PackageManifest, "ISC", 1.0)
+        ];
+        assert!(sources_conflict(&candidates));
+        // The registry wins the tie with the package.json
+        assert_eq!(best_candidate(&candidates), Some(&candidates[1]));
+
+        let mut package = Package::new("left-pad".to_string(), "1.3.0".to_string(), String::new(), None);
+        package.license = "BSD-3-Clause".to_string();
+        apply_candidates(&mut package, candidates);
+        assert_eq!(package.license, "MIT");
+        assert_eq!(package.license_source, Some(LicenseSource::Registry));
+        assert_eq!(package.license_url.as_deref(), Some("https://opensource.org/licenses/MIT"));
+        assert_eq!(
+            describe(&package.license_candidates),
+            "license-file: BSD-3-Clause, registry: MIT, package-manifest: ISC"
+        );
+    }
+
+    #[test]
+    fn test_agreeing_sources_do_not_conflict() {
+        let candidates = vec![
+            candidate(LicenseSource::Registry, "(MIT OR Apache-2.0)", 1.0),
+            candidate(LicenseSource::Github, "MIT", GITHUB_DETECTED_CONFIDENCE),
+            candidate(LicenseSource::LicenseFile, "MIT", 0.75)
+        ];
+        assert!(!sources_conflict(&candidates));
+        assert!(!sources_conflict(&candidates[..1]));
+        assert!(!sources_conflict(&[]));
+
+        assert_eq!(declared(LicenseSource::PackageManifest, "mit").unwrap().license, "MIT");
+        assert!(declared(LicenseSource::Registry, "UNKNOWN").is_none());
+    }
+
+    #[test]
+    fn test_archive_of_each_registry() {
+        let resolution = "left-pad@npm:1.3.0".to_string();
+        let mut package = Package::new("left-pad".to_string(), "1.3.0".to_string(), resolution, None);
+        package.registry = "npm".to_string();
+        assert_eq!(archive_url(&package).as_deref(), Some("https://registry.npmjs.org/left-pad/-/left-pad-1.3.0.tgz"));
+
+        package.resolution = "https://npm.example.com/left-pad/-/left-pad-1.3.0.tgz#abc123".to_string();
+        assert_eq!(archive_url(&package).as_deref(), Some("https://npm.example.com/left-pad/-/left-pad-1.3.0.tgz"));
+
+        let mut requests = Package::new("requests".to_string(), "2.31.0".to_string(), String::new(), None);
+        requests.registry = "pypi".to_string();
+        assert_eq!(archive_url(&requests), None);
+    }
+}
@@ -1,3 +1,4 @@
+use clap::ValueEnum;
 use serde::{ Serialize, Deserialize };
 use crate::package::Package;
 use std::fs;
@@ -25,19 +26,52 @@ impl LockfilePackage {
     }
 }
 
-pub fn parse_lockfile(path: &Path) -> Result<Vec<Package>, String> {
+/// Ecosystems selectable with --ecosystem, overriding detection by file name
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ecosystem {
+    /// npm package-lock.json or npm-shrinkwrap.json
+    Npm,
+    /// Yarn yarn.lock
+    Yarn,
+    /// Python poetry.lock
+    Poetry,
+    /// NuGet .csproj, packages.config or paket.lock
+    Nuget,
+}
+
+impl Ecosystem {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Ecosystem::Npm => "npm",
+            Ecosystem::Yarn => "yarn",
+            Ecosystem::Poetry => "poetry",
+            Ecosystem::Nuget => "nuget",
+        }
+    }
+}
+
+/// How lockfiles are read, from the scan's flags
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    pub use_nuget_license: bool, // Use the external nuget-license tool for .csproj files
+    pub ecosystem: Option<Ecosystem>, // Parser to use whatever the file name (--ecosystem)
+    pub allow_prerelease: bool, // Let package.json ranges resolve to pre-release versions
+}
+
+pub fn parse_lockfile(path: &Path, options: &ParseOptions) -> Result<Vec<Package>, String> {
+    let ParseOptions { use_nuget_license, ecosystem, allow_prerelease } = *options;
+
     // Check if file exists
     if !path.exists() || !path.is_file() {
         return Err(format!("File not found: {}", path.display()));
     }
 
     // Read the file content
-    let content = match fs::read_to_string(path) {
-        Ok(content) => content,
-        Err(e) => {
-            return Err(format!("Failed to read file: {}", e));
-        }
-    };
+    let content = read_lockfile(path)?;
+
+    if let Some(ecosystem) = ecosystem {
+        return parse_as_ecosystem(path, &content, ecosystem, use_nuget_license);
+    }
 
     // Determine file type by extension and parse accordingly
     let file_name = path.file_name().unwrap_or_default().to_string_lossy();
@@ -47,7 +81,11 @@ pub fn parse_lockfile(path: &Path) -> Result<Vec<Package>, String> {
 
     if file_name == "yarn.lock" {
         packages = parsers::yarn_parser::parse_yarn_lock(&content);
-    } else if file_name == "package-lock.json" {
+    } else if file_name == parsers::yarn_pnp_parser::PNP_DATA_FILE {
+        packages = parsers::yarn_pnp_parser::parse_pnp_data(&content)?;
+    } else if file_name == parsers::yarn_pnp_parser::PNP_LOADER_FILE {
+        packages = parsers::yarn_pnp_parser::parse_pnp_loader(&content)?;
+    } else if file_name == "package-lock.json" || file_name == parsers::npm_parser::SHRINKWRAP_FILE {
         packages = parsers::npm_parser::parse_package_lock(&content);
     } else if file_name == "poetry.lock" {
         packages = parsers::poetry_parser::parse_poetry_lock(&content);
@@ -55,7 +93,7 @@ pub fn parse_lockfile(path: &Path) -> Result<Vec<Package>, String> {
         // Also try to parse pyproject.toml if it exists in the same directory
         let pyproject_path = path.parent().unwrap().join("pyproject.toml");
         if pyproject_path.exists() && pyproject_path.is_file() {
-            if let Ok(pyproject_content) = fs::read_to_string(&pyproject_path) {
+            if let Ok(pyproject_content) = read_lockfile(&pyproject_path) {
                 if
                     let Ok(pyproject_packages) = parsers::poetry_parser::parse_pyproject_toml(
                         &pyproject_content
@@ -76,13 +114,31 @@ pub fn parse_lockfile(path: &Path) -> Result<Vec<Package>, String> {
                 }
             }
         }
-    } else if file_name == "pnpm-lock.yaml" {
-        return Err("pnpm-lock.yaml support is coming soon!".to_string());
+    } else if file_name == parsers::requirements_parser::REQUIREMENTS_FILE {
+        // Read by path, since -r includes are relative to the file
+        packages = parsers::requirements_parser::parse_requirements(path)?;
+    } else if file_name == parsers::pnpm_parser::PNPM_LOCK_FILE {
+        packages = parsers::pnpm_parser::parse_pnpm_lock(&content)?;
     } else if file_name == "bun.lock" {
         return Err("bun.lock support is coming soon!".to_string());
+    } else if file_name == "package.json" {
+        // Only scanned with --from-manifest; versions are ranges resolved against the registry
+        packages = parsers::manifest_parser::parse_package_json(&content, |name, range| {
+            match crate::npm_api::resolve_version_range(name, range, allow_prerelease) {
+                Ok(version) => version,
+                Err(e) => {
+                    eprintln!("INFO: Failed to resolve {}@{}: {}", name, range, e);
+                    None
+                }
+            }
+        })?;
+    } else if file_name == "packages.config" {
+        packages = parsers::nuget_parser::parse_packages_config(&content);
+    } else if file_name == "paket.lock" {
+        packages = parsers::nuget_parser::parse_paket_lock(&content);
     } else if extension == "csproj" {
         // For .csproj files, we pass the path directly to the nuget parser
-        packages = parsers::nuget_parser::parse_csproj(path)?;
+        packages = parsers::nuget_parser::parse_csproj(path, use_nuget_license)?;
     } else {
         return Err(format!("Unsupported lock file format: {}", file_name));
     }
@@ -90,9 +146,121 @@ pub fn parse_lockfile(path: &Path) -> Result<Vec<Package>, String> {
     Ok(packages)
 }
 
+/// Read a lockfile as text. Files saved on Windows often start with a byte order mark, and
+/// some are UTF-16; the mark is dropped and UTF-16 is decoded, so parsers only see the text.
+pub fn read_lockfile(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let (encoding, bom_length) = encoding_rs::Encoding::for_bom(&bytes).unwrap_or((encoding_rs::UTF_8, 0));
+    encoding
+        .decode_without_bom_handling_and_without_replacement(&bytes[bom_length..])
+        .map(|content| content.into_owned())
+        .ok_or_else(|| {
+            format!(
+                "Failed to read file: {} is not valid {} (UTF-16 files need a byte order mark)",
+                path.display(),
+                encoding.name()
+            )
+        })
+}
+
+/// Parse a file with the parser of a forced ecosystem, whatever its name
+fn parse_as_ecosystem(
+    path: &Path,
+    content: &str,
+    ecosystem: Ecosystem,
+    use_nuget_license: bool
+) -> Result<Vec<Package>, String> {
+    let packages = match ecosystem {
+        Ecosystem::Npm => parsers::npm_parser::parse_package_lock(content),
+        Ecosystem::Yarn => parsers::yarn_parser::parse_yarn_lock(content),
+        Ecosystem::Poetry => parsers::poetry_parser::parse_poetry_lock(content),
+        Ecosystem::Nuget => {
+            if path.extension().is_some_and(|ext| ext == "csproj") {
+                parsers::nuget_parser::parse_csproj(path, use_nuget_license)?
+            } else if content.trim_start().starts_with('<') {
+                parsers::nuget_parser::parse_packages_config(content)
+            } else {
+                parsers::nuget_parser::parse_paket_lock(content)
+            }
+        }
+    };
+
+    // The parsers skip what they can't read, so finding nothing means the file isn't in this format
+    if packages.is_empty() {
+        return Err(
+            format!("{} could not be parsed as a {} lockfile", path.display(), ecosystem.as_str())
+        );
+    }
+    Ok(packages)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parsers::yarn_parser::extract_package_name;
+
+    const POETRY_LOCK: &str =
+        r#"
+[[package]]
+name = "requests"
+version = "2.31.0"
+description = "Python HTTP for Humans."
+"#;
+
+    #[test]
+    fn test_forced_ecosystem_overrides_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        // Named like an npm lockfile, but holding poetry content
+        let path = dir.path().join("package-lock.json");
+        fs::write(&path, POETRY_LOCK).unwrap();
+
+        assert!(parse_lockfile(&path, &ParseOptions::default()).unwrap().is_empty());
+
+        let forced = |ecosystem| ParseOptions { ecosystem: Some(ecosystem), ..ParseOptions::default() };
+        let packages = parse_lockfile(&path, &forced(Ecosystem::Poetry)).unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].name, "requests");
+        assert_eq!(packages[0].registry, "pypi");
+
+        let err = parse_lockfile(&path, &forced(Ecosystem::Npm)).unwrap_err();
+        assert!(err.contains("could not be parsed as a npm lockfile"), "{}", err);
+    }
+
+    #[test]
+    fn test_byte_order_marks_and_utf16_are_decoded() {
+        let dir = tempfile::tempdir().unwrap();
+        let poetry_lock = dir.path().join("poetry.lock");
+        fs::write(&poetry_lock, [b"\xEF\xBB\xBF", POETRY_LOCK.as_bytes()].concat()).unwrap();
+        let packages = parse_lockfile(&poetry_lock, &ParseOptions::default()).unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].name, "requests");
+
+        let csproj = r#"<Project Sdk="Microsoft.NET.Sdk">
+  <ItemGroup>
+    <PackageReference Include="Newtonsoft.Json" Version="13.0.3" />
+  </ItemGroup>
+</Project>"#;
+        let utf16_le: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain(csproj.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let utf16_be: Vec<u8> = [0xFE, 0xFF]
+            .into_iter()
+            .chain(csproj.encode_utf16().flat_map(u16::to_be_bytes))
+            .collect();
+        for (file_name, content) in [("App.csproj", utf16_le), ("Api.csproj", utf16_be)] {
+            let path = dir.path().join(file_name);
+            fs::write(&path, content).unwrap();
+            let packages = parse_lockfile(&path, &ParseOptions::default()).unwrap();
+            assert_eq!(packages.len(), 1, "{}", file_name);
+            assert_eq!((packages[0].name.as_str(), packages[0].version.as_str()), ("Newtonsoft.Json", "13.0.3"));
+        }
+
+        let garbled = dir.path().join("yarn.lock");
+        fs::write(&garbled, b"left-pad@^1.3.0:\n  version \"1.3.0\xFF\"\n").unwrap();
+        let err = parse_lockfile(&garbled, &ParseOptions::default()).unwrap_err();
+        assert!(err.contains("is not valid UTF-8"), "{}", err);
+    }
 
     #[test]
     fn test_extract_package_name() {
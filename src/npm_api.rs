@@ -1,44 +1,267 @@
-use reqwest::blocking::Client;
+use reqwest::blocking::{ Client, RequestBuilder };
 use serde_json::Value;
 use std::error::Error;
+use once_cell::sync::OnceCell;
+use regex::Regex;
 use urlencoding::encode;
 
-use crate::package::Package;
+use crate::diagnostics::ResolutionStatus;
+use crate::http_cache::CachedResponse;
+use crate::integrity::IntegrityHash;
+use crate::package::{ is_unknown_license, LicenseSource, LicenseVerification, Package, UNKNOWN_LICENSE };
+use crate::package_filter::name_pattern;
+
+const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org";
+
+/// npm registry settings shared by all metadata requests
+struct RegistrySettings {
+    // Registry URL with environment variables resolved (may contain secrets)
+    base_url: String,
+    // Registry URL as given by the user, safe to log
+    display_url: String,
+    // Extra header sent with every metadata request to the default registry, e.g. Authorization
+    auth_header: Option<(String, String)>,
+    // Registries for packages matching a name pattern, in order; the first match wins
+    mirrors: Vec<RegistryMirror>,
+}
+
+/// A registry that serves the packages whose name matches a pattern, e.g. `@internal/*`
+struct RegistryMirror {
+    pattern: Regex,
+    base_url: String,
+    display_url: String,
+}
+
+static REGISTRY_SETTINGS: OnceCell<RegistrySettings> = OnceCell::new();
+
+/// How npm packages are looked up, beyond the registry metadata
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NpmOptions {
+    // Download the published tarball of packages whose registry metadata has no license.
+    // Off by default since it adds a download per package.
+    pub deep_scan: bool,
+    // Cross-check licenses declared in the registry against the package's LICENSE file text.
+    // Off by default since it fetches a license file per package.
+    pub verify_license_text: bool,
+}
+
+impl NpmOptions {
+    /// Whether a cached result predates --verify-license-text and has to be looked up again
+    pub fn needs_license_verification(&self, package: &Package) -> bool {
+        self.verify_license_text &&
+            package.registry == "npm" &&
+            package.license_source == Some(LicenseSource::Registry) &&
+            package.license_verification.is_none()
+    }
+}
+
+/// Configure the npm registry used for metadata requests, and the registries that serve
+/// packages matching a name pattern (`PATTERN=URL` entries, where `*` is a wildcard).
+/// URLs and the header may reference environment variables as `${VAR}`; the header is only
+/// sent to the default registry.
+pub fn configure_registry(
+    registry_url: Option<&str>,
+    auth_header: Option<&str>,
+    mirror_map: &[String]
+) -> Result<(), String> {
+    let settings = RegistrySettings::new(registry_url, auth_header, mirror_map)?;
+    REGISTRY_SETTINGS.set(settings).map_err(|_| "npm registry is already configured".to_string())
+}
+
+impl RegistrySettings {
+    fn new(registry_url: Option<&str>, auth_header: Option<&str>, mirror_map: &[String]) -> Result<Self, String> {
+        let display_url = registry_url.unwrap_or(DEFAULT_REGISTRY).trim_end_matches('/').to_string();
+        let base_url = crate::utils::substitute_env_vars(&display_url)?;
+
+        let auth_header = match auth_header {
+            Some(header) => {
+                let (name, value) = header
+                    .split_once(':')
+                    .ok_or_else(|| "Registry auth header must have the form \"Name: value\"".to_string())?;
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err("Registry auth header is missing a header name".to_string());
+                }
+                Some((name.to_string(), crate::utils::substitute_env_vars(value.trim())?))
+            }
+            None => None,
+        };
+
+        let mirrors = mirror_map
+            .iter()
+            .map(|entry| RegistryMirror::parse(entry))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RegistrySettings { base_url, display_url, auth_header, mirrors })
+    }
+
+    /// The mirror that serves a package, or None for the default registry
+    fn mirror_for(&self, package_name: &str) -> Option<&RegistryMirror> {
+        self.mirrors.iter().find(|mirror| mirror.pattern.is_match(package_name))
+    }
+
+    /// Resolved and printable base URL of the registry that serves a package
+    fn base_urls(&self, encoded_name: &str) -> (&str, &str) {
+        let package_name = urlencoding::decode(encoded_name).map(|name| name.into_owned()).unwrap_or_default();
+        match self.mirror_for(&package_name) {
+            Some(mirror) => (&mirror.base_url, &mirror.display_url),
+            None => (&self.base_url, &self.display_url),
+        }
+    }
+
+    /// Whether a package's metadata request carries credentials, in the auth header or in
+    /// environment variables substituted into the registry URL
+    fn sends_credentials(&self, encoded_name: &str) -> bool {
+        let (base_url, display_url) = self.base_urls(encoded_name);
+        base_url != display_url || (self.auth_header.is_some() && base_url == self.base_url)
+    }
+}
+
+impl RegistryMirror {
+    /// Parse a `PATTERN=URL` entry
+    fn parse(entry: &str) -> Result<Self, String> {
+        let (pattern, url) = entry
+            .split_once('=')
+            .ok_or_else(|| format!("Registry mirror \"{}\" must have the form PATTERN=URL", entry))?;
+        let pattern = pattern.trim();
+        let url = url.trim();
+        if pattern.is_empty() || url.is_empty() {
+            return Err(format!("Registry mirror \"{}\" must have the form PATTERN=URL", entry));
+        }
+
+        let display_url = url.trim_end_matches('/').to_string();
+        Ok(RegistryMirror {
+            pattern: name_pattern(pattern).ok_or_else(|| format!("Invalid package pattern: {}", pattern))?,
+            base_url: crate::utils::substitute_env_vars(&display_url)?,
+            display_url,
+        })
+    }
+}
+
+
+
+fn registry_settings() -> &'static RegistrySettings {
+    REGISTRY_SETTINGS.get_or_init(|| RegistrySettings {
+        base_url: DEFAULT_REGISTRY.to_string(),
+        display_url: DEFAULT_REGISTRY.to_string(),
+        auth_header: None,
+        mirrors: Vec::new(),
+    })
+}
+
+/// Build a metadata request against the registry that serves the package. The auth header
+/// only goes to the default registry, so its credentials never reach a mirror.
+fn registry_request(client: &Client, encoded_name: &str) -> RequestBuilder {
+    settings_request(registry_settings(), client, encoded_name)
+}
+
+fn settings_request(settings: &RegistrySettings, client: &Client, encoded_name: &str) -> RequestBuilder {
+    let (base_url, _) = settings.base_urls(encoded_name);
+    let request = client.get(format!("{}/{}", base_url, encoded_name));
+
+    match &settings.auth_header {
+        Some((name, value)) if base_url == settings.base_url => request.header(name.as_str(), value.as_str()),
+        _ => request,
+    }
+}
+
+/// Fetch a package's registry metadata, revalidating the copy stored by an earlier request
+fn fetch_registry_metadata(client: &Client, encoded_name: &str) -> Result<CachedResponse, Box<dyn Error>> {
+    let request = registry_request(client, encoded_name)
+        .header("Accept", "application/json")
+        .header("User-Agent", "Dependency-Scanner/1.0");
+    let url = registry_display_url(encoded_name);
+    if registry_settings().sends_credentials(encoded_name) {
+        crate::http_cache::send_authenticated(&url, request)
+    } else {
+        crate::http_cache::send(&url, request)
+    }
+}
+
+/// Explain a response that is a web page instead of metadata: private packages on registries
+/// behind single sign-on redirect to a login page when no credentials are sent
+fn authentication_required(response: &CachedResponse, display_url: &str) -> Option<String> {
+    if !response.is_html() {
+        return None;
+    }
+    Some(
+        format!(
+            "Authentication required: {} answered with an HTML page{} instead of package metadata; configure credentials with --registry-auth-header (or in the --registry-mirror-map URL)",
+            display_url,
+            if response.redirected() { " after a redirect" } else { "" }
+        )
+    )
+}
+
+/// Registry URL for a package that is safe to print (environment variables unresolved)
+fn registry_display_url(encoded_name: &str) -> String {
+    let (_, display_url) = registry_settings().base_urls(encoded_name);
+    format!("{}/{}", display_url, encoded_name)
+}
+
+/// Resolve a version range or dist-tag from a manifest to the highest matching published version
+pub fn resolve_version_range(
+    package_name: &str,
+    range: &str,
+    allow_prerelease: bool
+) -> Result<Option<String>, Box<dyn Error>> {
+    let client = crate::http_retry::metadata_client();
+
+    let encoded_name = if package_name.starts_with('@') {
+        package_name.replace('@', "%40").replace('/', "%2F")
+    } else {
+        encode(package_name).to_string()
+    };
 
-pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
-    let client = Client::new();
+    eprintln!("DEBUG: Resolving {}@{} from {}", package_name, range, registry_display_url(&encoded_name));
+
+    let response = fetch_registry_metadata(client, &encoded_name)?;
+
+    if let Some(error_msg) = authentication_required(&response, &registry_display_url(&encoded_name)) {
+        return Err(error_msg.into());
+    }
+    if !response.status().is_success() {
+        return Err(format!("npm registry returned status code {}", response.status().as_u16()).into());
+    }
+
+    let package_metadata: Value = response.json()?;
+    Ok(resolve_version_from_metadata(&package_metadata, range, allow_prerelease))
+}
+
+fn resolve_version_from_metadata(
+    package_metadata: &Value,
+    range: &str,
+    allow_prerelease: bool
+) -> Option<String> {
+    // Ranges like "latest" or "next" refer to dist-tags
+    if let Some(tagged_version) = package_metadata["dist-tags"][range].as_str() {
+        return Some(tagged_version.to_string());
+    }
+
+    let versions = package_metadata["versions"].as_object()?;
+    crate::semver_resolver::max_satisfying(
+        range,
+        versions.keys().map(|v| v.as_str()),
+        allow_prerelease
+    )
+}
+
+pub fn get_package_info(package: &Package, options: &NpmOptions) -> Result<Package, Box<dyn Error>> {
+    let client = crate::http_retry::metadata_client();
 
     // For scoped packages (starting with @), we need to handle them specially
     let package_name = &package.name;
     let version = &package.version;
 
-    // Custom package sources (GitHub, etc.)
+    // Custom package sources (GitHub, etc.); the GitHub lookup tries npm first on its own
     if package_resolution_is_github(&package.resolution) {
-        // Even for GitHub packages, try npm first since many are published there
-        match try_npm_registry(package_name, version, &client) {
-            Ok(Some(npm_package)) => {
-                eprintln!("INFO: GitHub package {} found in npm registry", package_name);
-                return Ok(npm_package);
-            }
-            Ok(None) => {
-                eprintln!("INFO: GitHub package {} not found in npm, redirecting to GitHub API", package_name);
-                return crate::github_api::get_package_info(package);
-            }
-            Err(e) => {
-                eprintln!(
-                    "INFO: Error checking npm registry for GitHub package {}: {}",
-                    package_name,
-                    e
-                );
-                return crate::github_api::get_package_info(package);
-            }
-        }
+        return crate::github_api::get_package_info(package);
     }
 
     // Check if the resolution is an archive that needs to be downloaded and extracted
     if crate::archive_handler::is_archive_url(&package.resolution) {
         // Try npm registry first before downloading and extracting the archive
-        match try_npm_registry(package_name, version, &client) {
+        match try_npm_registry(package_name, version, client) {
             Ok(Some(npm_package)) => {
                 eprintln!("INFO: Archive package {} found in npm registry", package_name);
                 return Ok(npm_package);
@@ -72,7 +295,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
         );
 
         result.registry = "npm".to_string();
-        result.license = "UNKNOWN".to_string();
+        result.license = UNKNOWN_LICENSE.to_string();
         result.debug_info = Some("Entry is a resolution definition, not a package".to_string());
         result.processed = true;
 
@@ -80,7 +303,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
     }
 
     // Clean up the package name to properly handle scoped packages
-    let clean_name = package_name.trim_matches(|c| (c == '"' || c == '\'' || c == ' '));
+    let clean_name = package_name.trim_matches(|c| c == '"' || c == '\'' || c == ' ');
 
     // Create package URL
     let package_url = format!("https://www.npmjs.com/package/{}", clean_name);
@@ -96,18 +319,10 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
 
     // Construct npm registry URL to fetch package metadata
     // Use the npm registry's public API endpoint format
-    let registry_url = format!("https://registry.npmjs.org/{}", encoded_name);
-
-    eprintln!("DEBUG: Fetching from npm registry: {}", registry_url);
+    eprintln!("DEBUG: Fetching from npm registry: {}", registry_display_url(&encoded_name));
 
     // Try to get the package info
-    let response = match
-        client
-            .get(&registry_url)
-            .header("Accept", "application/json")
-            .header("User-Agent", "Dependency-Scanner/1.0")
-            .send()
-    {
+    let response = match fetch_registry_metadata(client, &encoded_name) {
         Ok(resp) => resp,
         Err(e) => {
             let error_msg = format!("Network error when contacting npm registry: {}", e);
@@ -122,19 +337,24 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
 
             result.registry = "npm".to_string();
             result.display_name = format!("{}@{}", clean_name, version);
-            result.license = "UNKNOWN".to_string();
+            result.license = UNKNOWN_LICENSE.to_string();
             result.url = package_url;
             result.debug_info = Some(error_msg);
+            result.lookup_failed = true;
+            result.resolution_status = ResolutionStatus::NetworkError;
             result.processed = true;
 
             return Ok(result);
         }
     };
 
-    if !response.status().is_success() {
+    let auth_error = authentication_required(&response, &registry_display_url(&encoded_name));
+    if auth_error.is_some() || !response.status().is_success() {
         let status_code = response.status().as_u16();
         let reason = response.status().canonical_reason().unwrap_or("Unknown error");
-        let error_msg = format!("npm registry returned status code {}: {}", status_code, reason);
+        let error_msg = auth_error.unwrap_or_else(|| {
+            format!("npm registry returned status code {}: {}", status_code, reason)
+        });
 
         eprintln!("INFO: {}", error_msg);
 
@@ -147,9 +367,11 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
 
         result.registry = "npm".to_string();
         result.display_name = format!("{}@{}", clean_name, version);
-        result.license = "UNKNOWN".to_string();
+        result.license = UNKNOWN_LICENSE.to_string();
         result.url = package_url;
         result.debug_info = Some(error_msg);
+        result.lookup_failed = true;
+        result.resolution_status = response.failure_status();
         result.processed = true;
 
         return Ok(result);
@@ -171,9 +393,11 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
 
             result.registry = "npm".to_string();
             result.display_name = format!("{}@{}", clean_name, version);
-            result.license = "UNKNOWN".to_string();
+            result.license = UNKNOWN_LICENSE.to_string();
             result.url = package_url;
             result.debug_info = Some(error_msg);
+            result.lookup_failed = true;
+            result.resolution_status = ResolutionStatus::Error;
             result.processed = true;
 
             return Ok(result);
@@ -191,7 +415,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
     let dependencies = extract_dependencies(&package_metadata, version);
 
     // Store license value for comparison
-    let is_unknown = license == "UNKNOWN";
+    let is_unknown = is_unknown_license(&license);
 
     let mut result_package = Package::new(
         clean_name.to_string(),
@@ -203,19 +427,27 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
     result_package.registry = "npm".to_string();
     result_package.display_name = format!("{}@{}", clean_name, version);
     result_package.license = license.clone();
+    result_package.license_source = (!is_unknown).then_some(LicenseSource::Registry);
+    result_package.confidence = crate::license_detection::declared_confidence(&license);
     result_package.url = package_url;
-    result_package.license_url = license_url;
-    result_package.debug_info = if is_unknown { Some(license_debug.clone()) } else { None };
+    result_package.license_url = license_url.clone();
+    result_package.debug_info = if is_unknown {
+        Some(unknown_license_debug(&license_debug, license_url.as_deref()))
+    } else {
+        None
+    };
 
     // When license is unknown but we have a license URL, try to download and detect license
-    if is_unknown && result_package.license_url.is_some() {
-        match try_detect_license_from_url(result_package.license_url.as_ref().unwrap()) {
-            Ok(Some(detected_license)) => {
+    if let Some(detect_url) = license_url.as_ref().filter(|_| is_unknown) {
+        match try_detect_license_from_url(detect_url) {
+            Ok(Some((detected_license, confidence))) => {
                 result_package.license = detected_license;
+                result_package.license_source = Some(LicenseSource::LicenseText);
+                result_package.confidence = confidence;
                 result_package.debug_info = Some(
                     format!(
                         "License detected from URL: {}",
-                        result_package.license_url.as_ref().unwrap()
+                        detect_url
                     )
                 );
             }
@@ -225,7 +457,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
                     format!(
                         "{}; Attempted license detection from URL: {}",
                         license_debug,
-                        result_package.license_url.as_ref().unwrap()
+                        detect_url
                     )
                 );
             }
@@ -235,7 +467,7 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
                     format!(
                         "{}; Failed to download license from URL: {} ({})",
                         license_debug,
-                        result_package.license_url.as_ref().unwrap(),
+                        detect_url,
                         e
                     )
                 );
@@ -243,6 +475,31 @@ pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
         }
     }
 
+    // "SEE LICENSE IN <file>" names a file in the package's repository
+    if
+        let Some(file_url) = crate::license_detection
+            ::license_file_reference(&license)
+            .and_then(|file| referenced_license_file_url(&package_metadata, version, file))
+    {
+        detect_license_from_referenced_file(&mut result_package, &file_url);
+    }
+
+    if options.deep_scan && result_package.is_unknown() {
+        if let Some(url) = tarball_url(&package_metadata, version, &package.resolution) {
+            detect_license_from_tarball(&mut result_package, &url, package.strongest_integrity());
+        }
+    }
+
+    if options.verify_license_text && result_package.license_source == Some(LicenseSource::Registry) {
+        let license_file_url = repository_license_file_url(&package_metadata);
+        result_package.license_verification = Some(
+            verify_license_text(&result_package.license, license_file_url.as_deref())
+        );
+    }
+
+    result_package.repository = extract_repository(&package_metadata);
+    result_package.deprecated = extract_deprecation(&package_metadata, version);
+    (result_package.os, result_package.cpu) = extract_platforms(&package_metadata, version);
     result_package.dependencies = dependencies;
     result_package.processed = true;
 
@@ -341,7 +598,16 @@ fn extract_license_info_with_debug(
     }
 
     // If no license information found
-    ("UNKNOWN".to_string(), debug_info.join("; "))
+    (UNKNOWN_LICENSE.to_string(), debug_info.join("; "))
+}
+
+/// Why the license is UNKNOWN, noting when the repository has no license file to check by hand
+fn unknown_license_debug(license_debug: &str, license_url: Option<&str>) -> String {
+    match license_url {
+        Some(_) => license_debug.to_string(),
+        None if license_debug.is_empty() => "No license file found".to_string(),
+        None => format!("{}; no license file found", license_debug.trim_end()),
+    }
 }
 
 // Extract license URL from package metadata if available
@@ -367,13 +633,26 @@ fn extract_license_url(package_metadata: &Value, license: &str) -> Option<String
         }
     }
 
+    repository_license_file_url(package_metadata)
+}
+
+/// URL of the LICENSE file in the package's GitHub repository, found from its homepage or
+/// repository field
+fn repository_license_file_url(package_metadata: &Value) -> Option<String> {
     // Try to get license URL from the metadata
     if let Some(homepage) = package_metadata["homepage"].as_str() {
         if homepage.contains("github.com") {
             if let Some(normalized_url) = crate::utils::normalize_github_url(homepage) {
                 // Try to determine the default branch
                 let default_branch = "master"; // Normally we would determine this from API
-                return crate::utils::get_license_file_url(&normalized_url, default_branch);
+                if
+                    let Some(url) = crate::utils::get_license_file_url(
+                        &normalized_url,
+                        default_branch
+                    )
+                {
+                    return Some(url);
+                }
             }
         }
     }
@@ -394,6 +673,86 @@ fn extract_license_url(package_metadata: &Value, license: &str) -> Option<String
     None
 }
 
+/// The repository URL the registry records, e.g. `git+https://github.com/owner/repo.git`
+fn extract_repository(package_metadata: &Value) -> Option<String> {
+    let repository = &package_metadata["repository"];
+    repository["url"].as_str().or_else(|| repository.as_str()).map(str::to_string)
+}
+
+/// URL of a license file named by `SEE LICENSE IN`, read from the GitHub repository at the
+/// commit the version was published from when the registry records it
+fn referenced_license_file_url(package_metadata: &Value, version: &str, file: &str) -> Option<String> {
+    let version_data = &package_metadata["versions"][version];
+    let repository = if version_data["repository"].is_null() {
+        &package_metadata["repository"]
+    } else {
+        &version_data["repository"]
+    };
+    let repo_url = repository["url"].as_str().or_else(|| repository.as_str())?;
+    let repo_url = crate::utils::normalize_github_url(repo_url)?;
+    let git_ref = version_data["gitHead"].as_str().unwrap_or("HEAD");
+
+    Some(crate::utils::raw_github_file_url(&repo_url, git_ref, file))
+}
+
+/// Follow a `SEE LICENSE IN <file>` declaration: link the file and detect the license it holds.
+/// The declared text is kept when the file's license can't be recognized.
+pub fn detect_license_from_referenced_file(result_package: &mut Package, file_url: &str) {
+    result_package.license_url = Some(file_url.to_string());
+
+    match try_detect_license_from_url(file_url) {
+        Ok(Some((detected_license, confidence))) => {
+            result_package.license = detected_license;
+            result_package.license_source = Some(LicenseSource::LicenseText);
+            result_package.confidence = confidence;
+            result_package.debug_info = Some(format!("License detected from referenced file: {}", file_url));
+        }
+        Ok(None) => {
+            result_package.debug_info = Some(
+                format!("No known license detected in referenced file: {}", file_url)
+            );
+        }
+        Err(e) => {
+            result_package.debug_info = Some(
+                format!("Failed to download referenced license file: {} ({})", file_url, e)
+            );
+        }
+    }
+}
+
+/// The `os` and `cpu` fields of a version's manifest, which platform-specific binaries set
+fn extract_platforms(package_metadata: &Value, version: &str) -> (Vec<String>, Vec<String>) {
+    let version_data = &package_metadata["versions"][version];
+    let field = |name: &str| -> Vec<String> {
+        version_data[name]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect()
+    };
+    (field("os"), field("cpu"))
+}
+
+/// Read the deprecation message for a version, or note that it was unpublished
+fn extract_deprecation(package_metadata: &Value, version: &str) -> Option<String> {
+    // Unpublished packages keep only a tombstone in the time field
+    if package_metadata["time"]["unpublished"].is_object() {
+        return Some("Package was unpublished from the registry".to_string());
+    }
+
+    let version_data = &package_metadata["versions"][version];
+    if version_data.is_null() && package_metadata["time"][version].is_string() {
+        return Some(format!("Version {} was unpublished from the registry", version));
+    }
+
+    // npm un-deprecates a version by setting the message to an empty string
+    version_data["deprecated"]
+        .as_str()
+        .filter(|message| !message.trim().is_empty())
+        .map(|message| message.to_string())
+}
+
 fn extract_dependencies(package_metadata: &Value, requested_version: &str) -> Vec<Package> {
     let mut dependencies = Vec::new();
 
@@ -414,25 +773,7 @@ fn extract_dependencies(package_metadata: &Value, requested_version: &str) -> Ve
     if let Some(deps) = version_data["dependencies"].as_object() {
         for (name, version_value) in deps {
             if let Some(version_str) = version_value.as_str() {
-                let clean_version = version_str.trim_start_matches('^').trim_start_matches('~');
-
-                let dep = Package::new(
-                    name.clone(),
-                    clean_version.to_string(),
-                    if version_str.starts_with("github:") {
-                        format!("https://github.com/{}", version_str.trim_start_matches("github:"))
-                    } else {
-                        format!(
-                            "https://registry.npmjs.org/{}/-/{}-{}.tgz",
-                            name,
-                            name.replace('@', "").replace('/', "-"),
-                            clean_version
-                        )
-                    },
-                    None
-                );
-
-                dependencies.push(dep);
+                dependencies.push(crate::github_api::manifest_dependency(name, version_str));
             }
         }
     }
@@ -446,8 +787,11 @@ fn extract_info_from_archive(package: &Package) -> Result<Package, Box<dyn Error
     let version = &package.version;
     let resolution = &package.resolution;
 
-    match crate::archive_handler::extract_info_from_archive(resolution) {
-        Ok((license, license_content)) => {
+    let client = crate::http_retry::shared_client();
+    let _permit = crate::archive_handler::download_permit();
+    match crate::archive_handler::extract_info_from_archive(client, resolution, package.strongest_integrity()) {
+        Ok(info) => {
+            let license = info.license;
             let mut result = Package::new(
                 package_name.clone(),
                 version.clone(),
@@ -458,15 +802,17 @@ fn extract_info_from_archive(package: &Package) -> Result<Package, Box<dyn Error
             result.registry = "npm".to_string();
             result.display_name = format!("{}@{}", package_name, version);
             result.license = license.clone();
+            result.license_source = info.license_source;
+            result.confidence = info.confidence;
             result.url = format!("https://www.npmjs.com/package/{}", package_name);
-            result.debug_info = if license == "UNKNOWN" {
+            result.debug_info = if is_unknown_license(&license) {
                 Some(format!("License extracted from archive: {}", resolution))
             } else {
                 None
             };
 
-            if let Some(content) = license_content {
-                if license == "UNKNOWN" {
+            if let Some(content) = info.license_content {
+                if is_unknown_license(&license) {
                     let preview: String = content.chars().take(100).collect();
                     result.debug_info = Some(
                         format!("License file found but type unknown. Preview: {}...", preview)
@@ -485,6 +831,7 @@ fn extract_info_from_archive(package: &Package) -> Result<Package, Box<dyn Error
                     version.clone(),
                     "npm",
                     format!("https://www.npmjs.com/package/{}", package_name),
+                    ResolutionStatus::Error,
                     &format!("Failed to extract from archive: {}", e)
                 )
             )
@@ -492,31 +839,110 @@ fn extract_info_from_archive(package: &Package) -> Result<Package, Box<dyn Error
     }
 }
 
-// New function to download license text and detect license
-pub fn try_detect_license_from_url(url: &str) -> Result<Option<String>, Box<dyn Error>> {
-    let client = reqwest::blocking::Client
-        ::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()?;
+/// Tarball URL of a version, from the registry metadata or an archive resolution
+fn tarball_url(package_metadata: &Value, version: &str, resolution: &str) -> Option<String> {
+    if let Some(url) = package_metadata["versions"][version]["dist"]["tarball"].as_str() {
+        return Some(url.to_string());
+    }
 
-    let response = client.get(url).send()?;
+    // Yarn resolutions carry the checksum after a '#'
+    let url = resolution.split('#').next().unwrap_or(resolution);
+    if url.starts_with("http") && crate::archive_handler::is_archive_url(url) {
+        Some(url.to_string())
+    } else {
+        None
+    }
+}
 
-    if !response.status().is_success() {
-        return Err(format!("Failed to download license: HTTP status {}", response.status()).into());
+/// Download a package tarball and take the license from its package.json or LICENSE file
+fn detect_license_from_tarball(
+    result_package: &mut Package,
+    tarball_url: &str,
+    integrity: Option<&IntegrityHash>
+) {
+    let previous_debug = result_package.debug_info.clone().unwrap_or_default();
+
+    let client = crate::http_retry::shared_client();
+    let _permit = crate::archive_handler::download_permit();
+    match crate::archive_handler::extract_info_from_archive(client, tarball_url, integrity) {
+        Ok(info) if !is_unknown_license(&info.license) => {
+            result_package.license = info.license;
+            result_package.license_source = info.license_source;
+            result_package.confidence = info.confidence;
+            result_package.debug_info = Some(
+                format!("License detected from package tarball: {}", tarball_url)
+            );
+        }
+        Ok(_) => {
+            result_package.debug_info = Some(
+                format!("{}; No license found in package tarball: {}", previous_debug, tarball_url)
+            );
+        }
+        Err(e) => {
+            result_package.debug_info = Some(
+                format!(
+                    "{}; Failed to scan package tarball: {} ({})",
+                    previous_debug,
+                    tarball_url,
+                    e
+                )
+            );
+        }
     }
+}
 
-    let license_text = response.text()?;
+// New function to download license text and detect license, with the detection confidence
+pub fn try_detect_license_from_url(url: &str) -> Result<Option<(String, f32)>, Box<dyn Error>> {
+    let license_text = match crate::utils::get_license_text_from_cache(url) {
+        Some(license_text) => license_text,
+        None => {
+            let client = crate::http_retry::metadata_client();
+
+            let response = client.get(url).send()?;
+
+            if !response.status().is_success() {
+                return Err(format!("Failed to download license: HTTP status {}", response.status()).into());
+            }
+
+            let license_text = crate::http_retry::read_text(response)?;
+            if let Err(e) = crate::utils::save_license_text_to_cache(url, &license_text) {
+                eprintln!("Warning: Failed to cache license text from {}: {}", url, e);
+            }
+            license_text
+        }
+    };
 
     let detected_license = crate::license_detection::detect_license_from_text(&license_text);
 
     Ok(detected_license)
 }
 
+/// Compare a declared license with the license detected from the package's license file
+fn verify_license_text(declared: &str, license_file_url: Option<&str>) -> LicenseVerification {
+    let Some(url) = license_file_url else {
+        return LicenseVerification::Unverifiable;
+    };
+
+    match try_detect_license_from_url(url) {
+        Ok(Some((detected, _))) if crate::license_detection::declared_matches_detected(declared, &detected) => {
+            LicenseVerification::Match
+        }
+        Ok(Some((detected, _))) => LicenseVerification::Mismatch { detected },
+        Ok(None) => LicenseVerification::Unverifiable,
+        Err(e) => {
+            eprintln!("INFO: Failed to fetch license file {} for verification: {}", url, e);
+            LicenseVerification::Unverifiable
+        }
+    }
+}
+
 // Helper function to determine if package uses GitHub as source
 fn package_resolution_is_github(resolution: &str) -> bool {
     resolution.contains("github:") ||
         resolution.contains("github.com") ||
-        (resolution.contains("__archiveUrl=") && resolution.contains("github.com"))
+        crate::utils
+            ::archive_url_from_resolution(resolution)
+            .is_some_and(|archive_url| crate::utils::github_archive_details(&archive_url).is_some())
 }
 
 // Helper function to extract npm package name from resolution
@@ -536,7 +962,7 @@ pub fn try_npm_registry(
     version: &str,
     client: &Client
 ) -> Result<Option<Package>, Box<dyn Error>> {
-    let clean_name = package_name.trim_matches(|c| (c == '"' || c == '\'' || c == ' '));
+    let clean_name = package_name.trim_matches(|c| c == '"' || c == '\'' || c == ' ');
 
     let npm_name = if clean_name.starts_with("github:") {
         let parts: Vec<&str> = clean_name.trim_start_matches("github:").split('/').collect();
@@ -555,12 +981,14 @@ pub fn try_npm_registry(
         encode(&npm_name).to_string()
     };
 
-    let registry_url = format!("https://registry.npmjs.org/{}", encoded_name);
-
     eprintln!("DEBUG: Trying npm registry for package: {}", npm_name);
 
-    match client.get(&registry_url).header("Accept", "application/json").send() {
+    match fetch_registry_metadata(client, &encoded_name) {
         Ok(response) => {
+            if let Some(error_msg) = authentication_required(&response, &registry_display_url(&encoded_name)) {
+                eprintln!("INFO: {}", error_msg);
+                return Ok(None);
+            }
             if !response.status().is_success() {
                 return Ok(None);
             }
@@ -578,25 +1006,25 @@ pub fn try_npm_registry(
                     let mut result = Package::new(
                         clean_name.to_string(),
                         version.to_string(),
-                        format!(
-                            "https://registry.npmjs.org/{}/-/{}-{}.tgz",
-                            npm_name,
-                            npm_name.replace('@', "").replace('/', "-"),
-                            version
-                        ),
+                        crate::github_api::npm_tarball_url(&npm_name, version),
                         None
                     );
 
                     result.registry = "npm".to_string();
                     result.display_name = format!("{}@{}", npm_name, version);
                     result.license = license.clone();
+                    result.license_source = (!is_unknown_license(&license)).then_some(LicenseSource::Registry);
+                    result.confidence = crate::license_detection::declared_confidence(&license);
                     result.url = format!("https://www.npmjs.com/package/{}", npm_name);
-                    result.license_url = license_url;
-                    result.debug_info = if license == "UNKNOWN" {
-                        Some(license_debug)
+                    result.debug_info = if is_unknown_license(&license) {
+                        Some(unknown_license_debug(&license_debug, license_url.as_deref()))
                     } else {
                         None
                     };
+                    result.license_url = license_url;
+                    result.repository = extract_repository(&metadata);
+                    result.deprecated = extract_deprecation(&metadata, version);
+                    (result.os, result.cpu) = extract_platforms(&metadata, version);
                     result.dependencies = dependencies;
                     result.processed = true;
 
@@ -608,3 +1036,289 @@ pub fn try_npm_registry(
         Err(_) => Ok(None),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::{ Read, Write };
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_login_redirect_reports_authentication_required() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/%40private%2Fwidget", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let responses = [
+                "HTTP/1.1 302 Found\r\nLocation: /sso/login\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 27\r\nConnection: close\r\n\r\n<html>Please sign in</html>",
+            ];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 4096];
+                let _ = stream.read(&mut request).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let client = Client::builder().no_proxy().build().unwrap();
+        let response = crate::http_cache::send(&url, client.get(&url)).unwrap();
+        server.join().unwrap();
+
+        let error_msg = authentication_required(&response, "https://npm.example.com/%40private%2Fwidget").unwrap();
+        assert!(error_msg.starts_with("Authentication required: https://npm.example.com/%40private%2Fwidget"));
+        assert!(error_msg.contains("after a redirect"));
+        assert!(error_msg.contains("--registry-auth-header"));
+    }
+
+    #[test]
+    fn test_unknown_license_debug_without_license_file() {
+        let metadata = json!({
+            "versions": { "1.0.0": { "name": "no-license" } },
+            "repository": { "type": "git", "url": "https://gitlab.com/owner/no-license" }
+        });
+        let (license, license_debug) = extract_license_info_with_debug(&metadata, "1.0.0");
+        assert!(is_unknown_license(&license));
+
+        // The repository isn't on GitHub, so no license file is probed for
+        let license_url = extract_license_url(&metadata, &license);
+        assert_eq!(license_url, None);
+        let debug_info = unknown_license_debug(&license_debug, license_url.as_deref());
+        assert!(debug_info.starts_with("No license field in version 1.0.0"), "{}", debug_info);
+        assert!(debug_info.ends_with("; no license file found"), "{}", debug_info);
+
+        let license_file = "https://github.com/owner/repo/blob/master/COPYING";
+        let with_file = unknown_license_debug(&license_debug, Some(license_file));
+        assert!(!with_file.contains("no license file found"));
+    }
+
+    #[test]
+    fn test_mirror_map_routes_matching_packages() {
+        let settings = RegistrySettings::new(
+            None,
+            Some("Authorization: Bearer public-token"),
+            &[
+                "@internal/*=https://artifactory.example.com/api/npm/npm-local/".to_string(),
+                "@internal/legacy-*=https://unused.example.com".to_string()
+            ]
+        ).unwrap();
+        let client = Client::new();
+        let request = |name: &str| {
+            let encoded_name = name.replace('@', "%40").replace('/', "%2F");
+            settings_request(&settings, &client, &encoded_name).build().unwrap()
+        };
+
+        // The first matching pattern wins, and the default registry's header stays with it
+        let internal = request("@internal/legacy-ui");
+        assert_eq!(internal.url().as_str(), "https://artifactory.example.com/api/npm/npm-local/%40internal%2Flegacy-ui");
+        assert!(internal.headers().get("Authorization").is_none());
+
+        let public = request("left-pad");
+        assert_eq!(public.url().as_str(), "https://registry.npmjs.org/left-pad");
+        assert_eq!(public.headers()["Authorization"], "Bearer public-token");
+        assert_eq!(request("@internals/ui").url().host_str(), Some("registry.npmjs.org"));
+
+        assert!(settings.sends_credentials("left-pad"));
+        assert!(!settings.sends_credentials("%40internal%2Flegacy-ui"));
+
+        assert!(RegistrySettings::new(None, None, &["@internal/*".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_extract_deprecation() {
+        let metadata = json!({
+            "versions": {
+                "1.0.0": { "deprecated": "Use left-pad-ng instead" },
+                "1.1.0": { "deprecated": "" },
+                "1.2.0": {}
+            },
+            "time": { "1.0.0": "2016-01-01T00:00:00Z", "0.9.0": "2015-01-01T00:00:00Z" }
+        });
+
+        assert_eq!(extract_deprecation(&metadata, "1.0.0").as_deref(), Some("Use left-pad-ng instead"));
+        assert_eq!(extract_deprecation(&metadata, "1.1.0"), None);
+        assert_eq!(extract_deprecation(&metadata, "1.2.0"), None);
+        assert_eq!(
+            extract_deprecation(&metadata, "0.9.0").as_deref(),
+            Some("Version 0.9.0 was unpublished from the registry")
+        );
+    }
+
+    #[test]
+    fn test_unlicensed_is_proprietary() {
+        let metadata = json!({ "versions": { "1.0.0": { "license": "UNLICENSED" } } });
+        let (license, _) = extract_license_info_with_debug(&metadata, "1.0.0");
+        assert_eq!(license, crate::license_detection::PROPRIETARY);
+    }
+
+    #[test]
+    fn test_see_license_in_follows_the_named_file() {
+        let metadata = json!({
+            "repository": { "type": "git", "url": "git+https://github.com/acme/widget.git" },
+            "versions": {
+                "1.0.0": { "license": "SEE LICENSE IN LICENSE.md", "gitHead": "abc123" },
+                "2.0.0": { "license": "SEE LICENSE IN ./docs/EULA.txt" }
+            }
+        });
+        assert_eq!(
+            referenced_license_file_url(&metadata, "1.0.0", "LICENSE.md").as_deref(),
+            Some("https://raw.githubusercontent.com/acme/widget/abc123/LICENSE.md")
+        );
+        assert_eq!(
+            referenced_license_file_url(&metadata, "2.0.0", "docs/EULA.txt").as_deref(),
+            Some("https://raw.githubusercontent.com/acme/widget/HEAD/docs/EULA.txt")
+        );
+
+        // Serve the referenced file and detect the license it holds
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let file_url = format!("http://{}/acme/widget/abc123/LICENSE.md", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            use std::io::{ Read, Write };
+            let body =
+                "ISC License. Permission to use, copy, modify, and/or distribute this software for any purpose";
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+
+        let mut package = Package::new("widget".to_string(), "1.0.0".to_string(), String::new(), None);
+        package.license = "SEE LICENSE IN LICENSE.md".to_string();
+        detect_license_from_referenced_file(&mut package, &file_url);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(crate::utils::license_text_cache_file(&file_url).unwrap());
+
+        assert_eq!(package.license, "ISC");
+        assert_eq!(package.license_source, Some(LicenseSource::LicenseText));
+        assert_eq!(package.license_url.as_deref(), Some(file_url.as_str()));
+    }
+
+    #[test]
+    fn test_verify_license_text_flags_mismatches() {
+        // Seed the license text cache so no request leaves the machine
+        let id = std::process::id();
+        let gpl_url = format!("http://127.0.0.1:9/mislabeled-{}/LICENSE", id);
+        let mit_url = format!("http://127.0.0.1:9/honest-{}/LICENSE", id);
+        let gpl_text = "GNU GENERAL PUBLIC LICENSE Version 3, 29 June 2007";
+        let mit_text = "Permission is hereby granted, free of charge, subject to the following conditions";
+        crate::utils::save_license_text_to_cache(&gpl_url, gpl_text).unwrap();
+        crate::utils::save_license_text_to_cache(&mit_url, mit_text).unwrap();
+
+        let mislabeled = verify_license_text("MIT", Some(&gpl_url));
+        let honest = verify_license_text("(MIT OR Apache-2.0)", Some(&mit_url));
+        for url in [&gpl_url, &mit_url] {
+            let _ = std::fs::remove_file(crate::utils::license_text_cache_file(url).unwrap());
+        }
+
+        assert_eq!(mislabeled, LicenseVerification::Mismatch { detected: "GPL-3.0".to_string() });
+        assert_eq!(honest, LicenseVerification::Match);
+        assert_eq!(verify_license_text("MIT", None), LicenseVerification::Unverifiable);
+    }
+
+    #[test]
+    fn test_license_text_downloads_are_cached() {
+        // Serve the license text once; a second request would find no server
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/LICENSE", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            use std::io::{ Read, Write };
+            let body =
+                "ISC License. Permission to use, copy, modify, and/or distribute this software for any purpose";
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+
+        let first = try_detect_license_from_url(&url).unwrap();
+        server.join().unwrap();
+        assert!(crate::utils::get_license_text_from_cache(&url).is_some());
+
+        // The listener is gone, so this only succeeds from the cache
+        let second = try_detect_license_from_url(&url);
+        let _ = std::fs::remove_file(crate::utils::license_text_cache_file(&url).unwrap());
+
+        assert_eq!(first.as_ref().map(|(license, _)| license.as_str()), Some("ISC"));
+        assert_eq!(second.unwrap(), first);
+    }
+
+    /// Gzipped tarball laid out like an npm package, with the given files under package/
+    fn npm_tarball(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default())
+        );
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, format!("package/{}", name), content.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_license_detected_from_tarball() {
+        let tarball = npm_tarball(
+            &[
+                ("package.json", r#"{ "name": "no-license", "version": "1.0.0" }"#),
+                (
+                    "LICENSE",
+                    "MIT License\n\nPermission is hereby granted, free of charge, to any person obtaining a copy \
+                     of this software, subject to the following conditions:",
+                ),
+            ]
+        );
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let served_url = format!("http://{}/no-license/-/no-license-1.0.0.tgz", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            use std::io::{ Read, Write };
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                tarball.len()
+            );
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(&tarball).unwrap();
+        });
+
+        // Registry metadata without any license field
+        let metadata = json!({
+            "versions": { "1.0.0": { "dist": { "tarball": served_url } } }
+        });
+        let url = tarball_url(&metadata, "1.0.0", "").unwrap();
+
+        let mut package = Package::new("no-license".to_string(), "1.0.0".to_string(), String::new(), None);
+        package.license = "UNKNOWN".to_string();
+        detect_license_from_tarball(&mut package, &url, None);
+        server.join().unwrap();
+
+        assert_eq!(package.license, "MIT");
+        assert_eq!(package.license_source, Some(LicenseSource::LicenseFile));
+        assert!(package.confidence > 0.0 && package.confidence < 1.0);
+        assert!(package.debug_info.unwrap().contains("package tarball"));
+    }
+
+    #[test]
+    fn test_tarball_url_from_resolution() {
+        let metadata = json!({ "versions": {} });
+        assert_eq!(
+            tarball_url(&metadata, "1.0.0", "https://registry.yarnpkg.com/a/-/a-1.0.0.tgz#abc123").as_deref(),
+            Some("https://registry.yarnpkg.com/a/-/a-1.0.0.tgz")
+        );
+        assert_eq!(tarball_url(&metadata, "1.0.0", "a@^1.0.0"), None);
+    }
+}
@@ -0,0 +1,196 @@
+use reqwest::blocking::Client;
+use serde_json::Value;
+use std::error::Error;
+
+use crate::diagnostics::{ LookupError, ResolutionStatus };
+use crate::package::{ is_unknown_license, LicenseSource, Package, UNKNOWN_LICENSE };
+
+/// Get license information for a NuGet package from the NuGet registration API
+pub fn get_package_info(package: &Package) -> Result<Package, Box<dyn Error>> {
+    let client = crate::http_retry::metadata_client();
+
+    let package_id = &package.name;
+    let version = &package.version;
+
+    // The registration API expects lowercase ids and versions
+    let registration_url = format!(
+        "https://api.nuget.org/v3/registration5-semver1/{}/{}.json",
+        package_id.to_lowercase(),
+        version.to_lowercase()
+    );
+
+    eprintln!("DEBUG: Fetching from NuGet registration API: {}", registration_url);
+
+    let registration = match fetch_json(client, &registration_url) {
+        Ok(json) => json,
+        Err(e) => {
+            let error_msg = format!("NuGet registration lookup failed: {}", e);
+            eprintln!("INFO: {}", error_msg);
+            return Ok(unknown_result(package, ResolutionStatus::of_error(e.as_ref()), &error_msg));
+        }
+    };
+
+    // The catalog entry is usually a link to a separate document, but may be inlined
+    let catalog_entry = match &registration["catalogEntry"] {
+        Value::String(catalog_url) => {
+            match fetch_json(client, catalog_url) {
+                Ok(json) => json,
+                Err(e) => {
+                    let error_msg = format!("NuGet catalog lookup failed: {}", e);
+                    eprintln!("INFO: {}", error_msg);
+                    return Ok(unknown_result(package, ResolutionStatus::of_error(e.as_ref()), &error_msg));
+                }
+            }
+        }
+        Value::Object(_) => registration["catalogEntry"].clone(),
+        _ => {
+            return Ok(
+                unknown_result(package, ResolutionStatus::Error, "No catalogEntry in NuGet registration response")
+            );
+        }
+    };
+
+    Ok(package_from_catalog_entry(package, &catalog_entry))
+}
+
+/// Build the resolved package from a NuGet catalog entry
+fn package_from_catalog_entry(package: &Package, catalog_entry: &Value) -> Package {
+    let mut result = package.clone();
+    result.registry = "nuget".to_string();
+    result.display_name = format!("{}@{}", package.name, package.version);
+
+    let (license, license_url) = extract_license_info(catalog_entry);
+    result.license_source = (!is_unknown_license(&license)).then_some(LicenseSource::Registry);
+    result.confidence = crate::license_detection::declared_confidence(&license);
+    result.license = license;
+    result.license_url = license_url.clone();
+
+    if let Some(project_url) = catalog_entry["projectUrl"].as_str().filter(|u| !u.is_empty()) {
+        result.url = project_url.to_string();
+        result.repository = Some(project_url.to_string());
+    } else if result.url.is_empty() {
+        result.url = format!("https://www.nuget.org/packages/{}", package.name);
+    }
+
+    result.debug_info = if result.is_unknown() {
+        Some(match license_url {
+            Some(url) => format!("No licenseExpression in NuGet metadata; manual check needed at {}", url),
+            None => "No licenseExpression or licenseUrl in NuGet metadata".to_string(),
+        })
+    } else {
+        None
+    };
+
+    result.processed = true;
+    result
+}
+
+/// Extract the license id and URL from a NuGet catalog entry
+fn extract_license_info(catalog_entry: &Value) -> (String, Option<String>) {
+    let license_url = catalog_entry["licenseUrl"]
+        .as_str()
+        .filter(|u| !u.is_empty())
+        .map(|u| u.to_string());
+
+    // Modern packages declare an SPDX expression directly
+    if let Some(expression) = catalog_entry["licenseExpression"].as_str() {
+        if !expression.trim().is_empty() {
+            let license = crate::license_detection::normalize_license_id(expression);
+            let url = crate::license_urls::get_license_url(&license).or(license_url);
+            return (license, url);
+        }
+    }
+
+    // Older packages only have a licenseUrl; some of those point at well-known licenses
+    if let Some(url) = &license_url {
+        if let Some(license) = license_from_known_url(url) {
+            return (license, license_url);
+        }
+    }
+
+    (UNKNOWN_LICENSE.to_string(), license_url)
+}
+
+/// Map a license URL back to its SPDX id when it is one of the well-known license URLs
+fn license_from_known_url(url: &str) -> Option<String> {
+    // licenses.nuget.org URLs carry the expression in the path
+    if let Some(expression) = url.strip_prefix("https://licenses.nuget.org/") {
+        let expression = urlencoding::decode(expression).ok()?;
+        return Some(crate::license_detection::normalize_license_id(&expression));
+    }
+
+    crate::license_urls::LICENSE_URLS
+        .iter()
+        .find(|(_, known_url)| **known_url == url)
+        .map(|(license, _)| license.to_string())
+}
+
+fn fetch_json(client: &Client, url: &str) -> Result<Value, Box<dyn Error>> {
+    let response = client
+        .get(url)
+        .header("Accept", "application/json")
+        .header("User-Agent", "Dependency-Scanner/1.0")
+        .send()?;
+
+    if !response.status().is_success() {
+        let status_code = response.status().as_u16();
+        let reason = response.status().canonical_reason().unwrap_or("Unknown error");
+        return Err(
+            Box::new(LookupError {
+                status: ResolutionStatus::from_http_status(response.status()),
+                message: format!("status code {}: {}", status_code, reason),
+            })
+        );
+    }
+
+    Ok(serde_json::from_str(&crate::http_retry::read_text(response)?)?)
+}
+
+fn unknown_result(package: &Package, status: ResolutionStatus, error_msg: &str) -> Package {
+    Package::with_error(
+        package.name.clone(),
+        package.version.clone(),
+        "nuget",
+        format!("https://www.nuget.org/packages/{}", package.name),
+        status,
+        error_msg
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_license_expression_is_used() {
+        let entry = json!({
+            "licenseExpression": "MIT",
+            "licenseUrl": "https://licenses.nuget.org/MIT",
+            "projectUrl": "https://www.newtonsoft.com/json"
+        });
+        let package = Package::new(
+            "Newtonsoft.Json".to_string(),
+            "13.0.1".to_string(),
+            String::new(),
+            None
+        );
+
+        let result = package_from_catalog_entry(&package, &entry);
+        assert_eq!(result.license, "MIT");
+        assert_eq!(result.license_url.as_deref(), Some("https://opensource.org/licenses/MIT"));
+        assert_eq!(result.url, "https://www.newtonsoft.com/json");
+        assert!(result.processed);
+    }
+
+    #[test]
+    fn test_license_url_fallback() {
+        let entry = json!({ "licenseUrl": "https://licenses.nuget.org/Apache-2.0" });
+        assert_eq!(extract_license_info(&entry).0, "Apache-2.0");
+
+        let entry = json!({ "licenseUrl": "https://example.com/EULA.html" });
+        let (license, url) = extract_license_info(&entry);
+        assert_eq!(license, "UNKNOWN");
+        assert_eq!(url.as_deref(), Some("https://example.com/EULA.html"));
+    }
+}
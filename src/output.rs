@@ -0,0 +1,3246 @@
+use std::collections::{ BTreeMap, BTreeSet, HashMap, HashSet };
+use std::fs;
+use std::io::{ self, Write };
+use std::path::PathBuf;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::sync::{ Arc, Mutex };
+use clap::ValueEnum;
+use colored::{ ColoredString, Colorize };
+use serde::Serialize;
+use serde_json::{ json, Value };
+use sha2::{ Digest, Sha256 };
+use tinytemplate::TinyTemplate;
+
+use crate::diagnostics::{ self, RegistryDiagnostics };
+use crate::license_checker::{ Decision, LicenseChecker };
+use crate::license_compat::{ self, Compatibility };
+use crate::license_obligations::{ self, Obligations };
+use crate::package::{ is_unknown_license, LicenseCandidate, LicenseSource, LicenseVerification, Package, UNKNOWN_LICENSE };
+use crate::review::{ self, ReviewItem };
+use crate::semver_resolver;
+use crate::stats::{ LicenseCount, LicenseStats };
+use crate::utils;
+
+const TOOL_NAME: &str = "super-license-scanner";
+const TOOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Report formats selectable with --format
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable summary with license statistics
+    Text,
+    /// Unique packages as CSV with name, URL, and license
+    Csv,
+    /// Packages and summary as JSON
+    Json,
+    /// Dependency tree visualization
+    Tree,
+    /// Dependency tree as nested JSON objects
+    TreeJson,
+    /// CycloneDX 1.5 SBOM (JSON)
+    SbomCyclonedx,
+    /// SPDX 2.3 SBOM (JSON)
+    SbomSpdx,
+    /// Standalone HTML report
+    Html,
+    /// Markdown table, e.g. for PR comments
+    Markdown,
+    /// SARIF 2.1.0 for code scanning dashboards
+    Sarif,
+    /// JUnit XML for CI test reporting
+    Junit,
+    /// JSON in the shape of licensee / license_finder reports
+    LicenseeJson,
+}
+
+impl OutputFormat {
+    /// File extension used when writing this format to a directory
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Text | OutputFormat::Tree => "txt",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Json => "json",
+            OutputFormat::TreeJson => "tree.json",
+            OutputFormat::SbomCyclonedx => "cdx.json",
+            OutputFormat::SbomSpdx => "spdx.json",
+            OutputFormat::Html => "html",
+            OutputFormat::Markdown => "md",
+            OutputFormat::Sarif => "sarif",
+            OutputFormat::Junit => "xml",
+            OutputFormat::LicenseeJson => "licensee.json",
+        }
+    }
+
+    /// Name of the format as accepted by --format
+    pub fn name(&self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// A report to produce: its format and the file to write it to (stdout when `None`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputRequest {
+    pub format: OutputFormat,
+    pub path: Option<PathBuf>,
+    pub csv: CsvOptions, // How a CSV report is written (--csv-bom, --csv-delimiter)
+}
+
+impl OutputRequest {
+    /// A report in the given format for stdout, with the default CSV options
+    pub fn new(format: OutputFormat) -> Self {
+        OutputRequest { format, path: None, csv: CsvOptions::default() }
+    }
+}
+
+/// Pair each output path with the format given before it on the command line.
+///
+/// Both lists hold `(argument index, value)`. A path given before any format belongs to
+/// the first format. Only one format may be left without a path, since it goes to stdout.
+pub fn pair_output_requests(
+    formats: &[(usize, OutputFormat)],
+    paths: &[(usize, String)]
+) -> Result<Vec<OutputRequest>, String> {
+    let mut requests: Vec<OutputRequest> = formats
+        .iter()
+        .map(|(_, format)| OutputRequest::new(*format))
+        .collect();
+
+    for (path_index, path) in paths {
+        let target = formats
+            .iter()
+            .rposition(|(format_index, _)| format_index < path_index)
+            .unwrap_or(0);
+
+        match requests.get_mut(target) {
+            Some(request) if request.path.is_none() => {
+                request.path = Some(PathBuf::from(path));
+            }
+            Some(request) => {
+                return Err(
+                    format!("more than one output path given for --format {}", request.format.name())
+                );
+            }
+            None => {
+                return Err("an output path was given without a --format".to_string());
+            }
+        }
+    }
+
+    let stdout_formats: Vec<String> = requests
+        .iter()
+        .filter(|r| r.path.is_none())
+        .map(|r| r.format.name())
+        .collect();
+    if stdout_formats.len() > 1 {
+        return Err(
+            format!(
+                "only one format may write to stdout, but {} have no output path",
+                stdout_formats.join(", ")
+            )
+        );
+    }
+
+    Ok(requests)
+}
+
+/// Write each requested report to its file, or to `stdout` when it has no path
+pub fn write_outputs(requests: &[OutputRequest], report: &Report, stdout: &mut dyn Write) {
+    for request in requests {
+        match &request.path {
+            Some(path) => {
+                let written = fs::File
+                    ::create(path)
+                    .and_then(|mut file| write_report(request, report, &mut file));
+                match written {
+                    Ok(_) => println!("{} report written to {}", request.format.name(), path.display()),
+                    Err(e) => eprintln!("Error writing to file {}: {}", path.display(), e),
+                }
+            }
+            None => {
+                if let Err(e) = write_report(request, report, stdout).and_then(|_| stdout.flush()) {
+                    eprintln!("Error writing report: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Orderings for the package listing selectable with --sort
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    /// Alphabetically by package name
+    Name,
+    /// By license, then name
+    License,
+    /// By registry, then name
+    Registry,
+    /// Disallowed licenses first, then UNKNOWN ones, then the rest by name
+    ViolationsFirst,
+}
+
+/// Sort packages in place for the report listing
+pub fn sort_packages(packages: &mut [Package], key: SortKey, license_checker: &LicenseChecker) {
+    let by_name = |a: &Package, b: &Package| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version));
+
+    match key {
+        SortKey::Name => packages.sort_by(by_name),
+        SortKey::License => packages.sort_by(|a, b| a.license.cmp(&b.license).then_with(|| by_name(a, b))),
+        SortKey::Registry => packages.sort_by(|a, b| a.registry.cmp(&b.registry).then_with(|| by_name(a, b))),
+        SortKey::ViolationsFirst => {
+            // 0 = not allowed, 1 = unknown, 2 = fine
+            let rank = |p: &Package| {
+                if !license_checker.is_package_allowed(p) {
+                    0
+                } else if p.is_unknown() {
+                    1
+                } else {
+                    2
+                }
+            };
+            packages.sort_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| by_name(a, b)));
+        }
+    }
+}
+
+/// Display options that only affect the text format
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextOptions {
+    pub unknown: bool,
+    pub verbose: bool,
+    pub debug: bool,
+    pub retry: bool,
+    pub warn_deprecated: bool,
+    pub check_spdx_deprecations: bool,
+    pub verify_license_text: bool,
+    pub github_unknowns: bool,
+    pub obligations: bool,
+    pub needs_review: bool,
+    pub footprint: bool,
+    pub expand_licenses: bool,
+    pub warn_on_fallback: bool,
+    pub color: bool, // Style the text and tree formats with ANSI colors
+}
+
+/// Everything a writer needs to render a report
+pub struct Report<'a> {
+    pub packages: &'a [Package],
+    pub dependency_tree: &'a HashMap<String, Vec<String>>,
+    pub license_checker: &'a LicenseChecker,
+    pub allowed: &'a [String],
+    pub project_license: Option<&'a str>,
+    pub occurrences: Option<&'a HashMap<String, usize>>, // Set when the listing was deduplicated
+    pub truncated_at: Option<usize>, // Set when --max-packages left packages out of the scan
+    pub text_options: TextOptions,
+}
+
+// The tree of reports that don't track dependency edges
+#[cfg(test)]
+static NO_DEPENDENCY_TREE: once_cell::sync::Lazy<HashMap<String, Vec<String>>> =
+    once_cell::sync::Lazy::new(HashMap::new);
+
+impl<'a> Report<'a> {
+    /// A report of these packages with no allow-list, project license, dependency edges or
+    /// text options; tests set the rest with struct update syntax
+    #[cfg(test)]
+    pub fn new(packages: &'a [Package], license_checker: &'a LicenseChecker) -> Self {
+        Report {
+            packages,
+            dependency_tree: &NO_DEPENDENCY_TREE,
+            license_checker,
+            allowed: &[],
+            project_license: None,
+            occurrences: None,
+            truncated_at: None,
+            text_options: TextOptions::default(),
+        }
+    }
+
+    /// Packages sorted by name and version, for formats that need stable output
+    fn sorted_packages(&self) -> Vec<&Package> {
+        let mut packages: Vec<&Package> = self.packages.iter().collect();
+        packages.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+        packages
+    }
+
+    /// Packages only allowed because of an approved exception
+    fn approved_exceptions(&self) -> Vec<&Package> {
+        self.sorted_packages()
+            .into_iter()
+            .filter(|p| self.license_checker.is_approved_exception(p))
+            .collect()
+    }
+
+    fn violations_count(&self) -> usize {
+        self.packages
+            .iter()
+            .filter(|p| !self.license_checker.is_package_allowed(p))
+            .count()
+    }
+
+    /// Packages allowed but flagged by a warn severity rule
+    fn warnings_count(&self) -> usize {
+        self.packages
+            .iter()
+            .filter(|p| self.license_checker.is_warning(p))
+            .count()
+    }
+
+    /// Compatibility of a package's license with the project license, if one was given
+    fn compatibility(&self, package: &Package) -> Option<Compatibility> {
+        self.project_license.map(|project| license_compat::check_compatibility(project, &package.license))
+    }
+
+    fn license_stats(&self) -> LicenseStats {
+        LicenseStats::from_results(self.packages, self.license_checker)
+    }
+
+    /// Mean confidence of the packages whose license is known
+    fn average_confidence(&self) -> Option<f32> {
+        let confidences: Vec<f32> = self.packages
+            .iter()
+            .filter(|p| !p.is_unknown())
+            .map(|p| p.confidence)
+            .collect();
+
+        if confidences.is_empty() {
+            None
+        } else {
+            Some(confidences.iter().sum::<f32>() / (confidences.len() as f32))
+        }
+    }
+
+    /// Packages whose license confidence is below the --min-confidence threshold
+    fn needs_review(&self) -> Vec<&Package> {
+        self.sorted_packages()
+            .into_iter()
+            .filter(|p| self.license_checker.needs_review(p))
+            .collect()
+    }
+
+    /// Every package that needs a manual check, most severe first
+    fn review_list(&self) -> Vec<ReviewItem<'_>> {
+        review::review_list(&self.sorted_packages(), self.license_checker)
+    }
+
+    /// Packages whose registry lookup failed, as opposed to resolved packages without a license
+    fn failed_packages(&self) -> Vec<&Package> {
+        self.sorted_packages()
+            .into_iter()
+            .filter(|p| p.lookup_failed)
+            .collect()
+    }
+
+    fn unknown_count(&self) -> usize {
+        self.packages
+            .iter()
+            .filter(|p| p.is_unknown())
+            .count()
+    }
+}
+
+/// Whether colored output should be used: not when --no-color or NO_COLOR (any non-empty
+/// value) is set, and not when stdout is redirected to a file or pipe
+pub fn color_enabled(no_color_flag: bool, no_color_env: Option<&str>, stdout_is_terminal: bool) -> bool {
+    !no_color_flag && no_color_env.is_none_or(|value| value.is_empty()) && stdout_is_terminal
+}
+
+/// Whether output to stdout should be colored, given --no-color
+pub fn stdout_color(no_color_flag: bool) -> bool {
+    let no_color_env = std::env::var("NO_COLOR").ok();
+    let stdout_is_terminal = io::IsTerminal::is_terminal(&io::stdout()) || crate::quiet_success::replays_to_terminal();
+    color_enabled(no_color_flag, no_color_env.as_deref(), stdout_is_terminal)
+}
+
+/// Turn coloring on or off for the messages printed outside of reports
+pub fn configure_color(no_color_flag: bool) {
+    colored::control::set_override(stdout_color(no_color_flag));
+}
+
+/// Report styling that only applies when the report is colored
+trait Painted {
+    fn painted(self, color: bool) -> ColoredString;
+}
+
+impl Painted for ColoredString {
+    fn painted(self, color: bool) -> ColoredString {
+        if color { self } else { self.clear() }
+    }
+}
+
+/// Render the report in the requested format
+pub fn write_report(request: &OutputRequest, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    match request.format {
+        OutputFormat::Text => write_text(report, out),
+        OutputFormat::Csv => out.write_all(build_csv(report.packages, &request.csv).as_bytes()),
+        OutputFormat::Json => write_json(report, out),
+        OutputFormat::Tree => {
+            write_dependency_tree(report.dependency_tree, report.packages, report.text_options.color, out)
+        }
+        OutputFormat::TreeJson => write_dependency_tree_json(report.dependency_tree, report.packages, out),
+        OutputFormat::SbomCyclonedx => write_cyclonedx(report, out),
+        OutputFormat::SbomSpdx => write_spdx(report, out),
+        OutputFormat::Html => write_html(report, out),
+        OutputFormat::Markdown => write_markdown(report, out),
+        OutputFormat::Sarif => write_sarif(report, out),
+        OutputFormat::Junit => write_junit(report, out),
+        OutputFormat::LicenseeJson => write_licensee_json(report, out),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Text
+// ----------------------------------------------------------------------------
+
+fn write_text(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let options = report.text_options;
+
+    writeln!(out, "\n=== DEPENDENCY LICENSE SUMMARY ===\n")?;
+
+    for package_info in report.packages {
+        let decision = report.license_checker.evaluate_package(package_info);
+        let occurrences = report.occurrences
+            .and_then(|counts| counts.get(&generate_unique_package_key(package_info)))
+            .copied()
+            .unwrap_or(1);
+        write_package_info(out, package_info, &decision, occurrences, options)?;
+    }
+
+    let total_packages = report.packages.len();
+    let unknown_count = report.unknown_count();
+    let violations_count = report.violations_count();
+
+    // Print summary
+    writeln!(out, "\nTotal packages processed: {}", total_packages)?;
+    if let Some(max) = report.truncated_at {
+        let warning = format!("Scan truncated after {} packages (--max-packages); the report is incomplete", max);
+        writeln!(out, "{}", warning.yellow().bold().painted(options.color))?;
+    }
+
+    if unknown_count > 0 {
+        writeln!(out, "Packages with unknown licenses: {}", unknown_count.to_string().yellow().painted(options.color))?;
+    }
+
+    if let Some(average_confidence) = report.average_confidence() {
+        writeln!(out, "Average license confidence: {:.2}", average_confidence)?;
+    }
+
+    let license_stats = report.license_stats();
+    if let Some(compliance_score) = license_stats.compliance_score() {
+        writeln!(
+            out,
+            "Compliance score: {:.1}% ({} distinct licenses)",
+            compliance_score,
+            license_stats.distinct_licenses()
+        )?;
+    }
+
+    if options.warn_on_fallback {
+        let fallback_count = report.packages
+            .iter()
+            .filter(|p| p.license_fallback)
+            .count();
+        writeln!(out, "Licenses found through a fallback lookup: {}", fallback_count)?;
+    }
+
+    let warnings_count = report.warnings_count();
+    if warnings_count > 0 {
+        writeln!(
+            out,
+            "{} with licenses flagged as warn",
+            warnings_count.to_string().yellow().bold().painted(options.color)
+        )?;
+    }
+
+    if !report.allowed.is_empty() {
+        if violations_count > 0 {
+            writeln!(
+                out,
+                "{} with non-compliant licenses",
+                violations_count.to_string().red().bold().painted(options.color)
+            )?;
+        } else {
+            writeln!(out, "{}", "All licenses are compliant!".green().painted(options.color))?;
+        }
+        writeln!(out, "Allowed license patterns: {}", report.allowed.join(", "))?;
+        write_approved_exceptions(report, out)?;
+    }
+
+    // If unknown flag is set, specifically highlight we're in debugging mode
+    if options.unknown {
+        writeln!(
+            out,
+            "\nRunning in {} mode - showing only packages with unknown licenses",
+            "DEBUG".bright_cyan().bold().painted(options.color)
+        )?;
+
+        // If retry flag is also set, provide additional information
+        if options.retry {
+            writeln!(
+                out,
+                "{}",
+                "Retry mode enabled - cached results for unknown licenses will be ignored"
+                    .bright_cyan()
+                    .bold().painted(options.color)
+            )?;
+        }
+    }
+
+    // Print license usage statistics
+    writeln!(out, "\n=== LICENSE USAGE STATISTICS ===")?;
+
+    let sorted_packages = if options.expand_licenses { report.sorted_packages() } else { Vec::new() };
+    for license_count in report.license_stats().licenses {
+        let license_display = match &license_count.url {
+            Some(url) => format!("{} ({})", license_count.license, url),
+            None => license_count.license.clone(),
+        };
+
+        if license_count.allowed {
+            writeln!(
+                out,
+                "{}: {} packages ({:.1}%)",
+                license_display,
+                license_count.count,
+                license_count.percentage
+            )?;
+        } else {
+            writeln!(
+                out,
+                "{}: {} packages ({:.1}%) {}",
+                license_display,
+                license_count.count,
+                license_count.percentage,
+                "[NOT ALLOWED]".red().bold().painted(options.color)
+            )?;
+        }
+
+        // The UNKNOWN row also counts packages that were never resolved and have no license
+        let listed = |p: &&&Package| {
+            if license_count.license == UNKNOWN_LICENSE { p.is_unknown() } else { p.license == license_count.license }
+        };
+        for package in sorted_packages.iter().filter(listed) {
+            writeln!(out, "  {}@{}", package.name, package.version)?;
+        }
+    }
+
+    if let Some(project_license) = report.project_license {
+        write_compatibility_summary(report, project_license, out)?;
+    }
+
+    if options.warn_deprecated {
+        write_deprecation_warnings(report, out)?;
+    }
+
+    if options.check_spdx_deprecations {
+        write_spdx_deprecation_warnings(report, out)?;
+    }
+
+    if options.verify_license_text {
+        write_license_verification(report, out)?;
+    }
+
+    if options.github_unknowns {
+        write_github_unknowns(report, out)?;
+    }
+
+    if options.obligations {
+        write_obligations(report, out)?;
+    }
+
+    if options.footprint {
+        write_footprint(report, out)?;
+    }
+
+    // The review list already includes the low-confidence packages
+    if options.needs_review {
+        write_review_list(report, out)?;
+    } else {
+        write_needs_review(report, out)?;
+    }
+    write_expired_licenses(report, out)?;
+    write_failed_packages(report, out)?;
+
+    writeln!(out, "\nScan complete.")
+}
+
+// List the time-limited licenses that have run out
+fn write_expired_licenses(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let today = chrono::Utc::now().date_naive();
+    let expired: Vec<&Package> = report
+        .sorted_packages()
+        .into_iter()
+        .filter(|p| p.license_expired(today))
+        .collect();
+
+    if expired.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "\n=== EXPIRED LICENSES ===")?;
+    for package in &expired {
+        writeln!(
+            out,
+            "{} {}@{}: {} expired on {}",
+            "WARNING:".yellow().bold().painted(report.text_options.color),
+            package.name,
+            package.version,
+            package.license,
+            package.license_expiration.as_deref().unwrap_or_default()
+        )?;
+    }
+    writeln!(out, "{} package(s) with an expired license", expired.len())
+}
+
+// List the packages that couldn't be looked up, so they aren't mistaken for unlicensed ones
+fn write_failed_packages(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let failed = report.failed_packages();
+    if failed.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "\n=== FAILED LOOKUPS ===")?;
+    for package in &failed {
+        writeln!(
+            out,
+            "{}@{}: {} {}",
+            package.name,
+            package.version,
+            package.debug_info.as_deref().unwrap_or("unknown error"),
+            "[FAILED]".red().bold().painted(report.text_options.color)
+        )?;
+    }
+    writeln!(out, "{} package(s) could not be looked up", failed.len())
+}
+
+// List the packages whose license was found with less than the required confidence
+fn write_needs_review(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let packages = report.needs_review();
+    if packages.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "\n=== NEEDS REVIEW (LOW CONFIDENCE) ===")?;
+    for package in &packages {
+        writeln!(
+            out,
+            "{}@{}: {} {} (confidence {:.2}{})",
+            package.name,
+            package.version,
+            package.license,
+            "[NEEDS REVIEW]".yellow().bold().painted(report.text_options.color),
+            package.confidence,
+            package.license_source.map_or(String::new(), |source| format!(", from {}", source.as_str()))
+        )?;
+    }
+    writeln!(out, "{} package(s) need review", packages.len())
+}
+
+// List every package that needs a manual check, most severe first, with the reasons
+fn write_review_list(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let items = report.review_list();
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "\n=== REVIEW LIST ===")?;
+    for item in &items {
+        let severity = match item.severity {
+            "high" => "[HIGH]".red().bold().painted(report.text_options.color),
+            "medium" => "[MEDIUM]".yellow().bold().painted(report.text_options.color),
+            _ => "[LOW]".cyan().bold().painted(report.text_options.color),
+        };
+        let reasons: Vec<&str> = item.reasons
+            .iter()
+            .map(|entry| entry.detail.as_str())
+            .collect();
+        writeln!(out, "{} {}@{}: {} ({})", severity, item.name, item.version, item.license, reasons.join("; "))?;
+    }
+    writeln!(out, "{} package(s) need review", items.len())
+}
+
+// List the packages that pass only because of an approved exception
+fn write_approved_exceptions(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let exceptions = report.approved_exceptions();
+    if exceptions.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "\n=== APPROVED EXCEPTIONS ===")?;
+    for package in &exceptions {
+        writeln!(
+            out,
+            "{}@{}: {} {}",
+            package.name,
+            package.version,
+            package.license,
+            "[APPROVED]".cyan().painted(report.text_options.color)
+        )?;
+    }
+    writeln!(out, "{} package(s) allowed by exception", exceptions.len())
+}
+
+// List the packages the registry marks as deprecated or unpublished
+fn write_deprecation_warnings(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let deprecated: Vec<&Package> = report
+        .sorted_packages()
+        .into_iter()
+        .filter(|p| p.deprecated.is_some())
+        .collect();
+
+    if deprecated.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "\n=== DEPRECATED PACKAGES ===")?;
+    for package in &deprecated {
+        writeln!(
+            out,
+            "{} {}@{}: {}",
+            "WARNING:".yellow().bold().painted(report.text_options.color),
+            package.name,
+            package.version,
+            package.deprecated.as_deref().unwrap_or_default()
+        )?;
+    }
+    writeln!(out, "{} deprecated package(s)", deprecated.len())
+}
+
+// List the packages that declare deprecated SPDX ids, with the ids to use instead
+fn write_spdx_deprecation_warnings(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let mut count = 0;
+
+    for package in report.sorted_packages() {
+        let deprecated = crate::spdx_deprecations::declared_deprecated_ids(package);
+        if deprecated.is_empty() {
+            continue;
+        }
+
+        if count == 0 {
+            writeln!(out, "\n=== DEPRECATED SPDX IDENTIFIERS ===")?;
+        }
+        count += 1;
+
+        let suggestions: Vec<String> = deprecated
+            .iter()
+            .map(|(id, replacement)| format!("{} -> {}", id, replacement))
+            .collect();
+        writeln!(
+            out,
+            "{} {}@{}: {}",
+            "WARNING:".yellow().bold().painted(report.text_options.color),
+            package.name,
+            package.version,
+            suggestions.join(", ")
+        )?;
+    }
+
+    if count > 0 {
+        writeln!(out, "{} package(s) use deprecated SPDX identifiers", count)?;
+    }
+    Ok(())
+}
+
+// List the GitHub-sourced packages whose license is UNKNOWN, with a link to check by hand
+fn write_github_unknowns(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let mut unknowns: Vec<(String, String, &Package)> = report.packages
+        .iter()
+        .filter(|p| p.is_unknown())
+        .filter_map(|p| {
+            let (owner, repo, reference) = crate::github_api::repository_location(p)?;
+            Some((format!("{}/{}", owner, repo), reference, p))
+        })
+        .collect();
+    if unknowns.is_empty() {
+        return Ok(());
+    }
+    unknowns.sort_by(|a, b| (&a.0, &a.1, &a.2.name).cmp(&(&b.0, &b.1, &b.2.name)));
+
+    writeln!(out, "\n=== UNRESOLVED GITHUB PACKAGES ===")?;
+    for (repository, reference, package) in &unknowns {
+        writeln!(
+            out,
+            "{}@{}: https://github.com/{} (ref {})",
+            package.name,
+            package.version,
+            repository,
+            reference
+        )?;
+    }
+    writeln!(out, "{} GitHub package(s) need a manual license check", unknowns.len())
+}
+
+// List what each package's license asks of distributors; packages without obligations are left out
+fn write_obligations(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n=== LICENSE OBLIGATIONS ===")?;
+
+    let mut unknown = 0;
+    for package in report.sorted_packages() {
+        match license_obligations::obligations_for(&package.license) {
+            Some(obligations) => {
+                let descriptions = obligations.describe();
+                if !descriptions.is_empty() {
+                    writeln!(
+                        out,
+                        "{}@{} ({}): {}",
+                        package.name,
+                        package.version,
+                        package.license,
+                        descriptions.join(", ")
+                    )?;
+                }
+            }
+            None => unknown += 1,
+        }
+    }
+
+    if unknown > 0 {
+        writeln!(out, "{} package(s) have licenses with unknown obligations", unknown)?;
+    }
+    Ok(())
+}
+
+// List each top-level package with the number of packages it pulls in, largest first
+fn write_footprint(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n=== DEPENDENCY FOOTPRINT ===")?;
+
+    let footprints = dependency_footprints(report.dependency_tree, report.packages);
+    for footprint in &footprints {
+        writeln!(
+            out,
+            "{}@{} ({}): {} transitive package(s) under {} license(s)",
+            footprint.package.name,
+            footprint.package.version,
+            footprint.package.license,
+            footprint.transitive,
+            footprint.licenses
+        )?;
+    }
+    writeln!(out, "{} top-level package(s) with dependencies", footprints.len())
+}
+
+// List the packages whose LICENSE file contradicts their declared license, with totals
+fn write_license_verification(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n=== LICENSE TEXT VERIFICATION ===")?;
+
+    let (mut matches, mut mismatches, mut unverifiable) = (0, 0, 0);
+    for package in report.sorted_packages() {
+        match &package.license_verification {
+            Some(LicenseVerification::Match) => matches += 1,
+            Some(LicenseVerification::Mismatch { detected }) => {
+                mismatches += 1;
+                writeln!(
+                    out,
+                    "{} {}@{}: declares {} but its license file is {}",
+                    "MISMATCH:".red().bold().painted(report.text_options.color),
+                    package.name,
+                    package.version,
+                    package.license,
+                    detected
+                )?;
+            }
+            Some(LicenseVerification::Unverifiable) => unverifiable += 1,
+            None => {}
+        }
+    }
+
+    writeln!(out, "{} match(es), {} mismatch(es), {} unverifiable", matches, mismatches, unverifiable)
+}
+
+// List the packages whose license may not fit the project license
+fn write_compatibility_summary(report: &Report, project_license: &str, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "\n=== LICENSE COMPATIBILITY WITH {} ===", project_license)?;
+
+    let mut counts: HashMap<Compatibility, usize> = HashMap::new();
+    for package in report.sorted_packages() {
+        let compatibility = license_compat::check_compatibility(project_license, &package.license);
+        *counts.entry(compatibility).or_default() += 1;
+
+        let label = match compatibility {
+            Compatibility::Compatible => {
+                continue;
+            }
+            Compatibility::ReviewNeeded => "[REVIEW NEEDED]".yellow().bold().painted(report.text_options.color),
+            Compatibility::Incompatible => "[INCOMPATIBLE]".red().bold().painted(report.text_options.color),
+        };
+        writeln!(out, "{}@{}: {} {}", package.name, package.version, package.license, label)?;
+    }
+
+    writeln!(
+        out,
+        "Compatible: {}, review needed: {}, incompatible: {}",
+        counts.get(&Compatibility::Compatible).unwrap_or(&0),
+        counts.get(&Compatibility::ReviewNeeded).unwrap_or(&0),
+        counts.get(&Compatibility::Incompatible).unwrap_or(&0)
+    )
+}
+
+// Helper function to determine if a package should be displayed
+fn should_display_package(package: &Package, decision: &Decision, options: TextOptions) -> bool {
+    if options.debug {
+        // If --debug flag is set, show everything
+        true
+    } else if options.unknown {
+        // If --unknown flag is set, only show unknown licenses
+        package.is_unknown()
+    } else {
+        // Otherwise use the normal display logic; warnings are always shown
+        !decision.allowed || decision.is_warning() || options.verbose
+    }
+}
+
+// Show where the license was found, for telling declared licenses from detected ones
+fn write_license_source(out: &mut dyn Write, package: &Package, color: bool) -> io::Result<()> {
+    if let Some(source) = package.license_source {
+        writeln!(out, "    Source: {} (confidence {:.2})", source.as_str(), package.confidence)?;
+    }
+    if crate::license_sources::sources_conflict(&package.license_candidates) {
+        writeln!(
+            out,
+            "    Conflicting sources: {} {}",
+            crate::license_sources::describe(&package.license_candidates),
+            "[CONFLICT]".yellow().bold().painted(color)
+        )?;
+    }
+    Ok(())
+}
+
+// Show when a time-limited license runs out, warning once it has
+fn write_license_expiration(out: &mut dyn Write, package: &Package, color: bool) -> io::Result<()> {
+    let Some(expiration) = &package.license_expiration else {
+        return Ok(());
+    };
+    if package.license_expired(chrono::Utc::now().date_naive()) {
+        writeln!(out, "    License expired: {} {}", expiration, "[EXPIRED]".red().bold().painted(color))
+    } else {
+        writeln!(out, "    License expires: {}", expiration)
+    }
+}
+
+// Helper function to format and print package information
+fn write_package_info(
+    out: &mut dyn Write,
+    package: &Package,
+    decision: &Decision,
+    occurrences: usize,
+    options: TextOptions
+) -> io::Result<()> {
+    let is_allowed = decision.allowed;
+
+    // First determine if the package should be displayed
+    if !should_display_package(package, decision, options) {
+        return Ok(());
+    }
+
+    // Format the registry and name - ensure NuGet packages show correctly
+    let mut registry_name = if package.registry == "nuget" {
+        // For NuGet packages, use a consistent format
+        format!("nuget/{}", package.display_name)
+    } else if package.registry == "pypi" {
+        // For Python packages, use a consistent format
+        format!("pypi/{}", package.display_name)
+    } else if !package.display_name.is_empty() {
+        format!("{}/{}", package.registry, package.display_name)
+    } else {
+        format!("{}@{}", package.name, package.version)
+    };
+
+    // Deduplicated listings say how many entries each line stands for
+    if occurrences > 1 {
+        registry_name = format!("{} (x{})", registry_name, occurrences);
+    }
+
+    // Display differently based on license status and verbosity
+    if is_allowed && !package.is_unknown() {
+        if options.verbose || options.debug {
+            writeln!(
+                out,
+                "{} ({}): {}{}",
+                registry_name,
+                package.url,
+                package.license,
+                package.license_url.as_ref().map_or(String::new(), |url| format!(" ({})", url))
+            )?;
+
+            write_license_source(out, package, options.color)?;
+            write_license_expiration(out, package, options.color)?;
+            writeln!(out, "    Compliance: {}", decision.reason)?;
+
+            // In verbose mode, show debug info for all packages
+            if let Some(debug_info) = &package.debug_info {
+                writeln!(out, "    Info: {}", debug_info.yellow().painted(options.color))?;
+            }
+
+            write_raw_response(out, package, options)?;
+        } else if decision.is_warning() {
+            writeln!(
+                out,
+                "{}: {} {}",
+                registry_name,
+                package.license.yellow().painted(options.color),
+                "[WARN]".yellow().bold().painted(options.color)
+            )?;
+        } else {
+            writeln!(out, "{}: {}", registry_name, package.license)?;
+        }
+    } else if options.verbose || options.unknown || options.debug {
+        // Display for non-allowed or unknown licenses
+        writeln!(
+            out,
+            "{} ({}): {}{}",
+            registry_name,
+            package.url,
+            package.license.red().bold().painted(options.color),
+            package.license_url
+                .as_ref()
+                .map_or(String::new(), |url| format!(" ({})", url).red().bold().painted(options.color).to_string())
+        )?;
+
+        write_license_source(out, package, options.color)?;
+        write_license_expiration(out, package, options.color)?;
+        writeln!(out, "    Compliance: {}", decision.reason)?;
+
+        // Show debug info for all packages in verbose mode, or UNKNOWN in debug mode
+        if let Some(debug_info) = &package.debug_info {
+            writeln!(out, "    Info: {}", debug_info.yellow().painted(options.color))?;
+        }
+
+        write_raw_response(out, package, options)?;
+    } else {
+        writeln!(
+            out,
+            "{}: {}{}",
+            registry_name,
+            package.license.red().bold().painted(options.color),
+            package.license_url
+                .as_ref()
+                .map_or(String::new(), |url| format!(" ({})", url).red().bold().painted(options.color).to_string())
+        )?;
+
+        // Show minimal debug info even in non-verbose mode for UNKNOWN licenses
+        if package.is_unknown() {
+            writeln!(out, "    Registry URL: {}", package.url.yellow().painted(options.color))?;
+        }
+    }
+
+    Ok(())
+}
+
+// In debug mode, show complete raw API response if available
+fn write_raw_response(out: &mut dyn Write, package: &Package, options: TextOptions) -> io::Result<()> {
+    if let Some(raw_response) = package.raw_api_response.as_ref().filter(|_| options.debug) {
+        writeln!(out, "\n=== RAW API RESPONSE ===")?;
+        writeln!(out, "{}", raw_response.cyan().painted(options.color))?;
+        writeln!(out, "=== END API RESPONSE ===\n")?;
+    }
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// CSV
+// ----------------------------------------------------------------------------
+
+/// How CSV reports are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvOptions {
+    pub bom: bool, // Start with a UTF-8 byte order mark, without which Excel misreads UTF-8
+    pub delimiter: char, // ';' where the locale uses ',' as the decimal separator
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions { bom: false, delimiter: ',' }
+    }
+}
+
+/// Build the CSV content for the unique packages in the given list
+pub fn build_csv(packages: &[Package], options: &CsvOptions) -> String {
+    // Create a map to store unique packages using an improved normalization approach
+    let mut unique_packages: HashMap<String, &Package> = HashMap::new();
+
+    // First pass: collect all packages and prefer those with known licenses
+    for package in packages {
+        let key = generate_unique_package_key(package);
+
+        match unique_packages.get(&key) {
+            Some(existing) => {
+                // Replace if the new package has a known license and the existing one doesn't
+                if existing.is_unknown() && !package.is_unknown() {
+                    unique_packages.insert(key, package);
+                }
+                // Otherwise keep the existing one
+            }
+            None => {
+                unique_packages.insert(key, package);
+            }
+        }
+    }
+
+    // Sort keys for consistent output
+    let mut sorted_keys: Vec<_> = unique_packages.keys().collect();
+    sorted_keys.sort();
+
+    // Track which package names we've already output to ensure no duplicate entries
+    let mut output_names = HashSet::new();
+
+    // Prepare the CSV content
+    let mut csv_content = csv_header(options);
+
+    for key in sorted_keys {
+        let package = unique_packages.get(key).unwrap();
+
+        // Create a simple name key for final deduplication check
+        let output_key = format!("{}|{}", package.name, package.url);
+
+        // Skip if we've already output this package (final safety check)
+        if output_names.contains(&output_key) {
+            continue;
+        }
+
+        csv_content.push_str(&csv_line(package, options));
+
+        // Mark this package as output
+        output_names.insert(output_key);
+    }
+
+    csv_content
+}
+
+const CSV_COLUMNS: [&str; 3] = ["name", "url", "license"];
+
+/// The byte order mark, if requested, and the header row
+fn csv_header(options: &CsvOptions) -> String {
+    let bom = if options.bom { "\u{feff}" } else { "" };
+    format!("{}{}\n", bom, CSV_COLUMNS.join(&options.delimiter.to_string()))
+}
+
+/// Format one package as a CSV row
+fn csv_line(package: &Package, options: &CsvOptions) -> String {
+    // Clean fields to ensure proper CSV formatting: replace commas, the delimiter and quotes
+    let clean = |field: &str| field.replace([',', options.delimiter], " ").replace('"', "'");
+
+    format!(
+        "\"{}\"{delimiter}\"{}\"{delimiter}\"{}\"\n",
+        clean(&package.name),
+        clean(&package.url),
+        clean(&package.license),
+        delimiter = options.delimiter
+    )
+}
+
+/// Collapse packages that share a unique key, keeping the first entry unless a later one
+/// has a known license. Returns the unique packages in first-seen order and how many
+/// entries each key had.
+pub fn dedupe_packages(packages: Vec<Package>) -> (Vec<Package>, HashMap<String, usize>) {
+    let mut unique: Vec<Package> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+
+    for package in packages {
+        let key = generate_unique_package_key(&package);
+        *occurrences.entry(key.clone()).or_default() += 1;
+
+        match positions.get(&key) {
+            Some(&index) => {
+                if unique[index].is_unknown() && !package.is_unknown() {
+                    unique[index] = package;
+                }
+            }
+            None => {
+                positions.insert(key, unique.len());
+                unique.push(package);
+            }
+        }
+    }
+
+    (unique, occurrences)
+}
+
+/// Merge results for the same registry, name and version that were resolved separately, e.g.
+/// through different resolutions in different lockfiles. The most confident license wins, the
+/// lockfiles of all entries are kept, and a differing license is noted in debug_info.
+pub fn reconcile_packages(packages: Vec<Package>) -> Vec<Package> {
+    let mut reconciled: Vec<Package> = Vec::new();
+    let mut positions: HashMap<(String, String, String), usize> = HashMap::new();
+
+    for package in packages {
+        let key = (utils::package_registry(&package), package.name.clone(), package.version.clone());
+        let Some(&index) = positions.get(&key) else {
+            positions.insert(key, reconciled.len());
+            reconciled.push(package);
+            continue;
+        };
+
+        let existing = &mut reconciled[index];
+        let (mut kept, other) = if license_rank(&package) > license_rank(existing) {
+            (package, existing.clone())
+        } else {
+            (existing.clone(), package)
+        };
+
+        if other.license != kept.license {
+            let note = format!(
+                "also resolved as {} via {}",
+                other.license,
+                other.source_lockfiles.join(", ")
+            );
+            kept.debug_info = Some(match kept.debug_info.take() {
+                Some(debug_info) => format!("{}; {}", debug_info, note),
+                None => note,
+            });
+        }
+        for lockfile in other.source_lockfiles {
+            if !kept.source_lockfiles.contains(&lockfile) {
+                kept.source_lockfiles.push(lockfile);
+            }
+        }
+        kept.source_lockfiles.sort();
+
+        *existing = kept;
+    }
+
+    reconciled
+}
+
+/// Order licenses by how much they can be trusted: known over UNKNOWN, then successful
+/// lookups, then detection confidence
+fn license_rank(package: &Package) -> (bool, bool, f32) {
+    (!package.is_unknown(), !package.lookup_failed, package.confidence)
+}
+
+/// Generate a consistent unique key for a package by normalizing its name and version
+fn generate_unique_package_key(package: &Package) -> String {
+    // Normalize package name by:
+    // - Converting to lowercase
+    // - Stripping any registry prefixes (like github:)
+    let normalized_name = package.name.trim_start_matches("github:").to_lowercase();
+
+    // Normalize version by:
+    // - Removing leading ^ and ~ which are version range indicators
+    // - Canonicalizing semantic versions, so "1.2" matches "1.2.0" but not "1.2.0-beta"
+    // - Keeping anything else (git refs, ranges) as written
+    let version = crate::github_api::strip_range_operator(&package.version);
+    let normalized_version = semver_resolver
+        ::canonical_version(version)
+        .unwrap_or_else(|| version.to_string());
+
+    // Make URL part of the key to better distinguish same-named packages from different sources
+    let normalized_url = package.url.to_lowercase();
+
+    // Construct a compound key that includes all relevant unique identifiers
+    format!("{}|{}|{}", normalized_name, normalized_version, normalized_url)
+}
+
+// ----------------------------------------------------------------------------
+// Tree
+// ----------------------------------------------------------------------------
+
+/// Write the dependency tree visualization
+fn write_dependency_tree(
+    dep_tree: &HashMap<String, Vec<String>>,
+    packages: &[Package],
+    color: bool,
+    out: &mut dyn Write
+) -> io::Result<()> {
+    let package_map = tree_package_map(packages);
+    let root_packages = tree_roots(dep_tree, packages);
+
+    writeln!(out, "=== DEPENDENCY TREE ===\n")?;
+
+    // Print tree for each root package
+    for (i, root) in root_packages.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?; // Add empty line between root packages
+        }
+
+        if let Some(package) = package_map.get(root) {
+            writeln!(out, "{} ({})", package.name.bold().painted(color), package.license)?;
+            write_dependencies(out, root, dep_tree, &package_map, 1, &mut HashSet::new())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Map of package_id to package for quick lookup
+fn tree_package_map(packages: &[Package]) -> HashMap<String, &Package> {
+    packages
+        .iter()
+        .map(|p| (format!("{}@{}", p.name, p.version), p))
+        .collect()
+}
+
+/// Root packages (those that are not dependencies of any other package), sorted
+fn tree_roots(dep_tree: &HashMap<String, Vec<String>>, packages: &[Package]) -> Vec<String> {
+    let all_deps: HashSet<&String> = dep_tree.values().flatten().collect();
+
+    let mut root_packages: Vec<String> = packages
+        .iter()
+        .map(|package| format!("{}@{}", package.name, package.version))
+        .filter(|package_id| !all_deps.contains(package_id) && dep_tree.contains_key(package_id))
+        .collect();
+
+    // Sort root packages for consistent output
+    root_packages.sort();
+    root_packages
+}
+
+/// A top-level package and what it pulls in
+struct Footprint<'a> {
+    package: &'a Package,
+    transitive: usize, // Distinct packages reachable from it, shared ones counted once
+    licenses: usize, // Distinct licenses among them
+}
+
+/// The footprint of each tree root, largest subtree first
+fn dependency_footprints<'a>(dep_tree: &HashMap<String, Vec<String>>, packages: &'a [Package]) -> Vec<Footprint<'a>> {
+    let package_map = tree_package_map(packages);
+
+    let mut footprints: Vec<Footprint> = tree_roots(dep_tree, packages)
+        .iter()
+        .filter_map(|root| {
+            let package = *package_map.get(root)?;
+
+            // Walk the subtree once; the visited set also stops at cycles
+            let mut visited = HashSet::from([root.as_str()]);
+            let mut pending = vec![root.as_str()];
+            while let Some(package_id) = pending.pop() {
+                for dep_id in dep_tree.get(package_id).into_iter().flatten() {
+                    if visited.insert(dep_id.as_str()) {
+                        pending.push(dep_id);
+                    }
+                }
+            }
+
+            let subtree: Vec<&Package> = visited
+                .into_iter()
+                .filter(|package_id| package_id != root)
+                .filter_map(|package_id| package_map.get(package_id).copied())
+                .collect();
+            let licenses: HashSet<&str> = subtree
+                .iter()
+                .map(|p| p.license.as_str())
+                .collect();
+            Some(Footprint { package, transitive: subtree.len(), licenses: licenses.len() })
+        })
+        .collect();
+
+    // Roots are sorted by id, and the sort is stable
+    footprints.sort_by_key(|footprint| std::cmp::Reverse(footprint.transitive));
+    footprints
+}
+
+/// Helper function to recursively print dependencies
+fn write_dependencies(
+    out: &mut dyn Write,
+    package_id: &str,
+    dep_tree: &HashMap<String, Vec<String>>,
+    package_map: &HashMap<String, &Package>,
+    level: usize,
+    visited: &mut HashSet<String>
+) -> io::Result<()> {
+    // Check for circular dependencies
+    if visited.contains(package_id) {
+        let indent = "  ".repeat(level);
+        return writeln!(out, "{}└── {} [circular reference]", indent, package_id);
+    }
+
+    // Mark this package as visited
+    visited.insert(package_id.to_string());
+
+    // Get dependencies for this package
+    if let Some(deps) = dep_tree.get(package_id) {
+        let mut sorted_deps = deps.clone();
+        sorted_deps.sort();
+
+        for (i, dep_id) in sorted_deps.iter().enumerate() {
+            let is_last = i == sorted_deps.len() - 1;
+            let indent = "  ".repeat(level);
+            let prefix = if is_last { "└── " } else { "├── " };
+
+            if let Some(package) = package_map.get(dep_id) {
+                // Print dependency with its license
+                writeln!(out, "{}{}{} ({})", indent, prefix, package.name, package.license)?;
+
+                // Recursively print dependencies of this dependency
+                write_dependencies(out, dep_id, dep_tree, package_map, level + 1, visited)?;
+            } else {
+                // Package not found in map
+                writeln!(out, "{}{}{} [unknown]", indent, prefix, dep_id)?;
+            }
+        }
+    }
+
+    // Remove from visited set on way back up
+    visited.remove(package_id);
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct TreeNode<'a> {
+    name: &'a str,
+    version: &'a str,
+    license: &'a str,
+    // Set on a package already on the path from the root; its children are left out
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    circular: bool,
+    children: Vec<TreeNode<'a>>,
+}
+
+/// Write the dependency tree as an array of nested root nodes
+fn write_dependency_tree_json(
+    dep_tree: &HashMap<String, Vec<String>>,
+    packages: &[Package],
+    out: &mut dyn Write
+) -> io::Result<()> {
+    let package_map = tree_package_map(packages);
+    let root_packages = tree_roots(dep_tree, packages);
+
+    let roots: Vec<TreeNode> = root_packages
+        .iter()
+        .map(|root| build_tree_node(root, dep_tree, &package_map, &mut HashSet::new()))
+        .collect();
+
+    write_pretty_json(&roots, out)
+}
+
+fn build_tree_node<'a>(
+    package_id: &'a str,
+    dep_tree: &'a HashMap<String, Vec<String>>,
+    package_map: &HashMap<String, &'a Package>,
+    visited: &mut HashSet<&'a str>
+) -> TreeNode<'a> {
+    let mut node = match package_map.get(package_id) {
+        Some(package) =>
+            TreeNode {
+                name: &package.name,
+                version: &package.version,
+                license: &package.license,
+                circular: false,
+                children: Vec::new(),
+            },
+        None => {
+            // Not resolved; split the id after the name (scoped names start with '@')
+            let (name, version) = match package_id.rfind('@').filter(|&pos| pos > 0) {
+                Some(pos) => (&package_id[..pos], &package_id[pos + 1..]),
+                None => (package_id, ""),
+            };
+            TreeNode { name, version, license: UNKNOWN_LICENSE, circular: false, children: Vec::new() }
+        }
+    };
+
+    if visited.contains(package_id) {
+        node.circular = true;
+        return node;
+    }
+
+    if let Some(deps) = dep_tree.get(package_id) {
+        visited.insert(package_id);
+
+        let mut sorted_deps: Vec<&String> = deps.iter().collect();
+        sorted_deps.sort();
+        node.children = sorted_deps
+            .into_iter()
+            .map(|dep_id| build_tree_node(dep_id, dep_tree, package_map, visited))
+            .collect();
+
+        // Remove from visited set on way back up
+        visited.remove(package_id);
+    }
+
+    node
+}
+
+// ----------------------------------------------------------------------------
+// Graphviz
+// ----------------------------------------------------------------------------
+
+/// Write the dependency tree as a Graphviz DOT graph, with nodes colored by license status:
+/// green for allowed, red for not allowed, yellow for unknown licenses. Render with `dot -Tsvg`.
+pub fn write_dot_graph(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let package_map: HashMap<String, &Package> = report.packages
+        .iter()
+        .map(|p| (format!("{}@{}", p.name, p.version), p))
+        .collect();
+
+    let mut edges: Vec<(&String, &String)> = report.dependency_tree
+        .iter()
+        .flat_map(|(parent, children)| children.iter().map(move |child| (parent, child)))
+        .collect();
+    edges.sort();
+    edges.dedup();
+
+    let nodes: BTreeSet<&String> = edges
+        .iter()
+        .flat_map(|(parent, child)| [*parent, *child])
+        .collect();
+
+    writeln!(out, "digraph dependencies {{")?;
+    writeln!(out, "  node [shape=box, style=filled];")?;
+
+    for node in nodes {
+        let color = match package_map.get(node) {
+            Some(package) if package.is_unknown() => "#f7e08c",
+            Some(package) if !report.license_checker.is_package_allowed(package) => "#f4a6a6",
+            Some(_) => "#b7e4b0",
+            None => "#f7e08c", // Not resolved, so the license is unknown too
+        };
+        writeln!(out, "  \"{}\" [fillcolor=\"{}\"];", escape_dot(node), color)?;
+    }
+
+    for (parent, child) in edges {
+        writeln!(out, "  \"{}\" -> \"{}\";", escape_dot(parent), escape_dot(child))?;
+    }
+
+    writeln!(out, "}}")
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// ----------------------------------------------------------------------------
+// JSON
+// ----------------------------------------------------------------------------
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    packages: Vec<JsonPackage<'a>>,
+    errors: Vec<JsonError<'a>>,
+    review: Vec<ReviewItem<'a>>,
+    diagnostics: BTreeMap<String, RegistryDiagnostics>, // Lookup outcomes per registry
+    summary: JsonSummary<'a>,
+}
+
+#[derive(Serialize)]
+struct JsonError<'a> {
+    name: &'a str,
+    version: &'a str,
+    error: &'a str,
+    source_lockfiles: &'a [String],
+}
+
+#[derive(Serialize)]
+struct JsonPackage<'a> {
+    name: &'a str,
+    version: &'a str,
+    registry: &'a str,
+    license: &'a str,
+    license_url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    license_expiration: Option<&'a str>,
+    license_source: Option<LicenseSource>,
+    confidence: f32,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    license_candidates: &'a [LicenseCandidate],
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    needs_review: bool,
+    url: &'a str,
+    allowed: bool,
+    compliance: Decision,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    approved_exception: bool,
+    source_lockfiles: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compatibility: Option<Compatibility>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deprecated: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    license_verification: Option<&'a LicenseVerification>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    obligations: Option<Obligations>,
+    debug_info: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonSummary<'a> {
+    total: usize,
+    truncated: bool, // --max-packages left packages out
+    unknown: usize,
+    failed: usize,
+    violations: usize,
+    warnings: usize,
+    approved_exceptions: usize,
+    needs_review: usize,
+    average_confidence: Option<f32>,
+    compliance_score: Option<f64>, // Percentage of packages with a known, allowed license
+    distinct_licenses: usize,
+    licenses: Vec<LicenseCount>,
+    allowed_patterns: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project_license: Option<&'a str>,
+}
+
+impl<'a> JsonPackage<'a> {
+    fn new(package: &'a Package, license_checker: &LicenseChecker, compatibility: Option<Compatibility>) -> Self {
+        JsonPackage {
+            name: &package.name,
+            version: &package.version,
+            registry: &package.registry,
+            license: &package.license,
+            license_url: package.license_url.as_deref(),
+            license_expiration: package.license_expiration.as_deref(),
+            license_source: package.license_source,
+            confidence: package.confidence,
+            license_candidates: &package.license_candidates,
+            needs_review: license_checker.needs_review(package),
+            url: &package.url,
+            allowed: license_checker.is_package_allowed(package),
+            compliance: license_checker.evaluate_package(package),
+            approved_exception: license_checker.is_approved_exception(package),
+            source_lockfiles: &package.source_lockfiles,
+            compatibility,
+            deprecated: package.deprecated.as_deref(),
+            license_verification: package.license_verification.as_ref(),
+            obligations: license_obligations::obligations_for(&package.license),
+            debug_info: package.debug_info.as_deref(),
+        }
+    }
+}
+
+fn json_report<'a>(report: &'a Report) -> JsonReport<'a> {
+    let license_stats = report.license_stats();
+    JsonReport {
+        packages: report
+            .sorted_packages()
+            .into_iter()
+            .map(|p| JsonPackage::new(p, report.license_checker, report.compatibility(p)))
+            .collect(),
+        errors: report
+            .failed_packages()
+            .into_iter()
+            .map(|p| JsonError {
+                name: &p.name,
+                version: &p.version,
+                error: p.debug_info.as_deref().unwrap_or("unknown error"),
+                source_lockfiles: &p.source_lockfiles,
+            })
+            .collect(),
+        review: report.review_list(),
+        diagnostics: diagnostics::by_registry(report.packages),
+        summary: JsonSummary {
+            total: report.packages.len(),
+            truncated: report.truncated_at.is_some(),
+            unknown: report.unknown_count(),
+            failed: report.failed_packages().len(),
+            violations: report.violations_count(),
+            warnings: report.warnings_count(),
+            approved_exceptions: report.approved_exceptions().len(),
+            needs_review: report.needs_review().len(),
+            average_confidence: report.average_confidence(),
+            compliance_score: license_stats.compliance_score(),
+            distinct_licenses: license_stats.distinct_licenses(),
+            licenses: license_stats.licenses,
+            allowed_patterns: report.allowed,
+            project_license: report.project_license,
+        },
+    }
+}
+
+fn write_json(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    write_pretty_json(&json_report(report), out)
+}
+
+// ----------------------------------------------------------------------------
+// Templates
+// ----------------------------------------------------------------------------
+
+/// What --template files can refer to: the JSON report's `packages`, `errors` and `summary`,
+/// plus `violations` (the packages that aren't allowed) and `tool` (`name`, `version`)
+#[derive(Serialize)]
+struct TemplateContext<'a> {
+    #[serde(flatten)]
+    report: JsonReport<'a>,
+    violations: Vec<JsonPackage<'a>>,
+    tool: TemplateTool,
+}
+
+#[derive(Serialize)]
+struct TemplateTool {
+    name: &'static str,
+    version: &'static str,
+}
+
+/// Render the report with a TinyTemplate template; values are inserted without HTML escaping
+pub fn write_template(template: &str, report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let to_io_error = |e: tinytemplate::error::Error| io::Error::new(io::ErrorKind::InvalidData, e.to_string());
+
+    let mut engine = TinyTemplate::new();
+    engine.set_default_formatter(&tinytemplate::format_unescaped);
+    engine.add_template("report", template).map_err(to_io_error)?;
+
+    let context = TemplateContext {
+        report: json_report(report),
+        violations: report
+            .sorted_packages()
+            .into_iter()
+            .filter(|p| !report.license_checker.is_package_allowed(p))
+            .map(|p| JsonPackage::new(p, report.license_checker, report.compatibility(p)))
+            .collect(),
+        tool: TemplateTool { name: TOOL_NAME, version: TOOL_VERSION },
+    };
+
+    let rendered = engine.render("report", &context).map_err(to_io_error)?;
+    out.write_all(rendered.as_bytes())
+}
+
+#[derive(Serialize)]
+struct LicenseeReport<'a> {
+    dependencies: Vec<LicenseeDependency<'a>>,
+}
+
+#[derive(Serialize)]
+struct LicenseeDependency<'a> {
+    name: &'a str,
+    version: &'a str,
+    licenses: Vec<String>,
+}
+
+/// The alternatives of a top-level SPDX OR expression, each of which may be chosen;
+/// other licenses (including AND expressions) are a single entry
+fn license_alternatives(license: &str) -> Vec<String> {
+    if is_unknown_license(license) {
+        return Vec::new();
+    }
+
+    let mut license = license.trim();
+    // Drop parentheses wrapping the whole expression
+    while wrapped_in_parentheses(license) {
+        license = license[1..license.len() - 1].trim();
+    }
+
+    let mut alternatives = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in license.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ' ' if depth == 0 && license[i..].starts_with(" OR ") => {
+                alternatives.push(license[start..i].trim().to_string());
+                start = i + " OR ".len();
+            }
+            _ => {}
+        }
+    }
+    alternatives.push(license[start..].trim().to_string());
+    alternatives
+}
+
+/// Whether the whole expression sits inside one pair of parentheses, as in "(MIT OR ISC)"
+/// but not "(MIT) OR (ISC)"
+fn wrapped_in_parentheses(license: &str) -> bool {
+    if !license.starts_with('(') || !license.ends_with(')') {
+        return false;
+    }
+    let mut depth = 0;
+    for (i, c) in license.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 && i != license.len() - 1 {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    true
+}
+
+fn write_licensee_json(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let licensee_report = LicenseeReport {
+        dependencies: report
+            .sorted_packages()
+            .into_iter()
+            .map(|p| LicenseeDependency {
+                name: &p.name,
+                version: &p.version,
+                licenses: license_alternatives(&p.license),
+            })
+            .collect(),
+    };
+
+    write_pretty_json(&licensee_report, out)
+}
+
+fn write_pretty_json<T: Serialize>(value: &T, out: &mut dyn Write) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, value)?;
+    writeln!(out)
+}
+
+// ----------------------------------------------------------------------------
+// Streaming
+// ----------------------------------------------------------------------------
+
+/// Writes each package as soon as it is resolved instead of buffering a full report.
+/// CSV rows or JSON Lines; rows appear in completion order and are not deduplicated.
+pub struct StreamWriter {
+    format: OutputFormat,
+    csv: CsvOptions,
+    license_checker: Arc<LicenseChecker>,
+    out: Mutex<Box<dyn Write + Send>>,
+    written: AtomicUsize,
+    violations: AtomicUsize,
+    findings: AtomicUsize,
+}
+
+impl StreamWriter {
+    /// Whether a format can be streamed
+    pub fn supports(format: OutputFormat) -> bool {
+        matches!(format, OutputFormat::Csv | OutputFormat::Json)
+    }
+
+    pub fn new(
+        request: &OutputRequest,
+        license_checker: Arc<LicenseChecker>,
+        mut out: Box<dyn Write + Send>
+    ) -> io::Result<Self> {
+        if request.format == OutputFormat::Csv {
+            out.write_all(csv_header(&request.csv).as_bytes())?;
+        }
+
+        Ok(StreamWriter {
+            format: request.format,
+            csv: request.csv,
+            license_checker,
+            out: Mutex::new(out),
+            written: AtomicUsize::new(0),
+            violations: AtomicUsize::new(0),
+            findings: AtomicUsize::new(0),
+        })
+    }
+
+    /// Write one resolved package
+    pub fn write_package(&self, package: &Package) -> io::Result<()> {
+        let allowed = self.license_checker.is_package_allowed(package);
+        let line = match self.format {
+            OutputFormat::Csv => csv_line(package, &self.csv),
+            _ => format!("{}\n", serde_json::to_string(&JsonPackage::new(package, &self.license_checker, None))?),
+        };
+
+        let mut out = self.out.lock().unwrap();
+        out.write_all(line.as_bytes())?;
+        out.flush()?;
+
+        self.written.fetch_add(1, Ordering::Relaxed);
+        if !allowed {
+            self.violations.fetch_add(1, Ordering::Relaxed);
+        }
+        if !allowed || package.is_unknown() || package.lookup_failed {
+            self.findings.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Number of packages written so far
+    pub fn written(&self) -> usize {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    /// Number of written packages with disallowed licenses
+    pub fn violations(&self) -> usize {
+        self.violations.load(Ordering::Relaxed)
+    }
+
+    /// Number of written packages that are violations, have unknown licenses or failed lookups
+    pub fn findings(&self) -> usize {
+        self.findings.load(Ordering::Relaxed)
+    }
+}
+
+// ----------------------------------------------------------------------------
+// SBOM (CycloneDX and SPDX)
+// ----------------------------------------------------------------------------
+
+/// Build the package URL (purl) identifying a package in SBOMs
+fn package_url(package: &Package) -> String {
+    match package.registry.as_str() {
+        "pypi" => format!("pkg:pypi/{}@{}", package.name.to_lowercase(), package.version),
+        "nuget" => format!("pkg:nuget/{}@{}", package.name, package.version),
+        "github" => {
+            let source = if package.url.contains("github.com") { &package.url } else { &package.name };
+            match crate::github_api::extract_github_details(source) {
+                Ok((owner, repo, reference)) => format!("pkg:github/{}/{}@{}", owner, repo, reference),
+                Err(_) => format!("pkg:generic/{}@{}", package.name, package.version),
+            }
+        }
+        _ => {
+            // Scoped npm packages encode the @ of the namespace
+            let name = package.name.replacen('@', "%40", usize::from(package.name.starts_with('@')));
+            format!("pkg:npm/{}@{}", name, package.version)
+        }
+    }
+}
+
+/// Turn a license string into a valid SPDX license expression
+fn spdx_license_expression(license: &str) -> String {
+    if is_unknown_license(license) {
+        "NOASSERTION".to_string()
+    } else if crate::license_detection::is_spdx_expression(license) {
+        license.to_string()
+    } else {
+        // Free-text licenses must be expressed as LicenseRef ids
+        let id: String = license
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' })
+            .collect();
+        format!("LicenseRef-{}", id)
+    }
+}
+
+fn utc_timestamp() -> String {
+    chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn write_cyclonedx(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let components: Vec<Value> = report
+        .sorted_packages()
+        .into_iter()
+        .map(|p| {
+            let purl = package_url(p);
+            let mut component = json!({
+                "type": "library",
+                "bom-ref": purl,
+                "name": p.name,
+                "version": p.version,
+                "purl": purl,
+            });
+
+            // Scoped npm packages carry their scope as the group
+            if p.registry == "npm" {
+                if let Some((scope, name)) = p.name.strip_prefix('@').and_then(|n| n.split_once('/')) {
+                    component["group"] = json!(format!("@{}", scope));
+                    component["name"] = json!(name);
+                }
+            }
+
+            if !p.is_unknown() {
+                component["licenses"] = if p.license.contains(" OR ") || p.license.contains(" AND ") {
+                    json!([{ "expression": p.license }])
+                } else if crate::license_detection::is_spdx_expression(&p.license) {
+                    json!([{ "license": { "id": p.license } }])
+                } else {
+                    json!([{ "license": { "name": p.license } }])
+                };
+            }
+
+            if !p.url.is_empty() {
+                component["externalReferences"] = json!([{ "type": "website", "url": p.url }]);
+            }
+
+            component
+        })
+        .collect();
+
+    let bom = json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": utc_timestamp(),
+            "tools": {
+                "components": [{ "type": "application", "name": TOOL_NAME, "version": TOOL_VERSION }]
+            }
+        },
+        "components": components,
+    });
+
+    write_pretty_json(&bom, out)
+}
+
+fn write_spdx(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let packages = report.sorted_packages();
+
+    let spdx_packages: Vec<Value> = packages
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            json!({
+                "SPDXID": format!("SPDXRef-Package-{}", i + 1),
+                "name": p.name,
+                "versionInfo": p.version,
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "homepage": if p.url.is_empty() { "NOASSERTION" } else { p.url.as_str() },
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": spdx_license_expression(&p.license),
+                "copyrightText": "NOASSERTION",
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": package_url(p),
+                }],
+            })
+        })
+        .collect();
+
+    let relationships: Vec<Value> = (1..=packages.len())
+        .map(|i| {
+            json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": format!("SPDXRef-Package-{}", i),
+            })
+        })
+        .collect();
+
+    // The namespace must be unique per document; derive it from the creation time and contents
+    let created = utc_timestamp();
+    let namespace_seed = format!("{}{}", created, packages.iter().map(|p| package_url(p)).collect::<String>());
+    let namespace_hash = format!("{:x}", Sha256::digest(namespace_seed.as_bytes()));
+
+    let document = json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": format!("{}-report", TOOL_NAME),
+        "documentNamespace": format!("https://spdx.org/spdxdocs/{}-{}", TOOL_NAME, namespace_hash),
+        "creationInfo": {
+            "created": created,
+            "creators": [format!("Tool: {}-{}", TOOL_NAME, TOOL_VERSION)],
+        },
+        "packages": spdx_packages,
+        "relationships": relationships,
+    });
+
+    write_pretty_json(&document, out)
+}
+
+// ----------------------------------------------------------------------------
+// HTML and Markdown
+// ----------------------------------------------------------------------------
+
+/// Escape text for inclusion in HTML or XML
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn status_label(report: &Report, package: &Package) -> &'static str {
+    if !report.license_checker.is_package_allowed(package) {
+        "NOT ALLOWED"
+    } else if package.is_unknown() {
+        "UNKNOWN"
+    } else {
+        "OK"
+    }
+}
+
+fn write_html(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Dependency License Report</title>")?;
+    writeln!(
+        out,
+        "<style>body{{font-family:sans-serif}}table{{border-collapse:collapse}}\
+         td,th{{border:1px solid #ccc;padding:4px 8px;text-align:left}}\
+         .violation{{background:#fdd}}.unknown{{background:#ffd}}</style>"
+    )?;
+    writeln!(out, "</head>\n<body>\n<h1>Dependency License Report</h1>")?;
+    writeln!(
+        out,
+        "<p>Total packages: {} &middot; Unknown licenses: {} &middot; Violations: {}</p>",
+        report.packages.len(),
+        report.unknown_count(),
+        report.violations_count()
+    )?;
+    writeln!(out, "<table>")?;
+    writeln!(out, "<tr><th>Package</th><th>Version</th><th>License</th><th>Status</th></tr>")?;
+
+    for package in report.sorted_packages() {
+        let status = status_label(report, package);
+        let class = match status {
+            "NOT ALLOWED" => " class=\"violation\"",
+            "UNKNOWN" => " class=\"unknown\"",
+            _ => "",
+        };
+        let name = if package.url.is_empty() {
+            escape_xml(&package.name)
+        } else {
+            format!("<a href=\"{}\">{}</a>", escape_xml(&package.url), escape_xml(&package.name))
+        };
+        let license = match &package.license_url {
+            Some(url) => format!("<a href=\"{}\">{}</a>", escape_xml(url), escape_xml(&package.license)),
+            None => escape_xml(&package.license),
+        };
+
+        writeln!(
+            out,
+            "<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            class,
+            name,
+            escape_xml(&package.version),
+            license,
+            status
+        )?;
+    }
+
+    writeln!(out, "</table>\n</body>\n</html>")
+}
+
+/// Escape text for a Markdown table cell
+fn escape_markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn write_markdown(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "# Dependency License Report\n")?;
+    writeln!(
+        out,
+        "**Total packages:** {} | **Unknown licenses:** {} | **Violations:** {}\n",
+        report.packages.len(),
+        report.unknown_count(),
+        report.violations_count()
+    )?;
+    writeln!(out, "| Package | Version | License | Status |")?;
+    writeln!(out, "| --- | --- | --- | --- |")?;
+
+    for package in report.sorted_packages() {
+        let name = if package.url.is_empty() {
+            escape_markdown_cell(&package.name)
+        } else {
+            format!("[{}]({})", escape_markdown_cell(&package.name), package.url)
+        };
+        writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            name,
+            escape_markdown_cell(&package.version),
+            escape_markdown_cell(&package.license),
+            status_label(report, package)
+        )?;
+    }
+
+    writeln!(out, "\n## License usage\n")?;
+    writeln!(out, "| License | Packages | Share | Allowed |")?;
+    writeln!(out, "| --- | --- | --- | --- |")?;
+    for license_count in report.license_stats().licenses {
+        let license = match &license_count.url {
+            Some(url) => format!("[{}]({})", escape_markdown_cell(&license_count.license), url),
+            None => escape_markdown_cell(&license_count.license),
+        };
+        writeln!(
+            out,
+            "| {} | {} | {:.1}% | {} |",
+            license,
+            license_count.count,
+            license_count.percentage,
+            if license_count.allowed { "yes" } else { "no" }
+        )?;
+    }
+
+    Ok(())
+}
+
+// ----------------------------------------------------------------------------
+// SARIF and JUnit
+// ----------------------------------------------------------------------------
+
+fn write_sarif(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let results: Vec<Value> = report
+        .sorted_packages()
+        .into_iter()
+        .filter_map(|p| {
+            let (rule_id, level, message) = if !report.license_checker.is_package_allowed(p) {
+                (
+                    "license-not-allowed",
+                    "error",
+                    format!("{}@{} uses license {} which is not allowed", p.name, p.version, p.license),
+                )
+            } else if p.is_unknown() {
+                (
+                    "license-unknown",
+                    "warning",
+                    format!("{}@{} has an unknown license", p.name, p.version),
+                )
+            } else {
+                return None;
+            };
+
+            let locations: Vec<Value> = p.source_lockfiles
+                .iter()
+                .map(|lockfile| {
+                    json!({ "physicalLocation": { "artifactLocation": { "uri": lockfile } } })
+                })
+                .collect();
+
+            Some(
+                json!({
+                    "ruleId": rule_id,
+                    "level": level,
+                    "message": { "text": message },
+                    "locations": locations,
+                })
+            )
+        })
+        .collect();
+
+    let sarif = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": TOOL_NAME,
+                    "version": TOOL_VERSION,
+                    "rules": [
+                        {
+                            "id": "license-not-allowed",
+                            "shortDescription": { "text": "Dependency license is not in the allowed list" }
+                        },
+                        {
+                            "id": "license-unknown",
+                            "shortDescription": { "text": "Dependency license could not be determined" }
+                        }
+                    ]
+                }
+            },
+            "results": results,
+        }]
+    });
+
+    write_pretty_json(&sarif, out)
+}
+
+fn write_junit(report: &Report, out: &mut dyn Write) -> io::Result<()> {
+    let packages = report.sorted_packages();
+    let failures = report.violations_count();
+
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        out,
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">",
+        TOOL_NAME,
+        packages.len(),
+        failures
+    )?;
+    writeln!(
+        out,
+        "  <testsuite name=\"license-check\" tests=\"{}\" failures=\"{}\">",
+        packages.len(),
+        failures
+    )?;
+
+    for package in packages {
+        let classname = if package.registry.is_empty() { "unknown" } else { &package.registry };
+        let name = escape_xml(&format!("{}@{}", package.name, package.version));
+
+        if report.license_checker.is_package_allowed(package) {
+            writeln!(out, "    <testcase classname=\"{}\" name=\"{}\"/>", escape_xml(classname), name)?;
+        } else {
+            writeln!(out, "    <testcase classname=\"{}\" name=\"{}\">", escape_xml(classname), name)?;
+            writeln!(
+                out,
+                "      <failure message=\"License {} is not allowed\"/>",
+                escape_xml(&package.license)
+            )?;
+            writeln!(out, "    </testcase>")?;
+        }
+    }
+
+    writeln!(out, "  </testsuite>")?;
+    writeln!(out, "</testsuites>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::ResolutionStatus;
+
+    fn sample_package(name: &str, license: &str) -> Package {
+        let mut package = Package::new(name.to_string(), "1.0.0".to_string(), String::new(), None);
+        package.registry = "npm".to_string();
+        package.license = license.to_string();
+        package.url = format!("https://www.npmjs.com/package/{}", name);
+        package.source_lockfiles = vec!["yarn.lock".to_string()];
+        package
+    }
+
+    fn render(format: OutputFormat, packages: &[Package], allowed: &[String]) -> String {
+        let dependency_tree = HashMap::from([
+            ("@scope/app@1.0.0".to_string(), vec!["left-pad@1.0.0".to_string()]),
+        ]);
+        let license_checker = LicenseChecker::new(allowed.to_vec());
+        let report = Report {
+            dependency_tree: &dependency_tree,
+            allowed,
+            ..Report::new(packages, &license_checker)
+        };
+
+        let mut out = Vec::new();
+        write_report(&OutputRequest::new(format), &report, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_footprint_counts_each_subtree() {
+        // app pulls in a shared helper twice and a cycle; cli only one package
+        let dependency_tree = HashMap::from([
+            ("app@1.0.0".to_string(), vec!["router@1.0.0".to_string(), "view@1.0.0".to_string()]),
+            ("router@1.0.0".to_string(), vec!["helper@1.0.0".to_string()]),
+            ("view@1.0.0".to_string(), vec!["helper@1.0.0".to_string(), "loop-a@1.0.0".to_string()]),
+            ("loop-a@1.0.0".to_string(), vec!["loop-b@1.0.0".to_string()]),
+            ("loop-b@1.0.0".to_string(), vec!["loop-a@1.0.0".to_string()]),
+            ("cli@1.0.0".to_string(), vec!["args@1.0.0".to_string()]),
+        ]);
+        let packages = vec![
+            sample_package("app", "MIT"),
+            sample_package("router", "MIT"),
+            sample_package("view", "ISC"),
+            sample_package("helper", "MIT"),
+            sample_package("loop-a", "BSD-3-Clause"),
+            sample_package("loop-b", "MIT"),
+            sample_package("cli", "Apache-2.0"),
+            sample_package("args", "MIT"),
+            sample_package("standalone", "MIT")
+        ];
+
+        let footprints: Vec<(&str, usize, usize)> = dependency_footprints(&dependency_tree, &packages)
+            .iter()
+            .map(|footprint| (footprint.package.name.as_str(), footprint.transitive, footprint.licenses))
+            .collect();
+        assert_eq!(footprints, vec![("app", 5, 3), ("cli", 1, 1)]);
+
+        let license_checker = LicenseChecker::new(Vec::new());
+        let report = Report {
+            dependency_tree: &dependency_tree,
+            text_options: TextOptions { footprint: true, ..TextOptions::default() },
+            ..Report::new(&packages, &license_checker)
+        };
+        let mut out = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        let section = text.split("=== DEPENDENCY FOOTPRINT ===").nth(1).unwrap();
+        assert!(section.contains("app@1.0.0 (MIT): 5 transitive package(s) under 3 license(s)"));
+        assert!(section.contains("2 top-level package(s) with dependencies"));
+        assert!(section.find("app@1.0.0").unwrap() < section.find("cli@1.0.0").unwrap());
+    }
+
+    #[test]
+    fn test_expand_licenses_lists_packages_under_each_license() {
+        let packages = vec![
+            sample_package("react", "MIT"),
+            sample_package("left-pad", "WTFPL"),
+            sample_package("lodash", "MIT"),
+            sample_package("mystery", "UNKNOWN"),
+            sample_package("unresolved", "")
+        ];
+        let license_checker = LicenseChecker::new(vec!["MIT".to_string()]);
+        let mut report = Report::new(&packages, &license_checker);
+        let stats = |report: &Report| {
+            let mut out = Vec::new();
+            write_report(&OutputRequest::new(OutputFormat::Text), report, &mut out).unwrap();
+            let text = String::from_utf8(out).unwrap();
+            text.split("=== LICENSE USAGE STATISTICS ===").nth(1).unwrap().to_string()
+        };
+
+        assert!(!stats(&report).contains("lodash@1.0.0"));
+
+        report.text_options.expand_licenses = true;
+        let section = stats(&report);
+        let mit = section.find("MIT (").unwrap();
+        let unknown = section.find("UNKNOWN: 2 packages").unwrap();
+        let wtfpl = section.find("WTFPL (").unwrap();
+        assert!(mit < unknown && unknown < wtfpl);
+        assert_eq!(&section[mit..unknown].lines().skip(1).collect::<Vec<_>>(), &["  lodash@1.0.0", "  react@1.0.0"]);
+        let unknown_rows: Vec<&str> = section[unknown..wtfpl].lines().skip(1).collect();
+        assert_eq!(unknown_rows, ["  mystery@1.0.0", "  unresolved@1.0.0"]);
+        assert!(section[wtfpl..].lines().nth(1).is_some_and(|line| line == "  left-pad@1.0.0"));
+    }
+
+    #[test]
+    fn test_csv_bom_and_delimiter() {
+        let packages = vec![sample_package("café-utils", "MIT"), sample_package("left-pad", "WTFPL; or MIT")];
+
+        let plain = build_csv(&packages, &CsvOptions::default());
+        assert!(plain.as_bytes().starts_with(b"name,url,license\n"));
+        assert!(plain.contains("\"café-utils\",\"https://www.npmjs.com/package/café-utils\",\"MIT\""));
+
+        let excel = build_csv(&packages, &CsvOptions { bom: true, delimiter: ';' });
+        assert!(excel.as_bytes().starts_with(&[0xef, 0xbb, 0xbf]));
+        assert!(excel[3..].starts_with("name;url;license\n"));
+        // The delimiter never appears inside a field
+        assert!(excel.contains("\"left-pad\";\"https://www.npmjs.com/package/left-pad\";\"WTFPL  or MIT\""));
+        assert_eq!(excel.matches('\u{feff}').count(), 1);
+    }
+
+    #[test]
+    fn test_every_format_produces_output() {
+        let packages = vec![
+            sample_package("@scope/app", "MIT"),
+            sample_package("left-pad", "WTFPL"),
+            sample_package("mystery", "UNKNOWN")
+        ];
+        let allowed = vec!["MIT".to_string()];
+
+        for format in OutputFormat::value_variants() {
+            let output = render(*format, &packages, &allowed);
+            assert!(output.contains("left-pad"), "{} output is missing packages", format.name());
+
+            // The machine-readable JSON formats must parse
+            if format.extension().ends_with("json") || *format == OutputFormat::Sarif {
+                serde_json::from_str::<Value>(&output).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_outputs_to_file_and_stdout() {
+        let output_dir = tempfile::tempdir().unwrap();
+        let json_path = output_dir.path().join("report.json");
+        let requests = vec![
+            OutputRequest::new(OutputFormat::Text),
+            OutputRequest { path: Some(json_path.clone()), ..OutputRequest::new(OutputFormat::Json) }
+        ];
+
+        let packages = vec![sample_package("left-pad", "MIT")];
+        let license_checker = LicenseChecker::new(Vec::new());
+        let report = Report::new(&packages, &license_checker);
+
+        let mut stdout = Vec::new();
+        write_outputs(&requests, &report, &mut stdout);
+
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.contains("=== DEPENDENCY LICENSE SUMMARY ==="));
+        let json: Value = serde_json::from_str(&fs::read_to_string(json_path).unwrap()).unwrap();
+        assert_eq!(json["packages"][0]["name"], "left-pad");
+    }
+
+    #[test]
+    fn test_pair_output_requests() {
+        // --format text --format json --output report.json
+        let requests = pair_output_requests(
+            &[
+                (1, OutputFormat::Text),
+                (3, OutputFormat::Json),
+            ],
+            &[(5, "report.json".to_string())]
+        ).unwrap();
+        assert_eq!(requests[0].path, None);
+        assert_eq!(requests[1].path, Some(PathBuf::from("report.json")));
+
+        // -o bom.json --format sbom-cyclonedx
+        let requests = pair_output_requests(
+            &[(3, OutputFormat::SbomCyclonedx)],
+            &[(1, "bom.json".to_string())]
+        ).unwrap();
+        assert_eq!(requests[0].path, Some(PathBuf::from("bom.json")));
+
+        // Two formats on stdout
+        assert!(pair_output_requests(&[(1, OutputFormat::Text), (3, OutputFormat::Json)], &[]).is_err());
+    }
+
+    #[test]
+    fn test_failed_lookups_are_reported_separately() {
+        let mut failed = Package::with_error(
+            "unreachable".to_string(),
+            "2.0.0".to_string(),
+            "npm",
+            "https://www.npmjs.com/package/unreachable".to_string(),
+            ResolutionStatus::NetworkError,
+            "Network error when contacting npm registry: connection refused"
+        );
+        failed.source_lockfiles = vec!["app/yarn.lock".to_string()];
+        let packages = vec![failed, sample_package("no-license", "UNKNOWN"), sample_package("left-pad", "MIT")];
+
+        let json: Value = serde_json::from_str(&render(OutputFormat::Json, &packages, &[])).unwrap();
+        let errors = json["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0]["name"], "unreachable");
+        assert_eq!(errors[0]["error"], "Network error when contacting npm registry: connection refused");
+        assert_eq!(errors[0]["source_lockfiles"][0], "app/yarn.lock");
+        assert_eq!(json["summary"]["failed"], 1);
+        assert_eq!(json["summary"]["unknown"], 2);
+
+        let text = render(OutputFormat::Text, &packages, &[]);
+        assert!(text.contains("=== FAILED LOOKUPS ==="));
+        assert!(text.contains("1 package(s) could not be looked up"));
+    }
+
+    #[test]
+    fn test_truncated_scans_are_flagged_in_the_summary() {
+        let packages = vec![sample_package("left-pad", "MIT"), sample_package("lodash", "MIT")];
+        let license_checker = LicenseChecker::new(vec!["MIT".to_string()]);
+        let mut report = Report {
+            truncated_at: Some(2),
+            ..Report::new(&packages, &license_checker)
+        };
+
+        let render = |report: &Report, format| {
+            let mut out = Vec::new();
+            write_report(&OutputRequest::new(format), report, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let text = render(&report, OutputFormat::Text);
+        assert!(text.contains("Scan truncated after 2 packages (--max-packages)"), "{}", text);
+        let json: Value = serde_json::from_str(&render(&report, OutputFormat::Json)).unwrap();
+        assert_eq!(json["summary"]["truncated"], true);
+
+        report.truncated_at = None;
+        assert!(!render(&report, OutputFormat::Text).contains("Scan truncated"));
+        let json: Value = serde_json::from_str(&render(&report, OutputFormat::Json)).unwrap();
+        assert_eq!(json["summary"]["truncated"], false);
+    }
+
+    #[test]
+    fn test_json_diagnostics_count_lookup_outcomes_per_registry() {
+        let failed = |name: &str, registry: &str, status: ResolutionStatus, error: &str| {
+            Package::with_error(name.to_string(), "1.0.0".to_string(), registry, String::new(), status, error)
+        };
+        let mut widget = sample_package("widget", "MIT");
+        widget.registry = "github".to_string();
+        let packages = vec![
+            sample_package("left-pad", "MIT"),
+            sample_package("no-license", "UNKNOWN"),
+            failed("missing", "npm", ResolutionStatus::NotFound, "npm registry returned status code 404: Not Found"),
+            failed("offline", "npm", ResolutionStatus::NetworkError, "Network error: connection refused"),
+            widget,
+            failed("zeta", "github", ResolutionStatus::RateLimited, "GitHub API returned status code 429"),
+            failed("private", "github", ResolutionStatus::AuthRequired, "Authentication required: an HTML page"),
+            failed("broken", "github", ResolutionStatus::Error, "Failed to parse GitHub API response: EOF"),
+        ];
+
+        let json: Value = serde_json::from_str(&render(OutputFormat::Json, &packages, &[])).unwrap();
+        assert_eq!(
+            json["diagnostics"],
+            json!({
+                "github": {
+                    "ok": 1, "not_found": 0, "rate_limited": 1, "auth_required": 1, "network_error": 0, "error": 1
+                },
+                "npm": {
+                    "ok": 2, "not_found": 1, "rate_limited": 0, "auth_required": 0, "network_error": 1, "error": 0
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_compliance_decision_in_json_and_verbose_text() {
+        let packages = vec![sample_package("left-pad", "MIT"), sample_package("copyleft", "GPL-3.0")];
+
+        let json: Value = serde_json::from_str(&render(OutputFormat::Json, &packages, &["MIT".to_string()])).unwrap();
+        let compliance: Vec<&Value> = json["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| &p["compliance"])
+            .collect();
+        assert!(compliance.contains(&&json!({ "allowed": true, "reason": "matched pattern MIT" })));
+        assert!(
+            compliance.contains(
+                &&json!({ "allowed": false, "reason": "no matching allow pattern", "severity": "error" })
+            )
+        );
+        let license_checker = LicenseChecker::new(vec!["MIT".to_string()]);
+        let report = Report {
+            text_options: TextOptions { verbose: true, ..TextOptions::default() },
+            ..Report::new(&packages, &license_checker)
+        };
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("    Compliance: matched pattern MIT"), "{}", text);
+        assert!(text.contains("    Compliance: no matching allow pattern"), "{}", text);
+    }
+
+    #[test]
+    fn test_compatibility_in_text_and_json() {
+        let packages = vec![sample_package("copyleft", "GPL-3.0"), sample_package("permissive", "MIT")];
+        let license_checker = LicenseChecker::new(Vec::new());
+        let report = Report {
+            project_license: Some("MIT"),
+            ..Report::new(&packages, &license_checker)
+        };
+
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("copyleft@1.0.0: GPL-3.0"));
+        assert!(!text.contains("permissive@1.0.0: MIT"));
+
+        let mut json = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Json), &report, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["packages"][0]["compatibility"], "incompatible");
+        assert_eq!(json["packages"][1]["compatibility"], "compatible");
+    }
+
+    #[test]
+    fn test_approved_exceptions_reported_separately() {
+        let packages = vec![
+            sample_package("reviewed-gpl", "GPL-3.0"),
+            sample_package("other-gpl", "GPL-3.0"),
+            sample_package("permissive", "MIT"),
+        ];
+        let allowed = vec!["MIT".to_string()];
+        let exception = crate::license_checker::LicenseException::parse("reviewed-gpl@1.0.0:GPL-3.0").unwrap();
+        let license_checker = LicenseChecker::new(allowed.clone()).with_exceptions(vec![exception]);
+        let report = Report {
+            allowed: &allowed,
+            ..Report::new(&packages, &license_checker)
+        };
+
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("APPROVED EXCEPTIONS"));
+        assert!(text.contains("reviewed-gpl@1.0.0: GPL-3.0"));
+
+        let mut json = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Json), &report, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["summary"]["violations"], 1);
+        assert_eq!(json["summary"]["approved_exceptions"], 1);
+        assert_eq!(json["packages"][2]["name"], "reviewed-gpl");
+        assert_eq!(json["packages"][2]["approved_exception"], true);
+    }
+
+    #[test]
+    fn test_spdx_deprecation_warnings() {
+        let packages = vec![sample_package("old-gpl", "GPL-2.0+"), sample_package("current", "GPL-3.0-only")];
+        let license_checker = LicenseChecker::new(Vec::new());
+        let report = Report {
+            text_options: TextOptions { check_spdx_deprecations: true, ..TextOptions::default() },
+            ..Report::new(&packages, &license_checker)
+        };
+
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("old-gpl@1.0.0: GPL-2.0+ -> GPL-2.0-or-later"));
+        assert!(!text.contains("current@1.0.0"));
+        assert!(text.contains("1 package(s) use deprecated SPDX identifiers"));
+    }
+
+    #[test]
+    fn test_obligations_in_text_and_json() {
+        let packages = vec![
+            sample_package("server-lib", "AGPL-3.0-only"),
+            sample_package("left-pad", "MIT"),
+            sample_package("public", "CC0-1.0"),
+            sample_package("mystery", "UNKNOWN")
+        ];
+        let license_checker = LicenseChecker::new(Vec::new());
+        let report = Report {
+            text_options: TextOptions { obligations: true, ..TextOptions::default() },
+            ..Report::new(&packages, &license_checker)
+        };
+
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        let section = text.split("=== LICENSE OBLIGATIONS ===").nth(1).unwrap();
+        assert!(section.contains("left-pad@1.0.0 (MIT): attribution\n"), "{}", section);
+        assert!(
+            section.contains(
+                "server-lib@1.0.0 (AGPL-3.0-only): attribution, source disclosure, network use triggers disclosure"
+            )
+        );
+        assert!(!section.contains("public@"));
+        assert!(section.contains("1 package(s) have licenses with unknown obligations"));
+
+        let mut json = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Json), &report, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        let package = |name: &str| {
+            json["packages"].as_array().unwrap().iter().find(|p| p["name"] == name).unwrap().clone()
+        };
+        assert_eq!(package("server-lib")["obligations"]["network_use_triggers"], true);
+        assert_eq!(package("left-pad")["obligations"]["attribution_required"], true);
+        assert!(package("mystery").get("obligations").is_none());
+    }
+
+    #[test]
+    fn test_warn_licenses_are_highlighted_but_not_violations() {
+        let packages = vec![sample_package("left-pad", "MIT"), sample_package("glib-binding", "LGPL-2.1")];
+        let allowed = vec!["MIT".to_string()];
+        let license_checker = LicenseChecker::new(allowed.clone()).with_severity_rules(
+            vec![crate::license_checker::SeverityRule::parse("LGPL-*=warn").unwrap()]
+        );
+        let report = Report {
+            allowed: &allowed,
+            ..Report::new(&packages, &license_checker)
+        };
+        assert_eq!(report.violations_count(), 0);
+        assert_eq!(report.warnings_count(), 1);
+
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("glib-binding@1.0.0: LGPL-2.1 [WARN]"), "{}", text);
+        assert!(!text.contains("left-pad@1.0.0: MIT"));
+        assert!(text.contains("1 with licenses flagged as warn"));
+        assert!(text.contains("All licenses are compliant!"));
+
+        let mut json = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Json), &report, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["summary"]["violations"], 0);
+        assert_eq!(json["summary"]["warnings"], 1);
+        let binding = json["packages"].as_array().unwrap().iter().find(|p| p["name"] == "glib-binding").unwrap();
+        assert_eq!(binding["allowed"], true);
+        assert_eq!(binding["compliance"]["severity"], "warn");
+    }
+
+    #[test]
+    fn test_review_list_in_text_and_json() {
+        let mut failed = sample_package("offline-pkg", "UNKNOWN");
+        failed.lookup_failed = true;
+        failed.debug_info = Some("connection refused".to_string());
+        let packages = vec![
+            sample_package("left-pad", "MIT"),
+            sample_package("custom", "SEE LICENSE IN LICENSE.md"),
+            failed
+        ];
+        let license_checker = LicenseChecker::new(Vec::new());
+        let report = Report {
+            text_options: TextOptions { needs_review: true, ..TextOptions::default() },
+            ..Report::new(&packages, &license_checker)
+        };
+
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        let section = text.split("=== REVIEW LIST ===").nth(1).unwrap();
+        let high = section.find("offline-pkg@1.0.0: UNKNOWN (connection refused)").unwrap();
+        let medium = section.find("custom@1.0.0: SEE LICENSE IN LICENSE.md").unwrap();
+        assert!(high < medium, "{}", section);
+        assert!(!section.contains("left-pad"));
+        assert!(section.contains("2 package(s) need review"));
+
+        let mut json = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Json), &report, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["review"][0]["name"], "offline-pkg");
+        assert_eq!(json["review"][0]["severity"], "high");
+        assert_eq!(json["review"][0]["reasons"][0]["reason"], "lookup-failed");
+        assert_eq!(json["review"][1]["reasons"][0]["reason"], "non-spdx");
+    }
+
+    #[test]
+    fn test_github_unknowns_section() {
+        let github_package = |name: &str, license: &str, resolution: &str| {
+            let mut package = sample_package(name, license);
+            package.registry = "github".to_string();
+            package.resolution = resolution.to_string();
+            package
+        };
+        let packages = vec![
+            github_package("zeta-tool", "UNKNOWN", "zeta-tool@github:acme/zeta#v2.0.0"),
+            github_package("widget", "UNKNOWN", "https://github.com/acme/widget.git#3f2a9c1"),
+            github_package("licensed", "MIT", "github:acme/licensed#main"),
+            sample_package("npm-unknown", "UNKNOWN")
+        ];
+        let license_checker = LicenseChecker::new(Vec::new());
+        let report = Report {
+            text_options: TextOptions { github_unknowns: true, ..TextOptions::default() },
+            ..Report::new(&packages, &license_checker)
+        };
+
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        let section = text.split("=== UNRESOLVED GITHUB PACKAGES ===").nth(1).unwrap();
+        let lines: Vec<&str> = section.trim().lines().collect();
+
+        // Sorted by repository, not by package name
+        assert_eq!(
+            lines,
+            vec![
+                "widget@1.0.0: https://github.com/acme/widget (ref 3f2a9c1)",
+                "zeta-tool@1.0.0: https://github.com/acme/zeta (ref v2.0.0)",
+                "2 GitHub package(s) need a manual license check",
+                "",
+                "Scan complete."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dot_graph_edges() {
+        let packages = vec![
+            sample_package("app", "MIT"),
+            sample_package("left-pad", "GPL-3.0"),
+            sample_package("mystery", "UNKNOWN"),
+        ];
+        let dependency_tree = HashMap::from([
+            ("app@1.0.0".to_string(), vec!["left-pad@1.0.0".to_string(), "mystery@1.0.0".to_string()]),
+            ("left-pad@1.0.0".to_string(), vec!["app@1.0.0".to_string()]),
+        ]);
+        let allowed = vec!["MIT".to_string()];
+        let license_checker = LicenseChecker::new(allowed.clone());
+        let report = Report {
+            dependency_tree: &dependency_tree,
+            allowed: &allowed,
+            ..Report::new(&packages, &license_checker)
+        };
+
+        let mut dot = Vec::new();
+        write_dot_graph(&report, &mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+
+        assert!(dot.starts_with("digraph dependencies {"));
+        assert!(dot.contains("\"app@1.0.0\" -> \"left-pad@1.0.0\";"));
+        assert!(dot.contains("\"app@1.0.0\" -> \"mystery@1.0.0\";"));
+        assert!(dot.contains("\"left-pad@1.0.0\" -> \"app@1.0.0\";"));
+        assert!(dot.contains("\"app@1.0.0\" [fillcolor=\"#b7e4b0\"];"));
+        assert!(dot.contains("\"left-pad@1.0.0\" [fillcolor=\"#f4a6a6\"];"));
+        assert!(dot.contains("\"mystery@1.0.0\" [fillcolor=\"#f7e08c\"];"));
+    }
+
+    #[test]
+    fn test_tree_cycle_detection_on_wide_tree() {
+        // Every child of the root depends on the same shared package, which points back at the root
+        let mut packages = vec![sample_package("root", "MIT"), sample_package("shared", "MIT")];
+        let mut children = Vec::new();
+        let mut dependency_tree = HashMap::new();
+        for i in 0..50 {
+            let name = format!("child-{:02}", i);
+            packages.push(sample_package(&name, "MIT"));
+            children.push(format!("{}@1.0.0", name));
+            dependency_tree.insert(format!("{}@1.0.0", name), vec!["shared@1.0.0".to_string()]);
+        }
+        dependency_tree.insert("root@1.0.0".to_string(), children);
+        dependency_tree.insert("shared@1.0.0".to_string(), vec!["root@1.0.0".to_string()]);
+
+        // The root is part of the cycle, so render from a package outside it
+        packages.push(sample_package("app", "MIT"));
+        dependency_tree.insert("app@1.0.0".to_string(), vec!["root@1.0.0".to_string()]);
+
+        let mut out = Vec::new();
+        write_dependency_tree(&dependency_tree, &packages, false, &mut out).unwrap();
+        let tree = String::from_utf8(out).unwrap();
+
+        // Siblings each reach the shared package without it being reported as circular,
+        // and each reports the real cycle back to the root exactly once
+        assert_eq!(tree.matches("shared (MIT)").count(), 50);
+        assert_eq!(tree.matches("root@1.0.0 [circular reference]").count(), 50);
+        assert!(!tree.contains("shared@1.0.0 [circular reference]"));
+    }
+
+    #[test]
+    fn test_template_substitutes_report_values() {
+        let packages = vec![sample_package("left-pad", "MIT"), sample_package("gpl-lib", "GPL-3.0")];
+        let license_checker = LicenseChecker::new(vec!["MIT".to_string()]);
+        let report = Report::new(&packages, &license_checker);
+
+        let template = "{{ for p in packages }}{p.name}@{p.version}: {p.license}\n{{ endfor }}\
+            {summary.total} packages, {summary.violations} violation(s)\n\
+            {{ for v in violations }}VIOLATION {v.name} ({v.compliance.reason}) <{v.url}>\n{{ endfor }}\
+            {tool.name}\n";
+        let mut out = Vec::new();
+        write_template(template, &report, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "gpl-lib@1.0.0: GPL-3.0\nleft-pad@1.0.0: MIT\n\
+             2 packages, 1 violation(s)\n\
+             VIOLATION gpl-lib (no matching allow pattern) <https://www.npmjs.com/package/gpl-lib>\n\
+             super-license-scanner\n"
+        );
+
+        let mut out = Vec::new();
+        assert!(write_template("{unclosed", &report, &mut out).is_err());
+    }
+
+    #[test]
+    fn test_licensee_json_shape() {
+        let packages = vec![
+            sample_package("left-pad", "MIT"),
+            sample_package("dual", "(MIT OR Apache-2.0)"),
+            sample_package("combined", "(MIT OR GPL-2.0+) AND BSD-3-Clause"),
+            sample_package("grouped", "(MIT AND ISC) OR Apache-2.0"),
+            sample_package("mystery", "UNKNOWN")
+        ];
+        let output: Value = serde_json::from_str(&render(OutputFormat::LicenseeJson, &packages, &[])).unwrap();
+
+        // { "dependencies": [ { "name", "version", "licenses": [string] } ] } and nothing else
+        let report = output.as_object().unwrap();
+        assert_eq!(report.keys().collect::<Vec<_>>(), vec!["dependencies"]);
+        let dependencies = report["dependencies"].as_array().unwrap();
+        assert_eq!(dependencies.len(), packages.len());
+        for dependency in dependencies {
+            let fields = dependency.as_object().unwrap();
+            assert_eq!(fields.len(), 3);
+            assert!(fields["name"].is_string());
+            assert!(fields["version"].is_string());
+            assert!(fields["licenses"].as_array().unwrap().iter().all(Value::is_string));
+        }
+
+        let licenses = |name: &str| {
+            dependencies
+                .iter()
+                .find(|d| d["name"] == name)
+                .map(|d| d["licenses"].clone())
+                .unwrap()
+        };
+        assert_eq!(licenses("left-pad"), json!(["MIT"]));
+        assert_eq!(licenses("dual"), json!(["MIT", "Apache-2.0"]));
+        assert_eq!(licenses("combined"), json!(["(MIT OR GPL-2.0+) AND BSD-3-Clause"]));
+        assert_eq!(licenses("grouped"), json!(["(MIT AND ISC)", "Apache-2.0"]));
+        assert_eq!(licenses("mystery"), json!([]));
+    }
+
+    #[test]
+    fn test_tree_json_marks_cycles() {
+        let packages = vec![
+            sample_package("app", "MIT"),
+            sample_package("a", "ISC"),
+            sample_package("b", "MIT"),
+        ];
+        let dependency_tree = HashMap::from([
+            ("app@1.0.0".to_string(), vec!["a@1.0.0".to_string()]),
+            ("a@1.0.0".to_string(), vec!["b@1.0.0".to_string(), "@scope/missing@2.0.0".to_string()]),
+            ("b@1.0.0".to_string(), vec!["a@1.0.0".to_string()]),
+        ]);
+
+        let mut out = Vec::new();
+        write_dependency_tree_json(&dependency_tree, &packages, &mut out).unwrap();
+        let tree: Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(tree.as_array().unwrap().len(), 1);
+        let app = &tree[0];
+        assert_eq!(app["name"], "app");
+        assert_eq!(app["license"], "MIT");
+
+        let a = &app["children"][0];
+        assert_eq!(a["name"], "a");
+        assert_eq!(a["license"], "ISC");
+        assert_eq!(a["children"][0]["name"], "@scope/missing");
+        assert_eq!(a["children"][0]["version"], "2.0.0");
+        assert_eq!(a["children"][0]["license"], "UNKNOWN");
+
+        let b = &a["children"][1];
+        assert_eq!(b["name"], "b");
+        assert!(b.get("circular").is_none());
+        assert_eq!(b["children"][0]["name"], "a");
+        assert_eq!(b["children"][0]["circular"], true);
+        assert_eq!(b["children"][0]["children"], json!([]));
+    }
+
+    #[test]
+    fn test_license_source_in_verbose_text_and_json() {
+        let mut declared = sample_package("declared", "MIT");
+        declared.license_source = Some(LicenseSource::Registry);
+        let mut detected = sample_package("detected", "ISC");
+        detected.license_source = Some(LicenseSource::LicenseFile);
+        let packages = vec![declared, detected, sample_package("mystery", "UNKNOWN")];
+        let license_checker = LicenseChecker::new(Vec::new());
+        let report = Report {
+            text_options: TextOptions { verbose: true, ..TextOptions::default() },
+            ..Report::new(&packages, &license_checker)
+        };
+
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("    Source: registry (confidence 0.00)"));
+        assert!(text.contains("    Source: license-file (confidence 0.00)"));
+
+        let mut json = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Json), &report, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["packages"][0]["license_source"], "registry");
+        assert_eq!(json["packages"][1]["license_source"], "license-file");
+        assert_eq!(json["packages"][2]["license_source"], Value::Null);
+    }
+
+    #[test]
+    fn test_expired_licenses_are_flagged() {
+        let mut expired = sample_package("vendor-grid", "LicenseRef-Vendor");
+        expired.license_expiration = Some("2020-01-31".to_string());
+        let mut current = sample_package("vendor-charts", "LicenseRef-Vendor");
+        current.license_expiration = Some("2999-12-31".to_string());
+        let packages = vec![expired, current, sample_package("left-pad", "WTFPL")];
+        let license_checker = LicenseChecker::new(Vec::new());
+        let report = Report {
+            text_options: TextOptions { verbose: true, ..TextOptions::default() },
+            ..Report::new(&packages, &license_checker)
+        };
+
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("    License expired: 2020-01-31"));
+        assert!(text.contains("    License expires: 2999-12-31"));
+        assert!(text.contains("=== EXPIRED LICENSES ==="));
+        assert!(text.contains("vendor-grid@1.0.0: LicenseRef-Vendor expired on 2020-01-31"));
+        assert!(!text.contains("vendor-charts@1.0.0: LicenseRef-Vendor expired"));
+        assert!(text.contains("1 package(s) with an expired license"));
+
+        let mut json = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Json), &report, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        let by_name = |name: &str| json["packages"].as_array().unwrap().iter().find(|p| p["name"] == name).unwrap();
+        assert_eq!(by_name("vendor-grid")["license_expiration"], "2020-01-31");
+        assert_eq!(by_name("left-pad").get("license_expiration"), None);
+    }
+
+    #[test]
+    fn test_summary_reports_compliance_score() {
+        let packages = vec![
+            sample_package("a", "MIT"),
+            sample_package("b", "MIT"),
+            sample_package("c", "GPL-3.0"),
+            sample_package("d", "UNKNOWN")
+        ];
+        let allowed = vec!["MIT".to_string()];
+        let license_checker = LicenseChecker::new(allowed.clone());
+        let report = Report {
+            allowed: &allowed,
+            ..Report::new(&packages, &license_checker)
+        };
+
+        let mut json = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Json), &report, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["summary"]["compliance_score"], 50.0);
+        assert_eq!(json["summary"]["distinct_licenses"], 2);
+
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("Compliance score: 50.0% (2 distinct licenses)"), "{}", text);
+    }
+
+    #[test]
+    fn test_min_confidence_marks_needs_review() {
+        let mut declared = sample_package("declared", "MIT");
+        declared.license_source = Some(LicenseSource::Registry);
+        declared.confidence = crate::license_detection::declared_confidence("MIT");
+
+        let (license, confidence) = crate::license_detection
+            ::detect_license_from_text("The MIT License (MIT)")
+            .unwrap();
+        let mut detected = sample_package("detected", &license);
+        detected.license_source = Some(LicenseSource::LicenseFile);
+        detected.confidence = confidence;
+
+        let packages = vec![declared, detected, sample_package("mystery", "UNKNOWN")];
+        let license_checker = LicenseChecker::new(Vec::new()).with_min_confidence(Some(0.8));
+        let report = Report::new(&packages, &license_checker);
+
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("Average license confidence: 0.75"));
+        assert!(text.contains("detected@1.0.0: MIT"));
+        assert!(text.contains("1 package(s) need review"));
+
+        let mut json = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Json), &report, &mut json).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert!(json["packages"][0].get("needs_review").is_none());
+        assert_eq!(json["packages"][0]["confidence"], 1.0);
+        assert_eq!(json["packages"][1]["needs_review"], true);
+        assert_eq!(json["packages"][1]["confidence"], 0.5);
+        assert_eq!(json["summary"]["needs_review"], 1);
+        assert_eq!(json["summary"]["average_confidence"], 0.75);
+    }
+
+    #[test]
+    fn test_no_escape_sequences_without_color() {
+        assert!(!color_enabled(true, None, true));
+        assert!(!color_enabled(false, Some("1"), true));
+        assert!(!color_enabled(false, None, false));
+        assert!(color_enabled(false, Some(""), true));
+
+        // TextOptions::default() leaves color off, whatever the terminal running the tests
+        let packages = vec![sample_package("@scope/app", "MIT"), sample_package("left-pad", "WTFPL")];
+        let allowed = vec!["MIT".to_string()];
+        for format in [OutputFormat::Text, OutputFormat::Tree] {
+            let output = render(format, &packages, &allowed);
+            assert!(!output.contains('\x1b'), "{} output contains escape sequences", format.name());
+        }
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let mut gpl = sample_package("b-gpl", "GPL-3.0");
+        gpl.registry = "pypi".to_string();
+        let fixture = vec![
+            sample_package("d-mit", "MIT"),
+            gpl,
+            sample_package("a-unknown", "UNKNOWN"),
+            sample_package("c-apache", "Apache-2.0")
+        ];
+        let license_checker = LicenseChecker::new(vec!["MIT".to_string(), "Apache-2.0".to_string(), "UNKNOWN".to_string()]);
+
+        let order = |key: SortKey| -> Vec<String> {
+            let mut packages = fixture.clone();
+            sort_packages(&mut packages, key, &license_checker);
+            packages.into_iter().map(|p| p.name).collect()
+        };
+
+        assert_eq!(order(SortKey::Name), ["a-unknown", "b-gpl", "c-apache", "d-mit"]);
+        assert_eq!(order(SortKey::License), ["c-apache", "b-gpl", "d-mit", "a-unknown"]);
+        assert_eq!(order(SortKey::Registry), ["a-unknown", "c-apache", "d-mit", "b-gpl"]);
+        assert_eq!(order(SortKey::ViolationsFirst), ["b-gpl", "a-unknown", "c-apache", "d-mit"]);
+    }
+
+    /// Write target that can be inspected after being handed to a StreamWriter
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stream_writes_one_json_object_per_package() {
+        let buffer = SharedBuffer::default();
+        let license_checker = Arc::new(LicenseChecker::new(vec!["MIT".to_string()]));
+        let request = OutputRequest::new(OutputFormat::Json);
+        let writer = StreamWriter::new(&request, license_checker, Box::new(buffer.clone())).unwrap();
+
+        for (name, license) in [("a", "MIT"), ("b", "GPL-3.0"), ("c", "MIT")] {
+            writer.write_package(&sample_package(name, license)).unwrap();
+        }
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1]["name"], "b");
+        assert_eq!(lines[1]["allowed"], false);
+        assert_eq!(writer.written(), 3);
+        assert_eq!(writer.violations(), 1);
+    }
+
+    #[test]
+    fn test_reconcile_known_and_unknown_resolutions() {
+        // Cached as MIT for one project, looked up offline for the other
+        let mut known = sample_package("left-pad", "MIT");
+        known.confidence = 1.0;
+        known.source_lockfiles = vec!["web/yarn.lock".to_string()];
+        let mut unknown = sample_package("left-pad", "UNKNOWN");
+        unknown.resolution = "https://mirror.example.com/left-pad-1.0.0.tgz".to_string();
+        unknown.lookup_failed = true;
+        unknown.source_lockfiles = vec!["api/yarn.lock".to_string()];
+        let mut other_version = sample_package("left-pad", "UNKNOWN");
+        other_version.version = "2.0.0".to_string();
+
+        let reconciled = reconcile_packages(vec![unknown, other_version, known]);
+
+        assert_eq!(reconciled.len(), 2);
+        let left_pad = &reconciled[0];
+        assert_eq!(left_pad.license, "MIT");
+        assert!(!left_pad.lookup_failed);
+        assert_eq!(left_pad.source_lockfiles, vec!["api/yarn.lock", "web/yarn.lock"]);
+        assert_eq!(left_pad.debug_info.as_deref(), Some("also resolved as UNKNOWN via api/yarn.lock"));
+        assert_eq!(reconciled[1].version, "2.0.0");
+
+        // The merged entry appears once, as MIT, in the JSON report
+        let json: Value = serde_json::from_str(&render(OutputFormat::Json, &reconciled, &[])).unwrap();
+        let entries: Vec<&Value> = json["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|p| p["name"] == "left-pad" && p["version"] == "1.0.0")
+            .collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["license"], "MIT");
+    }
+
+    #[test]
+    fn test_reconcile_keeps_ecosystems_apart() {
+        // The same name and version on npm, PyPI and NuGet are three different packages
+        let mut npm = sample_package("requests", "Apache-2.0");
+        npm.registry = String::new();
+        npm.source_lockfiles = vec!["web/yarn.lock".to_string()];
+        let mut pypi = sample_package("requests", "UNKNOWN");
+        pypi.registry = "pypi".to_string();
+        pypi.source_lockfiles = vec!["api/poetry.lock".to_string()];
+        let mut nuget = sample_package("requests", "MIT");
+        nuget.registry = "nuget".to_string();
+        let mut npm_again = sample_package("requests", "Apache-2.0");
+        npm_again.source_lockfiles = vec!["admin/package-lock.json".to_string()];
+
+        let reconciled = reconcile_packages(vec![npm, pypi, nuget, npm_again]);
+
+        let entries: Vec<(&str, &str)> = reconciled
+            .iter()
+            .map(|p| (p.registry.as_str(), p.license.as_str()))
+            .collect();
+        assert_eq!(entries, [("", "Apache-2.0"), ("pypi", "UNKNOWN"), ("nuget", "MIT")]);
+        assert_eq!(reconciled[0].source_lockfiles, ["admin/package-lock.json", "web/yarn.lock"]);
+        assert!(reconciled[1].debug_info.is_none());
+    }
+
+    #[test]
+    fn test_unique_key_compares_versions_semantically() {
+        let with_version = |version: &str| {
+            let mut package = sample_package("lodash", "MIT");
+            package.version = version.to_string();
+            generate_unique_package_key(&package)
+        };
+
+        assert_eq!(with_version("1.2"), with_version("1.2.0"));
+        assert_ne!(with_version("1.0.0"), with_version("1.0.0-beta"));
+        assert_ne!(with_version("1.0.0-beta"), with_version("1.0.0-beta.2"));
+        assert_eq!(with_version("3f2a9c1"), with_version("3f2a9c1"));
+        assert_ne!(with_version("3f2a9c1"), with_version("3f2a9c2"));
+    }
+
+    #[test]
+    fn test_dedupe_packages() {
+        // The same lodash reached three times, first without a resolved license
+        let mut unresolved = sample_package("lodash", "UNKNOWN");
+        unresolved.version = "^1.0.0".to_string();
+        let fixture = vec![
+            unresolved,
+            sample_package("lodash", "MIT"),
+            sample_package("react", "MIT"),
+            sample_package("lodash", "MIT")
+        ];
+
+        let full_count = fixture.len();
+        let (unique, occurrences) = dedupe_packages(fixture);
+
+        assert_eq!(full_count, 4);
+        assert_eq!(unique.len(), 2);
+        assert_eq!(unique[0].license, "MIT");
+        assert_eq!(occurrences[&generate_unique_package_key(&unique[0])], 3);
+        assert_eq!(occurrences[&generate_unique_package_key(&unique[1])], 1);
+        let license_checker = LicenseChecker::new(Vec::new());
+        let report = Report {
+            occurrences: Some(&occurrences),
+            text_options: TextOptions { verbose: true, ..TextOptions::default() },
+            ..Report::new(&unique, &license_checker)
+        };
+        let mut text = Vec::new();
+        write_report(&OutputRequest::new(OutputFormat::Text), &report, &mut text).unwrap();
+        assert!(String::from_utf8(text).unwrap().contains("(x3)"));
+    }
+
+    #[test]
+    fn test_format_names() {
+        assert_eq!(OutputFormat::SbomCyclonedx.name(), "sbom-cyclonedx");
+        assert_eq!(OutputFormat::from_str("junit", true), Ok(OutputFormat::Junit));
+    }
+
+    #[test]
+    fn test_package_url_and_spdx_expression() {
+        assert_eq!(package_url(&sample_package("@scope/app", "MIT")), "pkg:npm/%40scope/app@1.0.0");
+        assert_eq!(spdx_license_expression("MIT OR Apache-2.0"), "MIT OR Apache-2.0");
+        assert_eq!(spdx_license_expression("UNKNOWN"), "NOASSERTION");
+        assert_eq!(spdx_license_expression("(MIT OR GPL-2.0+) AND BSD-3-Clause"), "(MIT OR GPL-2.0+) AND BSD-3-Clause");
+        assert_eq!(spdx_license_expression("SEE LICENSE IN LICENSE.md"), "LicenseRef-SEE-LICENSE-IN-LICENSE.md");
+        assert_eq!(spdx_license_expression("Custom, see file"), "LicenseRef-Custom--see-file");
+    }
+}
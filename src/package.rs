@@ -1,5 +1,68 @@
+use chrono::{ DateTime, NaiveDate };
 use serde::{ Serialize, Deserialize };
 
+use crate::diagnostics::ResolutionStatus;
+use crate::integrity::{ self, IntegrityHash };
+
+/// License of a package whose license couldn't be determined
+pub const UNKNOWN_LICENSE: &str = "UNKNOWN";
+
+/// Whether a license is unknown: the placeholder, or empty because it was never resolved
+pub fn is_unknown_license(license: &str) -> bool {
+    license.is_empty() || license == UNKNOWN_LICENSE
+}
+
+/// Where a package's license was found, from most to least authoritative
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LicenseSource {
+    Registry, // Declared in registry metadata (npm, PyPI, NuGet)
+    Github, // Declared in a GitHub repository's package.json
+    Gitlab, // Declared in a GitLab repository's package.json
+    PackageManifest, // Declared in the package.json of a downloaded archive
+    LicenseFile, // Detected from the LICENSE file of a downloaded archive
+    LicenseText, // Detected from license text downloaded from a URL
+}
+
+impl LicenseSource {
+    /// Name as shown in reports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LicenseSource::Registry => "registry",
+            LicenseSource::Github => "github",
+            LicenseSource::Gitlab => "gitlab",
+            LicenseSource::PackageManifest => "package-manifest",
+            LicenseSource::LicenseFile => "license-file",
+            LicenseSource::LicenseText => "license-text",
+        }
+    }
+
+    /// Whether the package itself declares the license, rather than it being detected from
+    /// license text
+    pub fn is_declared(&self) -> bool {
+        !matches!(self, LicenseSource::LicenseFile | LicenseSource::LicenseText)
+    }
+}
+
+/// The license one source gives for a package, gathered with --cross-check-licenses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LicenseCandidate {
+    pub source: LicenseSource,
+    pub license: String,
+    pub confidence: f32,
+}
+
+/// Outcome of checking a declared license against the package's LICENSE file text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum LicenseVerification {
+    Match, // The license file is the declared license
+    Mismatch {
+        detected: String, // License the file text was detected as
+    },
+    Unverifiable, // No license file was found, or its text wasn't recognized
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Package {
     // Basic fields (from lockfile parsing)
@@ -7,6 +70,8 @@ pub struct Package {
     pub version: String,
     pub resolution: String,
     pub checksum: Option<String>,
+    #[serde(default)]
+    pub integrity: Vec<IntegrityHash>, // Hashes parsed from the lockfile's SRI integrity string
 
     // Extended fields (filled in after API calls)
     #[serde(default)]
@@ -16,10 +81,12 @@ pub struct Package {
     #[serde(default)]
     pub license: String, // License type
     #[serde(default)]
-    pub license_expiration: Option<String>, // License expiration if available
+    pub license_expiration: Option<String>, // Date a time-limited license expires (YYYY-MM-DD)
     #[serde(default)]
     pub url: String, // URL to package or repo
     #[serde(default)]
+    pub repository: Option<String>, // Source repository named by the registry metadata
+    #[serde(default)]
     pub license_url: Option<String>, // URL to license if available
     #[serde(default)]
     pub debug_info: Option<String>, // Debug information
@@ -31,6 +98,28 @@ pub struct Package {
     pub retry_for_unknown: bool, // Flag to indicate this is a retry for an unknown license
     #[serde(default)]
     pub raw_api_response: Option<String>, // Raw API response (for debug output)
+    #[serde(default)]
+    pub source_lockfiles: Vec<String>, // Lockfiles this package was found through
+    #[serde(default)]
+    pub deprecated: Option<String>, // Deprecation or unpublish notice from the registry
+    #[serde(default)]
+    pub license_source: Option<LicenseSource>, // Where the license was found (None when unknown)
+    #[serde(default)]
+    pub license_fallback: bool, // The license source is less reliable than the resolver's primary lookup
+    #[serde(default)]
+    pub confidence: f32, // How reliable the license is, from 0 (unknown) to 1 (declared SPDX id)
+    #[serde(default)]
+    pub lookup_failed: bool, // Registry lookup failed; debug_info holds the error
+    #[serde(default)]
+    pub resolution_status: ResolutionStatus, // How the registry lookup failed, set with lookup_failed
+    #[serde(default)]
+    pub license_verification: Option<LicenseVerification>, // Set with --verify-license-text
+    #[serde(default)]
+    pub license_candidates: Vec<LicenseCandidate>, // Every source's license, set with --cross-check-licenses
+    #[serde(default)]
+    pub os: Vec<String>, // Operating systems the package supports (npm "os"; "!" excludes one)
+    #[serde(default)]
+    pub cpu: Vec<String>, // CPU architectures the package supports (npm "cpu"; "!" excludes one)
 }
 
 impl Package {
@@ -46,17 +135,30 @@ impl Package {
             version: version.clone(),
             resolution,
             checksum,
+            integrity: Vec::new(),
             registry: String::new(),
             display_name: String::new(),
             license: String::new(),
             license_expiration: None,
             url: String::new(),
+            repository: None,
             license_url: None,
             debug_info: None,
             dependencies: Vec::new(),
             processed: false,
             retry_for_unknown: false,
             raw_api_response: None,
+            source_lockfiles: Vec::new(),
+            deprecated: None,
+            license_source: None,
+            license_fallback: false,
+            confidence: 0.0,
+            lookup_failed: false,
+            resolution_status: ResolutionStatus::Ok,
+            license_verification: None,
+            license_candidates: Vec::new(),
+            os: Vec::new(),
+            cpu: Vec::new(),
         }
     }
 
@@ -66,6 +168,7 @@ impl Package {
         version: String,
         registry: &str,
         url: String,
+        status: ResolutionStatus,
         error_msg: &str
     ) -> Self {
         let display_name = format!("{}@{}", name, version);
@@ -75,20 +178,51 @@ impl Package {
             version,
             resolution: String::new(),
             checksum: None,
+            integrity: Vec::new(),
             registry: registry.to_string(),
             display_name,
-            license: "UNKNOWN".to_string(),
+            license: UNKNOWN_LICENSE.to_string(),
             license_expiration: None,
             url,
+            repository: None,
             license_url: None,
             debug_info: Some(error_msg.to_string()),
             dependencies: Vec::new(),
             processed: true,
             retry_for_unknown: false,
             raw_api_response: None,
+            source_lockfiles: Vec::new(),
+            deprecated: None,
+            license_source: None,
+            license_fallback: false,
+            confidence: 0.0,
+            lookup_failed: true,
+            resolution_status: status,
+            license_verification: None,
+            license_candidates: Vec::new(),
+            os: Vec::new(),
+            cpu: Vec::new(),
         }
     }
 
+    /// The integrity hash to verify downloads against, using the strongest algorithm listed
+    pub fn strongest_integrity(&self) -> Option<&IntegrityHash> {
+        integrity::strongest(&self.integrity)
+    }
+
+    /// Whether the license is unknown, including packages whose license was never resolved
+    pub fn is_unknown(&self) -> bool {
+        is_unknown_license(&self.license)
+    }
+
+    /// Whether the license expired before `today`
+    pub fn license_expired(&self, today: NaiveDate) -> bool {
+        self.license_expiration
+            .as_deref()
+            .and_then(parse_license_expiration)
+            .is_some_and(|expiration| expiration < today)
+    }
+
     /// Mark this package as processed
     #[allow(dead_code)] // Added attribute since this method isn't currently used
     pub fn mark_processed(&mut self) {
@@ -111,3 +245,41 @@ impl Package {
         }
     }
 }
+
+/// Parse a license expiration given as a date or an RFC 3339 timestamp
+pub fn parse_license_expiration(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .or_else(|| DateTime::parse_from_rfc3339(value).ok().map(|timestamp| timestamp.date_naive()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_and_placeholder_licenses_are_unknown() {
+        let mut package = Package::new("left-pad".to_string(), "1.3.0".to_string(), String::new(), None);
+        assert!(package.license.is_empty());
+        assert!(package.is_unknown());
+
+        package.license = UNKNOWN_LICENSE.to_string();
+        assert!(package.is_unknown());
+        let failed = Package::with_error(
+            "x".to_string(),
+            "1.0.0".to_string(),
+            "npm",
+            String::new(),
+            ResolutionStatus::NetworkError,
+            "offline"
+        );
+        assert!(failed.is_unknown());
+
+        package.license = "WTFPL".to_string();
+        assert!(!package.is_unknown());
+        assert!(is_unknown_license(""));
+        assert!(!is_unknown_license("Unknown-Vendor-License"));
+    }
+}
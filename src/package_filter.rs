@@ -0,0 +1,255 @@
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::sync::atomic::{ AtomicBool, AtomicUsize, Ordering };
+use std::sync::Mutex;
+
+use crate::package::Package;
+
+/// Package name patterns to leave out of the scan, e.g. first-party packages
+/// published to a private registry. Remembers which names it excluded.
+pub struct PackageExcluder {
+    patterns: Vec<Regex>,
+    excluded: Mutex<BTreeSet<String>>,
+}
+
+impl PackageExcluder {
+    /// Build an excluder from name patterns where `*` matches any sequence of characters
+    pub fn new(patterns: &[String]) -> Self {
+        PackageExcluder {
+            patterns: name_patterns(patterns),
+            excluded: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Whether the package name matches a pattern; matches are recorded
+    pub fn exclude(&self, name: &str) -> bool {
+        let is_excluded = self.patterns.iter().any(|pattern| pattern.is_match(name));
+        if is_excluded {
+            self.excluded.lock().unwrap().insert(name.to_string());
+        }
+        is_excluded
+    }
+
+    /// Remove excluded packages from a list
+    pub fn filter(&self, packages: Vec<Package>) -> Vec<Package> {
+        packages
+            .into_iter()
+            .filter(|package| !self.exclude(&package.name))
+            .collect()
+    }
+
+    /// Names of all packages excluded so far
+    pub fn excluded_names(&self) -> Vec<String> {
+        self.excluded.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Packages named with --package, for resolving a few packages and their dependencies
+/// instead of the whole lockfile
+pub struct PackageSelector {
+    patterns: Vec<Regex>,
+}
+
+impl PackageSelector {
+    /// Build a selector from name patterns where `*` matches any sequence of characters
+    pub fn new(patterns: &[String]) -> Self {
+        PackageSelector { patterns: name_patterns(patterns) }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(name))
+    }
+
+    /// Keep only the selected packages from a list
+    pub fn select(&self, packages: Vec<Package>) -> Vec<Package> {
+        packages
+            .into_iter()
+            .filter(|package| self.matches(&package.name))
+            .collect()
+    }
+}
+
+/// Cap on the number of distinct packages a scan processes (--max-packages), so a malformed
+/// lockfile or a runaway dependency graph can't grow the queue without bound
+pub struct PackageLimit {
+    max: Option<usize>,
+    claimed: AtomicUsize,
+    truncated: AtomicBool,
+}
+
+impl PackageLimit {
+    pub fn new(max: Option<usize>) -> Self {
+        PackageLimit {
+            max,
+            claimed: AtomicUsize::new(0),
+            truncated: AtomicBool::new(false),
+        }
+    }
+
+    /// Take a slot for processing one more package; false once the cap is reached
+    pub fn claim(&self) -> bool {
+        let Some(max) = self.max else {
+            return true;
+        };
+        let claimed = self.claimed.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |claimed| {
+            (claimed < max).then_some(claimed + 1)
+        });
+        if claimed.is_err() {
+            self.truncated.store(true, Ordering::SeqCst);
+        }
+        claimed.is_ok()
+    }
+
+    /// Whether every slot is taken, so new dependencies need not be queued
+    pub fn reached(&self) -> bool {
+        self.max.is_some_and(|max| self.claimed.load(Ordering::SeqCst) >= max)
+    }
+
+    /// Mark the scan as truncated when packages were left out because of the cap
+    pub fn mark_truncated(&self) {
+        self.truncated.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether packages were left out because of the cap
+    pub fn truncated(&self) -> bool {
+        self.truncated.load(Ordering::SeqCst)
+    }
+
+    pub fn max(&self) -> Option<usize> {
+        self.max
+    }
+}
+
+/// Target platform set with --platform as OS or OS-CPU in Node's names ("linux", "darwin-arm64").
+/// Packages whose npm `os`/`cpu` fields rule the target out, such as the platform-specific
+/// binaries in optionalDependencies, are skipped. Remembers which packages it skipped.
+pub struct PlatformFilter {
+    os: Option<String>,
+    cpu: Option<String>,
+    skipped: Mutex<BTreeSet<String>>,
+}
+
+impl PlatformFilter {
+    /// A filter for the platform, or one that keeps every package
+    pub fn new(platform: Option<&str>) -> Self {
+        let (os, cpu) = match platform.map(str::trim) {
+            Some(platform) =>
+                match platform.split_once('-') {
+                    Some((os, cpu)) => (Some(os.to_string()), Some(cpu.to_string())),
+                    None => (Some(platform.to_string()), None),
+                }
+            None => (None, None),
+        };
+        PlatformFilter { os, cpu, skipped: Mutex::new(BTreeSet::new()) }
+    }
+
+    /// Whether the package doesn't run on the target platform; skipped packages are recorded
+    pub fn skip(&self, package: &Package) -> bool {
+        let supported = |constraints: &[String], target: &Option<String>| {
+            target.as_deref().is_none_or(|target| platform_allowed(constraints, target))
+        };
+        let skip = !supported(&package.os, &self.os) || !supported(&package.cpu, &self.cpu);
+        if skip {
+            self.skipped.lock().unwrap().insert(format!("{}@{}", package.name, package.version));
+        }
+        skip
+    }
+
+    /// name@version of all packages skipped so far
+    pub fn skipped(&self) -> Vec<String> {
+        self.skipped.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// npm's reading of an `os`/`cpu` list: "!name" rules a value out, and plain names, when
+/// there are any, are the only values allowed. An empty list allows everything.
+fn platform_allowed(constraints: &[String], target: &str) -> bool {
+    if constraints.iter().any(|constraint| constraint.strip_prefix('!') == Some(target)) {
+        return false;
+    }
+    let mut allowed = constraints.iter().filter(|constraint| !constraint.starts_with('!')).peekable();
+    allowed.peek().is_none() || allowed.any(|constraint| constraint == target)
+}
+
+/// Compile name patterns, where `*` matches any sequence of characters, to anchored regexes
+fn name_patterns(patterns: &[String]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter_map(|pattern| name_pattern(pattern))
+        .collect()
+}
+
+/// Compile one name pattern, where `*` matches any sequence of characters, to an anchored regex
+pub fn name_pattern(pattern: &str) -> Option<Regex> {
+    let regex_str = format!("^{}$", regex::escape(pattern.trim()).replace("\\*", ".*"));
+    Regex::new(&regex_str).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclude_patterns() {
+        let excluder = PackageExcluder::new(&["@acme/*".to_string(), "internal-tool".to_string()]);
+
+        assert!(excluder.exclude("@acme/ui"));
+        assert!(excluder.exclude("internal-tool"));
+        assert!(!excluder.exclude("internal-tool-extra"));
+        assert!(!excluder.exclude("@acme-other/ui"));
+        assert_eq!(excluder.excluded_names(), vec!["@acme/ui", "internal-tool"]);
+    }
+
+    #[test]
+    fn test_platform_filter() {
+        let constrained = |os: &[&str], cpu: &[&str]| {
+            let mut package = Package::new("@esbuild/bin".to_string(), "0.19.0".to_string(), String::new(), None);
+            package.os = os.iter().map(|s| s.to_string()).collect();
+            package.cpu = cpu.iter().map(|s| s.to_string()).collect();
+            package
+        };
+
+        let linux = PlatformFilter::new(Some("linux"));
+        assert!(linux.skip(&constrained(&["darwin"], &["arm64"])));
+        assert!(!linux.skip(&constrained(&["linux"], &["arm64"])));
+        assert!(!linux.skip(&constrained(&["!win32"], &[])));
+        assert!(!linux.skip(&constrained(&[], &[])));
+        assert_eq!(linux.skipped(), vec!["@esbuild/bin@0.19.0"]);
+
+        let linux_x64 = PlatformFilter::new(Some("linux-x64"));
+        assert!(linux_x64.skip(&constrained(&["linux"], &["arm64"])));
+        assert!(linux_x64.skip(&constrained(&[], &["!x64"])));
+        assert!(!linux_x64.skip(&constrained(&["linux", "darwin"], &["x64"])));
+
+        // Without --platform every package is scanned
+        assert!(!PlatformFilter::new(None).skip(&constrained(&["darwin"], &["arm64"])));
+    }
+
+    #[test]
+    fn test_package_limit() {
+        let unlimited = PackageLimit::new(None);
+        assert!((0..1000).all(|_| unlimited.claim()));
+        assert!(!unlimited.reached());
+        assert!(!unlimited.truncated());
+
+        let limit = PackageLimit::new(Some(2));
+        assert!(limit.claim());
+        assert!(!limit.reached());
+        assert!(limit.claim());
+        assert!(limit.reached());
+        assert!(!limit.truncated());
+        assert!(!limit.claim());
+        assert!(limit.truncated());
+    }
+
+    #[test]
+    fn test_select_patterns() {
+        let selector = PackageSelector::new(&["@babel/*".to_string(), "lodash".to_string(), "left-*".to_string()]);
+
+        assert!(selector.matches("@babel/core"));
+        assert!(selector.matches("lodash"));
+        assert!(selector.matches("left-pad"));
+        assert!(!selector.matches("lodash.merge"));
+        assert!(!selector.matches("@babel-other/core"));
+    }
+}
@@ -0,0 +1,114 @@
+/// Turn JSONC (JSON with `//` and `/* */` comments and trailing commas) into strict JSON.
+/// Plain JSON passes through unchanged; string contents are never touched.
+pub fn strip_jsonc(content: &str) -> String {
+    without_trailing_commas(&without_comments(content))
+}
+
+fn without_comments(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            if c == '\\' {
+                if let Some(escaped) = chars.next() {
+                    output.push(escaped);
+                }
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => {
+                // Line comment: drop everything up to the newline, keep the newline
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        output.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                // Block comment: drop everything up to the closing */
+                chars.next();
+                let mut previous = '\0';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+                output.push(' ');
+            }
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
+fn without_trailing_commas(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut output = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            output.push(c);
+            if c == '\\' && i + 1 < chars.len() {
+                i += 1;
+                output.push(chars[i]);
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+            output.push(c);
+        } else if c == ',' {
+            // A comma followed only by whitespace before a closing bracket is dropped
+            let next = chars[i + 1..].iter().find(|next| !next.is_whitespace());
+            if !matches!(next, Some('}') | Some(']')) {
+                output.push(c);
+            }
+        } else {
+            output.push(c);
+        }
+        i += 1;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_jsonc() {
+        let jsonc =
+            r#"{
+            // a line comment
+            "url": "https://example.com/a,b//c", /* block */
+            "quoted": "say \"hi\", // not a comment",
+            "list": [1, 2, ],
+        }"#;
+
+        let value: serde_json::Value = serde_json::from_str(&strip_jsonc(jsonc)).unwrap();
+        assert_eq!(value["url"], "https://example.com/a,b//c");
+        assert_eq!(value["quoted"], "say \"hi\", // not a comment");
+        assert_eq!(value["list"], serde_json::json!([1, 2]));
+
+        let strict = r#"{"a": [1, 2], "b": "x"}"#;
+        assert_eq!(strip_jsonc(strict), strict);
+    }
+}
@@ -0,0 +1,104 @@
+use serde_json::Value;
+use crate::package::Package;
+
+/// Resolution prefix marking packages whose version was resolved from a package.json range
+pub const MANIFEST_RESOLUTION_PREFIX: &str = "manifest:";
+
+/// Note attached to packages resolved from a manifest, since nothing pins their versions
+pub const MANIFEST_NOTE: &str = "resolved from manifest, not locked";
+
+/// Parse the dependencies and devDependencies of a package.json.
+/// `resolve` maps a package name and version range to a concrete version.
+pub fn parse_package_json<F>(content: &str, mut resolve: F) -> Result<Vec<Package>, String>
+    where F: FnMut(&str, &str) -> Option<String>
+{
+    let manifest: Value = serde_json
+        ::from_str(content)
+        .map_err(|e| format!("Failed to parse package.json: {}", e))?;
+
+    let mut packages = Vec::new();
+
+    for section in ["dependencies", "devDependencies"] {
+        if let Some(dependencies) = manifest[section].as_object() {
+            for (name, spec) in dependencies {
+                let range = match spec.as_str() {
+                    Some(range) => range.trim(),
+                    None => {
+                        continue;
+                    }
+                };
+
+                if let Some(package) = manifest_package(name, range, &mut resolve) {
+                    // A package listed in both sections only needs checking once
+                    if !packages.iter().any(|p: &Package| p.name == package.name) {
+                        packages.push(package);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(packages)
+}
+
+fn manifest_package<F>(name: &str, range: &str, resolve: &mut F) -> Option<Package>
+    where F: FnMut(&str, &str) -> Option<String>
+{
+    // GitHub dependencies are resolved by the GitHub API later on
+    if range.starts_with("github:") {
+        return Some(Package::new(name.to_string(), "latest".to_string(), range.to_string(), None));
+    }
+
+    // Local and non-registry sources can't be looked up by version
+    if ["file:", "link:", "workspace:", "git", "http:", "https:", "npm:"].iter().any(|p| range.starts_with(p)) {
+        eprintln!("INFO: Skipping manifest dependency {} with unsupported source: {}", name, range);
+        return None;
+    }
+
+    let version = match resolve(name, range) {
+        Some(version) => version,
+        None => {
+            eprintln!("INFO: Could not resolve {}@{} from the registry", name, range);
+            crate::github_api::strip_range_operator(range).to_string()
+        }
+    };
+
+    Some(
+        Package::new(
+            name.to_string(),
+            version,
+            format!("{}{}", MANIFEST_RESOLUTION_PREFIX, range),
+            None
+        )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_package_json() {
+        let content =
+            r#"{
+            "name": "app",
+            "dependencies": { "left-pad": "^1.1.0", "local": "file:../local", "repo": "github:owner/repo#v1" },
+            "devDependencies": { "@types/node": "~20.1.0", "left-pad": "^1.1.0" }
+        }"#;
+
+        let packages = parse_package_json(content, |name, range| {
+            match (name, range) {
+                ("left-pad", "^1.1.0") => Some("1.3.0".to_string()),
+                _ => None,
+            }
+        }).unwrap();
+
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0].name, "left-pad");
+        assert_eq!(packages[0].version, "1.3.0");
+        assert_eq!(packages[0].resolution, "manifest:^1.1.0");
+        assert_eq!(packages[1].resolution, "github:owner/repo#v1");
+        assert_eq!(packages[2].name, "@types/node");
+        assert_eq!(packages[2].version, "20.1.0");
+    }
+}
@@ -1,6 +1,13 @@
 pub mod yarn_parser;
+pub mod yarn_pnp_parser;
 pub mod npm_parser;
+pub mod pnpm_parser;
 pub mod nuget_parser;
 pub mod poetry_parser; // Add the new parser module
+pub mod requirements_parser;
+pub mod manifest_parser;
+pub mod node_modules_parser;
+pub mod site_packages_parser;
+pub mod jsonc;
 
 // No need to re-export the parse functions since they're now accessed directly via the module path
@@ -0,0 +1,214 @@
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use crate::license_detection;
+use crate::package::{ LicenseSource, Package, UNKNOWN_LICENSE };
+
+pub const NODE_MODULES_DIR: &str = "node_modules";
+
+/// Whether a path is an installed node_modules directory
+pub fn is_node_modules_dir(path: &Path) -> bool {
+    path.is_dir() && path.file_name().is_some_and(|name| name == NODE_MODULES_DIR)
+}
+
+/// The node_modules directory of a project, if anything is installed in it
+pub fn find_node_modules(dir: &Path) -> Option<PathBuf> {
+    let node_modules = dir.join(NODE_MODULES_DIR);
+    let populated = fs::read_dir(&node_modules)
+        .ok()?
+        .filter_map(Result::ok)
+        .any(|entry| !entry.file_name().to_string_lossy().starts_with('.'));
+    if populated { Some(node_modules) } else { None }
+}
+
+/// Read every installed package from a node_modules directory, including scoped packages and
+/// the copies nested under other packages. Licenses come from each package.json, so nothing
+/// is looked up over the network.
+pub fn parse_node_modules(node_modules: &Path) -> Result<Vec<Package>, String> {
+    if !node_modules.is_dir() {
+        return Err(format!("Not a directory: {}", node_modules.display()));
+    }
+
+    let mut packages = Vec::new();
+    let mut seen = HashSet::new();
+    let mut visited = HashSet::new();
+    collect_packages(node_modules, &mut packages, &mut seen, &mut visited);
+    Ok(packages)
+}
+
+fn collect_packages(
+    node_modules: &Path,
+    packages: &mut Vec<Package>,
+    seen: &mut HashSet<(String, String)>,
+    visited: &mut HashSet<PathBuf>
+) {
+    // Symlinked workspaces can link back to a directory that was already read
+    let Ok(canonical) = node_modules.canonicalize() else {
+        return;
+    };
+    if !visited.insert(canonical) {
+        return;
+    }
+
+    for package_dir in package_dirs(node_modules) {
+        if let Some(package) = read_installed_package(&package_dir) {
+            if seen.insert((package.name.clone(), package.version.clone())) {
+                packages.push(package);
+            }
+        }
+        collect_packages(&package_dir.join(NODE_MODULES_DIR), packages, seen, visited);
+    }
+}
+
+/// Package directories directly under node_modules; @scope directories hold one more level
+fn package_dirs(node_modules: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let Ok(entries) = fs::read_dir(node_modules) else {
+        return dirs;
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        // .bin, .cache, .package-lock.json and other package manager files
+        if name.starts_with('.') || !path.is_dir() {
+            continue;
+        }
+
+        if name.starts_with('@') {
+            if let Ok(scoped) = fs::read_dir(&path) {
+                dirs.extend(
+                    scoped
+                        .filter_map(Result::ok)
+                        .map(|entry| entry.path())
+                        .filter(|path| path.is_dir())
+                );
+            }
+        } else {
+            dirs.push(path);
+        }
+    }
+
+    dirs.sort();
+    dirs
+}
+
+fn read_installed_package(package_dir: &Path) -> Option<Package> {
+    let content = fs::read_to_string(package_dir.join("package.json")).ok()?;
+    let manifest: Value = serde_json::from_str(&content).ok()?;
+    let name = manifest["name"].as_str()?;
+    let version = manifest["version"].as_str()?;
+
+    let mut package = Package::new(
+        name.to_string(),
+        version.to_string(),
+        package_dir.display().to_string(),
+        None
+    );
+    package.registry = "npm".to_string();
+    package.url = manifest["homepage"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("https://www.npmjs.com/package/{}", name));
+
+    match manifest_license(&manifest) {
+        Some(license) => {
+            package.license = license_detection::normalize_license_id(&license);
+            package.license_source = Some(LicenseSource::PackageManifest);
+            package.confidence = license_detection::declared_confidence(&package.license);
+        }
+        None => {
+            package.license = UNKNOWN_LICENSE.to_string();
+        }
+    }
+    package.processed = true;
+    Some(package)
+}
+
+/// The declared license: a string, a { "type": ... } object, or the legacy "licenses" array
+fn manifest_license(manifest: &Value) -> Option<String> {
+    let declared = match &manifest["license"] {
+        Value::String(license) => Some(license.as_str()),
+        Value::Object(license) => license.get("type").and_then(Value::as_str),
+        _ => None,
+    };
+
+    declared
+        .or_else(|| manifest["licenses"].as_array()?.first()?["type"].as_str())
+        .map(str::trim)
+        .filter(|license| !license.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install(node_modules: &Path, dir: &str, manifest: &str) {
+        let package_dir = node_modules.join(dir);
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(package_dir.join("package.json"), manifest).unwrap();
+    }
+
+    #[test]
+    fn test_parse_node_modules_tree() {
+        let project = tempfile::tempdir().unwrap();
+        let node_modules = project.path().join(NODE_MODULES_DIR);
+        install(&node_modules, "left-pad", r#"{ "name": "left-pad", "version": "1.3.0", "license": "WTFPL" }"#);
+        install(
+            &node_modules,
+            "@babel/core",
+            r#"{ "name": "@babel/core", "version": "7.24.0", "license": { "type": "MIT" } }"#
+        );
+        // A second version nested under the package that needs it
+        install(
+            &node_modules,
+            "@babel/core/node_modules/semver",
+            r#"{ "name": "semver", "version": "6.3.1", "licenses": [{ "type": "ISC" }] }"#
+        );
+        install(&node_modules, "semver", r#"{ "name": "semver", "version": "7.6.0", "license": "ISC" }"#);
+        install(&node_modules, "mystery", r#"{ "name": "mystery", "version": "0.1.0" }"#);
+        // Package manager files and directories without a package.json aren't packages
+        install(&node_modules, ".bin", r#"{ "name": "not-a-package", "version": "1.0.0" }"#);
+        fs::create_dir_all(node_modules.join("empty")).unwrap();
+
+        assert_eq!(find_node_modules(project.path()), Some(node_modules.clone()));
+        assert!(is_node_modules_dir(&node_modules));
+
+        let packages = parse_node_modules(&node_modules).unwrap();
+        let installed: Vec<(String, String, String)> = packages
+            .iter()
+            .map(|p| (p.name.clone(), p.version.clone(), p.license.clone()))
+            .collect();
+        let expected = [
+            ("@babel/core", "7.24.0", "MIT"),
+            ("semver", "6.3.1", "ISC"),
+            ("left-pad", "1.3.0", "WTFPL"),
+            ("mystery", "0.1.0", "UNKNOWN"),
+            ("semver", "7.6.0", "ISC"),
+        ];
+        assert_eq!(
+            installed,
+            expected
+                .iter()
+                .map(|(name, version, license)| (name.to_string(), version.to_string(), license.to_string()))
+                .collect::<Vec<_>>()
+        );
+
+        let left_pad = &packages[2];
+        assert!(left_pad.processed);
+        assert_eq!(left_pad.registry, "npm");
+        assert_eq!(left_pad.license_source, Some(LicenseSource::PackageManifest));
+        assert_eq!(left_pad.confidence, license_detection::declared_confidence("WTFPL"));
+    }
+
+    #[test]
+    fn test_empty_node_modules_is_not_a_source() {
+        let project = tempfile::tempdir().unwrap();
+        assert_eq!(find_node_modules(project.path()), None);
+
+        fs::create_dir_all(project.path().join(NODE_MODULES_DIR).join(".cache")).unwrap();
+        assert_eq!(find_node_modules(project.path()), None);
+    }
+}
@@ -1,26 +1,28 @@
 use serde_json::Value;
+use crate::integrity;
+use crate::parsers::jsonc;
 use crate::package::Package;
 use crate::utils;
 
-/// Parse an npm package-lock.json file into a vector of packages
+/// Lockfile of published packages, in the package-lock.json format. npm installs from it
+/// instead of a package-lock.json next to it.
+pub const SHRINKWRAP_FILE: &str = "npm-shrinkwrap.json";
+
+/// Parse an npm package-lock.json (or npm-shrinkwrap.json) file into a vector of packages
 pub fn parse_package_lock(content: &str) -> Vec<Package> {
     let mut packages = Vec::new();
 
     // Instead of using the package-lock-json-parser crate's structured types,
-    // parse the JSON directly to avoid private field access issues
-    match serde_json::from_str::<Value>(content) {
+    // parse the JSON directly to avoid private field access issues.
+    // Comments and trailing commas from hand edits or other tooling are tolerated.
+    match serde_json::from_str::<Value>(&jsonc::strip_jsonc(content)) {
         Ok(json) => {
             // Process the root dependencies
             if let Some(dependencies) = json.get("dependencies").and_then(|d| d.as_object()) {
                 for (name, dependency) in dependencies {
                     if let Some(version) = dependency.get("version").and_then(|v| v.as_str()) {
                         // Create a resolution URL (npm registry URL pattern)
-                        let resolution = format!(
-                            "https://registry.npmjs.org/{}/-/{}-{}.tgz",
-                            name,
-                            name.replace('@', "").replace('/', "-"),
-                            version
-                        );
+                        let resolution = crate::github_api::npm_tarball_url(name, version);
 
                         // Extract integrity hash if available as checksum
                         let checksum = dependency
@@ -35,9 +37,13 @@ pub fn parse_package_lock(content: &str) -> Vec<Package> {
                             resolution.clone(),
                             checksum
                         );
+                        package.integrity = package.checksum
+                            .as_deref()
+                            .map(integrity::parse_sri)
+                            .unwrap_or_default();
 
                         // Set the URL based on the package source
-                        package.url = determine_package_url(&name, &resolution, dependency);
+                        package.url = determine_package_url(name, &resolution, dependency);
 
                         packages.push(package);
                     }
@@ -48,7 +54,7 @@ pub fn parse_package_lock(content: &str) -> Vec<Package> {
             if let Some(packages_map) = json.get("packages").and_then(|p| p.as_object()) {
                 for (path, pkg_data) in packages_map {
                     // Skip the root package
-                    if path == "" {
+                    if path.is_empty() {
                         continue;
                     }
 
@@ -81,12 +87,7 @@ pub fn parse_package_lock(content: &str) -> Vec<Package> {
                     }
 
                     // Create a resolution URL
-                    let resolution = format!(
-                        "https://registry.npmjs.org/{}/-/{}-{}.tgz",
-                        name,
-                        name.replace('@', "").replace('/', "-"),
-                        version
-                    );
+                    let resolution = crate::github_api::npm_tarball_url(&name, &version);
 
                     // Extract integrity hash if available
                     let checksum = pkg_data
@@ -95,7 +96,11 @@ pub fn parse_package_lock(content: &str) -> Vec<Package> {
                         .map(|s| s.to_string());
 
                     // Create package object
-                    let package = Package::new(name, version, resolution, checksum);
+                    let mut package = Package::new(name, version, resolution, checksum);
+                    package.integrity = package.checksum
+                        .as_deref()
+                        .map(integrity::parse_sri)
+                        .unwrap_or_default();
 
                     // Only add if not already added (avoid duplicates)
                     if
@@ -109,14 +114,14 @@ pub fn parse_package_lock(content: &str) -> Vec<Package> {
             }
         }
         Err(e) => {
-            eprintln!("Error parsing package-lock.json: {}", e);
+            eprintln!("Error parsing npm lockfile: {}", e);
         }
     }
 
     // Generate fallback checksums for packages that don't have one
     for package in &mut packages {
         if package.checksum.is_none() {
-            let fallback = utils::generate_fallback_checksum(&package);
+            let fallback = utils::generate_fallback_checksum(package);
             package.checksum = Some(fallback);
         }
     }
@@ -153,3 +158,55 @@ fn determine_package_url(name: &str, resolution: &str, dependency: &Value) -> St
         format!("https://www.npmjs.com/package/{}", name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrity::HashAlgorithm;
+
+    #[test]
+    fn test_multi_hash_integrity_uses_strongest() {
+        let lockfile =
+            r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app" },
+                "node_modules/left-pad": {
+                    "version": "1.3.0",
+                    "integrity": "sha1-W4o6d2Xf4AEmHd6RVYnngvjJTR4= sha512-XI5MPzVNApjAyhQzphX8BkmKsKUxD4LdyK24iZeQEwmuLnQmV5jwu9Y5tm4cZ6MOCu2bbuvgd+yp/BHgKRcDcvw=="
+                }
+            }
+        }"#;
+
+        let packages = parse_package_lock(lockfile);
+        assert_eq!(packages[0].integrity.len(), 2);
+
+        let strongest = packages[0].strongest_integrity().unwrap();
+        assert_eq!(strongest.algorithm, HashAlgorithm::Sha512);
+        assert!(strongest.digest.starts_with("XI5MPzVN"));
+    }
+
+    #[test]
+    fn test_jsonc_lockfile() {
+        let lockfile =
+            r#"{
+            // edited by hand
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "app", },
+                "node_modules/left-pad": {
+                    "version": "1.3.0", // pinned
+                },
+                "node_modules/is-odd": { "version": "3.0.1" },
+            },
+        }"#;
+
+        let packages = parse_package_lock(lockfile);
+        let mut names: Vec<(&str, &str)> = packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.as_str()))
+            .collect();
+        names.sort();
+        assert_eq!(names, vec![("is-odd", "3.0.1"), ("left-pad", "1.3.0")]);
+    }
+}
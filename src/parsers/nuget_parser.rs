@@ -1,17 +1,118 @@
 use std::process::Command;
 use std::path::Path;
 use serde_json::Value;
-use crate::package::Package;
+use regex::Regex;
+use once_cell::sync::Lazy;
+use crate::package::{ is_unknown_license, LicenseSource, Package, UNKNOWN_LICENSE };
+
+// Matches a <package ... /> element in packages.config
+static PACKAGE_ELEMENT: Lazy<Regex> = Lazy::new(|| Regex::new(r"<package\s+([^>]*?)/?>").unwrap());
+
+// Matches an attribute like id="Newtonsoft.Json" inside an element
+static XML_ATTRIBUTE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"([A-Za-z_:][A-Za-z0-9_.:-]*)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap()
+});
+
+// Matches a <PackageReference ...> element in a .csproj, including an optional <Version> child
+static PACKAGE_REFERENCE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?s)<PackageReference\s+([^>]*?)(?:/>|>(.*?)</PackageReference>)"
+    ).unwrap()
+});
+
+// Matches a <Version> child element inside a PackageReference
+static VERSION_ELEMENT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<Version>\s*([^<]+?)\s*</Version>").unwrap()
+});
+
+// Matches a resolved package line in paket.lock, e.g. "    Newtonsoft.Json (13.0.1)"
+static PAKET_PACKAGE_LINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^    ([^\s(][^\s]*) \(([^)]+)\)").unwrap()
+});
 
 /// Parse a .csproj file to extract NuGet package information
-pub fn parse_csproj(file_path: &Path) -> Result<Vec<Package>, String> {
-    // Check if nuget-license command is available
-    if !check_nuget_license_command() {
-        return Err(
-            "nuget-license command not found. Please install it with 'dotnet tool install --global nuget-license'".to_string()
+///
+/// The external nuget-license tool is only used when requested and installed, since it
+/// also resolves transitive packages. Otherwise the PackageReference entries are read
+/// directly and resolved later through the NuGet API.
+pub fn parse_csproj(file_path: &Path, use_nuget_license: bool) -> Result<Vec<Package>, String> {
+    if use_nuget_license {
+        if check_nuget_license_command() {
+            eprintln!("INFO: Using nuget-license for {}", file_path.display());
+            return parse_csproj_with_nuget_license(file_path);
+        }
+        eprintln!(
+            "INFO: nuget-license command not found (install it with 'dotnet tool install --global nuget-license'), falling back to native parsing for {}",
+            file_path.display()
         );
+    } else {
+        eprintln!("INFO: Using native PackageReference parsing for {}", file_path.display());
+    }
+
+    let content = crate::lockfile_parser::read_lockfile(file_path)?;
+
+    Ok(parse_package_references(&content))
+}
+
+/// Parse the PackageReference entries of a .csproj file
+pub fn parse_package_references(content: &str) -> Vec<Package> {
+    let mut packages: Vec<Package> = Vec::new();
+
+    for element in PACKAGE_REFERENCE.captures_iter(content) {
+        let mut id = None;
+        let mut version = None;
+
+        for attribute in XML_ATTRIBUTE.captures_iter(&element[1]) {
+            let value = attribute
+                .get(2)
+                .or_else(|| attribute.get(3))
+                .map(|v| v.as_str().to_string());
+            match &attribute[1] {
+                "Include" => {
+                    id = value;
+                }
+                "Version" => {
+                    version = value;
+                }
+                _ => {}
+            }
+        }
+
+        // The version may also be given as a child element
+        if version.is_none() {
+            if let Some(body) = element.get(2) {
+                version = VERSION_ELEMENT.captures(body.as_str()).map(|c| c[1].to_string());
+            }
+        }
+
+        let (id, version) = match (id, version) {
+            (Some(id), Some(version)) => (id, version),
+            _ => {
+                continue;
+            }
+        };
+
+        // Exact-version ranges like [1.2.3] are common; use their lower bound
+        let version = version
+            .trim_matches(|c| c == '[' || c == '(' || c == ']' || c == ')')
+            .split(',')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_string();
+
+        if version.is_empty() || packages.iter().any(|p| p.name == id && p.version == version) {
+            continue;
+        }
+
+        packages.push(new_nuget_package(&id, &version));
     }
 
+    packages
+}
+
+/// Run the nuget-license tool on a .csproj file to extract NuGet package information
+fn parse_csproj_with_nuget_license(file_path: &Path) -> Result<Vec<Package>, String> {
     // Run nuget-license command to get package information
     let output = match
         Command::new("nuget-license")
@@ -68,7 +169,7 @@ fn parse_nuget_license_output(output: &str) -> Result<Vec<Package>, String> {
                     .unwrap_or("0.0.0")
                     .to_string();
                 let package_url = item["PackageProjectUrl"].as_str().unwrap_or("").to_string();
-                let license = item["License"].as_str().unwrap_or("UNKNOWN").to_string();
+                let license = item["License"].as_str().unwrap_or(UNKNOWN_LICENSE).to_string();
                 let license_url = item["LicenseUrl"].as_str().map(|s| s.to_string());
                 let authors = item["Authors"].as_str().unwrap_or("").to_string();
                 let copyright = item["Copyright"].as_str().unwrap_or("").to_string();
@@ -87,6 +188,8 @@ fn parse_nuget_license_output(output: &str) -> Result<Vec<Package>, String> {
                 // Set additional fields - ensure registry is explicitly set to "nuget"
                 package.registry = "nuget".to_string();
                 package.display_name = format!("{}@{}", package_id, package_version);
+                package.license_source = (!is_unknown_license(&license)).then_some(LicenseSource::Registry);
+                package.confidence = crate::license_detection::declared_confidence(&license);
                 package.license = license;
                 package.url = determine_package_url(&package_id, &package_url);
                 package.license_url = license_url;
@@ -123,3 +226,164 @@ fn determine_package_url(package_id: &str, project_url: &str) -> String {
         format!("https://www.nuget.org/packages/{}", package_id)
     }
 }
+
+/// Parse a packages.config file into NuGet packages resolved later through the NuGet API
+pub fn parse_packages_config(content: &str) -> Vec<Package> {
+    let mut packages = Vec::new();
+
+    for element in PACKAGE_ELEMENT.captures_iter(content) {
+        let mut id = None;
+        let mut version = None;
+
+        for attribute in XML_ATTRIBUTE.captures_iter(&element[1]) {
+            let value = attribute
+                .get(2)
+                .or_else(|| attribute.get(3))
+                .map(|v| v.as_str().to_string());
+            match &attribute[1] {
+                "id" => {
+                    id = value;
+                }
+                "version" => {
+                    version = value;
+                }
+                _ => {}
+            }
+        }
+
+        if let (Some(id), Some(version)) = (id, version) {
+            packages.push(new_nuget_package(&id, &version));
+        }
+    }
+
+    packages
+}
+
+/// Parse a paket.lock file, collecting the resolved packages of every NUGET section
+pub fn parse_paket_lock(content: &str) -> Vec<Package> {
+    let mut packages: Vec<Package> = Vec::new();
+    let mut in_nuget_section = false;
+
+    for line in content.lines() {
+        // Unindented lines start a new section (NUGET, GITHUB, HTTP, GROUP ...)
+        if !line.starts_with(' ') {
+            in_nuget_section = line.trim() == "NUGET";
+            continue;
+        }
+
+        if !in_nuget_section {
+            continue;
+        }
+
+        // Resolved packages are indented by four spaces; deeper lines are dependency ranges
+        if let Some(captures) = PAKET_PACKAGE_LINE.captures(line) {
+            let id = &captures[1];
+            let version = &captures[2];
+
+            if !packages.iter().any(|p| p.name == id && p.version == version) {
+                packages.push(new_nuget_package(id, version));
+            }
+        }
+    }
+
+    packages
+}
+
+/// Create an unresolved NuGet package entry
+fn new_nuget_package(id: &str, version: &str) -> Package {
+    let mut package = Package::new(
+        id.to_string(),
+        version.to_string(),
+        format!("nuget:{}/{}", id, version),
+        None
+    );
+
+    package.registry = "nuget".to_string();
+    package.display_name = format!("{}@{}", id, version);
+    package.url = determine_package_url(id, "");
+
+    package
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csproj_resolves_without_nuget_license() {
+        let dir = tempfile::tempdir().unwrap();
+        let csproj_path = dir.path().join("App.csproj");
+        std::fs::write(
+            &csproj_path,
+            r#"<Project Sdk="Microsoft.NET.Sdk">
+  <ItemGroup>
+    <PackageReference Include="Newtonsoft.Json" Version="13.0.1" />
+    <PackageReference Include="Serilog">
+      <Version>2.12.0</Version>
+    </PackageReference>
+    <PackageReference Include="Pinned.Package" Version="[1.2.3]" />
+  </ItemGroup>
+</Project>"#
+        ).unwrap();
+
+        // Without --use-nuget-license the external tool is never consulted
+        let packages = parse_csproj(&csproj_path, false).unwrap();
+        let ids: Vec<(&str, &str)> = packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.as_str()))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![("Newtonsoft.Json", "13.0.1"), ("Serilog", "2.12.0"), ("Pinned.Package", "1.2.3")]
+        );
+        assert!(packages.iter().all(|p| p.registry == "nuget" && !p.processed));
+    }
+
+    #[test]
+    fn test_parse_packages_config() {
+        let content = r#"<?xml version="1.0" encoding="utf-8"?>
+<packages>
+  <package id="Newtonsoft.Json" version="13.0.1" targetFramework="net472" />
+  <package version='4.5.0' id='System.Memory' targetFramework="net472"/>
+  <package id="MissingVersion" />
+</packages>"#;
+
+        let packages = parse_packages_config(content);
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "Newtonsoft.Json");
+        assert_eq!(packages[0].version, "13.0.1");
+        assert_eq!(packages[0].registry, "nuget");
+        assert!(!packages[0].processed);
+        assert_eq!(packages[1].name, "System.Memory");
+        assert_eq!(packages[1].version, "4.5.0");
+    }
+
+    #[test]
+    fn test_parse_paket_lock() {
+        let content = "\
+NUGET
+  remote: https://api.nuget.org/v3/index.json
+    FSharp.Core (6.0.1)
+    Newtonsoft.Json (13.0.1) - restriction: >= netstandard2.0
+      System.Runtime (>= 4.3)
+GITHUB
+  remote: fsharp/FAKE
+    src/app/FakeLib/Globbing/Globbing.fs (0341a2e614eb2a7f34607cec914eb0ed83ce9add)
+GROUP Build
+NUGET
+  remote: https://api.nuget.org/v3/index.json
+    FSharp.Core (6.0.1)
+    FAKE (5.20.4)
+";
+
+        let packages = parse_paket_lock(content);
+        let ids: Vec<(&str, &str)> = packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.as_str()))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![("FSharp.Core", "6.0.1"), ("Newtonsoft.Json", "13.0.1"), ("FAKE", "5.20.4")]
+        );
+    }
+}
@@ -0,0 +1,346 @@
+use serde_yaml::Value;
+use std::collections::{ HashMap, HashSet };
+use crate::integrity;
+use crate::package::Package;
+use crate::parsers::yarn_parser;
+use crate::utils;
+
+pub const PNPM_LOCK_FILE: &str = "pnpm-lock.yaml";
+
+// Dependency kinds listed per importer (workspace project)
+const DEPENDENCY_FIELDS: [&str; 3] = ["dependencies", "devDependencies", "optionalDependencies"];
+
+/// Parse a pnpm-lock.yaml file (lockfile versions 5 to 9) into a vector of packages.
+/// `catalog:` references are resolved from the lockfile's catalogs and its overrides are
+/// applied, so each package is reported at the version pnpm installs.
+pub fn parse_pnpm_lock(content: &str) -> Result<Vec<Package>, String> {
+    let lockfile: Value = serde_yaml
+        ::from_str(content)
+        .map_err(|e| format!("Error parsing {}: {}", PNPM_LOCK_FILE, e))?;
+    let catalogs = Catalogs::from_lockfile(&lockfile);
+    let overrides = Overrides::from_lockfile(&lockfile, &catalogs);
+
+    let mut packages = Vec::new();
+    let mut seen = HashSet::new();
+
+    // Every installed package has an entry keyed by name and version
+    for (key, entry) in lockfile.get("packages").and_then(Value::as_mapping).into_iter().flatten() {
+        let Some((name, key_version)) = key.as_str().and_then(parse_package_key) else {
+            continue;
+        };
+        // Tarball and git dependencies are keyed by their URL and carry the version separately
+        let locked_version = entry.get("version").and_then(Value::as_str).map(clean_version).unwrap_or(key_version);
+        let Some(version) = overrides.apply(&name, &locked_version) else {
+            continue;
+        };
+        if is_local(&version) || !seen.insert((name.clone(), version.clone())) {
+            continue;
+        }
+        // An override to another version makes the entry's resolution and integrity stale
+        let entry = (version == locked_version).then_some(entry);
+        packages.push(package_from_entry(name, version, entry));
+    }
+
+    // Workspace lockfiles list each project under importers; older single-project ones keep
+    // the root project's dependencies at the top level
+    let importers: Vec<&Value> = match lockfile.get("importers").and_then(Value::as_mapping) {
+        Some(importers) => importers.values().collect(),
+        None => vec![&lockfile],
+    };
+    // Direct dependencies are normally in the packages too; those that aren't are added
+    for importer in importers {
+        for field in DEPENDENCY_FIELDS {
+            for (name, dependency) in importer.get(field).and_then(Value::as_mapping).into_iter().flatten() {
+                let Some(name) = name.as_str() else {
+                    continue;
+                };
+                let Some((name, locked_version)) = catalogs.locked_version(name, dependency) else {
+                    continue;
+                };
+                let Some(version) = overrides.apply(&name, &locked_version) else {
+                    continue;
+                };
+                if !is_local(&version) && seen.insert((name.clone(), version.clone())) {
+                    packages.push(package_from_entry(name, version, None));
+                }
+            }
+        }
+    }
+
+    // Generate fallback checksums for packages that don't have one
+    for package in &mut packages {
+        if package.checksum.is_none() {
+            let fallback = utils::generate_fallback_checksum(package);
+            package.checksum = Some(fallback);
+        }
+    }
+
+    Ok(packages)
+}
+
+/// A package from its entry under `packages`, which records where it was downloaded from
+/// and its integrity
+fn package_from_entry(name: String, version: String, entry: Option<&Value>) -> Package {
+    let resolution = entry.map(|entry| &entry["resolution"]);
+    let tarball = resolution.and_then(|resolution| resolution["tarball"].as_str());
+    let git_commit = resolution.and_then(|resolution| {
+        Some(format!("{}#{}", resolution["repo"].as_str()?, resolution["commit"].as_str()?))
+    });
+    let resolution_url = tarball
+        .map(str::to_string)
+        .or(git_commit)
+        .unwrap_or_else(|| crate::github_api::npm_tarball_url(&name, &version));
+    let checksum = resolution.and_then(|resolution| resolution["integrity"].as_str()).map(str::to_string);
+
+    let mut package = Package::new(name.clone(), version, resolution_url, checksum);
+    package.integrity = package.checksum.as_deref().map(integrity::parse_sri).unwrap_or_default();
+    package.url = yarn_parser::determine_package_url(&name, &package.resolution);
+    package
+}
+
+/// Name and version from a `packages` key: `name@1.0.0` (v9), `/name@1.0.0` (v6) or
+/// `/name/1.0.0` (v5), each with its peer dependencies possibly appended
+fn parse_package_key(key: &str) -> Option<(String, String)> {
+    let key = key.trim_start_matches('/');
+    // Names can't contain `@` or `/` past the scope, so the first one ends the name
+    let scope_end = if key.starts_with('@') { key.find('/')? + 1 } else { 0 };
+    let name_end = scope_end + key[scope_end..].find(['@', '/'])?;
+    let (name, version) = (&key[..name_end], &key[name_end + 1..]);
+    if name_end == scope_end || version.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), clean_version(version)))
+}
+
+/// The version without the peer dependencies pnpm appends: `1.0.0(react@18.2.0)` since
+/// lockfile v6, `1.0.0_react@18.2.0` before
+fn clean_version(version: &str) -> String {
+    let version = version.split('(').next().unwrap_or(version);
+    if version.contains(':') {
+        // URLs and protocols can contain underscores
+        version.to_string()
+    } else {
+        version.split('_').next().unwrap_or(version).to_string()
+    }
+}
+
+/// Workspace packages and local directories aren't downloaded from a registry
+fn is_local(version: &str) -> bool {
+    ["link:", "file:", "workspace:"].iter().any(|protocol| version.starts_with(protocol))
+}
+
+/// Versions of the lockfile's `catalogs`, by catalog and package name
+struct Catalogs(HashMap<(String, String), String>);
+
+impl Catalogs {
+    fn from_lockfile(lockfile: &Value) -> Self {
+        let mut versions = HashMap::new();
+        for (catalog, entries) in lockfile.get("catalogs").and_then(Value::as_mapping).into_iter().flatten() {
+            for (name, entry) in entries.as_mapping().into_iter().flatten() {
+                let version = entry.get("version").or(Some(entry)).and_then(Value::as_str);
+                if let (Some(catalog), Some(name), Some(version)) = (catalog.as_str(), name.as_str(), version) {
+                    versions.insert((catalog.to_string(), name.to_string()), clean_version(version));
+                }
+            }
+        }
+        Catalogs(versions)
+    }
+
+    /// The version a `catalog:` (default catalog) or `catalog:<name>` reference stands for
+    fn version(&self, package_name: &str, reference: &str) -> Option<String> {
+        let catalog = reference.strip_prefix("catalog:")?;
+        let catalog = if catalog.is_empty() { "default" } else { catalog };
+        self.0.get(&(catalog.to_string(), package_name.to_string())).cloned()
+    }
+
+    /// Name and version an importer's dependency is locked at. The dependency is a version
+    /// (v5) or a specifier and version; aliases lock `name@version` of the aliased package.
+    fn locked_version(&self, name: &str, dependency: &Value) -> Option<(String, String)> {
+        let version = dependency.as_str().or_else(|| dependency.get("version").and_then(Value::as_str));
+        let specifier = dependency.get("specifier").and_then(Value::as_str);
+        let version = match version {
+            Some(version) if !version.starts_with("catalog:") => clean_version(version),
+            // Not locked to a version yet: the catalog the specifier refers to has it
+            _ => self.version(name, version.or(specifier)?)?,
+        };
+        if !version.contains(':') && version.get(1..).is_some_and(|rest| rest.contains('@')) {
+            return parse_package_key(&version);
+        }
+        Some((name.to_string(), version))
+    }
+}
+
+/// An `overrides` entry: every version of the package, or those in `range`, is replaced
+/// with `version`, or removed when it is `-`
+struct Override {
+    name: String,
+    range: Option<String>,
+    version: Option<String>,
+}
+
+struct Overrides(Vec<Override>);
+
+impl Overrides {
+    /// The lockfile's overrides (from pnpm.overrides or pnpm-workspace.yaml) that pin a
+    /// version. Overrides scoped to a parent (`parent>name`) need the dependency tree, and
+    /// ranges or `$` references are already resolved in the lockfile, so those are left out.
+    fn from_lockfile(lockfile: &Value, catalogs: &Catalogs) -> Self {
+        let mut overrides = Vec::new();
+        for (selector, value) in lockfile.get("overrides").and_then(Value::as_mapping).into_iter().flatten() {
+            let (Some(selector), Some(value)) = (selector.as_str(), value.as_str()) else {
+                continue;
+            };
+            if selector.contains('>') {
+                continue;
+            }
+            let (name, range) = match selector.get(1..).and_then(|rest| rest.find('@')) {
+                Some(at) => (&selector[..at + 1], Some(selector[at + 2..].to_string())),
+                None => (selector, None),
+            };
+            let version = if value == "-" {
+                None
+            } else if let Some(version) = catalogs.version(name, value) {
+                Some(version)
+            } else if semver::Version::parse(value).is_ok() {
+                Some(value.to_string())
+            } else {
+                continue;
+            };
+            overrides.push(Override { name: name.to_string(), range, version });
+        }
+        Overrides(overrides)
+    }
+
+    /// The version installed in place of `version`, or None when the package is removed
+    fn apply(&self, name: &str, version: &str) -> Option<String> {
+        let matching = self.0.iter().find(|entry| {
+            entry.name == name &&
+                entry.range.as_deref().is_none_or(|range| {
+                    crate::semver_resolver::max_satisfying(range, std::iter::once(version), true).is_some()
+                })
+        });
+        match matching {
+            Some(entry) => entry.version.clone(),
+            None => Some(version.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKSPACE_LOCK: &str =
+        r#"
+lockfileVersion: '9.0'
+
+catalogs:
+  default:
+    react:
+      specifier: ^18.2.0
+      version: 18.3.1
+  legacy:
+    react:
+      specifier: ^17.0.0
+      version: 17.0.2
+
+overrides:
+  lodash: 4.17.21
+  minimist@<1.2.6: 1.2.8
+  left-pad: '-'
+
+importers:
+  .:
+    dependencies:
+      react:
+        specifier: 'catalog:'
+        version: 18.3.1
+      lodash:
+        specifier: ^4.17.0
+        version: 4.17.20
+  packages/legacy:
+    dependencies:
+      react:
+        specifier: catalog:legacy
+        version: catalog:legacy
+      shared:
+        specifier: workspace:*
+        version: link:../shared
+      string-width-cjs:
+        specifier: npm:string-width@^4.2.0
+        version: string-width@4.2.3
+
+packages:
+  react@18.3.1:
+    resolution: {integrity: sha512-wS+hAgJShR0KhEvPJArfuPVN1+Hz1t0Y6n5jLrGQbkb4urgPE/0Rve+1kMB1v/oWgHgm4WIcV+i7F2pTVj+2iQ==}
+  minimist@1.2.5:
+    resolution: {integrity: sha512-FM9nNUYrRBAELZQT3xeZQ7fmMOBg6nWNmJKTcgsJeaLstP/UODVpGsr5OhXhhXg6f+qtJ8uiZ+PUxkDWcgIXLw==}
+  left-pad@1.3.0:
+    resolution: {integrity: sha512-XI5MPzVNApjAyhQzphX8BkmKsKUxD4LdyK24iZeQEnM/JOl7EsYCQ5l4NtxcX/4glIpFNQ0A1gaYi+Y5oOMm7A==}
+  '@babel/runtime@7.24.0':
+    resolution: {tarball: https://npm.example.com/@babel/runtime/-/runtime-7.24.0.tgz}
+
+snapshots:
+  react@18.3.1: {}
+"#;
+
+    fn versions(packages: &[Package]) -> Vec<String> {
+        packages.iter().map(|p| format!("{}@{}", p.name, p.version)).collect()
+    }
+
+    #[test]
+    fn test_catalog_references_and_overrides_resolve_to_installed_versions() {
+        let packages = parse_pnpm_lock(WORKSPACE_LOCK).unwrap();
+        assert_eq!(
+            versions(&packages),
+            [
+                "react@18.3.1",
+                "minimist@1.2.8",
+                "@babel/runtime@7.24.0",
+                "lodash@4.17.21",
+                "react@17.0.2",
+                "string-width@4.2.3",
+            ]
+        );
+
+        // The locked entry keeps its integrity; an overridden one is fetched at the new version
+        assert!(!packages[0].integrity.is_empty());
+        assert!(packages[1].integrity.is_empty());
+        assert_eq!(packages[1].resolution, "https://registry.npmjs.org/minimist/-/minimist-1.2.8.tgz");
+        assert_eq!(packages[2].resolution, "https://npm.example.com/@babel/runtime/-/runtime-7.24.0.tgz");
+    }
+
+    #[test]
+    fn test_older_lockfile_versions() {
+        let v6 =
+            r#"
+lockfileVersion: '6.0'
+dependencies:
+  react-dom:
+    specifier: ^18.2.0
+    version: 18.2.0(react@18.2.0)
+packages:
+  /react-dom@18.2.0(react@18.2.0):
+    resolution: {integrity: sha512-6IMTriUmvsjHUjNtEDudZfuDQUoWXVxKHhlEGSk81n4YFS+r/Kl99wXiwlVXtPBtJenozv2P+hxDsw9eA7Xo6g==}
+  /@types/node@20.11.0:
+    resolution: {integrity: sha512-o9bjXmDNcF7GbM4CNQpmi+TutCgap/K3w1JyKgxAjqx41zp9qlIAVFi0IhCNsJcXolEqLWhbFbEeL0PvYm4pcQ==}
+    dev: true
+"#;
+        assert_eq!(versions(&parse_pnpm_lock(v6).unwrap()), ["react-dom@18.2.0", "@types/node@20.11.0"]);
+
+        let v5 =
+            r#"
+lockfileVersion: 5.4
+specifiers:
+  react-dom: ^18.2.0
+dependencies:
+  react-dom: 18.2.0_react@18.2.0
+packages:
+  /react-dom/18.2.0_react@18.2.0:
+    resolution: {integrity: sha512-6IMTriUmvsjHUjNtEDudZfuDQUoWXVxKHhlEGSk81n4YFS+r/Kl99wXiwlVXtPBtJenozv2P+hxDsw9eA7Xo6g==}
+  /@types/node/20.11.0:
+    resolution: {integrity: sha512-o9bjXmDNcF7GbM4CNQpmi+TutCgap/K3w1JyKgxAjqx41zp9qlIAVFi0IhCNsJcXolEqLWhbFbEeL0PvYm4pcQ==}
+"#;
+        assert_eq!(versions(&parse_pnpm_lock(v5).unwrap()), ["react-dom@18.2.0", "@types/node@20.11.0"]);
+    }
+}
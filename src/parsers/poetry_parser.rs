@@ -1,6 +1,6 @@
 use toml::Value;
-use crate::package::Package;
-use reqwest::blocking::Client;
+use crate::diagnostics::ResolutionStatus;
+use crate::package::{ is_unknown_license, LicenseSource, Package, UNKNOWN_LICENSE };
 use std::error::Error;
 
 /// Parse a poetry.lock file into a vector of packages
@@ -275,7 +275,7 @@ fn extract_version_constraint(constraint: &Value) -> String {
 
 /// Get package info from PyPI API
 pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<dyn Error>> {
-    let client = Client::new();
+    let client = crate::http_retry::metadata_client();
     let package_name = &package.name;
     let version = &package.version;
 
@@ -298,7 +298,7 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
         match crate::github_api::get_package_info(&github_package) {
             Ok(mut result) => {
                 // If GitHub API couldn't determine the license, try to find a license file
-                if result.license == "UNKNOWN" && result.url.contains("github.com") {
+                if result.is_unknown() && result.url.contains("github.com") {
                     // Extract repo URL and branch/ref
                     let repo_url = result.url.clone();
 
@@ -322,8 +322,10 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
                     {
                         // Try to download and detect license from the license file
                         match crate::npm_api::try_detect_license_from_url(&license_url) {
-                            Ok(Some(detected_license)) => {
+                            Ok(Some((detected_license, confidence))) => {
                                 result.license = detected_license.clone(); // Clone before moving
+                                result.license_source = Some(LicenseSource::LicenseText);
+                                result.confidence = confidence;
                                 result.license_url = Some(license_url.clone()); // Clone before moving
                                 result.debug_info = Some(
                                     format!("License detected from GitHub repository license file: {}", license_url)
@@ -351,8 +353,7 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
                 }
 
                 // Preserve original source information
-                if package.debug_info.is_some() {
-                    let orig_debug = package.debug_info.as_ref().unwrap();
+                if let Some(orig_debug) = &package.debug_info {
                     if let Some(ref mut debug_info) = result.debug_info {
                         *debug_info = format!("{}; {}", orig_debug, debug_info);
                     } else {
@@ -383,8 +384,10 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
             package.resolution
         );
 
-        result.license = "UNKNOWN".to_string();
+        result.license = UNKNOWN_LICENSE.to_string();
         result.debug_info = Some(error_msg);
+        result.lookup_failed = true;
+        result.resolution_status = ResolutionStatus::Error;
         result.processed = true;
         return Ok(result);
     }
@@ -406,8 +409,10 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
             eprintln!("INFO: PyPI API request failed for {}: {}", package_name, error_msg);
 
             let mut result = package.clone();
-            result.license = "UNKNOWN".to_string();
+            result.license = UNKNOWN_LICENSE.to_string();
             result.debug_info = Some(error_msg);
+            result.lookup_failed = true;
+            result.resolution_status = ResolutionStatus::NetworkError;
             result.processed = true;
             return Ok(result);
         }
@@ -423,7 +428,7 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
     }
 
     // Get the response text for debug output
-    let response_text = response.text()?;
+    let response_text = crate::http_retry::read_text(response)?;
 
     // Store the full response text if in debug mode
     let mut raw_response = None;
@@ -439,8 +444,10 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
             eprintln!("INFO: {}", error_msg);
 
             let mut result = package.clone();
-            result.license = "UNKNOWN".to_string();
+            result.license = UNKNOWN_LICENSE.to_string();
             result.debug_info = Some(error_msg);
+            result.lookup_failed = true;
+            result.resolution_status = ResolutionStatus::Error;
             result.processed = true;
             return Ok(result);
         }
@@ -459,24 +466,30 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
 
     if let Some(info) = pypi_data.get("info") {
         // First try to get license from the license field
-        let mut license = "UNKNOWN".to_string();
+        let mut license = UNKNOWN_LICENSE.to_string();
 
         if let Some(license_str) = info.get("license").and_then(|l| l.as_str()) {
             let license_str = license_str.trim();
-            if !license_str.is_empty() && license_str != "UNKNOWN" {
+            if !is_unknown_license(license_str) {
                 license = crate::license_detection::normalize_license_id(license_str);
             }
         }
 
         // If license is still unknown, try to extract from classifiers
-        if license == "UNKNOWN" {
+        if is_unknown_license(&license) {
             if let Some(classifiers) = info.get("classifiers").and_then(|c| c.as_array()) {
-                if let Some(detected_license) = extract_license_from_classifiers(classifiers) {
+                if
+                    let Some(detected_license) = extract_license_from_classifiers(
+                        classifiers.iter().filter_map(|c| c.as_str())
+                    )
+                {
                     license = detected_license;
                 }
             }
         }
 
+        result.license_source = (!is_unknown_license(&license)).then_some(LicenseSource::Registry);
+        result.confidence = crate::license_detection::declared_confidence(&license);
         result.license = license;
 
         // Collect additional PyPI metadata for verbose output
@@ -567,38 +580,15 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
             }
         }
 
-        // If license is unknown, try to find from GitHub repo
-        if result.license == "UNKNOWN" {
-            // Check if there's a GitHub URL
-            let mut github_url = None;
-
-            // Look in project URLs dictionary
-            if let Some(project_urls) = info.get("project_urls").and_then(|p| p.as_object()) {
-                for (name, url_value) in project_urls {
-                    if let Some(url) = url_value.as_str() {
-                        if url.contains("github.com") {
-                            github_url = Some(url.to_string());
-                            debug_info.push(
-                                format!("Found GitHub URL in project_urls[{}]: {}", name, url)
-                            );
-                            break;
-                        }
-                    }
-                }
-            }
-
-            // Also check home_page
-            if github_url.is_none() {
-                if let Some(home_page) = info.get("home_page").and_then(|h| h.as_str()) {
-                    if home_page.contains("github.com") {
-                        github_url = Some(home_page.to_string());
-                        debug_info.push(format!("Found GitHub URL in home_page: {}", home_page));
-                    }
-                }
-            }
+        // The GitHub repository named in the project URLs or home page
+        let github_url = github_repository_url(info);
+        result.repository = github_url.clone();
 
+        // If license is unknown, try to find from GitHub repo
+        if result.is_unknown() {
             // If we found a GitHub URL, use the GitHub API to get license info
             if let Some(github_url) = github_url {
+                debug_info.push(format!("Found GitHub URL: {}", github_url));
                 // Create a temporary package for GitHub API
                 let mut github_package = Package::new(
                     format!("github:{}", package_name), // Mark as GitHub package
@@ -612,8 +602,10 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
                 // Use GitHub API to get license info
                 match crate::github_api::get_package_info(&github_package) {
                     Ok(github_result) => {
-                        if github_result.license != "UNKNOWN" {
+                        if !github_result.is_unknown() {
                             result.license = github_result.license;
+                            result.license_source = github_result.license_source;
+                            result.confidence = github_result.confidence;
                             result.license_url = github_result.license_url;
                             debug_info.push("License found via GitHub API".to_string());
                         } else {
@@ -629,8 +621,10 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
 
                                 // Try to detect license from the file content
                                 match crate::npm_api::try_detect_license_from_url(&license_url) {
-                                    Ok(Some(detected_license)) => {
+                                    Ok(Some((detected_license, confidence))) => {
                                         result.license = detected_license.clone(); // Clone before moving
+                                        result.license_source = Some(LicenseSource::LicenseText);
+                                        result.confidence = confidence;
                                         debug_info.push(
                                             format!("Detected license from file: {}", detected_license)
                                         );
@@ -662,7 +656,7 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
             result.debug_info = Some(debug_info.join("; "));
         }
     } else {
-        result.license = "UNKNOWN".to_string();
+        result.license = UNKNOWN_LICENSE.to_string();
         result.debug_info = Some("No info object found in PyPI API response".to_string());
     }
 
@@ -670,8 +664,20 @@ pub fn get_package_info(package: &Package, debug: bool) -> Result<Package, Box<d
     Ok(result)
 }
 
+/// The first GitHub URL in a PyPI project's `project_urls`, or else its `home_page`
+fn github_repository_url(info: &serde_json::Value) -> Option<String> {
+    let project_urls = info.get("project_urls").and_then(|p| p.as_object());
+    project_urls
+        .into_iter()
+        .flat_map(|urls| urls.values())
+        .chain(info.get("home_page"))
+        .filter_map(|url| url.as_str())
+        .find(|url| url.contains("github.com"))
+        .map(str::to_string)
+}
+
 /// Extract license information from Python classifiers
-fn extract_license_from_classifiers(classifiers: &[serde_json::Value]) -> Option<String> {
+pub fn extract_license_from_classifiers<'a>(classifiers: impl IntoIterator<Item = &'a str>) -> Option<String> {
     // Common license patterns in Python classifiers
     let license_patterns = [
         ("License :: OSI Approved :: MIT License", "MIT"),
@@ -696,7 +702,7 @@ fn extract_license_from_classifiers(classifiers: &[serde_json::Value]) -> Option
         ("License :: OSI Approved :: BSD 2-Clause License", "BSD-2-Clause"),
     ];
 
-    for classifier in classifiers.iter().filter_map(|c| c.as_str()) {
+    for classifier in classifiers {
         for (pattern, license) in license_patterns.iter() {
             if classifier.contains(pattern) {
                 return Some(license.to_string());
@@ -733,7 +739,7 @@ fn extract_license_from_classifiers(classifiers: &[serde_json::Value]) -> Option
 
 /// Fallback to get the latest version info when specific version fails
 fn get_latest_package_info(package: &Package, debug: bool) -> Result<Package, Box<dyn Error>> {
-    let client = Client::new();
+    let client = crate::http_retry::metadata_client();
     let package_name = &package.name;
 
     // Create PyPI API URL without version to get the latest
@@ -747,8 +753,10 @@ fn get_latest_package_info(package: &Package, debug: bool) -> Result<Package, Bo
             eprintln!("INFO: PyPI API request failed for {}: {}", package_name, error_msg);
 
             let mut result = package.clone();
-            result.license = "UNKNOWN".to_string();
+            result.license = UNKNOWN_LICENSE.to_string();
             result.debug_info = Some(error_msg);
+            result.lookup_failed = true;
+            result.resolution_status = ResolutionStatus::NetworkError;
             result.processed = true;
             return Ok(result);
         }
@@ -760,14 +768,16 @@ fn get_latest_package_info(package: &Package, debug: bool) -> Result<Package, Bo
         eprintln!("INFO: {}", error_msg);
 
         let mut result = package.clone();
-        result.license = "UNKNOWN".to_string();
+        result.license = UNKNOWN_LICENSE.to_string();
         result.debug_info = Some(error_msg);
+        result.lookup_failed = true;
+        result.resolution_status = ResolutionStatus::from_http_status(response.status());
         result.processed = true;
         return Ok(result);
     }
 
     // Get the response text for debug output
-    let response_text = response.text()?;
+    let response_text = crate::http_retry::read_text(response)?;
 
     // Store the full response text if in debug mode
     let mut raw_response = None;
@@ -781,8 +791,10 @@ fn get_latest_package_info(package: &Package, debug: bool) -> Result<Package, Bo
         Err(e) => {
             let error_msg = format!("Failed to parse JSON from PyPI API: {}", e);
             let mut result = package.clone();
-            result.license = "UNKNOWN".to_string();
+            result.license = UNKNOWN_LICENSE.to_string();
             result.debug_info = Some(error_msg);
+            result.lookup_failed = true;
+            result.resolution_status = ResolutionStatus::Error;
             result.processed = true;
             return Ok(result);
         }
@@ -796,24 +808,30 @@ fn get_latest_package_info(package: &Package, debug: bool) -> Result<Package, Bo
 
     if let Some(info) = pypi_data.get("info") {
         // First try to get license from the license field
-        let mut license = "UNKNOWN".to_string();
+        let mut license = UNKNOWN_LICENSE.to_string();
 
         if let Some(license_str) = info.get("license").and_then(|l| l.as_str()) {
             let license_str = license_str.trim();
-            if !license_str.is_empty() && license_str != "UNKNOWN" {
+            if !is_unknown_license(license_str) {
                 license = crate::license_detection::normalize_license_id(license_str);
             }
         }
 
         // If license is still unknown, try to extract from classifiers
-        if license == "UNKNOWN" {
+        if is_unknown_license(&license) {
             if let Some(classifiers) = info.get("classifiers").and_then(|c| c.as_array()) {
-                if let Some(detected_license) = extract_license_from_classifiers(classifiers) {
+                if
+                    let Some(detected_license) = extract_license_from_classifiers(
+                        classifiers.iter().filter_map(|c| c.as_str())
+                    )
+                {
                     license = detected_license;
                 }
             }
         }
 
+        result.license_source = (!is_unknown_license(&license)).then_some(LicenseSource::Registry);
+        result.confidence = crate::license_detection::declared_confidence(&license);
         result.license = license;
 
         if let Some(project_url) = info.get("project_url").and_then(|u| u.as_str()) {
@@ -831,7 +849,7 @@ fn get_latest_package_info(package: &Package, debug: bool) -> Result<Package, Bo
             )
         );
     } else {
-        result.license = "UNKNOWN".to_string();
+        result.license = UNKNOWN_LICENSE.to_string();
         result.debug_info = Some(
             "No info object found in PyPI API response for latest version".to_string()
         );
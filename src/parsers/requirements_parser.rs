@@ -0,0 +1,236 @@
+//! pip requirements files: `name==version` pins with their `--hash=` annotations, unpinned
+//! requirements, `-r` includes, and editable or VCS installs. Packages are looked up on PyPI;
+//! installs from a GitHub repository are looked up on GitHub.
+
+use std::collections::HashSet;
+use std::path::{ Path, PathBuf };
+
+use crate::package::Package;
+
+pub const REQUIREMENTS_FILE: &str = "requirements.txt";
+
+/// Ref of a VCS install that doesn't name one
+const DEFAULT_VCS_REF: &str = "HEAD";
+
+/// Parse a requirements file and the files it includes with `-r`
+pub fn parse_requirements(path: &Path) -> Result<Vec<Package>, String> {
+    let mut packages = Vec::new();
+    let mut visited = HashSet::new();
+    collect_requirements(path, &mut packages, &mut visited)?;
+    Ok(packages)
+}
+
+fn collect_requirements(
+    path: &Path,
+    packages: &mut Vec<Package>,
+    visited: &mut HashSet<PathBuf>
+) -> Result<(), String> {
+    // Files may include each other
+    let canonical = path.canonicalize().map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if !visited.insert(canonical) {
+        return Ok(());
+    }
+
+    let content = crate::lockfile_parser::read_lockfile(path)?;
+    for line in logical_lines(&content) {
+        if let Some(included) = include_target(&line) {
+            let included_path = path.parent().unwrap_or(Path::new(".")).join(included);
+            collect_requirements(&included_path, packages, visited)?;
+            continue;
+        }
+
+        let Some(package) = parse_requirement(&line) else {
+            continue;
+        };
+        if !packages.iter().any(|p| p.name == package.name && p.version == package.version) {
+            packages.push(package);
+        }
+    }
+    Ok(())
+}
+
+/// Lines with backslash continuations joined and comments removed
+fn logical_lines(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for line in content.lines() {
+        // A comment starts a line or follows whitespace; `#egg=` inside a URL isn't one
+        let line = match line.find(" #").or_else(|| line.find("\t#")) {
+            Some(comment) => &line[..comment],
+            None if line.trim_start().starts_with('#') => "",
+            None => line,
+        };
+
+        match line.trim_end().strip_suffix('\\') {
+            Some(continued) => {
+                current.push_str(continued);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                let joined = current.trim().to_string();
+                if !joined.is_empty() {
+                    lines.push(joined);
+                }
+                current.clear();
+            }
+        }
+    }
+    if !current.trim().is_empty() {
+        lines.push(current.trim().to_string());
+    }
+    lines
+}
+
+/// The file named by `-r file` or `--requirement file`
+fn include_target(line: &str) -> Option<&str> {
+    let target = line
+        .strip_prefix("-r")
+        .or_else(|| line.strip_prefix("--requirement"))?
+        .trim_start_matches('=')
+        .trim();
+    (!target.is_empty()).then_some(target)
+}
+
+/// The package a requirement line installs; None for options, constraints and local paths
+fn parse_requirement(line: &str) -> Option<Package> {
+    let spec = match line.strip_prefix("-e").or_else(|| line.strip_prefix("--editable")) {
+        Some(editable) => editable.trim_start_matches('=').trim(),
+        // -i, --index-url, -c constraint files and other pip options
+        None if line.starts_with('-') => {
+            return None;
+        }
+        None => line,
+    };
+
+    // Per-requirement options like --hash, then environment markers
+    let spec = spec.split(" --").next().unwrap_or(spec);
+    let spec = spec.split(';').next().unwrap_or(spec).trim();
+
+    if let Some((_, url)) = spec.split_once(" @ ").filter(|(_, url)| url.contains("://")) {
+        return vcs_package(url.trim(), spec);
+    }
+    if spec.starts_with("git+") || spec.contains("://") {
+        return vcs_package(spec, spec);
+    }
+    // Editable installs of local directories are the project itself
+    if spec.starts_with('.') || spec.starts_with('/') {
+        return None;
+    }
+
+    let version_start = spec.find(['=', '<', '>', '~', '!']).unwrap_or(spec.len());
+    let name = spec[..version_start].split('[').next().unwrap_or("").trim();
+    if name.is_empty() {
+        return None;
+    }
+    let specifier = spec[version_start..].replace(' ', "");
+    let version = match specifier.strip_prefix("===").or_else(|| specifier.strip_prefix("==")) {
+        Some(pinned) if !pinned.contains([',', '*']) => pinned.to_string(),
+        // Unpinned requirements resolve to the latest release on PyPI
+        _ if specifier.is_empty() => "*".to_string(),
+        _ => specifier,
+    };
+
+    let mut package = Package::new(
+        name.to_string(),
+        version.clone(),
+        format!("https://pypi.org/project/{}/{}/", name, version),
+        None
+    );
+    package.registry = "pypi".to_string();
+    package.display_name = format!("{}@{}", name, version);
+    package.url = format!("https://pypi.org/project/{}/", name);
+    Some(package)
+}
+
+/// A `git+https://github.com/owner/repo.git@ref#egg=name` install, looked up on GitHub
+fn vcs_package(url: &str, spec: &str) -> Option<Package> {
+    let url = url.trim_start_matches("git+");
+    let location = url.split('#').next().unwrap_or(url);
+    let Some(repo_path) = location
+        .strip_prefix("https://github.com/")
+        .or_else(|| location.strip_prefix("ssh://git@github.com/"))
+    else {
+        eprintln!("INFO: Skipping {}: only GitHub repositories can be looked up", spec);
+        return None;
+    };
+
+    let (repo_path, reference) = match repo_path.split_once('@') {
+        Some((repo_path, reference)) => (repo_path, reference),
+        None => (repo_path, DEFAULT_VCS_REF),
+    };
+    let repo_path = repo_path.trim_end_matches('/').trim_end_matches(".git");
+
+    let mut package = Package::new(
+        format!("github:{}", repo_path),
+        reference.to_string(),
+        format!("github:{}#{}", repo_path, reference),
+        None
+    );
+    package.registry = "github".to_string();
+    package.display_name = format!("{}@{}", repo_path, reference);
+    package.url = format!("https://github.com/{}", repo_path);
+    Some(package)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_pinned_unpinned_and_git_requirements() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(REQUIREMENTS_FILE),
+            r#"# Generated by pip-compile --generate-hashes
+requests==2.31.0 \
+    --hash=sha256:58cd2187c01e70e6e26505bca751777aa9f2ee0b7f4300988b709f44e013003f \
+    --hash=sha256:942c5a758f98d790eaed1a29cb6eefc7ffb0d1cf7af05c3d2791656dbd6ad1e1
+urllib3[socks]==2.2.1 ; python_version >= "3.8"
+Flask>=2.0,<3  # not pinned yet
+click
+--index-url https://pypi.org/simple
+-r requirements-dev.txt
+-e git+https://github.com/pallets/werkzeug.git@3.0.1#egg=werkzeug
+itsdangerous @ git+https://github.com/pallets/itsdangerous.git
+-e .
+git+https://gitlab.com/group/private.git#egg=private
+"#
+        ).unwrap();
+        // Includes back to the including file are read once
+        fs::write(
+            dir.path().join("requirements-dev.txt"),
+            "pytest==8.0.0\n-r requirements.txt\nrequests==2.31.0\n"
+        ).unwrap();
+
+        let packages = parse_requirements(&dir.path().join(REQUIREMENTS_FILE)).unwrap();
+        let parsed: Vec<(&str, &str, &str)> = packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.as_str(), p.registry.as_str()))
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                ("requests", "2.31.0", "pypi"),
+                ("urllib3", "2.2.1", "pypi"),
+                ("Flask", ">=2.0,<3", "pypi"),
+                ("click", "*", "pypi"),
+                ("pytest", "8.0.0", "pypi"),
+                ("github:pallets/werkzeug", "3.0.1", "github"),
+                ("github:pallets/itsdangerous", "HEAD", "github"),
+            ]
+        );
+
+        // Git installs are routed to the GitHub resolver
+        let werkzeug = &packages[5];
+        assert_eq!(werkzeug.resolution, "github:pallets/werkzeug#3.0.1");
+        assert_eq!(werkzeug.url, "https://github.com/pallets/werkzeug");
+        let registry = crate::resolver::ResolverRegistry::standard(false, crate::npm_api::NpmOptions::default());
+        assert_eq!(registry.resolver_for(werkzeug).map(|resolver| resolver.name()), Some("github"));
+        assert_eq!(registry.resolver_for(&packages[0]).map(|resolver| resolver.name()), Some("pypi"));
+
+        let err = parse_requirements(&dir.path().join("missing.txt")).unwrap_err();
+        assert!(err.starts_with("Failed to read"), "{}", err);
+    }
+}
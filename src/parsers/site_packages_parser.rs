@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use crate::license_detection;
+use crate::package::{ is_unknown_license, LicenseSource, Package, UNKNOWN_LICENSE };
+use crate::parsers::poetry_parser;
+
+/// Directories pip installs into: site-packages, or dist-packages for Debian's system Python
+const SITE_PACKAGES_DIRS: &[&str] = &["site-packages", "dist-packages"];
+
+/// Directories of a root filesystem that hold no installed packages: kernel interfaces that are
+/// empty in an unpacked image and would be endless on a live system
+pub const PSEUDO_FS_DIRS: &[&str] = &["proc", "sys", "dev"];
+
+/// Whether a path is an installed site-packages directory
+pub fn is_site_packages_dir(path: &Path) -> bool {
+    path.is_dir() &&
+        path.file_name().is_some_and(|name| SITE_PACKAGES_DIRS.iter().any(|dir| name == *dir))
+}
+
+/// Every site-packages directory under a root filesystem. Symlinks aren't followed, since in
+/// an unpacked image their absolute targets point into the host system.
+pub fn find_site_packages(root: &Path) -> Vec<PathBuf> {
+    let mut result = Vec::new();
+    find_site_packages_recursive(root, root, &mut result);
+    result.sort();
+    result
+}
+
+fn find_site_packages_recursive(root: &Path, dir: &Path, result: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            continue;
+        }
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if dir == root && PSEUDO_FS_DIRS.contains(&name.as_str()) {
+            continue;
+        }
+        if SITE_PACKAGES_DIRS.contains(&name.as_str()) {
+            result.push(path);
+        } else if name != crate::parsers::node_modules_parser::NODE_MODULES_DIR {
+            find_site_packages_recursive(root, &path, result);
+        }
+    }
+}
+
+/// Read every installed distribution from a site-packages directory: the METADATA of wheels
+/// (`*.dist-info`) and the PKG-INFO of eggs (`*.egg-info`). Licenses come from the metadata,
+/// so nothing is looked up over the network.
+pub fn parse_site_packages(site_packages: &Path) -> Result<Vec<Package>, String> {
+    let entries = fs
+        ::read_dir(site_packages)
+        .map_err(|e| format!("Failed to read {}: {}", site_packages.display(), e))?;
+
+    let mut metadata_files: Vec<(PathBuf, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            let extension = path.extension()?.to_str()?;
+            let metadata = match extension {
+                "dist-info" => path.join("METADATA"),
+                // An egg-info is a directory, or the PKG-INFO file itself for distutils installs
+                "egg-info" if path.is_dir() => path.join("PKG-INFO"),
+                "egg-info" => path.clone(),
+                _ => {
+                    return None;
+                }
+            };
+            Some((path, metadata))
+        })
+        .collect();
+    metadata_files.sort();
+
+    let mut packages = Vec::new();
+    let mut seen = HashSet::new();
+    for (dist_dir, metadata_file) in metadata_files {
+        let Ok(metadata) = fs::read_to_string(&metadata_file) else {
+            continue;
+        };
+        if let Some(package) = read_installed_distribution(&dist_dir, &metadata) {
+            if seen.insert((package.name.clone(), package.version.clone())) {
+                packages.push(package);
+            }
+        }
+    }
+    Ok(packages)
+}
+
+fn read_installed_distribution(dist_dir: &Path, metadata: &str) -> Option<Package> {
+    let headers = metadata_headers(metadata);
+    let field = |key: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.trim())
+            .filter(|value| !value.is_empty())
+    };
+    let name = field("Name")?;
+    let version = field("Version")?;
+
+    let mut package = Package::new(
+        name.to_string(),
+        version.to_string(),
+        dist_dir.display().to_string(),
+        None
+    );
+    package.registry = "pypi".to_string();
+    package.url = format!("https://pypi.org/project/{}/", name);
+
+    // License-Expression (metadata 2.4) is SPDX; older License fields often hold the whole
+    // license text, in which case the trove classifiers name it instead
+    let declared = field("License-Expression")
+        .or_else(|| field("License").filter(|license| !license.contains('\n') && license.len() <= 100))
+        .filter(|license| !is_unknown_license(license))
+        .map(license_detection::normalize_license_id)
+        .or_else(|| {
+            let classifiers = headers
+                .iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case("Classifier"))
+                .map(|(_, value)| value.as_str());
+            poetry_parser::extract_license_from_classifiers(classifiers)
+        });
+
+    match declared {
+        Some(license) => {
+            package.confidence = license_detection::declared_confidence(&license);
+            package.license = license;
+            package.license_source = Some(LicenseSource::PackageManifest);
+        }
+        None => {
+            package.license = UNKNOWN_LICENSE.to_string();
+        }
+    }
+    package.processed = true;
+    Some(package)
+}
+
+/// The `Key: value` headers before the description; indented lines continue the previous value
+fn metadata_headers(metadata: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in metadata.lines() {
+        if line.trim().is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+        } else if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install(site_packages: &Path, dist_dir: &str, file: &str, metadata: &str) {
+        let dir = site_packages.join(dist_dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(file), metadata).unwrap();
+    }
+
+    #[test]
+    fn test_parse_installed_distributions() {
+        let root = tempfile::tempdir().unwrap();
+        let site_packages = root.path().join("usr/lib/python3.11/site-packages");
+        install(
+            &site_packages,
+            "requests-2.31.0.dist-info",
+            "METADATA",
+            "Metadata-Version: 2.1\nName: requests\nVersion: 2.31.0\nLicense: Apache 2.0\n\nRequests is HTTP\n"
+        );
+        install(
+            &site_packages,
+            "attrs-23.2.0.dist-info",
+            "METADATA",
+            "Metadata-Version: 2.4\nName: attrs\nVersion: 23.2.0\nLicense-Expression: MIT\n"
+        );
+        // The full license text in License; the classifier names it
+        install(
+            &site_packages,
+            "six-1.16.0.dist-info",
+            "METADATA",
+            concat!(
+                "Name: six\nVersion: 1.16.0\nLicense: Copyright (c) 2010-2020 Benjamin Peterson\n",
+                "        Permission is hereby granted, free of charge\n",
+                "Classifier: Programming Language :: Python :: 3\n",
+                "Classifier: License :: OSI Approved :: MIT License\n"
+            )
+        );
+        install(&site_packages, "mystery-0.1.egg-info", "PKG-INFO", "Name: mystery\nVersion: 0.1\nLicense: UNKNOWN\n");
+        fs::write(site_packages.join("chardet.py"), "").unwrap();
+
+        let packages = parse_site_packages(&site_packages).unwrap();
+        let installed: Vec<(&str, &str, &str)> = packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.version.as_str(), p.license.as_str()))
+            .collect();
+        assert_eq!(installed, [
+            ("attrs", "23.2.0", "MIT"),
+            ("mystery", "0.1", "UNKNOWN"),
+            ("requests", "2.31.0", "Apache-2.0"),
+            ("six", "1.16.0", "MIT"),
+        ]);
+        assert!(packages.iter().all(|p| p.processed && p.registry == "pypi"));
+
+        // Found anywhere in the root filesystem, but not through symlinks
+        assert!(is_site_packages_dir(&site_packages));
+        fs::create_dir_all(root.path().join("proc/1/site-packages")).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.path().join("usr"), root.path().join("usr-link")).unwrap();
+        assert_eq!(find_site_packages(root.path()), vec![site_packages]);
+    }
+}
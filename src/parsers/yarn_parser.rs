@@ -1,10 +1,13 @@
+use std::collections::HashMap;
 use yarn_lock_parser::parse_str;
+use crate::integrity;
 use crate::package::Package;
 use crate::utils;
 
 /// Parse a yarn.lock file into a vector of packages using yarn-lock-parser
 pub fn parse_yarn_lock(content: &str) -> Vec<Package> {
     let mut packages = Vec::new();
+    let hashes = entry_hashes(content);
 
     // Use the yarn-lock-parser crate to parse the yarn.lock content
     match parse_str(content) {
@@ -12,7 +15,7 @@ pub fn parse_yarn_lock(content: &str) -> Vec<Package> {
             // The parser returns a vector of entries directly
             for entry in entries {
                 // Extract the package name
-                let package_name = extract_package_name(&entry.name);
+                let package_name = extract_package_name(entry.name);
 
                 // Convert version from &str to String
                 let version = entry.version.to_string();
@@ -35,15 +38,17 @@ pub fn parse_yarn_lock(content: &str) -> Vec<Package> {
                     entry.name.to_string()
                 };
 
-                // Extract checksum from the entry's descriptors
-                let checksum = entry.descriptors
-                    .iter()
-                    .find(|(key, _)| *key == "checksum")
-                    .map(|(_, value)| value.to_string());
+                // Yarn v1 records an SRI integrity string; berry only has its own checksum
+                let hashes = hashes.get(&(package_name.clone(), version.clone()));
+                let checksum = hashes.and_then(|h| h.checksum.clone());
 
                 // Create package object directly using Package::new
                 let mut package = Package::new(package_name.clone(), version, resolution, checksum);
 
+                if let Some(sri) = hashes.and_then(|h| h.integrity.as_deref()) {
+                    package.integrity = integrity::parse_sri(sri);
+                }
+
                 // Set the package URL based on its source/resolution
                 package.url = determine_package_url(&package_name, &package.resolution);
 
@@ -58,7 +63,7 @@ pub fn parse_yarn_lock(content: &str) -> Vec<Package> {
     // Generate fallback checksums for packages that don't have one
     for package in &mut packages {
         if package.checksum.is_none() {
-            let fallback = utils::generate_fallback_checksum(&package);
+            let fallback = utils::generate_fallback_checksum(package);
             package.checksum = Some(fallback);
         }
     }
@@ -66,8 +71,67 @@ pub fn parse_yarn_lock(content: &str) -> Vec<Package> {
     packages
 }
 
+#[derive(Default)]
+struct EntryHashes {
+    integrity: Option<String>,
+    checksum: Option<String>,
+}
+
+/// The `integrity` (yarn v1) and `checksum` (berry) fields of each entry, keyed by package
+/// name and version; yarn-lock-parser doesn't keep either
+fn entry_hashes(content: &str) -> HashMap<(String, String), EntryHashes> {
+    let mut hashes = HashMap::new();
+    let mut name: Option<String> = None;
+    let mut version = String::new();
+    let mut entry = EntryHashes::default();
+
+    let mut finish = |name: Option<String>, version: &str, entry: EntryHashes| {
+        if let Some(name) = name {
+            hashes.insert((name, version.to_string()), entry);
+        }
+    };
+
+    for line in content.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            // An entry header, e.g. `"lodash@^4.17.20", lodash@^4.17.21:`
+            finish(name.take(), &version, std::mem::take(&mut entry));
+            version.clear();
+            if line != "__metadata:" {
+                name = Some(extract_package_name(&line.trim_end_matches(':').replace('"', "")));
+            }
+            continue;
+        }
+        // Only the entry's own fields; deeper lines list its dependencies
+        if line.starts_with("   ") {
+            continue;
+        }
+        let Some((key, value)) = line.trim().split_once([' ', ':']) else {
+            continue;
+        };
+        let value = value.trim_start_matches(':').trim().trim_matches('"').to_string();
+        match key {
+            "version" => {
+                version = value;
+            }
+            "integrity" => {
+                entry.integrity = Some(value);
+            }
+            "checksum" => {
+                entry.checksum = Some(value);
+            }
+            _ => {}
+        }
+    }
+    finish(name, &version, entry);
+
+    hashes
+}
+
 /// Determine the appropriate URL for a package based on its name and resolution
-fn determine_package_url(name: &str, resolution: &str) -> String {
+pub fn determine_package_url(name: &str, resolution: &str) -> String {
     if name.starts_with("github:") {
         // GitHub package referenced by shorthand
         format!("https://github.com/{}", name.trim_start_matches("github:"))
@@ -145,4 +209,20 @@ mod tests {
             "get-intrinsic"
         );
     }
+
+    #[test]
+    fn test_reads_integrity_and_checksum() {
+        let packages = parse_yarn_lock(include_str!("../../tests/fixtures/yarn/v1/yarn.lock"));
+        let lodash = packages.iter().find(|p| p.name == "lodash").unwrap();
+        assert_eq!(lodash.integrity.len(), 1);
+        assert_eq!(lodash.integrity[0].algorithm, integrity::HashAlgorithm::Sha512);
+        let babel = packages.iter().find(|p| p.name == "@babel/helper-plugin-utils").unwrap();
+        assert_eq!(babel.version, "7.24.0");
+        assert_eq!(babel.integrity.len(), 1);
+
+        let packages = parse_yarn_lock(include_str!("../../tests/fixtures/yarn/berry/yarn.lock"));
+        assert_eq!(packages.len(), 1);
+        assert!(packages[0].integrity.is_empty());
+        assert!(packages[0].checksum.as_deref().is_some_and(|c| c.starts_with("eb835a2e51d381e5")));
+    }
 }
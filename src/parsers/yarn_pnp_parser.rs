@@ -0,0 +1,266 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{ Path, PathBuf };
+use crate::package::Package;
+use crate::parsers::yarn_parser;
+use crate::utils;
+
+/// PnP data written next to .pnp.cjs when pnpEnableInlining is off
+pub const PNP_DATA_FILE: &str = ".pnp.data.json";
+/// PnP loader, which embeds the same data by default
+pub const PNP_LOADER_FILE: &str = ".pnp.cjs";
+
+/// The PnP file of a Yarn Plug'n'Play project, preferring the standalone data file
+pub fn find_pnp_file(dir: &Path) -> Option<PathBuf> {
+    [PNP_DATA_FILE, PNP_LOADER_FILE]
+        .iter()
+        .map(|file_name| dir.join(file_name))
+        .find(|path| path.is_file())
+}
+
+/// Parse a .pnp.data.json file into a vector of packages.
+/// Each package carries its locked dependencies, so the tree has the exact edges.
+pub fn parse_pnp_data(content: &str) -> Result<Vec<Package>, String> {
+    let data: Value = serde_json
+        ::from_str(content)
+        .map_err(|e| format!("Error parsing {}: {}", PNP_DATA_FILE, e))?;
+    let registry = data["packageRegistryData"]
+        .as_array()
+        .ok_or_else(|| format!("No packageRegistryData found in {}", PNP_DATA_FILE))?;
+
+    // First pass: one package per name and version, with the locators of its dependencies.
+    // Virtual instances of a package (one per set of peers) share its version.
+    let mut packages: Vec<Package> = Vec::new();
+    let mut locked_dependencies: Vec<Vec<(String, String)>> = Vec::new();
+    let mut index_of: HashMap<(String, String), usize> = HashMap::new();
+    for entry in registry {
+        // The top-level workspace is registered under a null name
+        let Some(name) = entry[0].as_str() else {
+            continue;
+        };
+        for instance in entry[1].as_array().into_iter().flatten() {
+            let Some(version) = instance[0].as_str().and_then(locator_version) else {
+                continue;
+            };
+            let dependencies: Vec<(String, String)> = instance[1]["packageDependencies"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(dependency_locator)
+                .filter(|(dep_name, dep_version)| !(dep_name == name && *dep_version == version))
+                .collect();
+
+            match index_of.get(&(name.to_string(), version.clone())) {
+                Some(&index) => {
+                    for dependency in dependencies {
+                        if !locked_dependencies[index].contains(&dependency) {
+                            locked_dependencies[index].push(dependency);
+                        }
+                    }
+                }
+                None => {
+                    let resolution = format!(
+                        "{}@{}",
+                        name,
+                        strip_virtual(instance[0].as_str().unwrap_or_default())
+                    );
+                    let mut package = Package::new(name.to_string(), version, resolution, None);
+                    package.url = yarn_parser::determine_package_url(name, &package.resolution);
+                    package.checksum = Some(utils::generate_fallback_checksum(&package));
+                    index_of.insert((name.to_string(), package.version.clone()), packages.len());
+                    packages.push(package);
+                    locked_dependencies.push(dependencies);
+                }
+            }
+        }
+    }
+
+    // Second pass: dependencies point at the parsed packages themselves so they hash the same
+    let dependencies: Vec<Vec<Package>> = locked_dependencies
+        .iter()
+        .map(|locators| {
+            locators
+                .iter()
+                .filter_map(|locator| index_of.get(locator).map(|&index| packages[index].clone()))
+                .collect()
+        })
+        .collect();
+    for (package, dependencies) in packages.iter_mut().zip(dependencies) {
+        package.dependencies = dependencies;
+    }
+
+    Ok(packages)
+}
+
+/// Parse the PnP data that .pnp.cjs embeds as a string literal
+pub fn parse_pnp_loader(content: &str) -> Result<Vec<Package>, String> {
+    let start = content
+        .find("RAW_RUNTIME_STATE")
+        .ok_or_else(|| format!("No inlined PnP data found in {}", PNP_LOADER_FILE))?;
+    let literal = content[start..]
+        .find('\'')
+        .map(|quote| &content[start + quote + 1..])
+        .ok_or_else(|| format!("No inlined PnP data found in {}", PNP_LOADER_FILE))?;
+
+    // Undo the escaping of the single-quoted JavaScript string, up to its closing quote
+    let mut data = String::new();
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                return parse_pnp_data(&data);
+            }
+            '\\' =>
+                match chars.next() {
+                    Some('\n') => {}
+                    Some('n') => data.push('\n'),
+                    Some(escaped) => data.push(escaped),
+                    None => {
+                        break;
+                    }
+                }
+            _ => data.push(c),
+        }
+    }
+    Err(format!("Unterminated PnP data in {}", PNP_LOADER_FILE))
+}
+
+/// The locator of a `packageDependencies` entry; aliases are stored as [name, reference]
+fn dependency_locator(dependency: &Value) -> Option<(String, String)> {
+    let name = dependency[0].as_str()?;
+    let (name, reference) = match &dependency[1] {
+        Value::String(reference) => (name, reference.as_str()),
+        Value::Array(alias) => (alias.first()?.as_str()?, alias.get(1)?.as_str()?),
+        // Missing peer dependencies have a null reference
+        _ => {
+            return None;
+        }
+    };
+    Some((name.to_string(), locator_version(reference)?))
+}
+
+/// Virtual references wrap the real one: "virtual:<hash>#npm:1.0.0"
+fn strip_virtual(reference: &str) -> &str {
+    match reference.strip_prefix("virtual:") {
+        Some(virtual_reference) => virtual_reference.split_once('#').map_or(reference, |(_, r)| r),
+        None => reference,
+    }
+}
+
+/// The version a reference locks, or None for workspaces and other local packages
+fn locator_version(reference: &str) -> Option<String> {
+    let reference = strip_virtual(reference);
+    if
+        ["workspace:", "portal:", "link:", "file:"]
+            .iter()
+            .any(|protocol| reference.starts_with(protocol))
+    {
+        return None;
+    }
+
+    if let Some(version) = reference.strip_prefix("npm:") {
+        return Some(version.to_string());
+    }
+
+    // Patched packages record the version they patch as a parameter
+    if reference.starts_with("patch:") {
+        return reference
+            .split("::")
+            .nth(1)?
+            .split('&')
+            .find_map(|param| param.strip_prefix("version="))
+            .map(str::to_string);
+    }
+
+    // Git and tarball URLs: the commit is the closest thing to a version
+    Some(
+        reference
+            .rsplit_once('#')
+            .map_or(reference, |(_, commit)| commit.trim_start_matches("commit="))
+            .to_string()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNP_DATA: &str = include_str!("../../tests/fixtures/pnp/.pnp.data.json");
+
+    fn dependency_ids(package: &Package) -> Vec<String> {
+        package.dependencies
+            .iter()
+            .map(|dep| format!("{}@{}", dep.name, dep.version))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_pnp_data() {
+        let packages = parse_pnp_data(PNP_DATA).unwrap();
+        let ids: Vec<String> = packages
+            .iter()
+            .map(|p| format!("{}@{}", p.name, p.version))
+            .collect();
+
+        // The workspaces are left out and the virtual instance merges into its package
+        assert_eq!(
+            ids,
+            vec![
+                "@types/react@18.2.0",
+                "chalk@4.1.2",
+                "debug@4.3.4",
+                "lodash@4.17.21",
+                "ms@2.1.2",
+                "react-dom@18.2.0",
+                "react@18.2.0",
+                "resolve@1.22.8",
+                "string-width@4.2.3",
+                "widget@abc1234"
+            ]
+        );
+
+        let package = |name: &str| packages.iter().find(|p| p.name == name).unwrap();
+        assert_eq!(package("debug").resolution, "debug@npm:4.3.4");
+        assert_eq!(dependency_ids(package("debug")), vec!["ms@2.1.2"]);
+        // Peers of the virtual instance are edges too, the missing optional peer is not
+        assert_eq!(dependency_ids(package("react-dom")), vec!["react@18.2.0"]);
+        assert!(package("ms").dependencies.is_empty());
+        assert!(package("widget").url.contains("github.com/acme/widget"));
+        assert!(packages.iter().all(|p| p.checksum.is_some()));
+    }
+
+    #[test]
+    fn test_parse_pnp_loader_inlined_data() {
+        // Yarn escapes the JSON into a single-quoted string with line continuations
+        let escaped = PNP_DATA.replace('\\', "\\\\").replace('\'', "\\'").replace('\n', "\\\n");
+        let loader = format!(
+            "#!/usr/bin/env node\n/* eslint-disable */\n\"use strict\";\n\nconst RAW_RUNTIME_STATE =\n'{}';\n\nfunction $$SETUP_STATE(hydrateRuntimeState, basePath) {{}}\n",
+            escaped
+        );
+
+        let from_loader = parse_pnp_loader(&loader).unwrap();
+        let from_data = parse_pnp_data(PNP_DATA).unwrap();
+        assert_eq!(from_loader.len(), from_data.len());
+        assert_eq!(dependency_ids(&from_loader[2]), dependency_ids(&from_data[2]));
+
+        assert!(parse_pnp_loader("module.exports = {};").is_err());
+    }
+
+    #[test]
+    fn test_locator_version() {
+        assert_eq!(locator_version("npm:1.2.3").as_deref(), Some("1.2.3"));
+        assert_eq!(locator_version("virtual:0f3e#npm:18.2.0").as_deref(), Some("18.2.0"));
+        assert_eq!(
+            locator_version(
+                "patch:resolve@npm%3A1.22.8#optional!builtin<compat/resolve>::version=1.22.8&hash=c3c19d"
+            ).as_deref(),
+            Some("1.22.8")
+        );
+        assert_eq!(
+            locator_version("https://github.com/acme/widget.git#commit=abc1234").as_deref(),
+            Some("abc1234")
+        );
+        assert_eq!(locator_version("workspace:packages/app"), None);
+        assert_eq!(locator_version("link:../shared"), None);
+    }
+}
@@ -0,0 +1,215 @@
+//! --quiet-success: "no news is good news" for CI. The scan's stdout and stderr are redirected
+//! into temporary files, and what was captured is only passed on once the scan finds something
+//! to report or fails; a clean scan exits without printing anything.
+
+use std::io::{ self, IsTerminal, Write };
+use std::sync::atomic::{ AtomicBool, Ordering };
+use std::sync::Mutex;
+
+static CAPTURE: Mutex<Option<Capture>> = Mutex::new(None);
+
+// Whether the captured output will be replayed to a terminal, so the report keeps its colors
+static REPLAYS_TO_TERMINAL: AtomicBool = AtomicBool::new(false);
+
+struct Capture {
+    #[cfg(unix)]
+    redirects: Vec<unix::Redirect>,
+}
+
+/// Start capturing stdout and stderr for the rest of the scan
+pub fn capture() {
+    REPLAYS_TO_TERMINAL.store(io::stdout().is_terminal(), Ordering::Relaxed);
+    match start_capture() {
+        Ok(capture) => {
+            *CAPTURE.lock().unwrap() = Some(capture);
+        }
+        Err(e) => {
+            eprintln!("Warning: --quiet-success can't capture the output ({}); printing it as usual", e);
+            return;
+        }
+    }
+
+    // A panic is a failure, so its message and what led up to it are shown
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(
+        Box::new(move |info| {
+            release(true);
+            default_hook(info);
+        })
+    );
+}
+
+/// Whether the captured output will be replayed to a terminal
+pub fn replays_to_terminal() -> bool {
+    REPLAYS_TO_TERMINAL.load(Ordering::Relaxed)
+}
+
+/// The scan has violations, unknown licenses or failed lookups: pass on what was captured and
+/// stop capturing
+pub fn mark_findings() {
+    release(true);
+}
+
+/// The scan is over: pass on the captured output if it failed, otherwise drop it
+pub fn finish(exit_code: i32) {
+    release(exit_code != 0);
+}
+
+/// Exit with a code once the captured output has been passed on or dropped
+pub fn exit(exit_code: i32) -> ! {
+    finish(exit_code);
+    std::process::exit(exit_code)
+}
+
+fn release(replay: bool) {
+    // A panic while the lock is held must not deadlock the panic hook
+    let Ok(mut capture) = CAPTURE.try_lock() else {
+        return;
+    };
+    if let Some(capture) = capture.take() {
+        let _ = io::stdout().flush();
+        if let Err(e) = stop_capture(capture, replay) {
+            eprintln!("Warning: --quiet-success failed to pass on the scan output: {}", e);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn start_capture() -> io::Result<Capture> {
+    io::stdout().flush()?;
+    let stdout = unix::Redirect::start(1)?;
+    match unix::Redirect::start(2) {
+        Ok(stderr) => Ok(Capture { redirects: vec![stdout, stderr] }),
+        Err(e) => {
+            let _ = stdout.stop(false);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(unix)]
+fn stop_capture(capture: Capture, replay: bool) -> io::Result<()> {
+    // Every descriptor is restored even if passing on one of them fails
+    let mut result = Ok(());
+    for redirect in capture.redirects {
+        let stopped = redirect.stop(replay);
+        if result.is_ok() {
+            result = stopped;
+        }
+    }
+    result
+}
+
+#[cfg(not(unix))]
+fn start_capture() -> io::Result<Capture> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "only supported on Unix"))
+}
+
+#[cfg(not(unix))]
+fn stop_capture(_capture: Capture, _replay: bool) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::fs::File;
+    use std::io::{ self, Seek, SeekFrom };
+    use std::mem::ManuallyDrop;
+    use std::os::fd::{ AsRawFd, FromRawFd, RawFd };
+    use std::os::raw::c_int;
+
+    extern "C" {
+        fn dup(fd: c_int) -> c_int;
+        fn dup2(src: c_int, dst: c_int) -> c_int;
+        fn close(fd: c_int) -> c_int;
+    }
+
+    /// A file descriptor pointed at a temporary file, with its original target kept aside
+    pub struct Redirect {
+        fd: RawFd,
+        saved: RawFd,
+        captured: File,
+    }
+
+    impl Redirect {
+        pub fn start(fd: RawFd) -> io::Result<Self> {
+            let captured = tempfile::tempfile()?;
+            // SAFETY: plain descriptor calls; `saved` is owned by the Redirect until it is stopped
+            let saved = unsafe { dup(fd) };
+            if saved < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if unsafe { dup2(captured.as_raw_fd(), fd) } < 0 {
+                let error = io::Error::last_os_error();
+                unsafe { close(saved) };
+                return Err(error);
+            }
+            Ok(Redirect { fd, saved, captured })
+        }
+
+        /// Point the descriptor back at its original target, then write what was captured to it
+        pub fn stop(mut self, replay: bool) -> io::Result<()> {
+            // SAFETY: `saved` was duplicated in start and is closed only here
+            let restored = unsafe { dup2(self.saved, self.fd) };
+            unsafe { close(self.saved) };
+            if restored < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if replay {
+                self.captured.seek(SeekFrom::Start(0))?;
+                // The descriptor stays open; it belongs to whoever opened it
+                let mut target = ManuallyDrop::new(unsafe { File::from_raw_fd(self.fd) });
+                io::copy(&mut self.captured, &mut *target)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::unix::Redirect;
+    use std::fs::File;
+    use std::io::{ Read, Seek, SeekFrom, Write };
+    use std::mem::ManuallyDrop;
+    use std::os::fd::{ AsRawFd, FromRawFd, RawFd };
+
+    // Stands in for stdout: a file whose descriptor the redirect takes over
+    fn write_to(fd: RawFd, text: &str) {
+        let mut file = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn contents(mut file: &File) -> String {
+        let mut text = String::new();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn test_clean_scan_output_is_dropped() {
+        let target = tempfile::tempfile().unwrap();
+        write_to(target.as_raw_fd(), "before\n");
+
+        let redirect = Redirect::start(target.as_raw_fd()).unwrap();
+        write_to(target.as_raw_fd(), "Total packages processed: 3\n");
+        redirect.stop(false).unwrap();
+
+        write_to(target.as_raw_fd(), "after\n");
+        assert_eq!(contents(&target), "before\nafter\n");
+    }
+
+    #[test]
+    fn test_findings_replay_the_captured_output() {
+        let target = tempfile::tempfile().unwrap();
+
+        let redirect = Redirect::start(target.as_raw_fd()).unwrap();
+        write_to(target.as_raw_fd(), "Total packages processed: 3\n");
+        redirect.stop(true).unwrap();
+
+        // Output after the findings goes straight through
+        write_to(target.as_raw_fd(), "Which license does left-pad use?\n");
+        assert_eq!(contents(&target), "Total packages processed: 3\nWhich license does left-pad use?\n");
+    }
+}
@@ -0,0 +1,128 @@
+//! Project paths that are git URLs, e.g. `https://github.com/owner/repo#branch`. The repository
+//! is shallow-cloned into a temporary directory that is removed when the clone is dropped.
+
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Whether a project path names a remote repository rather than a local directory
+pub fn is_git_url(path: &str) -> bool {
+    const SCHEMES: &[&str] = &["https://", "http://", "ssh://", "git://", "file://"];
+    SCHEMES.iter().any(|scheme| path.starts_with(scheme)) ||
+        // scp-like syntax: git@github.com:owner/repo.git
+        (path.starts_with("git@") && path.contains(':'))
+}
+
+/// Split `url#ref` into the URL and the branch or tag to check out
+pub fn split_ref(spec: &str) -> (&str, Option<&str>) {
+    match spec.rsplit_once('#') {
+        Some((url, reference)) if !reference.is_empty() => (url, Some(reference)),
+        Some((url, _)) => (url, None),
+        None => (spec, None),
+    }
+}
+
+/// A shallow clone of a remote repository, deleted on drop
+pub struct ClonedRepo {
+    pub spec: String, // The URL as given, with its #ref
+    dir: TempDir,
+}
+
+impl ClonedRepo {
+    /// Clone the default branch, or the branch or tag after `#`, with only its latest commit
+    pub fn clone(spec: &str) -> Result<ClonedRepo, String> {
+        let (url, reference) = split_ref(spec);
+        let dir = tempfile::tempdir().map_err(|e| format!("Failed to create a temporary directory: {}", e))?;
+
+        let mut command = Command::new("git");
+        command.args(["clone", "--quiet", "--depth", "1"]);
+        if let Some(reference) = reference {
+            command.args(["--branch", reference]);
+        }
+        // Fail instead of waiting for credentials nobody is there to type
+        let output = command
+            .arg(url)
+            .arg(dir.path())
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()
+            .map_err(|e| format!("Failed to run git: {}", e))?;
+
+        if !output.status.success() {
+            return Err(
+                format!("Failed to clone {}: {}", spec, String::from_utf8_lossy(&output.stderr).trim())
+            );
+        }
+
+        Ok(ClonedRepo { spec: spec.to_string(), dir })
+    }
+
+    /// The working tree of the clone
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[test]
+    fn test_git_urls() {
+        assert!(is_git_url("https://github.com/owner/repo"));
+        assert!(is_git_url("git@github.com:owner/repo.git"));
+        assert!(is_git_url("file:///srv/git/repo.git"));
+        assert!(!is_git_url("./projects/app"));
+        assert!(!is_git_url("/home/me/github.com/repo"));
+
+        assert_eq!(split_ref("https://github.com/owner/repo#v2"), ("https://github.com/owner/repo", Some("v2")));
+        assert_eq!(split_ref("https://github.com/owner/repo#"), ("https://github.com/owner/repo", None));
+        assert_eq!(split_ref("https://github.com/owner/repo"), ("https://github.com/owner/repo", None));
+    }
+
+    #[test]
+    fn test_clone_checks_out_the_requested_branch() {
+        // A bare repository whose feature branch adds a lockfile
+        let origin = tempfile::tempdir().unwrap();
+        let work = origin.path().join("work");
+        fs::create_dir_all(&work).unwrap();
+        git(&work, &["init", "--quiet", "--initial-branch", "main"]);
+        fs::write(work.join("README.md"), "app").unwrap();
+        git(&work, &["add", "."]);
+        git(&work, &["commit", "--quiet", "-m", "init"]);
+        git(&work, &["checkout", "--quiet", "-b", "feature"]);
+        fs::create_dir_all(work.join("web")).unwrap();
+        fs::write(work.join("web/package-lock.json"), "{}").unwrap();
+        git(&work, &["add", "."]);
+        git(&work, &["commit", "--quiet", "-m", "add lockfile"]);
+        git(&work, &["checkout", "--quiet", "main"]);
+        let bare = origin.path().join("app.git");
+        git(origin.path(), &["clone", "--quiet", "--bare", "work", "app.git"]);
+        let url = format!("file://{}", bare.display());
+
+        let clone = ClonedRepo::clone(&format!("{}#feature", url)).unwrap();
+        assert!(clone.path().join("web/package-lock.json").is_file());
+        let clone_dir = clone.path().to_path_buf();
+        drop(clone);
+        assert!(!clone_dir.exists());
+
+        let default_branch = ClonedRepo::clone(&url).unwrap();
+        assert!(default_branch.path().join("README.md").is_file());
+        assert!(!default_branch.path().join("web").exists());
+
+        let err = ClonedRepo::clone(&format!("{}#missing", url)).err().unwrap();
+        assert!(err.starts_with(&format!("Failed to clone {}#missing", url)), "{}", err);
+    }
+}
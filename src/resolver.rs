@@ -0,0 +1,413 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use crate::package::{ LicenseSource, Package };
+use crate::license_sources;
+use crate::yarn_cache::YarnCaches;
+use crate::{ archive_handler, github_api, gitlab_api, npm_api, nuget_api, parsers };
+
+/// Looks up the license and metadata of the packages of one ecosystem
+pub trait Resolver {
+    /// Registry name, as shown in estimates
+    fn name(&self) -> &'static str;
+
+    /// Whether this resolver looks the package up
+    fn can_handle(&self, package: &Package) -> bool;
+
+    /// Where this resolver's primary lookup finds licenses; anything less reliable is a fallback
+    fn primary_source(&self) -> LicenseSource {
+        LicenseSource::Registry
+    }
+
+    fn resolve(&self, package: &Package) -> Result<Package, Box<dyn Error>>;
+}
+
+struct NugetResolver;
+
+impl Resolver for NugetResolver {
+    fn name(&self) -> &'static str {
+        "nuget"
+    }
+
+    fn can_handle(&self, package: &Package) -> bool {
+        package.registry == "nuget"
+    }
+
+    fn resolve(&self, package: &Package) -> Result<Package, Box<dyn Error>> {
+        if cfg!(debug_assertions) {
+            println!("DEBUG: Processing nuget package: {}", package.name);
+        }
+        if package.processed {
+            // Packages from nuget-license are already processed during parsing
+            Ok(package.clone())
+        } else {
+            // Packages parsed natively (packages.config, paket.lock) use the NuGet API
+            nuget_api::get_package_info(package)
+        }
+    }
+}
+
+struct PypiResolver {
+    debug: bool,
+}
+
+impl Resolver for PypiResolver {
+    fn name(&self) -> &'static str {
+        "pypi"
+    }
+
+    fn can_handle(&self, package: &Package) -> bool {
+        package.registry == "pypi"
+    }
+
+    fn resolve(&self, package: &Package) -> Result<Package, Box<dyn Error>> {
+        if cfg!(debug_assertions) || self.debug {
+            println!("DEBUG: Processing pypi package: {}", package.name);
+        }
+        parsers::poetry_parser::get_package_info(package, self.debug)
+    }
+}
+
+struct GithubResolver;
+
+impl Resolver for GithubResolver {
+    fn name(&self) -> &'static str {
+        "github"
+    }
+
+    fn can_handle(&self, package: &Package) -> bool {
+        package.resolution.starts_with("https://github.com") || package.name.starts_with("github:")
+    }
+
+    fn primary_source(&self) -> LicenseSource {
+        LicenseSource::Github
+    }
+
+    fn resolve(&self, package: &Package) -> Result<Package, Box<dyn Error>> {
+        if cfg!(debug_assertions) {
+            println!("DEBUG: Processing github package: {}", package.name);
+        }
+        github_api::get_package_info(package)
+    }
+}
+
+struct GitlabResolver;
+
+impl Resolver for GitlabResolver {
+    fn name(&self) -> &'static str {
+        "gitlab"
+    }
+
+    fn can_handle(&self, package: &Package) -> bool {
+        gitlab_api::repository_location(package).is_some()
+    }
+
+    fn primary_source(&self) -> LicenseSource {
+        LicenseSource::Gitlab
+    }
+
+    fn resolve(&self, package: &Package) -> Result<Package, Box<dyn Error>> {
+        if cfg!(debug_assertions) {
+            println!("DEBUG: Processing gitlab package: {}", package.name);
+        }
+        gitlab_api::get_package_info(package)
+    }
+}
+
+/// Fallback for everything no other resolver claims (npm, archives, GitHub-hosted npm packages)
+struct NpmResolver {
+    options: npm_api::NpmOptions,
+}
+
+impl Resolver for NpmResolver {
+    fn name(&self) -> &'static str {
+        "npm"
+    }
+
+    fn can_handle(&self, _package: &Package) -> bool {
+        true
+    }
+
+    fn resolve(&self, package: &Package) -> Result<Package, Box<dyn Error>> {
+        if cfg!(debug_assertions) {
+            println!("DEBUG: Processing npm package: {}", package.name);
+        }
+        npm_api::get_package_info(package, &self.options)
+    }
+}
+
+/// Reads packages from the archives in Yarn's `.yarn/cache`, without network requests.
+/// Registered ahead of the others when a scanned yarn.lock has a cache next to it.
+pub struct YarnCacheResolver {
+    caches: Arc<YarnCaches>,
+}
+
+impl YarnCacheResolver {
+    pub fn new(caches: Arc<YarnCaches>) -> Self {
+        YarnCacheResolver { caches }
+    }
+}
+
+impl Resolver for YarnCacheResolver {
+    fn name(&self) -> &'static str {
+        "yarn-cache"
+    }
+
+    fn can_handle(&self, package: &Package) -> bool {
+        self.caches.archive_for(package).is_some()
+    }
+
+    // The archive is all there is to read
+    fn primary_source(&self) -> LicenseSource {
+        LicenseSource::LicenseFile
+    }
+
+    fn resolve(&self, package: &Package) -> Result<Package, Box<dyn Error>> {
+        let archive = self.caches
+            .archive_for(package)
+            .ok_or_else(|| format!("No cached archive for {}@{}", package.name, package.version))?;
+        if cfg!(debug_assertions) {
+            println!("DEBUG: Processing cached yarn package: {}", package.name);
+        }
+
+        // The lockfile names the package; the archive only supplies its license
+        let cached = archive_handler::package_from_archive_file(archive)?;
+        let mut resolved = package.clone();
+        resolved.registry = "npm".to_string();
+        resolved.display_name = format!("{}@{}", package.name, package.version);
+        if resolved.url.is_empty() {
+            resolved.url = format!("https://www.npmjs.com/package/{}", package.name);
+        }
+        resolved.license = cached.license;
+        resolved.license_source = cached.license_source;
+        resolved.confidence = cached.confidence;
+        resolved.processed = true;
+        Ok(resolved)
+    }
+}
+
+/// Resolvers in priority order; the first one that can handle a package looks it up
+pub struct ResolverRegistry {
+    resolvers: Vec<Box<dyn Resolver>>,
+    cross_check_licenses: bool,
+}
+
+impl ResolverRegistry {
+    /// The built-in resolvers, with npm as the catch-all
+    pub fn standard(debug: bool, npm: npm_api::NpmOptions) -> Self {
+        ResolverRegistry {
+            resolvers: vec![
+                Box::new(NugetResolver),
+                Box::new(PypiResolver { debug }),
+                Box::new(GithubResolver),
+                Box::new(GitlabResolver),
+                Box::new(NpmResolver { options: npm })
+            ],
+            cross_check_licenses: false,
+        }
+    }
+
+    /// Gather every resolved package's license from all its sources (--cross-check-licenses)
+    pub fn cross_check_licenses(&mut self, enabled: bool) {
+        self.cross_check_licenses = enabled;
+    }
+
+    /// Add a resolver ahead of the ones already registered
+    pub fn register(&mut self, resolver: Box<dyn Resolver>) {
+        self.resolvers.insert(0, resolver);
+    }
+
+    /// The resolver that looks the package up
+    pub fn resolver_for(&self, package: &Package) -> Option<&dyn Resolver> {
+        self.resolvers
+            .iter()
+            .find(|resolver| resolver.can_handle(package))
+            .map(|resolver| resolver.as_ref())
+    }
+
+    /// Look the package up, marking licenses that came from a fallback past the primary lookup.
+    /// With --cross-check-licenses the most confident of all its sources' licenses is reported.
+    pub fn resolve(&self, package: &Package) -> Result<Package, Box<dyn Error>> {
+        self.resolve_with(package, license_sources::gather_candidates)
+    }
+
+    fn resolve_with<G>(&self, package: &Package, gather_candidates: G) -> Result<Package, Box<dyn Error>>
+        where G: FnOnce(&Package) -> Vec<crate::package::LicenseCandidate>
+    {
+        let resolver = self
+            .resolver_for(package)
+            .ok_or_else(|| format!("No resolver handles package {}", package.name))?;
+        let mut resolved = resolver.resolve(package)?;
+        if self.cross_check_licenses && !resolved.lookup_failed {
+            let candidates = gather_candidates(&resolved);
+            license_sources::apply_candidates(&mut resolved, candidates);
+        }
+        resolved.license_fallback = resolved.license_source.is_some_and(|source| source > resolver.primary_source());
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, registry: &str, resolution: &str) -> Package {
+        let mut package = Package::new(name.to_string(), "1.0.0".to_string(), resolution.to_string(), None);
+        package.registry = registry.to_string();
+        package
+    }
+
+    struct CargoResolver;
+
+    impl Resolver for CargoResolver {
+        fn name(&self) -> &'static str {
+            "crates.io"
+        }
+
+        fn can_handle(&self, package: &Package) -> bool {
+            package.registry == "crates.io"
+        }
+
+        fn resolve(&self, package: &Package) -> Result<Package, Box<dyn Error>> {
+            let mut resolved = package.clone();
+            resolved.license = "MIT OR Apache-2.0".to_string();
+            resolved.license_source = Some(LicenseSource::Registry);
+            resolved.processed = true;
+            Ok(resolved)
+        }
+    }
+
+    #[test]
+    fn test_dispatch_in_priority_order() {
+        let mut registry = ResolverRegistry::standard(false, npm_api::NpmOptions::default());
+        let name_for = |registry: &ResolverRegistry, package: &Package| {
+            registry.resolver_for(package).map(|resolver| resolver.name())
+        };
+
+        assert_eq!(name_for(&registry, &package("Newtonsoft.Json", "nuget", "")), Some("nuget"));
+        assert_eq!(name_for(&registry, &package("requests", "pypi", "")), Some("pypi"));
+        assert_eq!(
+            name_for(&registry, &package("widget", "", "https://github.com/owner/widget.git")),
+            Some("github")
+        );
+        assert_eq!(
+            name_for(&registry, &package("ui-kit", "", "git+https://gitlab.com/acme/design/ui-kit.git#v2")),
+            Some("gitlab")
+        );
+        assert_eq!(name_for(&registry, &package("@gitlab/ui", "npm", "")), Some("npm"));
+        assert_eq!(name_for(&registry, &package("serde", "crates.io", "")), Some("npm"));
+
+        // A registered resolver claims its packages; the others keep their resolvers
+        registry.register(Box::new(CargoResolver));
+        assert_eq!(name_for(&registry, &package("serde", "crates.io", "")), Some("crates.io"));
+        assert_eq!(name_for(&registry, &package("left-pad", "npm", "")), Some("npm"));
+
+        let resolved = registry.resolve(&package("serde", "crates.io", "")).unwrap();
+        assert_eq!(resolved.license, "MIT OR Apache-2.0");
+        assert!(resolved.processed);
+    }
+
+    /// A registry without license metadata, so licenses come from the downloaded archive
+    struct ArchiveOnlyResolver;
+
+    impl Resolver for ArchiveOnlyResolver {
+        fn name(&self) -> &'static str {
+            "archive-only"
+        }
+
+        fn can_handle(&self, package: &Package) -> bool {
+            package.registry == "archive-only"
+        }
+
+        fn resolve(&self, package: &Package) -> Result<Package, Box<dyn Error>> {
+            let mut resolved = package.clone();
+            resolved.license = "MIT".to_string();
+            resolved.license_source = Some(LicenseSource::LicenseFile);
+            Ok(resolved)
+        }
+    }
+
+    #[test]
+    fn test_archive_fallback_is_marked_and_warned_about() {
+        let mut registry = ResolverRegistry::standard(false, npm_api::NpmOptions::default());
+        registry.register(Box::new(ArchiveOnlyResolver));
+
+        let resolved = registry.resolve(&package("left-pad", "archive-only", "")).unwrap();
+        assert!(resolved.license_fallback);
+        let warning = crate::license_sources::fallback_warning(&resolved).unwrap();
+        assert!(warning.contains("left-pad@1.0.0 (MIT)") && warning.contains("license-file"), "{}", warning);
+
+        // A license declared by the resolver's primary lookup is no fallback
+        registry.register(Box::new(CargoResolver));
+        let resolved = registry.resolve(&package("serde", "crates.io", "")).unwrap();
+        assert!(!resolved.license_fallback);
+        assert!(crate::license_sources::fallback_warning(&resolved).is_none());
+    }
+
+    #[test]
+    fn test_cross_check_covers_every_registry() {
+        use crate::package::LicenseCandidate;
+
+        let candidate = |source, license: &str| LicenseCandidate {
+            source,
+            license: license.to_string(),
+            confidence: 1.0,
+        };
+        let gather = |resolved: &Package| {
+            assert_eq!(resolved.license, "MIT OR Apache-2.0");
+            vec![
+                candidate(LicenseSource::Registry, &resolved.license),
+                candidate(LicenseSource::PackageManifest, "ISC"),
+                LicenseCandidate { confidence: 0.75, ..candidate(LicenseSource::LicenseFile, "BSD-3-Clause") }
+            ]
+        };
+        let mut registry = ResolverRegistry::standard(false, npm_api::NpmOptions::default());
+        registry.register(Box::new(CargoResolver));
+        let serde = package("serde", "crates.io", "");
+
+        let unchecked = registry.resolve_with(&serde, gather).unwrap();
+        assert!(unchecked.license_candidates.is_empty());
+
+        registry.cross_check_licenses(true);
+        let resolved = registry.resolve_with(&serde, gather).unwrap();
+        assert_eq!(resolved.license, "MIT OR Apache-2.0");
+        assert_eq!(resolved.license_source, Some(LicenseSource::Registry));
+        assert_eq!(resolved.license_candidates.len(), 3);
+        assert!(license_sources::sources_conflict(&resolved.license_candidates));
+    }
+
+    #[test]
+    fn test_yarn_cache_resolves_offline() {
+        use std::io::Write;
+
+        let project = tempfile::tempdir().unwrap();
+        let cache_dir = project.path().join(crate::yarn_cache::YARN_CACHE_DIR);
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        writer.start_file("node_modules/left-pad/package.json", zip::write::FileOptions::default()).unwrap();
+        writer.write_all(br#"{ "name": "left-pad", "version": "1.3.0", "license": "WTFPL" }"#).unwrap();
+        let archive = writer.finish().unwrap().into_inner();
+        std::fs::write(cache_dir.join("left-pad-npm-1.3.0-2b2d2e8e4a-a2d8e9e1b2.zip"), archive).unwrap();
+
+        let lockfile = project.path().join("yarn.lock");
+        let caches = Arc::new(YarnCaches::find(std::slice::from_ref(&lockfile)));
+        let mut registry = ResolverRegistry::standard(false, npm_api::NpmOptions::default());
+        registry.register(Box::new(YarnCacheResolver::new(caches)));
+
+        let mut left_pad = package("left-pad", "", "left-pad@npm:1.3.0");
+        left_pad.version = "1.3.0".to_string();
+        left_pad.source_lockfiles = vec![lockfile.display().to_string()];
+        assert_eq!(registry.resolver_for(&left_pad).map(|resolver| resolver.name()), Some("yarn-cache"));
+
+        let resolved = registry.resolve(&left_pad).unwrap();
+        assert_eq!(resolved.license, "WTFPL");
+        assert_eq!(resolved.license_source, Some(crate::package::LicenseSource::PackageManifest));
+        assert_eq!(resolved.source_lockfiles, left_pad.source_lockfiles);
+        assert!(resolved.processed);
+        assert!(!resolved.license_fallback);
+
+        // Versions missing from the cache fall through to the registry
+        left_pad.version = "1.2.0".to_string();
+        assert_eq!(registry.resolver_for(&left_pad).map(|resolver| resolver.name()), Some("npm"));
+    }
+}
@@ -0,0 +1,190 @@
+//! Packages that need a human to look at them, in one list ordered by severity.
+//!
+//! Brings together the failed lookups, UNKNOWN licenses, conflicting license sources, licenses
+//! that aren't SPDX expressions and low-confidence detections that the report otherwise lists apart.
+
+use serde::Serialize;
+
+use crate::license_checker::LicenseChecker;
+use crate::license_detection;
+use crate::license_sources;
+use crate::package::Package;
+
+/// Why a package needs review, from the most to the least severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReviewReason {
+    LookupFailed, // The registry lookup failed, so the license was never checked
+    UnknownLicense, // No license was found
+    ConflictingSources, // Sources disagree on the license (--cross-check-licenses)
+    NonSpdx, // The license is free text rather than an SPDX expression
+    LowConfidence, // The license was found with less than --min-confidence
+}
+
+impl ReviewReason {
+    pub fn severity(&self) -> &'static str {
+        match self {
+            ReviewReason::LookupFailed | ReviewReason::UnknownLicense | ReviewReason::ConflictingSources => "high",
+            ReviewReason::NonSpdx => "medium",
+            ReviewReason::LowConfidence => "low",
+        }
+    }
+}
+
+/// A package that needs review, with every reason that applies to it
+#[derive(Debug, Serialize)]
+pub struct ReviewItem<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+    pub license: &'a str,
+    pub severity: &'static str,
+    pub reasons: Vec<ReviewEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewEntry {
+    pub reason: ReviewReason,
+    pub detail: String,
+}
+
+/// The reasons a package needs review, most severe first; empty when it doesn't
+pub fn review_reasons(package: &Package, license_checker: &LicenseChecker) -> Vec<ReviewEntry> {
+    let mut reasons = Vec::new();
+
+    if package.lookup_failed {
+        reasons.push(ReviewEntry {
+            reason: ReviewReason::LookupFailed,
+            detail: package.debug_info.clone().unwrap_or_else(|| "unknown error".to_string()),
+        });
+    }
+
+    let is_unknown = package.is_unknown();
+    // A failed lookup already explains the missing license
+    if is_unknown && !package.lookup_failed {
+        reasons.push(ReviewEntry {
+            reason: ReviewReason::UnknownLicense,
+            detail: "no license found".to_string(),
+        });
+    }
+
+    if license_sources::sources_conflict(&package.license_candidates) {
+        reasons.push(ReviewEntry {
+            reason: ReviewReason::ConflictingSources,
+            detail: license_sources::describe(&package.license_candidates),
+        });
+    }
+
+    if !is_unknown && !license_detection::is_spdx_expression(&package.license) {
+        reasons.push(ReviewEntry {
+            reason: ReviewReason::NonSpdx,
+            detail: format!("\"{}\" is not an SPDX expression", package.license),
+        });
+    }
+
+    if license_checker.needs_review(package) {
+        reasons.push(ReviewEntry {
+            reason: ReviewReason::LowConfidence,
+            detail: format!(
+                "confidence {:.2}{}",
+                package.confidence,
+                package.license_source.map_or(String::new(), |source| format!(", from {}", source.as_str()))
+            ),
+        });
+    }
+
+    reasons
+}
+
+/// Every package that needs review, most severe first, then by name and version
+pub fn review_list<'a>(packages: &[&'a Package], license_checker: &LicenseChecker) -> Vec<ReviewItem<'a>> {
+    let mut items: Vec<ReviewItem> = packages
+        .iter()
+        .filter_map(|package| {
+            let reasons = review_reasons(package, license_checker);
+            let severity = reasons.first()?.reason.severity();
+            Some(ReviewItem {
+                name: &package.name,
+                version: &package.version,
+                license: &package.license,
+                severity,
+                reasons,
+            })
+        })
+        .collect();
+
+    // The sort is stable, so packages keep their name order within a reason
+    items.sort_by_key(|item| item.reasons[0].reason);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::{ LicenseCandidate, LicenseSource };
+
+    fn package(name: &str, license: &str) -> Package {
+        let mut package = Package::new(name.to_string(), "1.0.0".to_string(), String::new(), None);
+        package.license = license.to_string();
+        package.confidence = license_detection::declared_confidence(license);
+        package
+    }
+
+    #[test]
+    fn test_review_list_covers_each_category() {
+        let failed = Package::with_error(
+            "offline-pkg".to_string(),
+            "1.0.0".to_string(),
+            "npm",
+            String::new(),
+            crate::diagnostics::ResolutionStatus::NetworkError,
+            "connection refused"
+        );
+        let mut low_confidence = package("guessed", "MIT");
+        low_confidence.confidence = 0.5;
+        low_confidence.license_source = Some(LicenseSource::LicenseFile);
+        let mut conflicting = package("disputed", "MIT");
+        let candidate = |source, license: &str, confidence| LicenseCandidate {
+            source,
+            license: license.to_string(),
+            confidence,
+        };
+        conflicting.license_candidates = vec![
+            candidate(LicenseSource::Registry, "MIT", 1.0),
+            candidate(LicenseSource::PackageManifest, "ISC", 1.0),
+            candidate(LicenseSource::LicenseFile, "BSD-3-Clause", 0.75)
+        ];
+        let packages = [
+            package("clean", "MIT"),
+            low_confidence,
+            conflicting,
+            package("custom", "SEE LICENSE IN LICENSE.md"),
+            package("mystery", "UNKNOWN"),
+            failed,
+        ];
+        let packages: Vec<&Package> = packages.iter().collect();
+        let license_checker = LicenseChecker::new(Vec::new()).with_min_confidence(Some(0.8));
+
+        let items = review_list(&packages, &license_checker);
+        let summary: Vec<(&str, &str, ReviewReason)> = items
+            .iter()
+            .map(|item| (item.name, item.severity, item.reasons[0].reason))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("offline-pkg", "high", ReviewReason::LookupFailed),
+                ("mystery", "high", ReviewReason::UnknownLicense),
+                ("disputed", "high", ReviewReason::ConflictingSources),
+                ("custom", "medium", ReviewReason::NonSpdx),
+                ("guessed", "low", ReviewReason::LowConfidence)
+            ]
+        );
+
+        assert!(items[0].reasons[0].detail.contains("connection refused"));
+        // The failed lookup isn't reported a second time as an unknown license
+        assert_eq!(items[0].reasons.len(), 1);
+        assert_eq!(items[2].reasons[0].detail, "registry: MIT, package-manifest: ISC, license-file: BSD-3-Clause");
+        assert_eq!(items[3].reasons[0].detail, "\"SEE LICENSE IN LICENSE.md\" is not an SPDX expression");
+        assert_eq!(items[4].reasons[0].detail, "confidence 0.50, from license-file");
+    }
+}
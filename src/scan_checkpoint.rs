@@ -0,0 +1,212 @@
+//! Checkpoints for --resume: the results, sources and pending queue of a scan in progress,
+//! written periodically so an interrupted scan continues where it stopped instead of walking
+//! the dependency graph again.
+
+use serde::{ Deserialize, Serialize };
+use sha2::{ Digest, Sha256 };
+use std::collections::{ BTreeSet, HashMap, HashSet };
+use std::error::Error;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::time::{ Duration, SystemTime };
+
+use crate::package::Package;
+use crate::utils::generate_package_hash;
+use crate::version_overrides;
+
+/// How often a running scan writes its checkpoint
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The state of a scan in progress
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanCheckpoint {
+    pub results: Vec<(String, Package)>,
+    pub pending: Vec<Package>,
+    pub sources: HashMap<String, BTreeSet<String>>,
+    pub dependency_tree: HashMap<String, Vec<String>>,
+}
+
+impl ScanCheckpoint {
+    /// Capture a scan's state. The tree is taken before the results and the results before the
+    /// queue, so an edge is never kept without its result and a package dequeued in between is
+    /// still reached from its parent on resume. Failed lookups are left out to be retried.
+    pub fn capture(
+        dependency_tree: HashMap<String, Vec<String>>,
+        sources: HashMap<String, BTreeSet<String>>,
+        results: &[(String, Package)],
+        pending: Vec<Package>
+    ) -> Self {
+        let results = results
+            .iter()
+            .filter(|(_, package)| !package.lookup_failed)
+            .cloned()
+            .collect();
+        ScanCheckpoint { results, pending, sources, dependency_tree }
+    }
+
+    /// Hashes of the packages that don't need resolving again
+    pub fn processed(&self) -> HashSet<String> {
+        self.results
+            .iter()
+            .map(|(package_hash, _)| package_hash.clone())
+            .collect()
+    }
+
+    /// The packages to queue when resuming: the pending queue, followed by the unprocessed
+    /// dependencies of recorded results. The latter are the packages a worker had dequeued but
+    /// not finished when the scan stopped; they inherit their parent's lockfiles.
+    pub fn resume_queue(&self) -> Vec<Package> {
+        let processed = self.processed();
+        let mut queued: HashSet<String> = self.pending.iter().map(generate_package_hash).collect();
+        let mut queue = self.pending.clone();
+
+        for (package_hash, package) in &self.results {
+            for dep in &package.dependencies {
+                let dep_hash = generate_package_hash(dep);
+                if processed.contains(&dep_hash) || !queued.insert(dep_hash) {
+                    continue;
+                }
+                let mut dep = dep.clone();
+                if let Some(lockfiles) = self.sources.get(package_hash) {
+                    dep.source_lockfiles = lockfiles.iter().cloned().collect();
+                }
+                queue.push(dep);
+            }
+        }
+        queue
+    }
+}
+
+/// An installed node_modules or site-packages directory stands in for a lockfile: its entries
+/// and their modification times, which change when a package is added, removed or upgraded
+fn hash_listing(hasher: &mut Sha256, dir: &Path) -> std::io::Result<()> {
+    let mut entries: Vec<(String, Option<SystemTime>)> = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let modified = entry.metadata().and_then(|metadata| metadata.modified()).ok();
+        entries.push((entry.file_name().to_string_lossy().to_string(), modified));
+    }
+    entries.sort();
+    for (name, modified) in entries {
+        hasher.update(format!("{}|{:?}\n", name, modified).as_bytes());
+    }
+    Ok(())
+}
+
+/// Where the checkpoint of one set of lockfiles and scan settings is kept
+pub struct CheckpointStore {
+    path: PathBuf,
+}
+
+impl CheckpointStore {
+    /// The checkpoint for these lockfiles; editing any of them or changing the settings starts over
+    pub fn new(dir: &Path, lockfiles: &[PathBuf], settings: &str) -> std::io::Result<Self> {
+        let mut hasher = Sha256::new();
+        for lockfile in lockfiles {
+            hasher.update(lockfile.display().to_string().as_bytes());
+            if lockfile.is_dir() {
+                hash_listing(&mut hasher, lockfile)?;
+            } else {
+                hasher.update(fs::read(lockfile)?);
+            }
+            if let Some(manifest) = version_overrides::manifest_path(lockfile).and_then(|path| fs::read(path).ok()) {
+                hasher.update(manifest);
+            }
+        }
+        hasher.update(settings.as_bytes());
+        Ok(CheckpointStore { path: dir.join(format!("{:x}.json", hasher.finalize())) })
+    }
+
+    pub fn load(&self) -> Option<ScanCheckpoint> {
+        let content = fs::read_to_string(&self.path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Write through a temporary file, so a scan killed while saving keeps its last checkpoint
+    pub fn save(&self, checkpoint: &ScanCheckpoint) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string(checkpoint)?)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Drop the checkpoint of a scan that ran to completion
+    pub fn remove(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, deps: &[&str]) -> Package {
+        let mut package = Package::new(name.to_string(), "1.0.0".to_string(), String::new(), None);
+        package.license = "MIT".to_string();
+        package.dependencies = deps
+            .iter()
+            .map(|dep| Package::new(dep.to_string(), "1.0.0".to_string(), String::new(), None))
+            .collect();
+        package
+    }
+
+    #[test]
+    fn test_checkpoint_is_kept_per_lockfile_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoints = dir.path().join("checkpoints");
+        let lockfiles = [dir.path().join("yarn.lock")];
+        fs::write(&lockfiles[0], "app@1.0.0:\n").unwrap();
+        let store = CheckpointStore::new(&checkpoints, &lockfiles, "settings").unwrap();
+        assert!(store.load().is_none());
+        let checkpoint = ScanCheckpoint::capture(
+            HashMap::new(),
+            HashMap::new(),
+            &[("app".to_string(), package("app", &[]))],
+            Vec::new()
+        );
+        store.save(&checkpoint).unwrap();
+        assert_eq!(store.load().unwrap().results.len(), 1);
+
+        // Another checkpoint is kept for different content or settings, and a finished scan drops its own
+        assert!(CheckpointStore::new(&checkpoints, &lockfiles, "deep").unwrap().load().is_none());
+        fs::write(&lockfiles[0], "app@2.0.0:\n").unwrap();
+        assert!(CheckpointStore::new(&checkpoints, &lockfiles, "settings").unwrap().load().is_none());
+        store.remove();
+        assert!(store.load().is_none());
+    }
+
+    #[test]
+    fn test_installed_packages_are_keyed_on_their_listing() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoints = dir.path().join("checkpoints");
+        let node_modules = dir.path().join("node_modules");
+        fs::create_dir_all(node_modules.join("left-pad")).unwrap();
+        let lockfiles = [node_modules.clone()];
+
+        let store = CheckpointStore::new(&checkpoints, &lockfiles, "settings").unwrap();
+        store.save(&ScanCheckpoint::default()).unwrap();
+        assert!(CheckpointStore::new(&checkpoints, &lockfiles, "settings").unwrap().load().is_some());
+
+        fs::create_dir_all(node_modules.join("lodash")).unwrap();
+        assert!(CheckpointStore::new(&checkpoints, &lockfiles, "settings").unwrap().load().is_none());
+    }
+
+    #[test]
+    fn test_failed_lookups_are_retried_on_resume() {
+        let mut failed = package("left-pad", &[]);
+        failed.lookup_failed = true;
+        let results = vec![
+            ("app".to_string(), package("app", &["left-pad"])),
+            (generate_package_hash(&failed), failed)
+        ];
+
+        let checkpoint = ScanCheckpoint::capture(HashMap::new(), HashMap::new(), &results, Vec::new());
+        assert_eq!(checkpoint.results.len(), 1);
+        let queue = checkpoint.resume_queue();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].name, "left-pad");
+    }
+}
@@ -0,0 +1,162 @@
+use semver::{ Comparator, Op, Prerelease, Version, VersionReq };
+
+/// Pick the highest of `versions` that satisfies an npm-style version range.
+/// Supports `||` alternatives, hyphen ranges, x-ranges, and space-separated comparators.
+/// Pre-release versions only satisfy ranges that name a pre-release of the same version,
+/// unless `allow_prerelease` is set (npm's `includePrerelease`).
+pub fn max_satisfying<'a, I>(range: &str, versions: I, allow_prerelease: bool) -> Option<String>
+    where I: IntoIterator<Item = &'a str>
+{
+    let alternatives: Vec<VersionReq> = range
+        .split("||")
+        .filter_map(to_version_req)
+        .collect();
+
+    if alternatives.is_empty() {
+        return None;
+    }
+
+    versions
+        .into_iter()
+        .filter_map(|v| Version::parse(v).ok())
+        .filter(|v| alternatives.iter().any(|req| matches(req, v, allow_prerelease)))
+        .max()
+        .map(|v| v.to_string())
+}
+
+/// A canonical spelling of a version, so that equal versions compare equal as strings:
+/// missing minor/patch parts are zero ("1.2" is "1.2.0") and build metadata is dropped.
+/// None for what isn't a version (git refs, ranges).
+pub fn canonical_version(version: &str) -> Option<String> {
+    let version = version.trim().trim_start_matches('v');
+    let core_end = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, suffix) = version.split_at(core_end);
+
+    let parts = core.split('.').count();
+    let numeric = core.split('.').all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+    if parts > 3 || !numeric {
+        return None;
+    }
+    let padded = format!("{}{}{}", core, ".0".repeat(3 - parts), suffix);
+
+    let mut parsed = Version::parse(&padded).ok()?;
+    parsed.build = semver::BuildMetadata::EMPTY;
+    Some(parsed.to_string())
+}
+
+fn matches(req: &VersionReq, version: &Version, allow_prerelease: bool) -> bool {
+    if !allow_prerelease || version.pre.is_empty() {
+        return req.matches(version);
+    }
+
+    // The semver crate admits a pre-release when some comparator names a pre-release of the
+    // same major.minor.patch. `>=M.m.p-0` is such a comparator and holds for every pre-release
+    // of M.m.p, so adding it lifts the restriction without changing which versions are in range.
+    let mut req = req.clone();
+    req.comparators.push(Comparator {
+        op: Op::GreaterEq,
+        major: version.major,
+        minor: Some(version.minor),
+        patch: Some(version.patch),
+        pre: Prerelease::new("0").unwrap(),
+    });
+    req.matches(version)
+}
+
+/// Convert a single npm range (no `||`) into a semver requirement
+fn to_version_req(range: &str) -> Option<VersionReq> {
+    let range = range.trim();
+
+    if range.is_empty() || range == "*" || range.eq_ignore_ascii_case("x") {
+        return Some(VersionReq::STAR);
+    }
+
+    // Hyphen ranges: "1.2.3 - 2.3.4" means ">=1.2.3 <=2.3.4"
+    if let Some((low, high)) = range.split_once(" - ") {
+        return VersionReq::parse(&format!(">={}, <={}", low.trim(), high.trim())).ok();
+    }
+
+    // npm separates comparators with spaces and allows a space after the operator
+    let mut comparators: Vec<String> = Vec::new();
+    let mut pending_operator = String::new();
+    for token in range.split_whitespace() {
+        if token.chars().all(|c| "<>=~^".contains(c)) {
+            pending_operator.push_str(token);
+            continue;
+        }
+        let token = format!("{}{}", pending_operator, token);
+        pending_operator.clear();
+        comparators.push(npm_comparator(&token));
+    }
+
+    VersionReq::parse(&comparators.join(", ")).ok()
+}
+
+/// Translate one npm comparator into semver crate syntax
+fn npm_comparator(token: &str) -> String {
+    let version_start = token.find(|c: char| !"<>=~^".contains(c)).unwrap_or(token.len());
+    let (operator, version) = token.split_at(version_start);
+    let version = version.trim_start_matches('v');
+
+    let is_wildcard = version.split('.').any(|part| part == "x" || part == "X" || part == "*");
+
+    // A bare version is exact in npm but a caret requirement in the semver crate
+    if operator.is_empty() && !is_wildcard {
+        format!("={}", version)
+    } else {
+        format!("{}{}", operator, version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSIONS: &[&str] = &["1.0.0", "1.2.3", "1.4.0", "2.0.0-beta.1", "2.0.0", "2.1.0", "3.0.0"];
+
+    fn resolve(range: &str) -> Option<String> {
+        max_satisfying(range, VERSIONS.iter().copied(), false)
+    }
+
+    #[test]
+    fn test_canonical_version() {
+        assert_eq!(canonical_version("1.2").as_deref(), Some("1.2.0"));
+        assert_eq!(canonical_version("1").as_deref(), Some("1.0.0"));
+        assert_eq!(canonical_version("v1.2.3+build.5").as_deref(), Some("1.2.3"));
+        assert_eq!(canonical_version("1.0.0-beta").as_deref(), Some("1.0.0-beta"));
+        assert_eq!(canonical_version("1.2-rc.1").as_deref(), Some("1.2.0-rc.1"));
+        assert_eq!(canonical_version("1.x"), None);
+        assert_eq!(canonical_version(">=1.0.0 <2"), None);
+        assert_eq!(canonical_version("3f2a9c1"), None);
+    }
+
+    #[test]
+    fn test_npm_ranges() {
+        assert_eq!(resolve("^1.0.0").as_deref(), Some("1.4.0"));
+        assert_eq!(resolve("~1.2.0").as_deref(), Some("1.2.3"));
+        assert_eq!(resolve("1.2.3").as_deref(), Some("1.2.3"));
+        assert_eq!(resolve("1.x").as_deref(), Some("1.4.0"));
+        assert_eq!(resolve("*").as_deref(), Some("3.0.0"));
+        assert_eq!(resolve(">= 1.2.0 < 2").as_deref(), Some("1.4.0"));
+        assert_eq!(resolve("1.0.0 - 2.0.0").as_deref(), Some("2.0.0"));
+        assert_eq!(resolve("^1.0.0 || ^3.0.0").as_deref(), Some("3.0.0"));
+        assert_eq!(resolve("^4.0.0"), None);
+        assert_eq!(resolve("not a range"), None);
+    }
+
+    #[test]
+    fn test_prerelease_only_with_allow_prerelease() {
+        let versions = ["1.0.0", "2.0.0-alpha.1", "2.0.0-alpha.2"];
+
+        // Only the alphas are above 1.0.0
+        assert_eq!(max_satisfying(">1.0.0", versions, false), None);
+        assert_eq!(max_satisfying(">1.0.0", versions, true).as_deref(), Some("2.0.0-alpha.2"));
+
+        // Ranges that name a pre-release already match it
+        assert_eq!(max_satisfying("^2.0.0-alpha.1", versions, false).as_deref(), Some("2.0.0-alpha.2"));
+
+        // Pre-releases still have to be in range
+        assert_eq!(max_satisfying("<2.0.0-alpha.2 >1.0.0", versions, true).as_deref(), Some("2.0.0-alpha.1"));
+        assert_eq!(max_satisfying("^1.0.0", versions, true).as_deref(), Some("1.0.0"));
+    }
+}
@@ -0,0 +1,52 @@
+//! Deprecated SPDX ids: the ids license_detection's normalization map canonicalizes to another
+
+use crate::license_detection;
+use crate::package::Package;
+
+/// Replacement for a deprecated SPDX id (ids compare case-insensitively)
+pub fn replacement_for(id: &str) -> Option<&'static str> {
+    license_detection::canonical_spdx_id(id)
+}
+
+/// Deprecated ids used in a license or SPDX expression, each with its replacement
+pub fn deprecated_ids(license: &str) -> Vec<(String, &'static str)> {
+    license
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .filter(|token| !matches!(*token, "" | "AND" | "OR" | "WITH"))
+        .filter_map(|token| replacement_for(token).map(|replacement| (token.to_string(), replacement)))
+        .collect()
+}
+
+/// Deprecated ids in the license a package declares. Licenses detected from license text are
+/// left out: the text doesn't say whether later versions are allowed, so detection names GPL
+/// licenses by their deprecated ids.
+pub fn declared_deprecated_ids(package: &Package) -> Vec<(String, &'static str)> {
+    if package.license_source.is_some_and(|source| !source.is_declared()) {
+        return Vec::new();
+    }
+    deprecated_ids(&package.license)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replacements() {
+        assert_eq!(replacement_for("GPL-3.0"), Some("GPL-3.0-only"));
+        assert_eq!(replacement_for("gpl-2.0+"), Some("GPL-2.0-or-later"));
+        assert_eq!(replacement_for("LGPL-2.1"), Some("LGPL-2.1-only"));
+        assert_eq!(replacement_for("GPL-3.0-only"), None);
+        assert_eq!(replacement_for("MIT"), None);
+    }
+
+    #[test]
+    fn test_deprecated_ids_in_expressions() {
+        assert_eq!(
+            deprecated_ids("(MIT OR GPL-2.0+) AND LGPL-3.0"),
+            vec![("GPL-2.0+".to_string(), "GPL-2.0-or-later"), ("LGPL-3.0".to_string(), "LGPL-3.0-only")]
+        );
+        assert!(deprecated_ids("Apache-2.0 WITH LLVM-exception").is_empty());
+        assert!(deprecated_ids("UNKNOWN").is_empty());
+    }
+}
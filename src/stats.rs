@@ -0,0 +1,198 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::license_checker::LicenseChecker;
+use crate::package::{ Package, UNKNOWN_LICENSE };
+
+/// How many packages use one license
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LicenseCount {
+    pub license: String,
+    pub count: usize,
+    pub percentage: f64, // Share of all packages, 0-100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub allowed: bool,
+}
+
+/// License usage across a set of results, most common license first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LicenseStats {
+    pub total: usize,
+    pub licenses: Vec<LicenseCount>,
+    #[serde(skip)]
+    compliant: usize, // Packages with a known license that passes their own checks
+}
+
+impl LicenseStats {
+    pub fn from_results(packages: &[Package], license_checker: &LicenseChecker) -> Self {
+        // Count each license, keeping the first license URL seen for it; a license is allowed
+        // only when every package using it is, so per-registry lists and exceptions apply
+        let mut counts: HashMap<&str, (usize, Option<&String>, bool)> = HashMap::new();
+        for package in packages {
+            let allowed = license_checker.is_package_allowed(package);
+            counts
+                // Packages never resolved have an empty license; count them with the unknown ones
+                .entry(if package.is_unknown() { UNKNOWN_LICENSE } else { package.license.as_str() })
+                .and_modify(|(count, _, all_allowed)| {
+                    *count += 1;
+                    *all_allowed &= allowed;
+                })
+                .or_insert((1, package.license_url.as_ref(), allowed));
+        }
+
+        let total = packages.len();
+        let compliant = packages
+            .iter()
+            .filter(|p| license_checker.is_package_allowed(p) && !p.is_unknown())
+            .count();
+        let mut licenses: Vec<LicenseCount> = counts
+            .into_iter()
+            .map(|(license, (count, license_url, allowed))| LicenseCount {
+                license: license.to_string(),
+                count,
+                percentage: ((count as f64) / (total as f64)) * 100.0,
+                // Prefer the canonical URL for well-known licenses
+                url: crate::license_urls::get_license_url(license).or_else(|| license_url.cloned()),
+                allowed,
+            })
+            .collect();
+
+        // Most common first; ties by name so the order is stable between runs
+        licenses.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.license.cmp(&b.license)));
+
+        LicenseStats { total, licenses, compliant }
+    }
+
+    /// Number of different licenses seen, not counting UNKNOWN
+    pub fn distinct_licenses(&self) -> usize {
+        self.licenses
+            .iter()
+            .filter(|l| l.license != UNKNOWN_LICENSE)
+            .count()
+    }
+
+    /// Share of packages whose license is known and allowed, 0-100; None when there are no packages
+    pub fn compliance_score(&self) -> Option<f64> {
+        if self.total == 0 {
+            return None;
+        }
+        Some(((self.compliant as f64) / (self.total as f64)) * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn package(name: &str, license: &str) -> Package {
+        let mut package = Package::new(name.to_string(), "1.0.0".to_string(), String::new(), None);
+        package.license = license.to_string();
+        package
+    }
+
+    #[test]
+    fn test_counts_and_percentages() {
+        let mut custom = package("custom", "Custom");
+        custom.license_url = Some("https://example.com/LICENSE".to_string());
+        let packages = vec![
+            package("a", "MIT"),
+            package("b", "ISC"),
+            package("c", "MIT"),
+            package("d", "GPL-3.0"),
+            package("e", "MIT"),
+            package("f", "ISC"),
+            package("g", "UNKNOWN"),
+            custom
+        ];
+        let checker = LicenseChecker::new(vec!["MIT".to_string(), "ISC".to_string()]);
+
+        let stats = LicenseStats::from_results(&packages, &checker);
+        assert_eq!(stats.total, 8);
+
+        let summary: Vec<(&str, usize, bool)> = stats.licenses
+            .iter()
+            .map(|l| (l.license.as_str(), l.count, l.allowed))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("MIT", 3, true),
+                ("ISC", 2, true),
+                ("Custom", 1, false),
+                ("GPL-3.0", 1, false),
+                ("UNKNOWN", 1, false)
+            ]
+        );
+
+        assert_eq!(stats.licenses[0].percentage, 37.5);
+        assert_eq!(stats.licenses[1].percentage, 25.0);
+        assert_eq!(stats.licenses[2].url.as_deref(), Some("https://example.com/LICENSE"));
+        assert!(stats.licenses[0].url.is_some());
+    }
+
+    #[test]
+    fn test_compliance_score_and_distinct_licenses() {
+        // 6 of 8 packages have an allowed license; the unknown one doesn't count even though
+        // an empty allow-list would let it through
+        let packages = vec![
+            package("a", "MIT"),
+            package("b", "ISC"),
+            package("c", "MIT"),
+            package("d", "GPL-3.0"),
+            package("e", "MIT"),
+            package("f", "Apache-2.0"),
+            package("g", "UNKNOWN"),
+            package("h", "ISC")
+        ];
+        let checker = LicenseChecker::new(vec!["MIT".to_string(), "ISC".to_string(), "Apache-*".to_string()]);
+        let stats = LicenseStats::from_results(&packages, &checker);
+        assert_eq!(stats.compliance_score(), Some(75.0));
+        assert_eq!(stats.distinct_licenses(), 4);
+
+        let stats = LicenseStats::from_results(&packages, &LicenseChecker::new(Vec::new()));
+        assert_eq!(stats.compliance_score(), Some(87.5));
+    }
+
+    #[test]
+    fn test_allowed_follows_each_package_registry() {
+        // MIT is allowed globally, but GitHub packages may only use ISC
+        let mut from_github = package("gh", "MIT");
+        from_github.registry = "github".to_string();
+        let packages = vec![package("a", "MIT"), from_github, package("b", "ISC")];
+        let checker = LicenseChecker::new(vec!["MIT".to_string(), "ISC".to_string()])
+            .with_registry_allowed(BTreeMap::from([("github".to_string(), vec!["ISC".to_string()])]));
+
+        let stats = LicenseStats::from_results(&packages, &checker);
+        let summary: Vec<(&str, bool)> = stats.licenses
+            .iter()
+            .map(|l| (l.license.as_str(), l.allowed))
+            .collect();
+        assert_eq!(summary, vec![("MIT", false), ("ISC", true)]);
+
+        // The MIT bucket isn't allowed as a whole, but its npm package still counts as compliant
+        let score = stats.compliance_score().unwrap();
+        assert!((score - 200.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_empty_and_unknown_licenses_are_counted_together() {
+        let packages = vec![package("a", ""), package("b", "UNKNOWN"), package("c", "MIT")];
+        let stats = LicenseStats::from_results(&packages, &LicenseChecker::new(vec!["MIT".to_string()]));
+        let summary: Vec<(&str, usize)> = stats.licenses
+            .iter()
+            .map(|l| (l.license.as_str(), l.count))
+            .collect();
+        assert_eq!(summary, vec![("UNKNOWN", 2), ("MIT", 1)]);
+    }
+
+    #[test]
+    fn test_empty_results() {
+        let stats = LicenseStats::from_results(&[], &LicenseChecker::new(Vec::new()));
+        assert_eq!(stats.total, 0);
+        assert!(stats.licenses.is_empty());
+        assert_eq!(stats.compliance_score(), None);
+        assert_eq!(stats.distinct_licenses(), 0);
+    }
+}
@@ -0,0 +1,124 @@
+//! --interactive: after the scan, ask for the license of each package that came back UNKNOWN.
+//! The answers become `license-overrides` entries in the config file, so later scans reuse them.
+
+use std::collections::HashSet;
+use std::io::{ self, BufRead, Write };
+
+use crate::license_detection;
+use crate::license_overrides::LicenseOverride;
+use crate::package::Package;
+
+/// Ask for a license per unique UNKNOWN package. An empty answer skips the package and `q` or
+/// the end of input stops asking; the licenses given so far are returned.
+pub fn prompt_decisions(
+    packages: &[Package],
+    input: &mut dyn BufRead,
+    output: &mut dyn Write
+) -> io::Result<Vec<LicenseOverride>> {
+    let mut seen = HashSet::new();
+    let unknown: Vec<&Package> = packages
+        .iter()
+        .filter(|package| package.is_unknown())
+        .filter(|package| seen.insert((package.name.as_str(), package.version.as_str())))
+        .collect();
+    if unknown.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    writeln!(
+        output,
+        "\n{} package(s) have an UNKNOWN license. Type a license (SPDX id), Enter to skip, or q to stop.",
+        unknown.len()
+    )?;
+
+    let mut decisions = Vec::new();
+    for (index, package) in unknown.iter().enumerate() {
+        writeln!(output, "\n[{}/{}] {}@{}", index + 1, unknown.len(), package.name, package.version)?;
+        if let Some(url) = package_url(package) {
+            writeln!(output, "  {} ({})", url, package.registry)?;
+        }
+        if let Some(error) = package.debug_info.as_deref().filter(|_| package.lookup_failed) {
+            writeln!(output, "  Lookup failed: {}", error)?;
+        }
+        write!(output, "  License: ")?;
+        output.flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            writeln!(output)?;
+            break;
+        }
+        match answer.trim() {
+            "" => {}
+            "q" | "Q" => break,
+            license => {
+                decisions.push(LicenseOverride {
+                    name: package.name.clone(),
+                    version: Some(package.version.clone()),
+                    license: license_detection::normalize_license_id(license),
+                    expires: None,
+                });
+            }
+        }
+    }
+    Ok(decisions)
+}
+
+/// Where to look the package up by hand: its repository or registry page, else its resolution
+fn package_url(package: &Package) -> Option<&str> {
+    [package.url.as_str(), package.resolution.as_str()]
+        .into_iter()
+        .find(|url| !url.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, license: &str) -> Package {
+        let mut package = Package::new(
+            name.to_string(),
+            "1.0.0".to_string(),
+            format!("https://registry.npmjs.org/{}/-/{}-1.0.0.tgz", name, name),
+            None
+        );
+        package.registry = "npm".to_string();
+        package.license = license.to_string();
+        package
+    }
+
+    #[test]
+    fn test_scripted_answers_become_overrides() {
+        let packages = vec![
+            package("left-pad", ""),
+            package("lodash", "MIT"),
+            package("internal-ui", "UNKNOWN"),
+            package("left-pad", ""),
+            package("ui-kit", ""),
+            package("leftover", "")
+        ];
+
+        let mut output = Vec::new();
+        let mut input = io::Cursor::new("apache 2.0\n\n  ISC \nq\n");
+        let decisions = prompt_decisions(&packages, &mut input, &mut output).unwrap();
+
+        let decided: Vec<(&str, &str)> = decisions
+            .iter()
+            .map(|d| (d.name.as_str(), d.license.as_str()))
+            .collect();
+        assert_eq!(decided, [("left-pad", "Apache-2.0"), ("ui-kit", "ISC")]);
+        assert_eq!(decisions[0].version.as_deref(), Some("1.0.0"));
+
+        // Each unknown package is shown once, with where to look it up
+        let transcript = String::from_utf8(output).unwrap();
+        assert!(transcript.contains("4 package(s) have an UNKNOWN license"));
+        assert!(transcript.contains("[2/4] internal-ui@1.0.0"));
+        assert!(transcript.contains("https://registry.npmjs.org/left-pad/-/left-pad-1.0.0.tgz (npm)"));
+        assert!(!transcript.contains("lodash"));
+
+        // Input ending early keeps the answers given so far
+        let mut input = io::Cursor::new("MIT\n");
+        let decisions = prompt_decisions(&packages, &mut input, &mut Vec::new()).unwrap();
+        assert_eq!(decisions.len(), 1);
+    }
+}
@@ -18,33 +18,165 @@ pub const LICENSE_FILE_PATTERNS: [&str; 9] = [
     "COPYING.txt",
 ];
 
+/// Cache key of a package: a hash of its canonical identity
 pub fn generate_package_hash(package: &Package) -> String {
-    let mut hasher = Sha256::new();
+    format!("{:x}", Sha256::digest(package_identity(package).as_bytes()))
+}
+
+/// A package's identity, spelled the same whichever lockfile format or registry it came from:
+/// `github:owner/repo#ref` for GitHub sources, `url:<url>` for other archives and
+/// `<registry>:<name>@<version>` for everything else
+pub fn package_identity(package: &Package) -> String {
+    if let Some((owner, repo, ref_or_commit)) = github_source(package) {
+        // Different spellings of the same reference share an entry while distinct refs don't
+        return format!("github:{}/{}#{}", owner.to_lowercase(), repo.to_lowercase(), ref_or_commit);
+    }
+    if let Some(archive_url) = archive_url_from_resolution(&package.resolution) {
+        return format!("url:{}", canonical_url(&archive_url));
+    }
+
+    let registry = package_registry(package);
+    let name = match registry.as_str() {
+        // PyPI names compare case-insensitively, with runs of -, _ and . alike (PEP 503)
+        "pypi" => {
+            package.name
+                .to_ascii_lowercase()
+                .split(['-', '_', '.'])
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join("-")
+        }
+        "nuget" => package.name.to_ascii_lowercase(),
+        _ => package.name.clone(),
+    };
+    // Berry records "npm:1.2.3"; some manifests write "v1.2.3" or "=1.2.3"
+    let version = package.version.trim().trim_start_matches("npm:").trim_start_matches(['v', '=']);
+
+    format!("{}:{}@{}", registry, name, version)
+}
+
+/// The registry a package comes from, e.g. "npm" or "pypi"
+pub fn package_registry(package: &Package) -> String {
+    // Yarn and npm lockfiles leave the registry empty; it is npm
+    match package.registry.split(':').next().unwrap_or_default() {
+        "" => "npm".to_string(),
+        registry => registry.to_ascii_lowercase(),
+    }
+}
 
-    // Create a string that uniquely identifies a package
-    let package_id = if
-        package.name.starts_with("github:") ||
-        package.resolution.contains("github:")
+/// Owner, repo and ref of a package that comes from GitHub, from any of the places lockfiles
+/// record it: yarn's archive URLs and github: resolutions, npm's git URL versions, or the name
+fn github_source(package: &Package) -> Option<(String, String, String)> {
+    if
+        let Some(details) = archive_url_from_resolution(&package.resolution).and_then(|url| {
+            github_archive_details(&url)
+        })
     {
-        // For GitHub packages, use the name and resolution
-        format!("github:{}/{}", package.name, package.resolution)
-    } else if package.resolution.contains("__archiveUrl=") {
-        // For packages with archive URLs, extract the URL
-        if let Some(archive_url_index) = package.resolution.find("__archiveUrl=") {
-            let archive_url = &package.resolution[archive_url_index + 13..];
-            format!("url:{}", archive_url)
-        } else {
-            format!("npm:{}@{}", package.name, package.version)
+        return Some(details);
+    }
+
+    // npm lockfiles keep a GitHub dependency's git URL as its version, and synthesize a
+    // registry resolution around it, so the version is read first
+    let references = [package.version.as_str(), package.resolution.as_str(), package.name.as_str()];
+
+    // Archive links carry their ref in the path
+    let archive = references
+        .iter()
+        .filter_map(|reference| {
+            let start = ["https://", "http://"].iter().find_map(|scheme| reference.find(scheme))?;
+            github_archive_details(&reference[start..])
+        })
+        .next();
+    if archive.is_some() {
+        return archive;
+    }
+
+    // Resolutions are often prefixed with the package name, e.g. "pkg@github:owner/repo#ref"
+    const MARKERS: &[&str] = &[
+        "github:",
+        "git+https://github.com/",
+        "https://github.com/",
+        "git+ssh://git@github.com/",
+        "ssh://git@github.com/",
+        "git@github.com:",
+        "git://github.com/",
+    ];
+    references.iter().find_map(|reference| {
+        let index = MARKERS.iter().filter_map(|marker| reference.find(marker)).min()?;
+        crate::github_api::extract_github_details(&reference[index..]).ok()
+    })
+}
+
+/// A URL with its scheme and host lowercased and any trailing slash dropped
+fn canonical_url(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    format!("{}://{}{}", scheme.to_ascii_lowercase(), host.to_ascii_lowercase(), path)
+}
+
+/// The archive URL yarn records in a resolution as `__archiveUrl=<url-encoded URL>`, decoded
+pub fn archive_url_from_resolution(resolution: &str) -> Option<String> {
+    let (_, encoded) = resolution.split_once("__archiveUrl=")?;
+    let encoded = encoded.split(['&', '#']).next().unwrap_or(encoded);
+    if encoded.is_empty() {
+        return None;
+    }
+    urlencoding::decode(encoded).ok().map(|url| url.into_owned())
+}
+
+/// Owner, repo and ref of a GitHub archive URL: codeload.github.com tar.gz/zip links,
+/// github.com archive, tarball and zipball links, or a plain repository URL with a #ref.
+/// Refs may contain slashes; a refs/heads/ or refs/tags/ prefix is dropped.
+pub fn github_archive_details(archive_url: &str) -> Option<(String, String, String)> {
+    let url = archive_url.trim().split(['?', '#']).next().unwrap_or(archive_url);
+    let url = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")).unwrap_or(url);
+
+    let (owner, repo, reference) = if let Some(path) = url.strip_prefix("codeload.github.com/") {
+        // codeload.github.com/owner/repo/tar.gz/<ref>
+        let mut parts = path.splitn(4, '/');
+        let (owner, repo, kind, reference) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        if !matches!(kind, "tar.gz" | "zip" | "legacy.tar.gz" | "legacy.zip") {
+            return None;
+        }
+        (owner, repo, reference.to_string())
+    } else if let Some(path) = url.strip_prefix("github.com/") {
+        let mut parts = path.splitn(4, '/');
+        let (owner, repo) = (parts.next()?, parts.next()?);
+        match (parts.next(), parts.next()) {
+            // github.com/owner/repo/archive/<ref>.tar.gz
+            (Some("archive"), Some(reference)) => {
+                let reference = reference
+                    .strip_suffix(".tar.gz")
+                    .or_else(|| reference.strip_suffix(".zip"))
+                    .unwrap_or(reference);
+                (owner, repo, reference.to_string())
+            }
+            // github.com/owner/repo/tarball/<ref>
+            (Some("tarball" | "zipball"), Some(reference)) => (owner, repo, reference.to_string()),
+            // github.com/owner/repo(.git)#<ref>
+            (None, None) => {
+                return crate::github_api::extract_github_details(archive_url.trim()).ok();
+            }
+            _ => {
+                return None;
+            }
         }
     } else {
-        // For npm packages, use name + version
-        format!("npm:{}@{}", package.name, package.version)
+        return None;
     };
 
-    hasher.update(package_id.as_bytes());
-    let result = hasher.finalize();
-
-    format!("{:x}", result)
+    let repo = repo.trim_end_matches(".git");
+    let reference = reference
+        .trim_start_matches("refs/heads/")
+        .trim_start_matches("refs/tags/")
+        .trim_end_matches('/');
+    if owner.is_empty() || repo.is_empty() || reference.is_empty() {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string(), reference.to_string()))
 }
 
 /// Generate a fallback checksum for a package when none is provided
@@ -85,9 +217,44 @@ pub fn generate_fallback_checksum(package: &Package) -> String {
     format!("fallback:{:x}", hash)
 }
 
+/// Replace `${VAR}` references with the values of the corresponding environment variables.
+/// Fails when a referenced variable is not set, so a missing token doesn't go unnoticed.
+pub fn substitute_env_vars(input: &str) -> Result<String, String> {
+    substitute_vars(input, |name| std::env::var(name).ok())
+}
+
+/// Replace `${VAR}` references with the values `lookup` gives for them
+fn substitute_vars<F>(input: &str, lookup: F) -> Result<String, String> where F: Fn(&str) -> Option<String> {
+    let mut result = String::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("Unterminated variable reference in: {}", input))?;
+        let var_name = &after[..end];
+        if var_name.is_empty() {
+            return Err(format!("Empty variable reference in: {}", input));
+        }
+        let value = lookup(var_name).ok_or_else(|| format!("Environment variable {} is not set", var_name))?;
+        result.push_str(&value);
+        rest = &after[end + 1..];
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Where cached lookups are kept, relative to the working directory
+pub fn cache_dir() -> PathBuf {
+    Path::new(".").join(".cache")
+}
+
 // Initialize cache directory
 pub fn init_cache_dir() -> Result<PathBuf, Box<dyn Error>> {
-    let cache_dir = Path::new(".").join(".cache");
+    let cache_dir = cache_dir();
 
     // Create cache directory if it doesn't exist
     if !cache_dir.exists() {
@@ -98,9 +265,9 @@ pub fn init_cache_dir() -> Result<PathBuf, Box<dyn Error>> {
     Ok(cache_dir)
 }
 
-// Save package info to cache
-pub fn save_to_cache(package_hash: &str, package_info: &Package) -> Result<(), Box<dyn Error>> {
-    let cache_dir = init_cache_dir()?;
+// Save package info to the cache kept in a given directory
+pub fn save_to_cache_in(cache_dir: &Path, package_hash: &str, package_info: &Package) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(cache_dir)?;
     let cache_file = cache_dir.join(format!("{}.json", package_hash));
 
     // Serialize the package info to JSON
@@ -121,7 +288,11 @@ pub fn get_from_cache(package_hash: &str) -> Option<Package> {
             return None;
         }
     };
+    get_from_cache_in(&cache_dir, package_hash)
+}
 
+// Try to get package info from the cache kept in a given directory
+pub fn get_from_cache_in(cache_dir: &Path, package_hash: &str) -> Option<Package> {
     let cache_file = cache_dir.join(format!("{}.json", package_hash));
 
     if !cache_file.exists() {
@@ -137,7 +308,7 @@ pub fn get_from_cache(package_hash: &str) -> Option<Package> {
     };
 
     let mut content = String::new();
-    if let Err(_) = file.read_to_string(&mut content) {
+    if file.read_to_string(&mut content).is_err() {
         return None;
     }
 
@@ -147,13 +318,41 @@ pub fn get_from_cache(package_hash: &str) -> Option<Package> {
             // Always reset the retry_for_unknown flag when loading from cache
             // It will be set again if needed by the caller
             package_info.retry_for_unknown = false;
+            // Source lockfiles belong to the current scan, not to the cached entry
+            package_info.source_lockfiles.clear();
+            // Entries cached before confidence scoring have none; rate them as declared
+            if package_info.confidence == 0.0 && package_info.license_source.is_none() {
+                package_info.confidence = crate::license_detection::declared_confidence(
+                    &package_info.license
+                );
+            }
             Some(package_info)
         }
         Err(_) => None,
     }
 }
 
+// License texts downloaded from a URL are cached apart from packages, since many packages
+// point at the same canonical license file
+pub fn license_text_cache_file(url: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let cache_dir = init_cache_dir()?.join("license-texts");
+    fs::create_dir_all(&cache_dir)?;
+    Ok(cache_dir.join(format!("{:x}.txt", Sha256::digest(url.as_bytes()))))
+}
+
+// Save a downloaded license text under its URL
+pub fn save_license_text_to_cache(url: &str, text: &str) -> Result<(), Box<dyn Error>> {
+    fs::write(license_text_cache_file(url)?, text)?;
+    Ok(())
+}
+
+// Try to get a previously downloaded license text for a URL
+pub fn get_license_text_from_cache(url: &str) -> Option<String> {
+    fs::read_to_string(license_text_cache_file(url).ok()?).ok()
+}
+
 // Format repo URL with appropriate license file if it exists
+// Returns None when no license file could be confirmed in the repository
 pub fn get_license_file_url(repo_url: &str, branch_or_commit: &str) -> Option<String> {
     // This function makes HTTP requests to check if license files exist
     let client = reqwest::blocking::Client
@@ -162,44 +361,67 @@ pub fn get_license_file_url(repo_url: &str, branch_or_commit: &str) -> Option<St
         .build()
         .unwrap_or_default();
 
-    // For GitHub repositories, try the API
-    if repo_url.contains("github.com") {
-        // Extract owner and repo from URL
-        let parts: Vec<&str> = repo_url.split('/').collect();
-        if parts.len() >= 5 {
-            let owner = parts[3];
-            let repo = parts[4];
-
-            // Try to get the repository contents for each license pattern
-            for pattern in LICENSE_FILE_PATTERNS.iter() {
-                let api_path = format!(
-                    "https://api.github.com/repos/{}/{}/contents/{}?ref={}",
-                    owner,
-                    repo,
-                    pattern,
-                    branch_or_commit
-                );
+    find_license_file_url(repo_url, branch_or_commit, |api_path| {
+        match client.get(api_path).header("User-Agent", "Dependency-Scanner").send() {
+            Ok(response) => Some(response.status().is_success()),
+            Err(_) => None,
+        }
+    })
+}
+
+// Probe each license file pattern through the GitHub contents API.
+// `file_exists` returns Some(true) when the file is present, Some(false) when it is
+// missing, and None when the request itself failed (rate limits, network errors).
+fn find_license_file_url<F>(repo_url: &str, branch_or_commit: &str, mut file_exists: F) -> Option<String>
+    where F: FnMut(&str) -> Option<bool>
+{
+    // Only GitHub repositories can be probed through the API
+    if !repo_url.contains("github.com") {
+        return None;
+    }
 
-                match client.get(&api_path).header("User-Agent", "Dependency-Scanner").send() {
-                    Ok(response) => {
-                        if response.status().is_success() {
-                            return Some(
-                                format!("{}/blob/{}/{}", repo_url, branch_or_commit, pattern)
-                            );
-                        }
-                    }
-                    Err(_) => {
-                        // If we hit rate limits or network errors, don't keep trying
-                        break;
-                    }
-                }
+    // Extract owner and repo from URL
+    let parts: Vec<&str> = repo_url.split('/').collect();
+    if parts.len() < 5 {
+        return None;
+    }
+    let owner = parts[3];
+    let repo = parts[4];
+
+    // Try to get the repository contents for each license pattern
+    for pattern in LICENSE_FILE_PATTERNS.iter() {
+        let api_path = format!(
+            "https://api.github.com/repos/{}/{}/contents/{}?ref={}",
+            owner,
+            repo,
+            pattern,
+            branch_or_commit
+        );
+
+        match file_exists(&api_path) {
+            Some(true) => {
+                return Some(format!("{}/blob/{}/{}", repo_url, branch_or_commit, pattern));
+            }
+            Some(false) => {}
+            None => {
+                // If we hit rate limits or network errors, don't keep trying
+                break;
             }
         }
     }
 
-    // If we couldn't verify any license files, return a generic LICENSE link
-    // as a fallback, since it's the most common name
-    Some(format!("{}/blob/{}/LICENSE", repo_url, branch_or_commit))
+    // No license file could be confirmed, so don't point at a URL that may not exist
+    None
+}
+
+// Raw download URL for a file in a GitHub repository at a branch, tag or commit
+pub fn raw_github_file_url(repo_url: &str, git_ref: &str, path: &str) -> String {
+    format!(
+        "{}/{}/{}",
+        repo_url.replacen("https://github.com/", "https://raw.githubusercontent.com/", 1),
+        git_ref,
+        path.trim_start_matches('/')
+    )
 }
 
 // Normalize GitHub URL to a standard format
@@ -215,3 +437,249 @@ pub fn normalize_github_url(url: &str) -> Option<String> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn github_package(name: &str, resolution: &str) -> Package {
+        Package::new(name.to_string(), "1.0.0".to_string(), resolution.to_string(), None)
+    }
+
+    #[test]
+    fn test_github_hash_equivalent_references_match() {
+        let shorthand = github_package("my-lib", "my-lib@github:Owner/Repo#abc123");
+        let https = github_package("my-lib", "my-lib@https://github.com/owner/repo.git#commit=abc123");
+        let git_plus = github_package("my-lib", "git+https://github.com/owner/repo.git#abc123");
+        let name_only = github_package("github:owner/repo#abc123", "");
+
+        let hash = generate_package_hash(&shorthand);
+        assert_eq!(hash, generate_package_hash(&https));
+        assert_eq!(hash, generate_package_hash(&git_plus));
+        assert_eq!(hash, generate_package_hash(&name_only));
+    }
+
+    #[test]
+    fn test_github_archive_details() {
+        let details = |url: &str| github_archive_details(url).map(|(o, r, f)| format!("{}/{}#{}", o, r, f));
+
+        assert_eq!(
+            details("https://codeload.github.com/owner/repo/tar.gz/abc123").as_deref(),
+            Some("owner/repo#abc123")
+        );
+        assert_eq!(
+            details("https://codeload.github.com/owner/repo/zip/v1.2.0").as_deref(),
+            Some("owner/repo#v1.2.0")
+        );
+        assert_eq!(
+            details("https://codeload.github.com/owner/repo/tar.gz/refs/heads/feature/login").as_deref(),
+            Some("owner/repo#feature/login")
+        );
+        assert_eq!(
+            details("https://github.com/owner/repo/archive/refs/tags/release/2024.tar.gz").as_deref(),
+            Some("owner/repo#release/2024")
+        );
+        assert_eq!(
+            details("https://github.com/owner/repo/archive/abc123.zip").as_deref(),
+            Some("owner/repo#abc123")
+        );
+        assert_eq!(details("https://github.com/owner/repo/tarball/main").as_deref(), Some("owner/repo#main"));
+        assert_eq!(details("https://github.com/owner/repo.git#abc123").as_deref(), Some("owner/repo#abc123"));
+        assert_eq!(details("https://github.com/owner/repo#abc123").as_deref(), Some("owner/repo#abc123"));
+        assert_eq!(details("https://github.com/owner/repo/issues/1"), None);
+        assert_eq!(details("https://example.com/owner/repo/tar.gz/abc123"), None);
+    }
+
+    #[test]
+    fn test_archive_url_resolution_matches_other_spellings() {
+        let encoded = concat!(
+            "my-lib@https://codeload.github.com/owner/repo/tar.gz/abc123",
+            "::__archiveUrl=https%3A%2F%2Fcodeload.github.com%2FOwner%2FRepo.git%2Ftar.gz%2Fabc123"
+        );
+        assert_eq!(
+            archive_url_from_resolution(encoded).as_deref(),
+            Some("https://codeload.github.com/Owner/Repo.git/tar.gz/abc123")
+        );
+        assert_eq!(archive_url_from_resolution("my-lib@npm:1.0.0"), None);
+
+        let shorthand = github_package("my-lib", "my-lib@github:owner/repo#abc123");
+        assert_eq!(generate_package_hash(&github_package("my-lib", encoded)), generate_package_hash(&shorthand));
+
+        // Archives outside GitHub are keyed on their URL
+        let other = github_package("my-lib", "my-lib@https://x.test/a.tgz::__archiveUrl=https%3A%2F%2Fx.test%2Fa.tgz");
+        let moved = github_package("my-lib", "my-lib@https://x.test/b.tgz::__archiveUrl=https%3A%2F%2Fx.test%2Fb.tgz");
+        assert_ne!(generate_package_hash(&other), generate_package_hash(&moved));
+    }
+
+    #[test]
+    fn test_yarn_and_npm_lockfiles_hash_identically() {
+        let yarn_lock = r#"# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+# yarn lockfile v1
+
+
+"@scope/util@^2.0.0":
+  version "2.1.0"
+  resolved "https://registry.yarnpkg.com/@scope/util/-/util-2.1.0.tgz#0123456789abcdef"
+  integrity sha512-AAAA
+
+left-pad@^1.3.0:
+  version "1.3.0"
+  resolved "https://registry.yarnpkg.com/left-pad/-/left-pad-1.3.0.tgz#5b8a3a7765dfe001261dde915589e782f8c94d1e"
+  integrity sha512-BBBB
+"#;
+        // Yarn Berry records the GitHub source in its resolution, npm in the version
+        let pnp_data = include_str!("../tests/fixtures/pnp/.pnp.data.json");
+        let package_lock = r#"{
+  "lockfileVersion": 2,
+  "dependencies": {
+    "@scope/util": { "version": "2.1.0", "integrity": "sha512-AAAA" },
+    "left-pad": { "version": "1.3.0", "resolved": "https://registry.npmjs.org/left-pad/-/left-pad-1.3.0.tgz" },
+    "debug": { "version": "4.3.4" },
+    "widget": { "version": "git+ssh://git@github.com/Acme/widget.git#abc1234", "from": "widget@github:acme/widget" }
+  }
+}"#;
+
+        let mut from_yarn: Vec<String> = crate::parsers::yarn_parser
+            ::parse_yarn_lock(yarn_lock)
+            .iter()
+            .chain(
+                crate::parsers::yarn_pnp_parser
+                    ::parse_pnp_data(pnp_data)
+                    .unwrap()
+                    .iter()
+                    .filter(|p| p.name == "debug" || p.name == "widget")
+            )
+            .map(package_identity)
+            .collect();
+        from_yarn.sort();
+        let mut from_npm: Vec<String> = crate::parsers::npm_parser
+            ::parse_package_lock(package_lock)
+            .iter()
+            .map(package_identity)
+            .collect();
+        from_npm.sort();
+
+        assert_eq!(
+            from_yarn,
+            vec!["github:acme/widget#abc1234", "npm:@scope/util@2.1.0", "npm:debug@4.3.4", "npm:left-pad@1.3.0"]
+        );
+        assert_eq!(from_yarn, from_npm);
+
+        // The same package read from node_modules, which records the registry
+        let mut installed = Package::new("left-pad".to_string(), "1.3.0".to_string(), String::new(), None);
+        installed.registry = "npm".to_string();
+        assert_eq!(package_identity(&installed), "npm:left-pad@1.3.0");
+    }
+
+    #[test]
+    fn test_canonical_identity_per_registry() {
+        let package = |registry: &str, name: &str, version: &str| {
+            let mut package = Package::new(name.to_string(), version.to_string(), String::new(), None);
+            package.registry = registry.to_string();
+            package
+        };
+
+        // Registries don't share names
+        assert_ne!(
+            generate_package_hash(&package("pypi", "requests", "2.31.0")),
+            generate_package_hash(&package("", "requests", "2.31.0"))
+        );
+        assert_eq!(package_identity(&package("pypi", "Typing_Extensions", "4.9.0")), "pypi:typing-extensions@4.9.0");
+        assert_eq!(package_identity(&package("nuget", "Newtonsoft.Json", "13.0.3")), "nuget:newtonsoft.json@13.0.3");
+        assert_eq!(package_identity(&package("", "react", "npm:18.2.0")), "npm:react@18.2.0");
+        assert_eq!(package_identity(&package("", "react", "v18.2.0")), "npm:react@18.2.0");
+
+        // GitHub Packages is an npm registry, not a GitHub source
+        let hosted = Package::new(
+            "@acme/widget".to_string(),
+            "1.0.0".to_string(),
+            "https://npm.pkg.github.com/download/@acme/widget/1.0.0/0123abcd".to_string(),
+            None
+        );
+        assert_eq!(package_identity(&hosted), "npm:@acme/widget@1.0.0");
+
+        let archive = |host: &str| {
+            let resolution = format!("pkg@https://{0}/pkg.tgz::__archiveUrl=https%3A%2F%2F{0}%2Fpkg.tgz", host);
+            Package::new("pkg".to_string(), "1.0.0".to_string(), resolution, None)
+        };
+        assert_eq!(
+            generate_package_hash(&archive("Files.Example.com")),
+            generate_package_hash(&archive("files.example.com"))
+        );
+    }
+
+    #[test]
+    fn test_github_hash_distinct_refs_differ() {
+        let main = github_package("my-lib", "my-lib@github:owner/repo#main");
+        let commit = github_package("my-lib", "my-lib@github:owner/repo#abc123");
+        let tag = github_package("my-lib", "my-lib@github:owner/repo#v1.0.0");
+        let other_repo = github_package("my-lib", "my-lib@github:owner/other#abc123");
+
+        let hashes: HashSet<String> = [&main, &commit, &tag, &other_repo]
+            .iter()
+            .map(|p| generate_package_hash(p))
+            .collect();
+        assert_eq!(hashes.len(), 4);
+    }
+
+    #[test]
+    fn test_github_hash_default_ref_is_main() {
+        let implicit = github_package("my-lib", "my-lib@github:owner/repo");
+        let explicit = github_package("my-lib", "my-lib@github:owner/repo#main");
+        assert_eq!(generate_package_hash(&implicit), generate_package_hash(&explicit));
+    }
+
+    #[test]
+    fn test_substitute_vars_resolves_from_lookup() {
+        let lookup = |name: &str| (name == "NPM_TOKEN").then(|| "s3cr3t".to_string());
+        assert_eq!(
+            substitute_vars("https://npm.example.com/${NPM_TOKEN}/", lookup).unwrap(),
+            "https://npm.example.com/s3cr3t/"
+        );
+        assert_eq!(substitute_vars("Bearer ${NPM_TOKEN}", lookup).unwrap(), "Bearer s3cr3t");
+        assert_eq!(substitute_vars("no variables", lookup).unwrap(), "no variables");
+        assert_eq!(substitute_vars("${OTHER}", lookup).unwrap_err(), "Environment variable OTHER is not set");
+
+        // PATH is set wherever the tests run
+        let path = std::env::var("PATH").unwrap();
+        assert_eq!(substitute_env_vars("${PATH}").unwrap(), path);
+    }
+
+    #[test]
+    fn test_substitute_env_vars_errors_on_missing_variable() {
+        assert!(substitute_env_vars("${SLS_TEST_UNSET_VARIABLE}").is_err());
+        assert!(substitute_env_vars("${UNTERMINATED").is_err());
+    }
+
+    #[test]
+    fn test_license_file_url_none_when_repo_has_no_license() {
+        let mut probes = 0;
+        let url = find_license_file_url("https://github.com/owner/no-license", "main", |_| {
+            probes += 1;
+            Some(false)
+        });
+        assert_eq!(url, None);
+        assert_eq!(probes, LICENSE_FILE_PATTERNS.len());
+    }
+
+    #[test]
+    fn test_license_file_url_points_at_found_file() {
+        let url = find_license_file_url("https://github.com/owner/repo", "v1.0.0", |api_path| {
+            Some(api_path.contains("/contents/COPYING?"))
+        });
+        assert_eq!(url, Some("https://github.com/owner/repo/blob/v1.0.0/COPYING".to_string()));
+    }
+
+    #[test]
+    fn test_license_file_url_none_on_network_error() {
+        let url = find_license_file_url("https://github.com/owner/repo", "main", |_| None);
+        assert_eq!(url, None);
+    }
+
+    #[test]
+    fn test_license_file_url_none_for_non_github_repo() {
+        let url = find_license_file_url("https://gitlab.com/owner/repo", "main", |_| Some(true));
+        assert_eq!(url, None);
+    }
+}
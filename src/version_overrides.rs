@@ -0,0 +1,304 @@
+//! Versions forced by a project's package.json: yarn's `resolutions` and npm's `overrides`.
+//! Registry manifests name the versions a package asks for; these decide which one is
+//! installed, so dependencies are queued at the forced version instead.
+
+use serde_json::{ Map, Value };
+use std::collections::HashMap;
+use std::path::{ Path, PathBuf };
+
+use crate::package::Package;
+
+const MANIFEST_FILE: &str = "package.json";
+
+/// Lockfiles whose project manifest can force versions
+const JS_LOCKFILES: &[&str] = &[
+    "yarn.lock",
+    "package-lock.json",
+    crate::parsers::npm_parser::SHRINKWRAP_FILE,
+    MANIFEST_FILE,
+    crate::parsers::yarn_pnp_parser::PNP_DATA_FILE,
+    crate::parsers::yarn_pnp_parser::PNP_LOADER_FILE,
+];
+
+/// One forced version
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct VersionOverride {
+    name: String,
+    parent: Option<String>, // Only for dependencies of this package; None for any parent
+    selector: Option<String>, // Only for requested versions in this range, from `name@range` keys
+    version: String,
+}
+
+impl VersionOverride {
+    fn matches(&self, parent: &str, dep: &Package) -> bool {
+        self.name == dep.name &&
+            self.parent.as_deref().is_none_or(|name| name == parent) &&
+            self.selector.as_deref().is_none_or(|range| {
+                crate::semver_resolver::max_satisfying(range, [dep.version.as_str()], true).is_some()
+            })
+    }
+}
+
+/// The versions one project forces
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ProjectOverrides {
+    overrides: Vec<VersionOverride>,
+}
+
+impl ProjectOverrides {
+    /// The overrides in the package.json next to a lockfile, if it has any
+    pub fn find(lockfile: &Path) -> Option<ProjectOverrides> {
+        let manifest_path = lockfile.parent()?.join(MANIFEST_FILE);
+        let content = crate::lockfile_parser::read_lockfile(&manifest_path).ok()?;
+        let manifest: Value = match serde_json::from_str(&content) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                eprintln!("Warning: Failed to read version overrides from {}: {}", manifest_path.display(), e);
+                return None;
+            }
+        };
+        let overrides = ProjectOverrides::from_manifest(&manifest);
+        if overrides.overrides.is_empty() { None } else { Some(overrides) }
+    }
+
+    pub fn from_manifest(manifest: &Value) -> ProjectOverrides {
+        let mut overrides = Vec::new();
+        if let Some(resolutions) = manifest["resolutions"].as_object() {
+            overrides.extend(resolutions.iter().filter_map(|(key, version)| yarn_resolution(key, version)));
+        }
+        if let Some(npm_overrides) = manifest["overrides"].as_object() {
+            collect_npm_overrides(npm_overrides, None, manifest, &mut overrides);
+        }
+        // Overrides for one parent win over the ones for every parent
+        overrides.sort_by_key(|o| o.parent.is_none());
+        ProjectOverrides { overrides }
+    }
+
+    /// The version forced for a dependency of `parent`
+    fn version_for(&self, parent: &str, dep: &Package) -> Option<&str> {
+        self.overrides
+            .iter()
+            .find(|o| o.matches(parent, dep))
+            .map(|o| o.version.as_str())
+    }
+}
+
+/// A yarn `resolutions` entry: `name`, `**/name`, `parent/name` or `parent/**/name`, where
+/// names may be scoped and carry a `@range`
+fn yarn_resolution(key: &str, version: &Value) -> Option<VersionOverride> {
+    let version = version.as_str()?;
+    let mut names: Vec<String> = Vec::new();
+    let mut segments = key.split('/');
+    while let Some(segment) = segments.next() {
+        match segment {
+            "" => {}
+            // Scoped names span two segments
+            scope if scope.starts_with('@') => names.push(format!("{}/{}", scope, segments.next()?)),
+            name => names.push(name.to_string()),
+        }
+    }
+
+    let target = names.pop()?;
+    let parent = names
+        .pop()
+        .filter(|parent| parent != "**")
+        .map(|parent| split_selector(&parent).0.to_string());
+    let (name, selector) = split_selector(&target);
+    Some(override_for(name, parent, selector, version))
+}
+
+/// npm `overrides`: `"name": "version"`, or an object whose `"."` entry overrides the package
+/// itself and whose other entries override its dependencies. `$name` refers to the version
+/// the project itself depends on.
+fn collect_npm_overrides(
+    entries: &Map<String, Value>,
+    parent: Option<&str>,
+    manifest: &Value,
+    overrides: &mut Vec<VersionOverride>
+) {
+    for (key, value) in entries {
+        if key == "." {
+            continue;
+        }
+        let (name, selector) = split_selector(key);
+        let own_version = match value {
+            Value::String(version) => Some(version.as_str()),
+            Value::Object(nested) => {
+                collect_npm_overrides(nested, Some(name), manifest, overrides);
+                nested.get(".").and_then(Value::as_str)
+            }
+            _ => None,
+        };
+        let Some(version) = own_version.and_then(|version| resolve_reference(version, manifest)) else {
+            continue;
+        };
+        overrides.push(override_for(name, parent.map(str::to_string), selector, version));
+    }
+}
+
+fn override_for(name: &str, parent: Option<String>, selector: Option<&str>, version: &str) -> VersionOverride {
+    VersionOverride {
+        name: name.to_string(),
+        parent,
+        selector: selector.map(str::to_string),
+        // Dependencies are queued with the operator dropped, as the registry manifests are read
+        version: crate::github_api::strip_range_operator(version).to_string(),
+    }
+}
+
+/// Split `name@range` into the name and the range; a leading `@` is the scope
+fn split_selector(key: &str) -> (&str, Option<&str>) {
+    match key.rfind('@') {
+        Some(at) if at > 0 => (&key[..at], Some(&key[at + 1..])),
+        _ => (key, None),
+    }
+}
+
+/// Resolve a `$name` reference to the project's own dependency on `name`
+fn resolve_reference<'a>(version: &'a str, manifest: &'a Value) -> Option<&'a str> {
+    let Some(name) = version.strip_prefix('$') else {
+        return Some(version);
+    };
+    ["dependencies", "devDependencies", "optionalDependencies", "peerDependencies"]
+        .iter()
+        .find_map(|section| manifest[section][name].as_str())
+}
+
+/// The forced versions of the scanned projects, keyed by lockfile path as recorded in
+/// `Package::source_lockfiles`
+#[derive(Default)]
+pub struct VersionOverrides {
+    projects: HashMap<String, ProjectOverrides>,
+}
+
+/// The package.json whose overrides apply to a lockfile, for JavaScript lockfiles
+pub fn manifest_path(lockfile: &Path) -> Option<PathBuf> {
+    let file_name = lockfile.file_name()?.to_string_lossy();
+    JS_LOCKFILES.contains(&file_name.as_ref()).then(|| lockfile.with_file_name(MANIFEST_FILE))
+}
+
+impl VersionOverrides {
+    /// Read the overrides of the project next to each JavaScript lockfile
+    pub fn find(lockfiles: &[PathBuf]) -> Self {
+        let mut projects = HashMap::new();
+        for lockfile in lockfiles {
+            if manifest_path(lockfile).is_none() {
+                continue;
+            }
+            if let Some(overrides) = ProjectOverrides::find(lockfile) {
+                println!("Applying {} version override(s) for {}", overrides.overrides.len(), lockfile.display());
+                projects.insert(lockfile.display().to_string(), overrides);
+            }
+        }
+        VersionOverrides { projects }
+    }
+
+    /// The overrides a lockfile's project forced at an earlier revision. `previous` is the
+    /// lockfile as it was then, next to the package.json of the same revision.
+    pub fn find_previous(lockfile: &Path, previous: &Path) -> Self {
+        let mut projects = HashMap::new();
+        if manifest_path(lockfile).is_some() {
+            if let Some(overrides) = ProjectOverrides::find(previous) {
+                projects.insert(lockfile.display().to_string(), overrides);
+            }
+        }
+        VersionOverrides { projects }
+    }
+
+    /// A resolved package with its dependencies at the versions its project forces
+    pub fn apply(&self, parent: &Package, mut package_info: Package) -> Package {
+        if !self.projects.is_empty() {
+            package_info.dependencies = std::mem
+                ::take(&mut package_info.dependencies)
+                .into_iter()
+                .map(|dep| self.force_version(parent, dep))
+                .collect();
+        }
+        package_info
+    }
+
+    /// The dependency at the version its project forces, or unchanged when none is forced
+    fn force_version(&self, parent: &Package, dep: Package) -> Package {
+        let forced = parent.source_lockfiles
+            .iter()
+            .filter_map(|lockfile| self.projects.get(lockfile))
+            .find_map(|project| project.version_for(&parent.name, &dep));
+        match forced {
+            Some(version) if version != dep.version => {
+                let resolution = crate::github_api::dependency_resolution(&dep.name, version);
+                let mut forced_dep = Package::new(dep.name, version.to_string(), resolution, None);
+                forced_dep.source_lockfiles = dep.source_lockfiles;
+                forced_dep
+            }
+            _ => dep,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    fn dep(name: &str, version: &str) -> Package {
+        Package::new(name.to_string(), version.to_string(), String::new(), None)
+    }
+
+    #[test]
+    fn test_yarn_resolutions_and_npm_overrides() {
+        let manifest = json!({
+            "dependencies": { "react": "^18.2.0" },
+            "resolutions": {
+                "**/minimist": "1.2.8",
+                "webpack/@babel/core": "7.24.0"
+            },
+            "overrides": {
+                "semver@^5": "5.7.2",
+                "react-dom": "$react",
+                "eslint": { ".": "8.57.0", "glob": "10.3.10" }
+            }
+        });
+        let overrides = ProjectOverrides::from_manifest(&manifest);
+
+        assert_eq!(overrides.version_for("mkdirp", &dep("minimist", "0.0.8")), Some("1.2.8"));
+        assert_eq!(overrides.version_for("webpack", &dep("@babel/core", "7.1.0")), Some("7.24.0"));
+        assert_eq!(overrides.version_for("jest", &dep("@babel/core", "7.1.0")), None);
+        assert_eq!(overrides.version_for("jest", &dep("semver", "5.3.0")), Some("5.7.2"));
+        assert_eq!(overrides.version_for("jest", &dep("semver", "7.5.0")), None);
+        assert_eq!(overrides.version_for("app", &dep("react-dom", "18.0.0")), Some("18.2.0"));
+        assert_eq!(overrides.version_for("app", &dep("eslint", "8.0.0")), Some("8.57.0"));
+        assert_eq!(overrides.version_for("eslint", &dep("glob", "7.2.0")), Some("10.3.10"));
+        assert_eq!(overrides.version_for("rimraf", &dep("glob", "7.2.0")), None);
+        assert!(ProjectOverrides::from_manifest(&json!({ "name": "app" })).overrides.is_empty());
+    }
+
+    #[test]
+    fn test_override_changes_a_transitive_dependency_version() {
+        let project = tempfile::tempdir().unwrap();
+        let lockfile = project.path().join("yarn.lock");
+        fs::write(&lockfile, "").unwrap();
+        fs::write(project.path().join(MANIFEST_FILE), r#"{ "resolutions": { "**/minimist": "^1.2.8" } }"#).unwrap();
+
+        let overrides = VersionOverrides::find(&[lockfile.clone(), project.path().join("poetry.lock")]);
+        assert_eq!(overrides.projects.len(), 1);
+
+        let mut parent = dep("mkdirp", "0.5.1");
+        parent.source_lockfiles = vec![lockfile.display().to_string()];
+
+        let mut resolved = parent.clone();
+        resolved.dependencies = vec![dep("minimist", "0.0.8"), dep("mkdirp", "0.5.1")];
+
+        let forced = overrides.apply(&parent, resolved.clone());
+        let versions: Vec<(&str, &str)> = forced.dependencies
+            .iter()
+            .map(|dep| (dep.name.as_str(), dep.version.as_str()))
+            .collect();
+        assert_eq!(versions, [("minimist", "1.2.8"), ("mkdirp", "0.5.1")]);
+        assert_eq!(forced.dependencies[0].resolution, "https://registry.npmjs.org/minimist/-/minimist-1.2.8.tgz");
+
+        // Packages of other projects keep the version their parent asks for
+        parent.source_lockfiles = vec!["other/yarn.lock".to_string()];
+        assert_eq!(overrides.apply(&parent, resolved).dependencies[0].version, "0.0.8");
+    }
+}
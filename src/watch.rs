@@ -0,0 +1,136 @@
+use notify::{ Event, RecursiveMode, Watcher };
+use std::collections::{ HashMap, HashSet };
+use std::path::{ Path, PathBuf };
+use std::sync::mpsc;
+use std::time::Duration;
+
+// How long to wait for more events after a change before re-scanning.
+// Editors and package managers often write a lockfile in several steps.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(300);
+
+/// Watch the given lockfiles and call `on_change` with the ones that changed, as they were given.
+/// Runs until the process is interrupted (Ctrl-C).
+pub fn watch_lockfiles<F>(lockfiles: &[PathBuf], mut on_change: F) where F: FnMut(&[PathBuf]) {
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("Failed to start file watcher: {}", e);
+            return;
+        }
+    };
+
+    // Watch the parent directories, since many tools replace lockfiles instead of
+    // writing them in place, which would drop a watch on the file itself.
+    // Events name canonical paths; each maps back to the path the lockfile was given as.
+    let watched_lockfiles: HashMap<PathBuf, PathBuf> = lockfiles
+        .iter()
+        .map(|lockfile| (canonical_path(lockfile), lockfile.clone()))
+        .collect();
+    let watched_dirs: HashSet<PathBuf> = watched_lockfiles
+        .keys()
+        .filter_map(|lockfile| lockfile.parent().map(|p| p.to_path_buf()))
+        .collect();
+
+    for dir in &watched_dirs {
+        if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+            eprintln!("Failed to watch {}: {}", dir.display(), e);
+        }
+    }
+
+    println!(
+        "\nWatching {} lockfile(s) for changes. Press Ctrl-C to stop.",
+        watched_lockfiles.len()
+    );
+
+    while let Ok(first_event) = rx.recv() {
+        let mut changed = HashSet::new();
+        collect_changed_lockfiles(first_event, &watched_lockfiles, &mut changed);
+
+        // Drain any follow-up events belonging to the same change
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE_DELAY) {
+            collect_changed_lockfiles(event, &watched_lockfiles, &mut changed);
+        }
+
+        if changed.is_empty() {
+            continue;
+        }
+
+        let mut changed: Vec<PathBuf> = changed.into_iter().collect();
+        changed.sort();
+
+        println!("\n=== CHANGE DETECTED ===");
+        for lockfile in &changed {
+            println!("Modified: {}", lockfile.display());
+        }
+
+        on_change(&changed);
+
+        println!("\nWatching for further changes. Press Ctrl-C to stop.");
+    }
+}
+
+/// Add the watched lockfiles touched by a watcher event to `changed`, as they were given
+fn collect_changed_lockfiles(
+    event: notify::Result<Event>,
+    watched_lockfiles: &HashMap<PathBuf, PathBuf>,
+    changed: &mut HashSet<PathBuf>
+) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            eprintln!("Watch error: {}", e);
+            return;
+        }
+    };
+
+    // Access events (reads) don't change content
+    if event.kind.is_access() {
+        return;
+    }
+
+    for path in event.paths {
+        let path = canonical_path(&path);
+        if let Some(lockfile) = watched_lockfiles.get(&path).filter(|_| path.exists()) {
+            changed.insert(lockfile.clone());
+        }
+    }
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{ AccessKind, CreateKind, EventKind, ModifyKind };
+
+    #[test]
+    fn test_changes_are_reported_under_the_given_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let lockfile = dir.path().join("package-lock.json");
+        std::fs::write(&lockfile, "{}").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+        // Given through a relative component, as a user might type it
+        let given = dir.path().join(".").join("package-lock.json");
+        let watched: HashMap<PathBuf, PathBuf> = [(canonical_path(&given), given.clone())].into();
+        let event = |kind, path: PathBuf| Ok(Event::new(kind).add_path(path));
+
+        let mut changed = HashSet::new();
+        collect_changed_lockfiles(event(EventKind::Access(AccessKind::Any), lockfile.clone()), &watched, &mut changed);
+        let notes = dir.path().join("notes.txt");
+        collect_changed_lockfiles(event(EventKind::Create(CreateKind::File), notes), &watched, &mut changed);
+        assert!(changed.is_empty());
+
+        collect_changed_lockfiles(event(EventKind::Modify(ModifyKind::Any), lockfile.clone()), &watched, &mut changed);
+        assert_eq!(changed, HashSet::from([given]));
+
+        // A lockfile that was removed has nothing to scan
+        std::fs::remove_file(&lockfile).unwrap();
+        let mut removed = HashSet::new();
+        collect_changed_lockfiles(event(EventKind::Modify(ModifyKind::Any), lockfile), &watched, &mut removed);
+        assert!(removed.is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+//! Yarn Berry's offline mirror: the package archives Yarn keeps in `.yarn/cache` next to
+//! yarn.lock. Reading a package's license from its archive needs no network request.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use crate::package::Package;
+
+/// Cache directory, relative to the project's yarn.lock
+pub const YARN_CACHE_DIR: &str = ".yarn/cache";
+
+/// The archives in one `.yarn/cache` directory
+pub struct YarnCache {
+    // Archive file names are `<slug>-npm-<version>-<hash>[-<checksum>].zip`, where the slug is
+    // the package name with the scope's slash turned into a dash; keyed by slug
+    archives: HashMap<String, Vec<(String, PathBuf)>>,
+}
+
+impl YarnCache {
+    /// The cache next to a lockfile, if it holds any archives
+    pub fn find(lockfile: &Path) -> Option<YarnCache> {
+        let dir = lockfile.parent()?.join(YARN_CACHE_DIR);
+        let cache = YarnCache::open(&dir)?;
+        if cache.archives.is_empty() { None } else { Some(cache) }
+    }
+
+    fn open(dir: &Path) -> Option<YarnCache> {
+        let mut archives: HashMap<String, Vec<(String, PathBuf)>> = HashMap::new();
+        for entry in fs::read_dir(dir).ok()?.filter_map(Result::ok) {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let Some(stem) = file_name.strip_suffix(".zip") else {
+                continue;
+            };
+            // Only npm packages; git and patch: entries carry other protocols
+            if let Some((slug, reference)) = stem.split_once("-npm-") {
+                archives.entry(slug.to_string()).or_default().push((reference.to_string(), entry.path()));
+            }
+        }
+        Some(YarnCache { archives })
+    }
+
+    /// The archive of a package version
+    pub fn archive_for(&self, name: &str, version: &str) -> Option<&Path> {
+        let slug = name.replace('/', "-");
+        self.archives
+            .get(&slug)?
+            .iter()
+            .find(|(reference, _)| {
+                // What follows the version are hashes, which tells 1.0.0 from 1.0.0-beta.1
+                reference
+                    .strip_prefix(version)
+                    .and_then(|hashes| hashes.strip_prefix('-'))
+                    .is_some_and(|hashes| {
+                        hashes.split('-').all(|hash| !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit()))
+                    })
+            })
+            .map(|(_, path)| path.as_path())
+    }
+
+    pub fn len(&self) -> usize {
+        self.archives.values().map(Vec::len).sum()
+    }
+}
+
+/// The Yarn caches of the scanned lockfiles, keyed by lockfile path as recorded in
+/// `Package::source_lockfiles`
+#[derive(Default)]
+pub struct YarnCaches {
+    caches: HashMap<String, YarnCache>,
+}
+
+impl YarnCaches {
+    /// Find the cache next to each yarn.lock (or Plug'n'Play file)
+    pub fn find(lockfiles: &[PathBuf]) -> Self {
+        let mut caches = HashMap::new();
+        for lockfile in lockfiles {
+            let file_name = lockfile.file_name().unwrap_or_default().to_string_lossy();
+            let is_yarn = file_name == "yarn.lock" ||
+                file_name == crate::parsers::yarn_pnp_parser::PNP_DATA_FILE ||
+                file_name == crate::parsers::yarn_pnp_parser::PNP_LOADER_FILE;
+            if !is_yarn {
+                continue;
+            }
+            if let Some(cache) = YarnCache::find(lockfile) {
+                println!("Using yarn cache for {}: {} archives", lockfile.display(), cache.len());
+                caches.insert(lockfile.display().to_string(), cache);
+            }
+        }
+        YarnCaches { caches }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.caches.is_empty()
+    }
+
+    /// The cached archive of a package, from the cache of any lockfile it was found in
+    pub fn archive_for(&self, package: &Package) -> Option<&Path> {
+        package.source_lockfiles
+            .iter()
+            .filter_map(|lockfile| self.caches.get(lockfile))
+            .find_map(|cache| cache.archive_for(&package.name, &package.version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_names_map_to_packages() {
+        let project = tempfile::tempdir().unwrap();
+        let cache_dir = project.path().join(YARN_CACHE_DIR);
+        fs::create_dir_all(&cache_dir).unwrap();
+        for file_name in [
+            "left-pad-npm-1.3.0-2b2d2e8e4a-a2d8e9e1b2.zip",
+            "@babel-core-npm-7.24.0-8a6f2b3c1d-0e5b4c8a9f.zip",
+            "react-npm-19.0.0-beta.1-5c1d2e3f4a-b2c3d4e5f6.zip",
+            "widget-https-7f1e2d3c4b-9a8b7c6d5e.zip",
+            ".gitignore",
+        ] {
+            fs::write(cache_dir.join(file_name), "").unwrap();
+        }
+        let lockfile = project.path().join("yarn.lock");
+
+        let cache = YarnCache::find(&lockfile).unwrap();
+        assert_eq!(cache.len(), 3);
+        assert_eq!(
+            cache.archive_for("left-pad", "1.3.0"),
+            Some(cache_dir.join("left-pad-npm-1.3.0-2b2d2e8e4a-a2d8e9e1b2.zip").as_path())
+        );
+        assert!(cache.archive_for("@babel/core", "7.24.0").is_some());
+        // A pre-release isn't mistaken for the release
+        assert!(cache.archive_for("react", "19.0.0").is_none());
+        assert!(cache.archive_for("react", "19.0.0-beta.1").is_some());
+        assert!(cache.archive_for("left-pad", "1.2.0").is_none());
+
+        // Packages are matched through the lockfile they came from
+        let caches = YarnCaches::find(&[lockfile.clone(), project.path().join("package-lock.json")]);
+        let mut package = Package::new("left-pad".to_string(), "1.3.0".to_string(), String::new(), None);
+        assert!(caches.archive_for(&package).is_none());
+        package.source_lockfiles = vec![lockfile.display().to_string()];
+        assert!(caches.archive_for(&package).is_some());
+
+        assert!(YarnCache::find(&tempfile::tempdir().unwrap().path().join("yarn.lock")).is_none());
+    }
+}
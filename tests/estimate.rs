@@ -0,0 +1,84 @@
+//! --estimate against a real scan. Runs the binary in a temporary working directory, so the
+//! scan's cache and registry settings stay out of the unit tests' process.
+
+use std::fs;
+use std::io::{ Read, Write };
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::sync::Arc;
+use std::thread;
+
+/// A registry that answers every metadata request with an MIT-licensed 1.0.0, counting them
+fn start_registry() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server_requests = Arc::clone(&requests);
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request);
+            server_requests.fetch_add(1, Ordering::SeqCst);
+            let body = r#"{ "versions": { "1.0.0": { "license": "MIT" } } }"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (url, requests)
+}
+
+/// A package-lock.json installing each package at 1.0.0
+fn write_lockfile(project: &Path, names: &[&str]) {
+    let packages: Vec<String> = names
+        .iter()
+        .map(|name| {
+            let resolved = format!("https://registry.npmjs.org/{0}/-/{0}-1.0.0.tgz", name);
+            format!(r#""node_modules/{}": {{ "version": "1.0.0", "resolved": "{}" }}"#, name, resolved)
+        })
+        .collect();
+    fs::write(
+        project.join("package-lock.json"),
+        format!(r#"{{ "lockfileVersion": 3, "packages": {{ "": {{}}, {} }} }}"#, packages.join(", "))
+    ).unwrap();
+}
+
+fn scan(project: &Path, registry: &str, extra_args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_super-license-scanner"))
+        .current_dir(project)
+        .args(["package-lock.json", "--registry", registry, "--allowed", "MIT"])
+        .args(extra_args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_estimate_matches_fetches_of_a_real_run() {
+    let (registry, requests) = start_registry();
+    let project = tempfile::tempdir().unwrap();
+
+    // A first scan caches two packages
+    write_lockfile(project.path(), &["est-a", "est-b"]);
+    scan(project.path(), &registry, &[]);
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+    write_lockfile(project.path(), &["est-a", "est-b", "est-c", "est-d", "est-e"]);
+    let estimate = scan(project.path(), &registry, &["--estimate"]);
+    assert_eq!(requests.load(Ordering::SeqCst), 2, "--estimate made requests");
+    let npm_row = estimate
+        .lines()
+        .find(|line| line.starts_with("npm "))
+        .unwrap_or_else(|| panic!("no npm row in: {}", estimate));
+    let columns: Vec<&str> = npm_row.split_whitespace().collect();
+    assert_eq!(columns, ["npm", "5", "2", "3"]);
+
+    scan(project.path(), &registry, &[]);
+    assert_eq!(requests.load(Ordering::SeqCst), 2 + 3);
+}
@@ -0,0 +1,59 @@
+#!/usr/bin/env python3
+"""Regenerate SPDX_LICENSE_FLAGS in src/license_groups.rs from the SPDX license list.
+
+    tools/spdx_license_flags.py v3.27.0           # fetches json/licenses.json for that release
+    tools/spdx_license_flags.py path/to/licenses.json
+"""
+
+import json
+import pathlib
+import re
+import sys
+import urllib.request
+
+URL = "https://raw.githubusercontent.com/spdx/license-list-data/{}/json/licenses.json"
+TARGET = pathlib.Path(__file__).resolve().parent.parent / "src" / "license_groups.rs"
+
+
+def load(source):
+    if source.startswith("v"):
+        with urllib.request.urlopen(URL.format(source)) as response:
+            return json.load(response)
+    with open(source) as file:
+        return json.load(file)
+
+
+def main():
+    if len(sys.argv) != 2:
+        sys.exit(__doc__)
+    data = load(sys.argv[1])
+
+    licenses = sorted(data["licenses"], key=lambda license: license["licenseId"].lower())
+    rows = "".join(
+        '    ("{}", {}, {}),\n'.format(
+            license["licenseId"],
+            str(license.get("isOsiApproved", False)).lower(),
+            str(license.get("isFsfLibre", False)).lower(),
+        )
+        for license in licenses
+    )
+
+    source = TARGET.read_text()
+    source, versions = re.subn(
+        r'(pub const SPDX_LICENSE_LIST_VERSION: &str = ")[^"]*(";)',
+        lambda match: match.group(1) + data["licenseListVersion"] + match.group(2),
+        source,
+    )
+    source, tables = re.subn(
+        r"(const SPDX_LICENSE_FLAGS: &\[\(&str, bool, bool\)\] = &\[\n).*?(^\];)",
+        lambda match: match.group(1) + rows + match.group(2),
+        source,
+        flags=re.DOTALL | re.MULTILINE,
+    )
+    if versions != 1 or tables != 1:
+        sys.exit("SPDX_LICENSE_LIST_VERSION or SPDX_LICENSE_FLAGS not found in {}".format(TARGET))
+    TARGET.write_text(source)
+
+
+if __name__ == "__main__":
+    main()